@@ -0,0 +1,110 @@
+use std::{
+    collections::{hash_map::RandomState, VecDeque},
+    fmt,
+    fs::File,
+    hash::{BuildHasher, Hasher},
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_web::error::Error;
+use bytes::Bytes;
+use futures_core::{ready, Stream};
+
+use crate::{ChunkedReadFile, HttpRange};
+
+/// Generates a random multipart boundary.
+pub(crate) fn boundary() -> String {
+    // hashers are seeded with random keys
+    let random = || RandomState::new().build_hasher().finish();
+    format!("{:016x}{:016x}", random(), random())
+}
+
+/// A `multipart/byteranges` body, defined in
+/// [RFC7233](https://tools.ietf.org/html/rfc7233#appendix-A), reading each range of a file
+/// chunk-by-chunk.
+pub(crate) struct ChunkedReadFileRanges {
+    parts: VecDeque<(Bytes, ChunkedReadFile)>,
+    current: Option<ChunkedReadFile>,
+    closing: Option<Bytes>,
+    size: u64,
+}
+
+impl ChunkedReadFileRanges {
+    pub(crate) fn new(
+        file: File,
+        file_size: u64,
+        ranges: &[HttpRange],
+        content_type: &str,
+        boundary: &str,
+    ) -> io::Result<Self> {
+        let mut parts = VecDeque::with_capacity(ranges.len());
+        let mut size = 0;
+
+        for (idx, range) in ranges.iter().enumerate() {
+            // the CRLF preceding a delimiter belongs to it; the first one can be omitted
+            let delimiter = if idx == 0 { "" } else { "\r\n" };
+
+            let headers = format!(
+                "{}--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                delimiter,
+                boundary,
+                content_type,
+                range.start,
+                range.start + range.length - 1,
+                file_size
+            );
+
+            size += headers.len() as u64 + range.length;
+
+            // cloned handles share a cursor but reads always seek to their offset first
+            let reader = ChunkedReadFile::new(range.length, range.start, file.try_clone()?);
+            parts.push_back((Bytes::from(headers), reader));
+        }
+
+        let closing = format!("\r\n--{}--\r\n", boundary);
+        size += closing.len() as u64;
+
+        Ok(Self {
+            parts,
+            current: None,
+            closing: Some(Bytes::from(closing)),
+            size,
+        })
+    }
+
+    /// Returns total size of the body.
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl fmt::Debug for ChunkedReadFileRanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ChunkedReadFileRanges")
+    }
+}
+
+impl Stream for ChunkedReadFileRanges {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.as_mut().get_mut();
+
+        if let Some(ref mut reader) = this.current {
+            match ready!(Pin::new(reader).poll_next(cx)) {
+                Some(chunk) => return Poll::Ready(Some(chunk)),
+                None => this.current = None,
+            }
+        }
+
+        match this.parts.pop_front() {
+            Some((headers, reader)) => {
+                this.current = Some(reader);
+                Poll::Ready(Some(Ok(headers)))
+            }
+            None => Poll::Ready(this.closing.take().map(Ok)),
+        }
+    }
+}
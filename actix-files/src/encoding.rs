@@ -1,5 +1,18 @@
+use std::cmp::Ordering;
+
+use actix_web::{
+    dev::RequestHead,
+    http::{header, ContentEncoding},
+};
 use mime::Mime;
 
+/// Encodings of precompressed files, with their file extensions, in order of server preference.
+const PRECOMPRESSED: [(ContentEncoding, &str); 3] = [
+    (ContentEncoding::Br, "br"),
+    (ContentEncoding::Zstd, "zst"),
+    (ContentEncoding::Gzip, "gz"),
+];
+
 /// Transforms MIME `text/*` types into their UTF-8 equivalent, if supported.
 ///
 /// MIME types that are converted
@@ -39,6 +52,63 @@ pub(crate) fn equiv_utf8_text(ct: Mime) -> Mime {
     ct
 }
 
+/// Negotiates `Accept-Encoding` against the supported precompressed file encodings.
+///
+/// Returns acceptable encodings, with the extension of their files, ordered by client preference,
+/// then server preference.
+pub(crate) fn precompressed_encodings(
+    req: &RequestHead,
+) -> Vec<(ContentEncoding, &'static str)> {
+    let accept_encoding = match req.headers.get(header::ACCEPT_ENCODING) {
+        Some(hdr) => hdr.to_str().unwrap_or(""),
+        None => return Vec::new(),
+    };
+
+    let mut wildcard = None;
+    let mut codings = Vec::new();
+
+    for item in accept_encoding.split(',') {
+        let mut params = item.split(';');
+        let coding = params.next().unwrap_or("").trim().to_ascii_lowercase();
+
+        // missing quality defaults to 1; invalid quality makes the coding unacceptable
+        let quality = params
+            .map(str::trim)
+            .find(|param| param.starts_with("q=") || param.starts_with("Q="))
+            .map_or(1.0, |param| param[2..].trim().parse::<f32>().unwrap_or(0.0));
+
+        match coding.as_str() {
+            "*" => wildcard = Some(quality),
+            "br" => codings.push((ContentEncoding::Br, quality)),
+            "zstd" => codings.push((ContentEncoding::Zstd, quality)),
+            "gzip" | "x-gzip" => codings.push((ContentEncoding::Gzip, quality)),
+            _ => {}
+        }
+    }
+
+    let mut encodings = PRECOMPRESSED
+        .iter()
+        .filter_map(|&(encoding, ext)| {
+            let quality = codings
+                .iter()
+                .find(|(coding, _)| *coding == encoding)
+                .map(|&(_, quality)| quality)
+                .or(wildcard)?;
+
+            Some((quality, encoding, ext))
+        })
+        .filter(|&(quality, _, _)| quality > 0.0)
+        .collect::<Vec<_>>();
+
+    // stable sort keeps server preference for equal qualities
+    encodings.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+
+    encodings
+        .into_iter()
+        .map(|(_, encoding, ext)| (encoding, ext))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,4 +119,33 @@ mod tests {
         assert_eq!(equiv_utf8_text(mime::TEXT_XML), mime::TEXT_XML);
         assert_eq!(equiv_utf8_text(mime::IMAGE_PNG), mime::IMAGE_PNG);
     }
+
+    #[test]
+    fn test_precompressed_encodings() {
+        use actix_web::test::TestRequest;
+
+        fn encodings(accept_encoding: &str) -> Vec<ContentEncoding> {
+            let req = TestRequest::default()
+                .insert_header((header::ACCEPT_ENCODING, accept_encoding))
+                .to_http_request();
+
+            precompressed_encodings(req.head())
+                .into_iter()
+                .map(|(encoding, _)| encoding)
+                .collect()
+        }
+
+        use ContentEncoding::{Br, Gzip, Zstd};
+
+        assert_eq!(encodings("gzip, deflate, br"), vec![Br, Gzip]);
+        assert_eq!(encodings("gzip;q=1.0, br;q=0.5"), vec![Gzip, Br]);
+        assert_eq!(encodings("x-gzip, zstd; Q=0.8"), vec![Gzip, Zstd]);
+        assert_eq!(encodings("*"), vec![Br, Zstd, Gzip]);
+        assert_eq!(encodings("br;q=0, *;q=0.1"), vec![Zstd, Gzip]);
+        assert_eq!(encodings("gzip;q=invalid, identity"), vec![]);
+        assert_eq!(encodings(""), vec![]);
+
+        let req = TestRequest::default().to_http_request();
+        assert!(precompressed_encodings(req.head()).is_empty());
+    }
 }
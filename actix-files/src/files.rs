@@ -49,6 +49,7 @@ pub struct Files {
     use_guards: Option<Rc<dyn Guard>>,
     guards: Vec<Rc<dyn Guard>>,
     hidden_files: bool,
+    max_ranges: usize,
 }
 
 impl fmt::Debug for Files {
@@ -73,6 +74,7 @@ impl Clone for Files {
             use_guards: self.use_guards.clone(),
             guards: self.guards.clone(),
             hidden_files: self.hidden_files,
+            max_ranges: self.max_ranges,
         }
     }
 }
@@ -119,6 +121,7 @@ impl Files {
             use_guards: None,
             guards: Vec::new(),
             hidden_files: false,
+            max_ranges: named::DEFAULT_MAX_RANGES,
         }
     }
 
@@ -228,6 +231,32 @@ impl Files {
         self
     }
 
+    /// Specifies whether to serve precompressed files.
+    ///
+    /// When enabled, a `.br`, `.zst` or `.gz` file next to the requested one (e.g. `app.js.br` for
+    /// `app.js`) is served instead, if the client accepts its encoding. Encodings are chosen by
+    /// the client's `Accept-Encoding` preferences, then in the order listed. Responses carry a
+    /// `Content-Encoding` header, which stops the [`Compress`](actix_web::middleware::Compress)
+    /// middleware from compressing them again, and `Vary: Accept-Encoding`.
+    ///
+    /// The uncompressed file must exist; it determines the content type of the response.
+    ///
+    /// Default is false.
+    pub fn use_precompressed(mut self, value: bool) -> Self {
+        self.file_flags.set(named::Flags::PRECOMPRESSED, value);
+        self
+    }
+
+    /// Sets the maximum number of ranges served in a `multipart/byteranges` response.
+    ///
+    /// See [`NamedFile::max_ranges`](crate::NamedFile::max_ranges) for details.
+    ///
+    /// Default is 16.
+    pub fn max_ranges(mut self, max_ranges: usize) -> Self {
+        self.max_ranges = max_ranges;
+        self
+    }
+
     /// Adds a routing guard.
     ///
     /// Use this to allow multiple chained file services that respond to strictly different
@@ -365,6 +394,7 @@ impl ServiceFactory<ServiceRequest> for Files {
             file_flags: self.file_flags,
             guards: self.use_guards.clone(),
             hidden_files: self.hidden_files,
+            max_ranges: self.max_ranges,
         };
 
         if let Some(ref default) = *self.default.borrow() {
@@ -23,6 +23,7 @@ use actix_web::{
 use mime_guess::from_ext;
 use std::path::Path;
 
+mod byte_ranges;
 mod chunked;
 mod directory;
 mod encoding;
@@ -494,6 +495,88 @@ mod tests {
         assert_eq!(bytes, data);
     }
 
+    #[actix_rt::test]
+    async fn test_named_file_multiple_ranges() {
+        let srv = actix_test::start(|| App::new().service(Files::new("/", ".")));
+        let data = fs::read("tests/test.binary").unwrap();
+
+        let mut response = srv
+            .get("/tests/test.binary")
+            .insert_header((header::RANGE, "bytes=10-19, 90-"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert!(response.headers().get(header::CONTENT_RANGE).is_none());
+
+        let content_type = response.headers().get(header::CONTENT_TYPE).unwrap();
+        let content_type = content_type.to_str().unwrap().to_owned();
+        assert!(content_type.starts_with("multipart/byteranges; boundary="));
+        let boundary = &content_type["multipart/byteranges; boundary=".len()..];
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Type: application/octet-stream\r\n\
+                 Content-Range: bytes 10-19/100\r\n\r\n",
+                boundary
+            )
+            .as_bytes(),
+        );
+        expected.extend_from_slice(&data[10..20]);
+        expected.extend_from_slice(
+            format!(
+                "\r\n--{}\r\nContent-Type: application/octet-stream\r\n\
+                 Content-Range: bytes 90-99/100\r\n\r\n",
+                boundary
+            )
+            .as_bytes(),
+        );
+        expected.extend_from_slice(&data[90..]);
+        expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        let content_length = response.headers().get(header::CONTENT_LENGTH).unwrap();
+        assert_eq!(content_length.to_str().unwrap(), expected.len().to_string());
+
+        let bytes = response.body().await.unwrap();
+        assert_eq!(bytes, expected);
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_max_ranges() {
+        let srv =
+            test::init_service(App::new().service(Files::new("/", ".").max_ranges(2))).await;
+
+        let request = TestRequest::get()
+            .uri("/tests/test.binary")
+            .insert_header((header::RANGE, "bytes=0-1, 2-3"))
+            .to_request();
+        let response = test::call_service(&srv, request).await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+
+        // too many ranges; whole file is sent
+        let request = TestRequest::get()
+            .uri("/tests/test.binary")
+            .insert_header((header::RANGE, "bytes=0-1, 2-3, 4-5"))
+            .to_request();
+        let response = test::call_service(&srv, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+        let bytes = test::read_body(response).await;
+        assert_eq!(bytes, fs::read("tests/test.binary").unwrap());
+
+        // single ranges are always served
+        let file = NamedFile::open("tests/test.binary").unwrap().max_ranges(0);
+        let req = TestRequest::default()
+            .insert_header((header::RANGE, "bytes=0-1"))
+            .to_http_request();
+        let response = file.respond_to(&req);
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    }
+
     #[actix_rt::test]
     async fn test_head_content_length_headers() {
         let srv = actix_test::start(|| App::new().service(Files::new("/", ".")));
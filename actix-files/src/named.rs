@@ -11,20 +11,24 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use std::os::unix::fs::MetadataExt;
 
 use actix_web::{
-    dev::{BodyEncoding, ServiceRequest, ServiceResponse, SizedStream},
+    dev::{BodyEncoding, RequestHead, ServiceRequest, ServiceResponse, SizedStream},
     http::{
         header::{
             self, Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue,
         },
-        ContentEncoding, StatusCode,
+        ContentEncoding, Precondition, StatusCode,
     },
-    Error, HttpMessage, HttpRequest, HttpResponse, Responder,
+    Error, HttpRequest, HttpResponse, HttpResponseBuilder, Responder,
 };
 use bitflags::bitflags;
 use mime_guess::from_path;
 
 use crate::ChunkedReadFile;
-use crate::{encoding::equiv_utf8_text, range::HttpRange};
+use crate::{
+    byte_ranges::{boundary, ChunkedReadFileRanges},
+    encoding::{equiv_utf8_text, precompressed_encodings},
+    range::HttpRange,
+};
 
 bitflags! {
     pub(crate) struct Flags: u8 {
@@ -32,9 +36,13 @@ bitflags! {
         const LAST_MD =             0b0000_0010;
         const CONTENT_DISPOSITION = 0b0000_0100;
         const PREFER_UTF8 =         0b0000_1000;
+        const PRECOMPRESSED =       0b0001_0000;
     }
 }
 
+/// Default maximum number of ranges served in a `multipart/byteranges` response.
+pub(crate) const DEFAULT_MAX_RANGES: usize = 16;
+
 impl Default for Flags {
     fn default() -> Self {
         Flags::from_bits_truncate(0b0000_0111)
@@ -76,6 +84,8 @@ pub struct NamedFile {
     pub(crate) content_type: mime::Mime,
     pub(crate) content_disposition: header::ContentDisposition,
     pub(crate) encoding: Option<ContentEncoding>,
+    pub(crate) precompressed: Option<ContentEncoding>,
+    pub(crate) max_ranges: usize,
 }
 
 impl NamedFile {
@@ -159,6 +169,8 @@ impl NamedFile {
             md,
             modified,
             encoding,
+            precompressed: None,
+            max_ranges: DEFAULT_MAX_RANGES,
             status_code: StatusCode::OK,
             flags: Flags::default(),
         })
@@ -177,6 +189,30 @@ impl NamedFile {
         Self::from_file(File::open(&path)?, path)
     }
 
+    /// Attempts to open a precompressed sibling of a file (e.g. `app.js.br` for `app.js`) in an
+    /// encoding accepted by the request, falling back to the file itself.
+    pub(crate) fn open_precompressed(path: &Path, req: &RequestHead) -> io::Result<NamedFile> {
+        for (encoding, ext) in precompressed_encodings(req) {
+            let mut sibling = path.as_os_str().to_owned();
+            sibling.push(".");
+            sibling.push(ext);
+            let sibling = PathBuf::from(sibling);
+
+            if !sibling.is_file() {
+                continue;
+            }
+
+            if let Ok(file) = File::open(&sibling) {
+                // content type and disposition are determined by the original file name
+                let mut named_file = Self::from_file(file, path)?;
+                named_file.precompressed = Some(encoding);
+                return Ok(named_file);
+            }
+        }
+
+        Self::open(path)
+    }
+
     /// Returns reference to the underlying `File` object.
     #[inline]
     pub fn file(&self) -> &File {
@@ -277,6 +313,18 @@ impl NamedFile {
         self
     }
 
+    /// Sets the maximum number of ranges served in a `multipart/byteranges` response.
+    ///
+    /// Requests for more ranges are responded to with the whole file, limiting the work clients can
+    /// cause with many small or overlapping ranges. A maximum of 1 disables multi-range responses.
+    ///
+    /// Default is 16.
+    #[inline]
+    pub fn max_ranges(mut self, max_ranges: usize) -> Self {
+        self.max_ranges = max_ranges;
+        self
+    }
+
     pub(crate) fn etag(&self) -> Option<header::EntityTag> {
         // This etag format is similar to Apache's.
         self.modified.as_ref().map(|mtime| {
@@ -295,13 +343,21 @@ impl NamedFile {
                 .duration_since(UNIX_EPOCH)
                 .expect("modification time must be after epoch");
 
-            header::EntityTag::strong(format!(
+            let mut tag = format!(
                 "{:x}:{:x}:{:x}:{:x}",
                 ino,
                 self.md.len(),
                 dur.as_secs(),
                 dur.subsec_nanos()
-            ))
+            );
+
+            // representations in different encodings must not share a strong tag
+            if let Some(encoding) = self.precompressed {
+                tag.push('-');
+                tag.push_str(encoding.as_str());
+            }
+
+            header::EntityTag::strong(tag)
         })
     }
 
@@ -309,6 +365,17 @@ impl NamedFile {
         self.modified.map(|mtime| mtime.into())
     }
 
+    fn insert_precompressed_headers(&self, res: &mut HttpResponseBuilder) {
+        // compress middleware leaves responses with a content encoding untouched
+        if let Some(encoding) = self.precompressed {
+            res.insert_header((header::CONTENT_ENCODING, encoding.as_str()));
+        }
+
+        if self.flags.contains(Flags::PRECOMPRESSED) {
+            res.insert_header((header::VARY, "Accept-Encoding"));
+        }
+    }
+
     /// Creates an `HttpResponse` with file as a streaming body.
     pub fn into_response(self, req: &HttpRequest) -> HttpResponse {
         if self.status_code != StatusCode::OK {
@@ -332,6 +399,8 @@ impl NamedFile {
                 res.encoding(current_encoding);
             }
 
+            self.insert_precompressed_headers(&mut res);
+
             let reader = ChunkedReadFile::new(self.md.len(), 0, self.file);
 
             return res.streaming(reader);
@@ -349,50 +418,16 @@ impl NamedFile {
             None
         };
 
-        // check preconditions
-        let precondition_failed = if !any_match(etag.as_ref(), req) {
-            true
-        } else if let (Some(ref m), Some(header::IfUnmodifiedSince(ref since))) =
-            (last_modified, req.get_header())
-        {
-            let t1: SystemTime = (*m).into();
-            let t2: SystemTime = (*since).into();
-
-            match (t1.duration_since(UNIX_EPOCH), t2.duration_since(UNIX_EPOCH)) {
-                (Ok(t1), Ok(t2)) => t1.as_secs() > t2.as_secs(),
-                _ => false,
-            }
-        } else {
-            false
-        };
-
-        // check last modified
-        let not_modified = if !none_match(etag.as_ref(), req) {
-            true
-        } else if req.headers().contains_key(header::IF_NONE_MATCH) {
-            false
-        } else if let (Some(ref m), Some(header::IfModifiedSince(ref since))) =
-            (last_modified, req.get_header())
-        {
-            let t1: SystemTime = (*m).into();
-            let t2: SystemTime = (*since).into();
-
-            match (t1.duration_since(UNIX_EPOCH), t2.duration_since(UNIX_EPOCH)) {
-                (Ok(t1), Ok(t2)) => t1.as_secs() <= t2.as_secs(),
-                _ => false,
-            }
-        } else {
-            false
-        };
+        let precondition = Precondition::evaluate(req, etag.as_ref(), last_modified);
 
         let mut resp = HttpResponse::build(self.status_code);
 
-        if self.flags.contains(Flags::PREFER_UTF8) {
-            let ct = equiv_utf8_text(self.content_type.clone());
-            resp.insert_header((header::CONTENT_TYPE, ct.to_string()));
+        let content_type = if self.flags.contains(Flags::PREFER_UTF8) {
+            equiv_utf8_text(self.content_type.clone()).to_string()
         } else {
-            resp.insert_header((header::CONTENT_TYPE, self.content_type.to_string()));
-        }
+            self.content_type.to_string()
+        };
+        resp.insert_header((header::CONTENT_TYPE, content_type.clone()));
 
         if self.flags.contains(Flags::CONTENT_DISPOSITION) {
             resp.insert_header((
@@ -406,6 +441,8 @@ impl NamedFile {
             resp.encoding(current_encoding);
         }
 
+        self.insert_precompressed_headers(&mut resp);
+
         if let Some(lm) = last_modified {
             resp.insert_header((header::LAST_MODIFIED, lm.to_string()));
         }
@@ -418,19 +455,32 @@ impl NamedFile {
 
         let mut length = self.md.len();
         let mut offset = 0;
+        let mut multiple_ranges = None;
 
         // check for range header
         if let Some(ranges) = req.headers().get(header::RANGE) {
             if let Ok(ranges_header) = ranges.to_str() {
                 if let Ok(ranges) = HttpRange::parse(ranges_header, length) {
-                    length = ranges[0].length;
-                    offset = ranges[0].start;
+                    if ranges.len() == 1 {
+                        length = ranges[0].length;
+                        offset = ranges[0].start;
+
+                        resp.encoding(ContentEncoding::Identity);
+                        resp.insert_header((
+                            header::CONTENT_RANGE,
+                            format!(
+                                "bytes {}-{}/{}",
+                                offset,
+                                offset + length - 1,
+                                self.md.len()
+                            ),
+                        ));
+                    } else if ranges.len() <= self.max_ranges {
+                        resp.encoding(ContentEncoding::Identity);
+                        multiple_ranges = Some(ranges);
+                    }
 
-                    resp.encoding(ContentEncoding::Identity);
-                    resp.insert_header((
-                        header::CONTENT_RANGE,
-                        format!("bytes {}-{}/{}", offset, offset + length - 1, self.md.len()),
-                    ));
+                    // too many ranges are ignored and the whole file is sent
                 } else {
                     resp.insert_header((header::CONTENT_RANGE, format!("bytes */{}", length)));
                     return resp.status(StatusCode::RANGE_NOT_SATISFIABLE).finish();
@@ -440,10 +490,32 @@ impl NamedFile {
             };
         };
 
-        if precondition_failed {
-            return resp.status(StatusCode::PRECONDITION_FAILED).finish();
-        } else if not_modified {
-            return resp.status(StatusCode::NOT_MODIFIED).finish();
+        if let Some(status) = precondition.status_code() {
+            return resp.status(status).finish();
+        }
+
+        if let Some(ranges) = multiple_ranges {
+            let boundary = boundary();
+
+            let body = match ChunkedReadFileRanges::new(
+                self.file,
+                self.md.len(),
+                &ranges,
+                &content_type,
+                &boundary,
+            ) {
+                Ok(body) => body,
+                Err(err) => return HttpResponse::from_error(err),
+            };
+
+            resp.insert_header((
+                header::CONTENT_TYPE,
+                format!("multipart/byteranges; boundary={}", boundary),
+            ));
+
+            return resp
+                .status(StatusCode::PARTIAL_CONTENT)
+                .body(SizedStream::new(body.size(), body));
         }
 
         let reader = ChunkedReadFile::new(length, offset, self.file);
@@ -470,46 +542,6 @@ impl DerefMut for NamedFile {
     }
 }
 
-/// Returns true if `req` has no `If-Match` header or one which matches `etag`.
-fn any_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
-    match req.get_header::<header::IfMatch>() {
-        None | Some(header::IfMatch::Any) => true,
-
-        Some(header::IfMatch::Items(ref items)) => {
-            if let Some(some_etag) = etag {
-                for item in items {
-                    if item.strong_eq(some_etag) {
-                        return true;
-                    }
-                }
-            }
-
-            false
-        }
-    }
-}
-
-/// Returns true if `req` doesn't have an `If-None-Match` header matching `req`.
-fn none_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
-    match req.get_header::<header::IfNoneMatch>() {
-        Some(header::IfNoneMatch::Any) => false,
-
-        Some(header::IfNoneMatch::Items(ref items)) => {
-            if let Some(some_etag) = etag {
-                for item in items {
-                    if item.weak_eq(some_etag) {
-                        return false;
-                    }
-                }
-            }
-
-            true
-        }
-
-        None => true,
-    }
-}
-
 impl Responder for NamedFile {
     fn respond_to(self, req: &HttpRequest) -> HttpResponse {
         self.into_response(req)
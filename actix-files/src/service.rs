@@ -1,4 +1,8 @@
-use std::{fmt, io, path::PathBuf, rc::Rc};
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use actix_service::Service;
 use actix_utils::future::ok;
@@ -29,9 +33,18 @@ pub struct FilesService {
     pub(crate) file_flags: named::Flags,
     pub(crate) guards: Option<Rc<dyn Guard>>,
     pub(crate) hidden_files: bool,
+    pub(crate) max_ranges: usize,
 }
 
 impl FilesService {
+    fn open_file(&self, path: &Path, req: &ServiceRequest) -> io::Result<NamedFile> {
+        if self.file_flags.contains(named::Flags::PRECOMPRESSED) {
+            NamedFile::open_precompressed(path, req.head())
+        } else {
+            NamedFile::open(path)
+        }
+    }
+
     fn handle_err(
         &self,
         err: io::Error,
@@ -121,6 +134,7 @@ impl Service<ServiceRequest> for FilesService {
                     named_file.content_disposition.disposition = new_disposition;
                 }
                 named_file.flags = self.file_flags;
+                named_file.max_ranges = self.max_ranges;
 
                 let (req, _) = req.into_parts();
                 let res = named_file.into_response(&req);
@@ -140,7 +154,7 @@ impl Service<ServiceRequest> for FilesService {
             };
 
             match self.index {
-                Some(ref index) => match NamedFile::open(path.join(index)) {
+                Some(ref index) => match self.open_file(&path.join(index), &req) {
                     Ok(named_file) => serve_named_file(req, named_file),
                     Err(_) if self.show_index => show_index(req),
                     Err(err) => self.handle_err(err, req),
@@ -152,13 +166,14 @@ impl Service<ServiceRequest> for FilesService {
                 ))),
             }
         } else {
-            match NamedFile::open(path) {
+            match self.open_file(&path, &req) {
                 Ok(mut named_file) => {
                     if let Some(ref mime_override) = self.mime_override {
                         let new_disposition = mime_override(&named_file.content_type.type_());
                         named_file.content_disposition.disposition = new_disposition;
                     }
                     named_file.flags = self.file_flags;
+                    named_file.max_ranges = self.max_ranges;
 
                     let (req, _) = req.into_parts();
                     let res = named_file.into_response(&req);
@@ -36,3 +36,96 @@ async fn test_utf8_file_contents() {
         Some(&HeaderValue::from_static("text/plain; charset=utf-8")),
     );
 }
+
+#[actix_rt::test]
+async fn test_precompressed_files() {
+    let srv = test::init_service(
+        App::new()
+            .service(Files::new("/", "./tests/fixtures/precompressed").use_precompressed(true)),
+    )
+    .await;
+
+    let original = std::fs::read("./tests/fixtures/precompressed/app.js").unwrap();
+    let brotli = std::fs::read("./tests/fixtures/precompressed/app.js.br").unwrap();
+    let gzip = std::fs::read("./tests/fixtures/precompressed/app.js.gz").unwrap();
+
+    for &(accept_encoding, encoding, contents) in &[
+        ("gzip, deflate, br", Some("br"), &brotli),
+        ("br;q=0.5, gzip", Some("gzip"), &gzip),
+        ("zstd", None, &original),
+        ("identity", None, &original),
+    ] {
+        let req = TestRequest::with_uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, accept_encoding))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()
+                .get(header::CONTENT_ENCODING)
+                .map(|hdr| hdr.to_str().unwrap()),
+            encoding,
+        );
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/javascript"
+        );
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept-Encoding");
+
+        let body = test::read_body(res).await;
+        assert_eq!(&body[..], &contents[..]);
+    }
+
+    // each encoding has its own entity tag
+    let mut etags = Vec::new();
+    for accept_encoding in &["br", "gzip", "identity"] {
+        let req = TestRequest::with_uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, *accept_encoding))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        etags.push(res.headers().get(header::ETAG).unwrap().clone());
+    }
+    assert_ne!(etags[0], etags[1]);
+    assert_ne!(etags[0], etags[2]);
+    assert_ne!(etags[1], etags[2]);
+
+    let req = TestRequest::with_uri("/app.js")
+        .insert_header((header::ACCEPT_ENCODING, "gzip"))
+        .insert_header((header::IF_NONE_MATCH, etags[1].clone()))
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+    // ranges apply to the compressed file
+    let req = TestRequest::with_uri("/app.js")
+        .insert_header((header::ACCEPT_ENCODING, "gzip"))
+        .insert_header((header::RANGE, "bytes=10-19"))
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+    assert_eq!(
+        res.headers().get(header::CONTENT_RANGE).unwrap(),
+        format!("bytes 10-19/{}", gzip.len()).as_str()
+    );
+    let body = test::read_body(res).await;
+    assert_eq!(&body[..], &gzip[10..20]);
+}
+
+#[actix_rt::test]
+async fn test_precompressed_files_disabled() {
+    let srv = test::init_service(
+        App::new().service(Files::new("/", "./tests/fixtures/precompressed")),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("/app.js")
+        .insert_header((header::ACCEPT_ENCODING, "br, gzip"))
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+    assert!(res.headers().get(header::VARY).is_none());
+}
@@ -9,7 +9,7 @@ use std::{
 use bytes::{Bytes, BytesMut};
 use futures_core::{ready, Stream};
 
-use crate::error::Error;
+use crate::{error::Error, header::HeaderMap};
 
 use super::{BodySize, BodyStream, MessageBody, MessageBodyMapErr, SizedStream};
 
@@ -84,6 +84,13 @@ impl MessageBody for AnyBody {
             },
         }
     }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        match self.get_mut() {
+            AnyBody::Message(body) => body.as_pin_mut().take_trailers(),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq for AnyBody {
@@ -230,4 +237,8 @@ impl MessageBody for BoxAnyBody {
             None => Poll::Ready(None),
         }
     }
+
+    fn take_trailers(mut self: Pin<&mut Self>) -> Option<HeaderMap> {
+        self.0.as_mut().take_trailers()
+    }
 }
@@ -11,7 +11,7 @@ use bytes::{Bytes, BytesMut};
 use futures_core::ready;
 use pin_project_lite::pin_project;
 
-use crate::error::Error;
+use crate::{error::Error, header::HeaderMap};
 
 use super::BodySize;
 
@@ -27,6 +27,17 @@ pub trait MessageBody {
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, Self::Error>>>;
+
+    /// Take trailer fields to be sent after the body.
+    ///
+    /// Called once, after `poll_next` has signalled the end of the body. Trailers are only sent
+    /// on HTTP/1.1 chunked responses to clients that accept them (`TE: trailers`) and on
+    /// HTTP/2 responses, as a trailing HEADERS frame.
+    ///
+    /// The default implementation returns `None`.
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        None
+    }
 }
 
 impl MessageBody for () {
@@ -61,6 +72,10 @@ where
     ) -> Poll<Option<Result<Bytes, Self::Error>>> {
         Pin::new(self.get_mut().as_mut()).poll_next(cx)
     }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        Pin::new(self.get_mut().as_mut()).take_trailers()
+    }
 }
 
 impl<B> MessageBody for Pin<Box<B>>
@@ -80,6 +95,10 @@ where
     ) -> Poll<Option<Result<Bytes, Self::Error>>> {
         self.as_mut().poll_next(cx)
     }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        self.get_mut().as_mut().take_trailers()
+    }
 }
 
 impl MessageBody for Bytes {
@@ -229,4 +248,8 @@ where
             None => Poll::Ready(None),
         }
     }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        self.project().body.take_trailers()
+    }
 }
@@ -8,7 +8,7 @@ use bytes::Bytes;
 use futures_core::{ready, Stream};
 use pin_project::pin_project;
 
-use crate::error::Error;
+use crate::{error::Error, header::HeaderMap};
 
 use super::{Body, BodySize, MessageBody};
 
@@ -63,6 +63,13 @@ where
     ) -> Poll<Option<Result<Bytes, Self::Error>>> {
         Stream::poll_next(self, cx)
     }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        match self.project() {
+            ResponseBodyProj::Body(body) => body.take_trailers(),
+            ResponseBodyProj::Other(body) => Pin::new(body).take_trailers(),
+        }
+    }
 }
 
 impl<B> Stream for ResponseBody<B>
@@ -1,15 +1,17 @@
-use std::{error::Error as StdError, fmt, marker::PhantomData, net, rc::Rc};
+use std::{
+    error::Error as StdError, fmt, marker::PhantomData, net, rc::Rc, time::Duration,
+};
 
 use actix_codec::Framed;
 use actix_service::{IntoServiceFactory, Service, ServiceFactory};
 
 use crate::{
     body::{AnyBody, MessageBody},
-    config::{KeepAlive, ServiceConfig},
+    config::{H2Config, KeepAlive, RequestLimits, ServiceConfig},
     h1::{self, ExpectHandler, H1Service, UpgradeHandler},
     h2::H2Service,
     service::HttpService,
-    ConnectCallback, Extensions, Request, Response,
+    ConnectCallback, Extensions, Request, Response, ShutdownSignal,
 };
 
 /// A HTTP service builder
@@ -22,9 +24,12 @@ pub struct HttpServiceBuilder<T, S, X = ExpectHandler, U = UpgradeHandler> {
     client_disconnect: u64,
     secure: bool,
     local_addr: Option<net::SocketAddr>,
+    limits: RequestLimits,
+    h2: H2Config,
     expect: X,
     upgrade: Option<U>,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+    shutdown: Option<ShutdownSignal>,
     _phantom: PhantomData<S>,
 }
 
@@ -43,9 +48,12 @@ where
             client_disconnect: 0,
             secure: false,
             local_addr: None,
+            limits: RequestLimits::default(),
+            h2: H2Config::default(),
             expect: ExpectHandler,
             upgrade: None,
             on_connect_ext: None,
+            shutdown: None,
             _phantom: PhantomData,
         }
     }
@@ -111,6 +119,148 @@ where
         self
     }
 
+    /// Set limits applied when parsing request heads.
+    ///
+    /// See [`RequestLimits`] for defaults. Individual limits can also be set with
+    /// [`max_request_headers`](Self::max_request_headers),
+    /// [`max_request_head_size`](Self::max_request_head_size),
+    /// [`max_uri_len`](Self::max_uri_len) and
+    /// [`max_request_trailer_size`](Self::max_request_trailer_size).
+    pub fn request_limits(mut self, limits: RequestLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set maximum number of headers allowed in a request head.
+    ///
+    /// Requests with more headers are responded to with `431 Request Header Fields Too Large`.
+    ///
+    /// By default 96 headers are allowed.
+    ///
+    /// # Panics
+    /// Panics if `val` is zero.
+    pub fn max_request_headers(mut self, val: usize) -> Self {
+        self.limits = self.limits.max_headers(val);
+        self
+    }
+
+    /// Set maximum size of a request head (request line and headers) in bytes.
+    ///
+    /// Requests with a larger head are responded to with
+    /// `431 Request Header Fields Too Large`.
+    ///
+    /// By default request head size is limited to 128KiB.
+    ///
+    /// # Panics
+    /// Panics if `val` is zero.
+    pub fn max_request_head_size(mut self, val: usize) -> Self {
+        self.limits = self.limits.max_head_size(val);
+        self
+    }
+
+    /// Set maximum length of a request URI in bytes.
+    ///
+    /// Requests with a longer URI are responded to with `414 URI Too Long`.
+    ///
+    /// By default URI length is only limited by the maximum request head size.
+    ///
+    /// # Panics
+    /// Panics if `val` is zero.
+    pub fn max_uri_len(mut self, val: usize) -> Self {
+        self.limits = self.limits.max_uri_len(val);
+        self
+    }
+
+    /// Set maximum size of the trailer section of a chunked request body in bytes.
+    ///
+    /// Request bodies with a larger trailer section fail with a payload error.
+    ///
+    /// By default trailer section size is limited to the maximum request head size.
+    ///
+    /// # Panics
+    /// Panics if `val` is zero.
+    pub fn max_request_trailer_size(mut self, val: usize) -> Self {
+        self.limits = self.limits.max_trailer_size(val);
+        self
+    }
+
+    /// Set HTTP/2 connection settings.
+    ///
+    /// See [`H2Config`] for defaults. Individual settings can also be set with the `h2_*`
+    /// methods of this builder.
+    pub fn h2_config(mut self, config: H2Config) -> Self {
+        self.h2 = config;
+        self
+    }
+
+    /// Set initial HTTP/2 flow control window size of each stream, in bytes.
+    ///
+    /// By default the window is 65,535 bytes.
+    ///
+    /// # Panics
+    /// Panics if `val` is larger than 2^31 - 1.
+    pub fn h2_initial_window_size(mut self, val: u32) -> Self {
+        self.h2 = self.h2.initial_window_size(val);
+        self
+    }
+
+    /// Set initial HTTP/2 flow control window size of a connection, in bytes.
+    ///
+    /// By default the window is 65,535 bytes.
+    ///
+    /// # Panics
+    /// Panics if `val` is larger than 2^31 - 1.
+    pub fn h2_initial_connection_window_size(mut self, val: u32) -> Self {
+        self.h2 = self.h2.initial_connection_window_size(val);
+        self
+    }
+
+    /// Set maximum number of concurrent streams a client may open on an HTTP/2 connection.
+    ///
+    /// By default the number of concurrent streams is not limited.
+    pub fn h2_max_concurrent_streams(mut self, val: u32) -> Self {
+        self.h2 = self.h2.max_concurrent_streams(val);
+        self
+    }
+
+    /// Set maximum size of an HTTP/2 frame payload the server is willing to receive, in bytes.
+    ///
+    /// By default frames are limited to 16,384 bytes.
+    ///
+    /// # Panics
+    /// Panics if `val` is not within 16,384 and 16,777,215 (inclusive).
+    pub fn h2_max_frame_size(mut self, val: u32) -> Self {
+        self.h2 = self.h2.max_frame_size(val);
+        self
+    }
+
+    /// Set maximum size of an HTTP/2 request's header list, in bytes.
+    ///
+    /// By default the header list size is limited to 16MiB.
+    pub fn h2_max_header_list_size(mut self, val: u32) -> Self {
+        self.h2 = self.h2.max_header_list_size(val);
+        self
+    }
+
+    /// Set interval at which keep-alive PING frames are sent on HTTP/2 connections.
+    ///
+    /// Connections that do not acknowledge a PING within the
+    /// [keep-alive timeout](Self::h2_keep_alive_timeout) are closed.
+    ///
+    /// By default keep-alive PINGs are disabled.
+    pub fn h2_keep_alive_interval(mut self, val: Duration) -> Self {
+        self.h2 = self.h2.keep_alive_interval(val);
+        self
+    }
+
+    /// Set time to wait for an HTTP/2 keep-alive PING acknowledgement.
+    ///
+    /// By default the timeout is 20 seconds.
+    pub fn h2_keep_alive_timeout(mut self, val: Duration) -> Self {
+        self.h2 = self.h2.keep_alive_timeout(val);
+        self
+    }
+
     /// Provide service for `EXPECT: 100-Continue` support.
     ///
     /// Service get called with request that contains `EXPECT` header.
@@ -129,9 +279,12 @@ where
             client_disconnect: self.client_disconnect,
             secure: self.secure,
             local_addr: self.local_addr,
+            limits: self.limits,
+            h2: self.h2,
             expect: expect.into_factory(),
             upgrade: self.upgrade,
             on_connect_ext: self.on_connect_ext,
+            shutdown: self.shutdown,
             _phantom: PhantomData,
         }
     }
@@ -153,9 +306,12 @@ where
             client_disconnect: self.client_disconnect,
             secure: self.secure,
             local_addr: self.local_addr,
+            limits: self.limits,
+            h2: self.h2,
             expect: self.expect,
             upgrade: Some(upgrade.into_factory()),
             on_connect_ext: self.on_connect_ext,
+            shutdown: self.shutdown,
             _phantom: PhantomData,
         }
     }
@@ -173,6 +329,16 @@ where
         self
     }
 
+    /// Set signal for gracefully shutting down HTTP/2 connections.
+    ///
+    /// Once the signal is triggered, HTTP/2 connections send a GOAWAY frame to their clients,
+    /// let in-flight streams complete, refuse new streams and close. This signal has no effect
+    /// on HTTP/1 connections.
+    pub fn shutdown_signal(mut self, signal: ShutdownSignal) -> Self {
+        self.shutdown = Some(signal);
+        self
+    }
+
     /// Finish service configuration and create a HTTP Service for HTTP/1 protocol.
     pub fn h1<F, B>(self, service: F) -> H1Service<T, S, B, X, U>
     where
@@ -188,7 +354,9 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .request_limits(self.limits)
+        .h2(self.h2);
 
         H1Service::with_config(cfg, service.into_factory())
             .expect(self.expect)
@@ -213,10 +381,13 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .request_limits(self.limits)
+        .h2(self.h2);
 
         H2Service::with_config(cfg, service.into_factory())
             .on_connect_ext(self.on_connect_ext)
+            .shutdown_signal(self.shutdown)
     }
 
     /// Finish service configuration and create `HttpService` instance.
@@ -236,11 +407,14 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .request_limits(self.limits)
+        .h2(self.h2);
 
         HttpService::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
             .on_connect_ext(self.on_connect_ext)
+            .shutdown_signal(self.shutdown)
     }
 }
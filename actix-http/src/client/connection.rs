@@ -19,6 +19,7 @@ use crate::{body::MessageBody, Error};
 
 use super::error::SendRequestError;
 use super::pool::Acquired;
+use super::proxy::Proxy;
 use super::{h1proto, h2proto};
 
 /// Trait alias for types impl [tokio::io::AsyncRead] and [tokio::io::AsyncWrite].
@@ -56,6 +57,11 @@ impl<Io: ConnectionIo> H1Connection<Io> {
             .release(ConnectionInnerType::H1(io), self.created);
     }
 
+    /// Returns the proxy that forwards requests sent on the connection.
+    pub(super) fn proxy(&self) -> Option<&Proxy> {
+        self.acquired.proxy()
+    }
+
     fn io_pin_mut(self: Pin<&mut Self>) -> Pin<&mut Io> {
         Pin::new(self.get_mut().io.as_mut().unwrap())
     }
@@ -263,10 +269,12 @@ where
         Box::pin(async move {
             match self {
                 Connection::Tcp(ConnectionType::H1(conn)) => {
-                    h1proto::send_request(conn, head.into(), body).await
+                    // plain connections to proxies forward requests to their target
+                    let proxy = conn.proxy().cloned();
+                    h1proto::send_request(conn, head.into(), body, proxy).await
                 }
                 Connection::Tls(ConnectionType::H1(conn)) => {
-                    h1proto::send_request(conn, head.into(), body).await
+                    h1proto::send_request(conn, head.into(), body, None).await
                 }
                 Connection::Tls(ConnectionType::H2(conn)) => {
                     h2proto::send_request(conn, head.into(), body).await
@@ -288,12 +296,15 @@ where
     > {
         Box::pin(async move {
             match self {
-                Connection::Tcp(ConnectionType::H1(ref _conn)) => {
-                    let (head, framed) = h1proto::open_tunnel(self, head.into()).await?;
+                Connection::Tcp(ConnectionType::H1(ref conn)) => {
+                    let proxy = conn.proxy().cloned();
+                    let (head, framed) =
+                        h1proto::open_tunnel(self, head.into(), proxy).await?;
                     Ok((head, framed))
                 }
                 Connection::Tls(ConnectionType::H1(ref _conn)) => {
-                    let (head, framed) = h1proto::open_tunnel(self, head.into()).await?;
+                    let (head, framed) =
+                        h1proto::open_tunnel(self, head.into(), None).await?;
                     Ok((head, framed))
                 }
                 Connection::Tls(ConnectionType::H2(mut conn)) => {
@@ -1,13 +1,15 @@
 use std::{
     fmt,
     future::Future,
-    net::IpAddr,
+    io,
+    net::{IpAddr, SocketAddr},
     pin::Pin,
     rc::Rc,
     task::{Context, Poll},
     time::Duration,
 };
 
+use actix_codec::ReadBuf;
 use actix_rt::{
     net::{ActixStream, TcpStream},
     time::{sleep, Sleep},
@@ -15,16 +17,20 @@ use actix_rt::{
 use actix_service::Service;
 use actix_tls::connect::{
     new_connector, Connect as TcpConnect, ConnectError as TcpConnectError,
-    Connection as TcpConnection, Resolver,
+    Connection as TcpConnection, Resolve, Resolver,
 };
+use actix_utils::future::poll_fn;
+use bytes::BytesMut;
 use futures_core::{future::LocalBoxFuture, ready};
-use http::Uri;
+use http::{StatusCode, Uri};
 use pin_project::pin_project;
 
 use super::config::ConnectorConfig;
 use super::connection::{Connection, ConnectionIo};
+use super::dns::DnsConfig;
 use super::error::ConnectError;
 use super::pool::ConnectionPool;
+use super::proxy::{self, Proxy, ProxyConfig};
 use super::Connect;
 use super::Protocol;
 
@@ -60,6 +66,9 @@ pub struct Connector<T> {
     config: ConnectorConfig,
     #[allow(dead_code)]
     ssl: SslConnector,
+    proxy: ProxyConfig,
+    /// Resolution of host names before connecting; `None` when custom connectors resolve them.
+    dns: Option<DnsConfig>,
 }
 
 impl Connector<()> {
@@ -75,6 +84,8 @@ impl Connector<()> {
             ssl: Self::build_ssl(vec![b"h2".to_vec(), b"http/1.1".to_vec()]),
             connector: new_connector(resolver::resolver()),
             config: ConnectorConfig::default(),
+            proxy: ProxyConfig::default(),
+            dns: Some(DnsConfig::default()),
         }
     }
 
@@ -117,6 +128,11 @@ impl Connector<()> {
 
 impl<S> Connector<S> {
     /// Use custom connector.
+    ///
+    /// Host names are passed to the custom connector to resolve, unless host resolution is
+    /// configured afterwards with [`resolver`](Self::resolver), [`resolve`](Self::resolve),
+    /// [`happy_eyeballs_delay`](Self::happy_eyeballs_delay) or
+    /// [`address_timeout`](Self::address_timeout).
     pub fn connector<S1, Io1>(self, connector: S1) -> Connector<S1>
     where
         Io1: ActixStream + fmt::Debug + 'static,
@@ -130,6 +146,8 @@ impl<S> Connector<S> {
             connector,
             config: self.config,
             ssl: self.ssl,
+            proxy: self.proxy,
+            dns: None,
         }
     }
 }
@@ -261,6 +279,94 @@ where
         self
     }
 
+    /// Use a custom resolver to look up the addresses of hosts.
+    ///
+    /// The resolver is also used to look up the addresses of proxies.
+    pub fn resolver(mut self, resolver: impl Resolve + 'static) -> Self {
+        self.dns_mut().set_resolver(Resolver::new_custom(resolver));
+        self
+    }
+
+    /// Connect to `addr` when connecting to `port` of `host`, instead of resolving it.
+    ///
+    /// Connections to other ports of `host` are resolved as usual. Calling this again for the
+    /// same host and port adds another address to try.
+    pub fn resolve(mut self, host: &str, port: u16, addr: SocketAddr) -> Self {
+        self.dns_mut().add_host(host, port, addr);
+        self
+    }
+
+    /// Set the delay before the next address of a host is tried while connecting to the previous
+    /// one is still pending.
+    ///
+    /// Hosts with several addresses are connected to "Happy Eyeballs" style ([RFC 8305]):
+    /// addresses are tried alternating between IPv6 and IPv4, and the first connection
+    /// established is used. Set to 250 milliseconds by default.
+    ///
+    /// [RFC 8305]: https://datatracker.ietf.org/doc/html/rfc8305
+    pub fn happy_eyeballs_delay(mut self, delay: Duration) -> Self {
+        self.dns_mut().set_attempt_delay(delay);
+        self
+    }
+
+    /// Set the max time to connect to each address of a host. The next address is tried when it
+    /// is reached.
+    ///
+    /// There is no limit per address by default; the overall [`timeout`](Self::timeout) applies.
+    pub fn address_timeout(mut self, timeout: Duration) -> Self {
+        self.dns_mut().set_attempt_timeout(timeout);
+        self
+    }
+
+    fn dns_mut(&mut self) -> &mut DnsConfig {
+        self.dns.get_or_insert_with(DnsConfig::default)
+    }
+
+    /// Route connections to both HTTP and HTTPS hosts through `proxy`.
+    ///
+    /// Plain HTTP requests are forwarded by the proxy, while connections to HTTPS hosts are
+    /// tunneled through it with the `CONNECT` method. No proxy is used by default.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy.set_http(Some(proxy.clone()));
+        self.proxy.set_https(Some(proxy));
+        self
+    }
+
+    /// Route connections to HTTP hosts through `proxy`.
+    pub fn http_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy.set_http(Some(proxy));
+        self
+    }
+
+    /// Route connections to HTTPS hosts through `proxy`.
+    pub fn https_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy.set_https(Some(proxy));
+        self
+    }
+
+    /// Set hosts that are connected to directly instead of through a proxy.
+    ///
+    /// Hosts match themselves and their subdomains, eg. `example.com` matches `example.com` and
+    /// `api.example.com`. A single `*` disables proxies for all hosts.
+    pub fn no_proxy<I, H>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = H>,
+        H: AsRef<str>,
+    {
+        self.proxy.set_no_proxy(hosts);
+        self
+    }
+
+    /// Read proxy configuration from the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment
+    /// variables, or their lowercase forms. Replaces any proxies set before.
+    ///
+    /// `NO_PROXY` is a comma separated list of hosts, as accepted by
+    /// [`no_proxy`](Self::no_proxy).
+    pub fn proxy_from_env(mut self) -> Self {
+        self.proxy = ProxyConfig::from_env();
+        self
+    }
+
     /// Finish configuration process and create connector service.
     /// The Connector builder always concludes by calling `finish()` last in
     /// its combinator chain.
@@ -268,8 +374,12 @@ where
         let local_address = self.config.local_address;
         let timeout = self.config.timeout;
 
-        let tcp_service_inner =
-            TcpConnectorInnerService::new(self.connector, timeout, local_address);
+        let tcp_service_inner = TcpConnectorInnerService::new(
+            self.connector,
+            timeout,
+            local_address,
+            self.dns.map(Rc::new),
+        );
 
         #[allow(clippy::redundant_clone)]
         let tcp_service = TcpConnectorService {
@@ -353,7 +463,11 @@ where
         let tls_pool = tls_service
             .map(move |tls_service| ConnectionPool::new(tls_service, tls_config));
 
-        ConnectorServicePriv { tcp_pool, tls_pool }
+        ConnectorServicePriv {
+            tcp_pool,
+            tls_pool,
+            proxy: self.proxy,
+        }
     }
 }
 
@@ -512,6 +626,7 @@ pub struct TcpConnectorInnerService<S: Clone> {
     service: S,
     timeout: Duration,
     local_address: Option<std::net::IpAddr>,
+    dns: Option<Rc<DnsConfig>>,
 }
 
 impl<S: Clone> TcpConnectorInnerService<S> {
@@ -519,11 +634,13 @@ impl<S: Clone> TcpConnectorInnerService<S> {
         service: S,
         timeout: Duration,
         local_address: Option<std::net::IpAddr>,
+        dns: Option<Rc<DnsConfig>>,
     ) -> Self {
         Self {
             service,
             timeout,
             local_address,
+            dns,
         }
     }
 }
@@ -536,47 +653,183 @@ where
             Error = TcpConnectError,
         > + Clone
         + 'static,
+    Io: ConnectionIo,
 {
     type Response = S::Response;
     type Error = ConnectError;
-    type Future = TcpConnectorInnerFuture<S::Future>;
+    type Future = TcpConnectorInnerFuture<
+        LocalBoxFuture<'static, Result<TcpConnection<Uri, Io>, TcpConnectError>>,
+        Io,
+    >;
 
     actix_service::forward_ready!(service);
 
     fn call(&self, req: Connect) -> Self::Future {
-        let mut req = TcpConnect::new(req.uri).set_addr(req.addr);
+        let (uri, tunnel) = match req.proxy {
+            Some(proxy) => {
+                let uri = proxy.uri();
+
+                let tunnel = if proxy::is_tunneled(&req.uri) {
+                    Some((req.uri, proxy))
+                } else {
+                    None
+                };
 
-        if let Some(local_addr) = self.local_address {
-            req = req.set_local_addr(local_addr);
-        }
+                (uri, tunnel)
+            }
+            None => (req.uri, None),
+        };
+
+        let fut = match (&self.dns, req.addr) {
+            (Some(dns), None) => {
+                dns.clone()
+                    .connect(self.service.clone(), uri, self.local_address)
+            }
+            (_, addr) => {
+                let mut tcp_req = TcpConnect::new(uri).set_addr(addr);
+
+                if let Some(local_addr) = self.local_address {
+                    tcp_req = tcp_req.set_local_addr(local_addr);
+                }
+
+                Box::pin(self.service.call(tcp_req))
+            }
+        };
 
         TcpConnectorInnerFuture {
-            fut: self.service.call(req),
+            fut,
+            tunnel,
+            tunnel_fut: None,
             timeout: sleep(self.timeout),
         }
     }
 }
 
+type TunnelFuture<Io> =
+    LocalBoxFuture<'static, Result<TcpConnection<Uri, Io>, ConnectError>>;
+
 #[pin_project]
-pub struct TcpConnectorInnerFuture<Fut> {
+pub struct TcpConnectorInnerFuture<Fut, Io> {
     #[pin]
     fut: Fut,
+    /// target and proxy of a tunnel to open once connected to the proxy.
+    tunnel: Option<(Uri, Proxy)>,
+    tunnel_fut: Option<TunnelFuture<Io>>,
     #[pin]
     timeout: Sleep,
 }
 
-impl<Fut, Io> Future for TcpConnectorInnerFuture<Fut>
+impl<Fut, Io> Future for TcpConnectorInnerFuture<Fut, Io>
 where
     Fut: Future<Output = Result<TcpConnection<Uri, Io>, TcpConnectError>>,
+    Io: ConnectionIo,
 {
     type Output = Result<TcpConnection<Uri, Io>, ConnectError>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
-        match this.fut.poll(cx) {
-            Poll::Ready(res) => Poll::Ready(res.map_err(ConnectError::from)),
-            Poll::Pending => this.timeout.poll(cx).map(|_| Err(ConnectError::Timeout)),
+
+        if this.tunnel_fut.is_none() {
+            if let Poll::Ready(res) = this.fut.poll(cx) {
+                let conn = res?;
+
+                match this.tunnel.take() {
+                    Some((target, proxy)) => {
+                        *this.tunnel_fut = Some(Box::pin(tunnel(conn, target, proxy)));
+                    }
+                    None => return Poll::Ready(Ok(conn)),
+                }
+            }
         }
+
+        if let Some(fut) = this.tunnel_fut.as_mut() {
+            if let Poll::Ready(res) = fut.as_mut().poll(cx) {
+                return Poll::Ready(res);
+            }
+        }
+
+        this.timeout.poll(cx).map(|_| Err(ConnectError::Timeout))
+    }
+}
+
+/// Max size of a proxy's response to a `CONNECT` request.
+const MAX_TUNNEL_RESPONSE_SIZE: usize = 8192;
+
+/// Opens a tunnel to `target` through the proxy `conn` is connected to.
+async fn tunnel<Io: ConnectionIo>(
+    conn: TcpConnection<Uri, Io>,
+    target: Uri,
+    proxy: Proxy,
+) -> Result<TcpConnection<Uri, Io>, ConnectError> {
+    let mut io = conn.into_parts().0;
+
+    let port = target
+        .port_u16()
+        .unwrap_or_else(|| match target.scheme_str() {
+            Some("https") | Some("wss") => 443,
+            _ => 80,
+        });
+    let authority = format!(
+        "{}:{}",
+        target.host().ok_or(ConnectError::Unresolved)?,
+        port
+    );
+
+    let mut buf = BytesMut::with_capacity(256);
+    buf.extend_from_slice(
+        format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority).as_bytes(),
+    );
+    if let Some(value) = proxy.authorization() {
+        buf.extend_from_slice(b"Proxy-Authorization: ");
+        buf.extend_from_slice(value.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+    }
+    buf.extend_from_slice(b"\r\n");
+
+    let mut written = 0;
+    while written < buf.len() {
+        match poll_fn(|cx| Pin::new(&mut io).poll_write(cx, &buf[written..])).await? {
+            0 => return Err(io::Error::from(io::ErrorKind::WriteZero).into()),
+            n => written += n,
+        }
+    }
+    poll_fn(|cx| Pin::new(&mut io).poll_flush(cx)).await?;
+
+    // the proxy sends nothing after its response until the TLS handshake starts
+    buf.clear();
+    let status = loop {
+        let mut chunk = [0; 1024];
+        let mut read_buf = ReadBuf::new(&mut chunk);
+        poll_fn(|cx| Pin::new(&mut io).poll_read(cx, &mut read_buf)).await?;
+
+        if read_buf.filled().is_empty() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        buf.extend_from_slice(read_buf.filled());
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut res = httparse::Response::new(&mut headers);
+
+        match res.parse(&buf) {
+            Ok(httparse::Status::Complete(_)) => break res.code.unwrap_or_default(),
+            Ok(httparse::Status::Partial) if buf.len() < MAX_TUNNEL_RESPONSE_SIZE => {}
+            Ok(httparse::Status::Partial) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "proxy response to CONNECT request is too large",
+                )
+                .into())
+            }
+            Err(err) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, err).into())
+            }
+        }
+    };
+
+    match StatusCode::from_u16(status) {
+        Ok(status) if status.is_success() => Ok(TcpConnection::new(io, target)),
+        Ok(status) => Err(ConnectError::ProxyTunnel(status)),
+        Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err).into()),
     }
 }
 
@@ -607,6 +860,7 @@ where
 {
     tcp_pool: ConnectionPool<S1, Io1>,
     tls_pool: Option<ConnectionPool<S2, Io2>>,
+    proxy: ProxyConfig,
 }
 
 impl<S1, S2, Io1, Io2> Service<Connect> for ConnectorServicePriv<S1, S2, Io1, Io2>
@@ -632,7 +886,11 @@ where
         Poll::Ready(Ok(()))
     }
 
-    fn call(&self, req: Connect) -> Self::Future {
+    fn call(&self, mut req: Connect) -> Self::Future {
+        if req.proxy.is_none() && req.addr.is_none() {
+            req.proxy = self.proxy.select(&req.uri).cloned();
+        }
+
         match req.uri.scheme_str() {
             Some("https") | Some("wss") => match self.tls_pool {
                 None => ConnectorServiceFuture::SslIsNotSupported,
@@ -685,16 +943,16 @@ where
 }
 
 #[cfg(not(feature = "trust-dns"))]
-mod resolver {
+pub(super) mod resolver {
     use super::*;
 
-    pub(super) fn resolver() -> Resolver {
+    pub(crate) fn resolver() -> Resolver {
         Resolver::Default
     }
 }
 
 #[cfg(feature = "trust-dns")]
-mod resolver {
+pub(super) mod resolver {
     use std::{cell::RefCell, net::SocketAddr};
 
     use actix_tls::connect::Resolve;
@@ -707,7 +965,7 @@ mod resolver {
 
     use super::*;
 
-    pub(super) fn resolver() -> Resolver {
+    pub(crate) fn resolver() -> Resolver {
         // new type for impl Resolve trait for TokioAsyncResolver.
         struct TrustDnsResolver(TokioAsyncResolver);
 
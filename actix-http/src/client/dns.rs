@@ -0,0 +1,364 @@
+//! Host name resolution and connection racing for the default TCP connector.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    io,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::time::{sleep, timeout, Instant, Sleep};
+use actix_service::Service;
+use actix_tls::connect::{
+    Connect as TcpConnect, ConnectError as TcpConnectError, Connection as TcpConnection,
+    Resolver,
+};
+use futures_core::future::LocalBoxFuture;
+use http::Uri;
+
+use super::connector::resolver::resolver;
+
+/// Delay before connecting to the next address while an attempt is pending, as recommended by
+/// [RFC 8305 §8](https://datatracker.ietf.org/doc/html/rfc8305#section-8).
+const DEFAULT_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Resolves host names and connects to their addresses.
+///
+/// Addresses are tried alternating between IPv6 and IPv4, starting with the family of the first
+/// resolved address. An attempt is started whenever the previous one fails or has been pending
+/// for the attempt delay, and the first connection established is used ("Happy Eyeballs",
+/// [RFC 8305]).
+///
+/// [RFC 8305]: https://datatracker.ietf.org/doc/html/rfc8305
+#[derive(Clone)]
+pub(crate) struct DnsConfig {
+    resolver: Resolver,
+    hosts: HashMap<(String, u16), Vec<SocketAddr>>,
+    attempt_delay: Duration,
+    attempt_timeout: Option<Duration>,
+}
+
+impl Default for DnsConfig {
+    fn default() -> Self {
+        DnsConfig {
+            resolver: resolver(),
+            hosts: HashMap::new(),
+            attempt_delay: DEFAULT_ATTEMPT_DELAY,
+            attempt_timeout: None,
+        }
+    }
+}
+
+impl DnsConfig {
+    pub(crate) fn set_resolver(&mut self, resolver: Resolver) {
+        self.resolver = resolver;
+    }
+
+    /// Connects to `addr` instead of resolving `host` for connections to `port`. Adds to the
+    /// addresses set before.
+    pub(crate) fn add_host(&mut self, host: &str, port: u16, addr: SocketAddr) {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        self.hosts
+            .entry((host.to_ascii_lowercase(), port))
+            .or_default()
+            .push(addr);
+    }
+
+    pub(crate) fn set_attempt_delay(&mut self, delay: Duration) {
+        self.attempt_delay = delay;
+    }
+
+    pub(crate) fn set_attempt_timeout(&mut self, timeout: Duration) {
+        self.attempt_timeout = Some(timeout);
+    }
+
+    /// Resolves the host of `uri` and connects to it with `service`.
+    pub(crate) fn connect<S, Io>(
+        self: Rc<Self>,
+        service: S,
+        uri: Uri,
+        local_addr: Option<IpAddr>,
+    ) -> LocalBoxFuture<'static, Result<TcpConnection<Uri, Io>, TcpConnectError>>
+    where
+        S: Service<
+                TcpConnect<Uri>,
+                Response = TcpConnection<Uri, Io>,
+                Error = TcpConnectError,
+            > + 'static,
+        Io: 'static,
+    {
+        Box::pin(async move {
+            let addrs = self.lookup(&uri).await?;
+            let attempt_delay = self.attempt_delay;
+
+            let connect = move |addr| {
+                let mut req = TcpConnect::new(uri.clone()).set_addr(Some(addr));
+
+                if let Some(local_addr) = local_addr {
+                    req = req.set_local_addr(local_addr);
+                }
+
+                let fut = service.call(req);
+
+                match self.attempt_timeout {
+                    Some(dur) => Box::pin(async move {
+                        timeout(dur, fut).await.unwrap_or_else(|_| {
+                            Err(TcpConnectError::Io(io::ErrorKind::TimedOut.into()))
+                        })
+                    }) as LocalBoxFuture<'static, _>,
+                    None => Box::pin(fut),
+                }
+            };
+
+            RacingConnect {
+                addrs: interleave(addrs),
+                attempts: Vec::new(),
+                connect: Box::new(connect),
+                delay: Box::pin(sleep(attempt_delay)),
+                attempt_delay,
+                err: None,
+            }
+            .await
+        })
+    }
+
+    /// Returns the addresses of the host and port of `uri`, from the overrides or the resolver.
+    pub(crate) async fn lookup(
+        &self,
+        uri: &Uri,
+    ) -> Result<Vec<SocketAddr>, TcpConnectError> {
+        let host = uri
+            .host()
+            .unwrap_or("")
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase();
+        let port = uri.port_u16().unwrap_or_else(|| match uri.scheme_str() {
+            Some("https") | Some("wss") => 443,
+            _ => 80,
+        });
+
+        if let Some(addrs) = self.hosts.get(&(host, port)) {
+            return Ok(addrs.clone());
+        }
+
+        let req = self.resolver.call(TcpConnect::new(uri.clone())).await?;
+        Ok(req.addrs().collect())
+    }
+}
+
+/// Orders addresses alternating between families, starting with the family of the first one.
+fn interleave(addrs: Vec<SocketAddr>) -> VecDeque<SocketAddr> {
+    let first_ipv6 = addrs.first().map_or(false, SocketAddr::is_ipv6);
+
+    let (mut first, mut second): (VecDeque<_>, VecDeque<_>) = addrs
+        .into_iter()
+        .partition(|addr| addr.is_ipv6() == first_ipv6);
+
+    let mut addrs = VecDeque::with_capacity(first.len() + second.len());
+
+    while !first.is_empty() || !second.is_empty() {
+        addrs.extend(first.pop_front());
+        addrs.extend(second.pop_front());
+    }
+
+    addrs
+}
+
+type Attempt<Io> =
+    LocalBoxFuture<'static, Result<TcpConnection<Uri, Io>, TcpConnectError>>;
+
+/// Races connection attempts to a list of addresses.
+struct RacingConnect<Io> {
+    addrs: VecDeque<SocketAddr>,
+    attempts: Vec<Attempt<Io>>,
+    connect: Box<dyn FnMut(SocketAddr) -> Attempt<Io>>,
+    delay: Pin<Box<Sleep>>,
+    attempt_delay: Duration,
+    /// Error of the last failed attempt.
+    err: Option<TcpConnectError>,
+}
+
+impl<Io> Future for RacingConnect<Io> {
+    type Output = Result<TcpConnection<Uri, Io>, TcpConnectError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        loop {
+            let mut failed = false;
+            let mut idx = 0;
+
+            while idx < this.attempts.len() {
+                match this.attempts[idx].as_mut().poll(cx) {
+                    Poll::Ready(Ok(conn)) => return Poll::Ready(Ok(conn)),
+                    Poll::Ready(Err(err)) => {
+                        drop(this.attempts.swap_remove(idx));
+                        this.err = Some(err);
+                        failed = true;
+                    }
+                    Poll::Pending => idx += 1,
+                }
+            }
+
+            if this.addrs.is_empty() {
+                if this.attempts.is_empty() {
+                    let err = this.err.take().unwrap_or(TcpConnectError::NoRecords);
+                    return Poll::Ready(Err(err));
+                }
+
+                return Poll::Pending;
+            }
+
+            // the next address is tried when an attempt fails or the delay has passed
+            if failed
+                || this.attempts.is_empty()
+                || this.delay.as_mut().poll(cx).is_ready()
+            {
+                let addr = this.addrs.pop_front().unwrap();
+                this.attempts.push((this.connect)(addr));
+
+                let deadline = Instant::now() + this.attempt_delay;
+                this.delay.as_mut().reset(deadline);
+                continue;
+            }
+
+            return Poll::Pending;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, net::TcpListener};
+
+    use actix_rt::net::TcpStream;
+    use actix_service::fn_service;
+
+    use super::*;
+
+    #[test]
+    fn test_interleave() {
+        let v4 = |n: u8| SocketAddr::from(([10, 0, 0, n], 80));
+        let v6 = |n: u16| SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, n], 80));
+
+        assert_eq!(
+            interleave(vec![v6(1), v6(2), v6(3), v4(1), v4(2)]),
+            vec![v6(1), v4(1), v6(2), v4(2), v6(3)]
+        );
+        assert_eq!(
+            interleave(vec![v4(1), v4(2), v6(1)]),
+            vec![v4(1), v6(1), v4(2)]
+        );
+        assert!(interleave(vec![]).is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_host_override() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut config = DnsConfig::default();
+        config.add_host("Example.Invalid", 80, addr);
+
+        let service = actix_tls::connect::default_connector();
+        let conn = Rc::new(config.clone())
+            .connect(service, Uri::from_static("http://example.invalid/"), None)
+            .await
+            .unwrap();
+
+        assert_eq!(conn.host(), "example.invalid");
+        assert_eq!(conn.peer_addr().unwrap(), addr);
+
+        // overrides only apply to their port
+        let service = actix_tls::connect::default_connector();
+        let res = Rc::new(config.clone())
+            .connect(service, Uri::from_static("https://example.invalid/"), None)
+            .await;
+        assert!(res.is_err());
+
+        config.add_host("example.invalid", 443, addr);
+
+        let service = actix_tls::connect::default_connector();
+        let conn = Rc::new(config)
+            .connect(service, Uri::from_static("https://example.invalid/"), None)
+            .await
+            .unwrap();
+        assert_eq!(conn.peer_addr().unwrap(), addr);
+    }
+
+    #[actix_rt::test]
+    async fn test_racing() {
+        // the first address never connects, the second fails and the third connects
+        let stalled = SocketAddr::from(([10, 0, 0, 1], 80));
+        let failed = SocketAddr::from(([10, 0, 0, 2], 80));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let attempts = Rc::new(RefCell::new(Vec::new()));
+
+        let service = {
+            let attempts = attempts.clone();
+
+            fn_service(move |req: TcpConnect<Uri>| {
+                let target = req.addrs().next().unwrap();
+                attempts.borrow_mut().push(target);
+
+                async move {
+                    if target == stalled {
+                        sleep(Duration::from_secs(60)).await;
+                    }
+
+                    if target == failed {
+                        return Err(TcpConnectError::Io(
+                            io::ErrorKind::ConnectionRefused.into(),
+                        ));
+                    }
+
+                    let io = TcpStream::connect(target).await.unwrap();
+                    Ok(TcpConnection::new(
+                        io,
+                        Uri::from_static("http://racing.invalid/"),
+                    ))
+                }
+            })
+        };
+
+        let mut config = DnsConfig::default();
+        config.add_host("racing.invalid", 80, stalled);
+        config.add_host("racing.invalid", 80, failed);
+        config.add_host("racing.invalid", 80, addr);
+        config.set_attempt_delay(Duration::from_millis(20));
+
+        let conn = Rc::new(config)
+            .connect(
+                service.clone(),
+                Uri::from_static("http://racing.invalid/"),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(conn.peer_addr().unwrap(), addr);
+        assert_eq!(*attempts.borrow(), vec![stalled, failed, addr]);
+
+        // attempts time out individually
+        attempts.borrow_mut().clear();
+
+        let mut config = DnsConfig::default();
+        config.add_host("racing.invalid", 80, stalled);
+        config.set_attempt_timeout(Duration::from_millis(20));
+
+        let res = Rc::new(config)
+            .connect(service, Uri::from_static("http://racing.invalid/"), None)
+            .await;
+        assert!(matches!(
+            res,
+            Err(TcpConnectError::Io(ref err)) if err.kind() == io::ErrorKind::TimedOut
+        ));
+    }
+}
@@ -48,6 +48,11 @@ pub enum ConnectError {
     /// Connection io error
     #[display(fmt = "{}", _0)]
     Io(io::Error),
+
+    /// Proxy refused to open a tunnel to the host
+    #[display(fmt = "Proxy responded to CONNECT request with {}", _0)]
+    #[from(ignore)]
+    ProxyTunnel(http::StatusCode),
 }
 
 impl std::error::Error for ConnectError {}
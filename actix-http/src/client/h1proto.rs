@@ -13,7 +13,7 @@ use futures_util::SinkExt as _;
 
 use crate::h1;
 use crate::http::{
-    header::{HeaderMap, IntoHeaderValue, EXPECT, HOST},
+    header::{HeaderMap, IntoHeaderValue, EXPECT, HOST, PROXY_AUTHORIZATION},
     StatusCode,
 };
 use crate::message::{RequestHeadType, ResponseHead};
@@ -22,12 +22,14 @@ use crate::{error::PayloadError, Error};
 
 use super::connection::{ConnectionIo, H1Connection};
 use super::error::{ConnectError, SendRequestError};
+use super::proxy::Proxy;
 use crate::body::{BodySize, MessageBody};
 
 pub(crate) async fn send_request<Io, B>(
     io: H1Connection<Io>,
     mut head: RequestHeadType,
     body: B,
+    proxy: Option<Proxy>,
 ) -> Result<(ResponseHead, Payload), SendRequestError>
 where
     Io: ConnectionIo,
@@ -62,7 +64,7 @@ where
     }
 
     // create Framed and prepare sending request
-    let mut framed = Framed::new(io, h1::ClientCodec::default());
+    let mut framed = Framed::new(io, client_codec(&mut head, proxy.as_ref()));
 
     // Check EXPECT header and enable expect handle flag accordingly.
     //
@@ -130,13 +132,14 @@ where
 
 pub(crate) async fn open_tunnel<Io>(
     io: Io,
-    head: RequestHeadType,
+    mut head: RequestHeadType,
+    proxy: Option<Proxy>,
 ) -> Result<(ResponseHead, Framed<Io, h1::ClientCodec>), SendRequestError>
 where
     Io: ConnectionIo,
 {
     // create Framed and send request.
-    let mut framed = Framed::new(io, h1::ClientCodec::default());
+    let mut framed = Framed::new(io, client_codec(&mut head, proxy.as_ref()));
     framed.send((head, BodySize::None).into()).await?;
 
     // read response head.
@@ -147,6 +150,30 @@ where
     Ok((head, framed))
 }
 
+/// Creates the codec for sending `head`, which is sent in absolute-form along with the proxy's
+/// credentials when the connection is to a forward proxy.
+fn client_codec(head: &mut RequestHeadType, proxy: Option<&Proxy>) -> h1::ClientCodec {
+    let mut codec = h1::ClientCodec::default();
+
+    if let Some(proxy) = proxy {
+        codec.set_absolute_form(true);
+
+        if let Some(value) = proxy.authorization() {
+            match head {
+                RequestHeadType::Owned(ref mut head) => {
+                    head.headers.insert(PROXY_AUTHORIZATION, value.clone());
+                }
+                RequestHeadType::Rc(_, ref mut extra_headers) => {
+                    let headers = extra_headers.get_or_insert(HeaderMap::new());
+                    headers.insert(PROXY_AUTHORIZATION, value.clone());
+                }
+            }
+        }
+    }
+
+    codec
+}
+
 /// send request body to the peer
 pub(crate) async fn send_body<Io, B>(
     body: B,
@@ -5,22 +5,48 @@ use http::Uri;
 mod config;
 mod connection;
 mod connector;
+mod dns;
 mod error;
 mod h1proto;
 mod h2proto;
 mod pool;
+mod proxy;
+mod socks5;
 
 pub use actix_tls::connect::{
     Connect as TcpConnect, ConnectError as TcpConnectError, Connection as TcpConnection,
+    Resolve,
 };
 
 pub use self::connection::{Connection, ConnectionIo};
 pub use self::connector::{Connector, ConnectorService};
 pub use self::error::{ConnectError, FreezeRequestError, InvalidUrl, SendRequestError};
+pub use self::proxy::Proxy;
+pub use self::socks5::Socks5Connector;
 pub use crate::Protocol;
 
 #[derive(Clone)]
 pub struct Connect {
     pub uri: Uri,
     pub addr: Option<std::net::SocketAddr>,
+
+    /// Proxy to connect through. When no `addr` is given, the proxy is chosen from the
+    /// connector's configuration.
+    proxy: Option<Proxy>,
+}
+
+impl Connect {
+    /// Constructs a request to connect to `uri`, or to `addr` if it is given.
+    pub fn new(uri: Uri, addr: Option<std::net::SocketAddr>) -> Self {
+        Connect {
+            uri,
+            addr,
+            proxy: None,
+        }
+    }
+
+    /// Returns the proxy the connection is made through.
+    pub fn proxy(&self) -> Option<&Proxy> {
+        self.proxy.as_ref()
+    }
 }
@@ -28,18 +28,19 @@ use super::connection::{
 };
 use super::error::ConnectError;
 use super::h2proto::handshake;
+use super::proxy::{self, Proxy};
 use super::Connect;
 use super::Protocol;
 
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub struct Key {
     authority: Authority,
-}
 
-impl From<Authority> for Key {
-    fn from(authority: Authority) -> Key {
-        Key { authority }
-    }
+    /// proxy the connection is routed through.
+    proxy: Option<Proxy>,
+
+    /// whether the connection is tunneled through the proxy rather than forwarded by it.
+    tunnel: bool,
 }
 
 #[doc(hidden)]
@@ -169,7 +170,11 @@ where
 
         Box::pin(async move {
             let key = if let Some(authority) = req.uri.authority() {
-                authority.clone().into()
+                Key {
+                    authority: authority.clone(),
+                    tunnel: req.proxy.is_some() && proxy::is_tunneled(&req.uri),
+                    proxy: req.proxy.clone(),
+                }
             } else {
                 return Err(ConnectError::Unresolved);
             };
@@ -350,6 +355,12 @@ where
 }
 
 impl<Io: ConnectionIo> Acquired<Io> {
+    /// Returns the proxy that forwards requests sent on the connection, unless the connection
+    /// is tunneled through it.
+    pub(super) fn proxy(&self) -> Option<&Proxy> {
+        self.key.proxy.as_ref().filter(|_| !self.key.tunnel)
+    }
+
     /// Close the IO.
     pub(super) fn close(&self, conn: ConnectionInnerType<Io>) {
         self.inner.close(conn);
@@ -470,10 +481,7 @@ mod test {
 
         let pool = super::ConnectionPool::new(connector, config);
 
-        let req = Connect {
-            uri: Uri::from_static("http://localhost"),
-            addr: None,
-        };
+        let req = Connect::new(Uri::from_static("http://localhost"), None);
 
         let conn = pool.call(req.clone()).await.unwrap();
 
@@ -510,10 +518,7 @@ mod test {
 
         let pool = super::ConnectionPool::new(connector, config);
 
-        let req = Connect {
-            uri: Uri::from_static("http://localhost"),
-            addr: None,
-        };
+        let req = Connect::new(Uri::from_static("http://localhost"), None);
 
         let conn = pool.call(req.clone()).await.unwrap();
         assert_eq!(1, generated_clone.get());
@@ -552,10 +557,7 @@ mod test {
 
         let pool = super::ConnectionPool::new(connector, config);
 
-        let req = Connect {
-            uri: Uri::from_static("http://localhost"),
-            addr: None,
-        };
+        let req = Connect::new(Uri::from_static("http://localhost"), None);
 
         let conn = pool.call(req.clone()).await.unwrap();
         assert_eq!(1, generated_clone.get());
@@ -591,10 +593,7 @@ mod test {
 
         let pool = super::ConnectionPool::new(connector, config);
 
-        let req = Connect {
-            uri: Uri::from_static("https://crates.io"),
-            addr: None,
-        };
+        let req = Connect::new(Uri::from_static("https://crates.io"), None);
 
         let conn = pool.call(req.clone()).await.unwrap();
         assert_eq!(1, generated_clone.get());
@@ -604,10 +603,7 @@ mod test {
         assert_eq!(1, generated_clone.get());
         release(conn);
 
-        let req = Connect {
-            uri: Uri::from_static("https://google.com"),
-            addr: None,
-        };
+        let req = Connect::new(Uri::from_static("https://google.com"), None);
 
         let conn = pool.call(req.clone()).await.unwrap();
         assert_eq!(2, generated_clone.get());
@@ -628,19 +624,13 @@ mod test {
 
         let pool = Rc::new(super::ConnectionPool::new(connector, config));
 
-        let req = Connect {
-            uri: Uri::from_static("https://crates.io"),
-            addr: None,
-        };
+        let req = Connect::new(Uri::from_static("https://crates.io"), None);
 
         let conn = pool.call(req.clone()).await.unwrap();
         assert_eq!(1, generated_clone.get());
         release(conn);
 
-        let req = Connect {
-            uri: Uri::from_static("https://google.com"),
-            addr: None,
-        };
+        let req = Connect::new(Uri::from_static("https://google.com"), None);
         let conn = pool.call(req.clone()).await.unwrap();
         assert_eq!(2, generated_clone.get());
         release(conn);
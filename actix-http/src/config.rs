@@ -8,7 +8,8 @@ use actix_rt::{
     task::JoinHandle,
     time::{interval, sleep_until, Instant, Sleep},
 };
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
+use h2::server::{Builder as H2Builder, Handshake as H2Handshake};
 use time::OffsetDateTime;
 
 /// "Sun, 06 Nov 1994 08:49:37 GMT".len()
@@ -41,6 +42,268 @@ impl From<Option<usize>> for KeepAlive {
     }
 }
 
+/// Limits applied when parsing HTTP/1 request heads and the trailer sections of chunked request
+/// bodies.
+///
+/// Requests exceeding the header count or head size limits are responded to with
+/// `431 Request Header Fields Too Large`; requests with a URI longer than the URI length limit
+/// are responded to with `414 URI Too Long`. Trailer sections exceeding the header count or
+/// trailer size limits fail the request body with a payload error.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RequestLimits {
+    max_headers: usize,
+    max_head_size: usize,
+    max_uri_len: usize,
+    max_trailer_size: Option<usize>,
+}
+
+impl RequestLimits {
+    /// Default maximum number of request headers.
+    pub const DEFAULT_MAX_HEADERS: usize = 96;
+
+    /// Default maximum size of request head (request line and headers) in bytes.
+    pub const DEFAULT_MAX_HEAD_SIZE: usize = 131_072;
+
+    /// Create limits with default values.
+    ///
+    /// By default requests may contain 96 headers and a 128KiB head. The URI length is only
+    /// bounded by the head size, and the trailer section is bounded like the head.
+    pub const fn new() -> Self {
+        Self {
+            max_headers: Self::DEFAULT_MAX_HEADERS,
+            max_head_size: Self::DEFAULT_MAX_HEAD_SIZE,
+            max_uri_len: usize::MAX,
+            max_trailer_size: None,
+        }
+    }
+
+    /// Set maximum number of headers in a request head.
+    ///
+    /// # Panics
+    /// Panics if `max_headers` is zero.
+    pub fn max_headers(mut self, max_headers: usize) -> Self {
+        assert!(max_headers > 0, "max_headers must be greater than zero");
+        self.max_headers = max_headers;
+        self
+    }
+
+    /// Set maximum size of a request head, in bytes.
+    ///
+    /// # Panics
+    /// Panics if `max_head_size` is zero.
+    pub fn max_head_size(mut self, max_head_size: usize) -> Self {
+        assert!(max_head_size > 0, "max_head_size must be greater than zero");
+        self.max_head_size = max_head_size;
+        self
+    }
+
+    /// Set maximum length of a request URI, in bytes.
+    ///
+    /// # Panics
+    /// Panics if `max_uri_len` is zero.
+    pub fn max_uri_len(mut self, max_uri_len: usize) -> Self {
+        assert!(max_uri_len > 0, "max_uri_len must be greater than zero");
+        self.max_uri_len = max_uri_len;
+        self
+    }
+
+    /// Set maximum size of the trailer section of a chunked request body, in bytes.
+    ///
+    /// By default the maximum head size is used.
+    ///
+    /// # Panics
+    /// Panics if `max_trailer_size` is zero.
+    pub fn max_trailer_size(mut self, max_trailer_size: usize) -> Self {
+        assert!(
+            max_trailer_size > 0,
+            "max_trailer_size must be greater than zero"
+        );
+        self.max_trailer_size = Some(max_trailer_size);
+        self
+    }
+
+    /// Returns maximum number of headers in a request head.
+    pub fn headers(&self) -> usize {
+        self.max_headers
+    }
+
+    /// Returns maximum size of a request head, in bytes.
+    pub fn head_size(&self) -> usize {
+        self.max_head_size
+    }
+
+    /// Returns maximum length of a request URI, in bytes.
+    pub fn uri_len(&self) -> usize {
+        self.max_uri_len
+    }
+
+    /// Returns maximum size of the trailer section of a chunked request body, in bytes.
+    pub fn trailer_size(&self) -> usize {
+        self.max_trailer_size.unwrap_or(self.max_head_size)
+    }
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// HTTP/2 connection settings.
+///
+/// Unset values use the defaults of the underlying `h2` implementation. Keep-alive PINGs are
+/// disabled by default.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct H2Config {
+    initial_window_size: Option<u32>,
+    initial_connection_window_size: Option<u32>,
+    max_concurrent_streams: Option<u32>,
+    max_frame_size: Option<u32>,
+    max_header_list_size: Option<u32>,
+    keep_alive_interval: Option<Duration>,
+    keep_alive_timeout: Duration,
+}
+
+impl H2Config {
+    /// Largest allowed flow control window size.
+    const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+
+    /// Create settings with default values.
+    pub const fn new() -> Self {
+        Self {
+            initial_window_size: None,
+            initial_connection_window_size: None,
+            max_concurrent_streams: None,
+            max_frame_size: None,
+            max_header_list_size: None,
+            keep_alive_interval: None,
+            keep_alive_timeout: Duration::from_secs(20),
+        }
+    }
+
+    /// Set initial flow control window size of each stream, in bytes.
+    ///
+    /// By default the window is 65,535 bytes.
+    ///
+    /// # Panics
+    /// Panics if `size` is larger than 2^31 - 1.
+    pub fn initial_window_size(mut self, size: u32) -> Self {
+        assert!(
+            size <= Self::MAX_WINDOW_SIZE,
+            "initial_window_size must not be larger than 2^31 - 1"
+        );
+        self.initial_window_size = Some(size);
+        self
+    }
+
+    /// Set initial flow control window size of a connection, in bytes.
+    ///
+    /// By default the window is 65,535 bytes.
+    ///
+    /// # Panics
+    /// Panics if `size` is larger than 2^31 - 1.
+    pub fn initial_connection_window_size(mut self, size: u32) -> Self {
+        assert!(
+            size <= Self::MAX_WINDOW_SIZE,
+            "initial_connection_window_size must not be larger than 2^31 - 1"
+        );
+        self.initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Set maximum number of concurrent streams a client may open on a connection.
+    ///
+    /// By default the number of concurrent streams is not limited.
+    pub fn max_concurrent_streams(mut self, max: u32) -> Self {
+        self.max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Set maximum size of a frame payload the server is willing to receive, in bytes.
+    ///
+    /// By default frames are limited to 16,384 bytes.
+    ///
+    /// # Panics
+    /// Panics if `max` is not within 16,384 and 16,777,215 (inclusive).
+    pub fn max_frame_size(mut self, max: u32) -> Self {
+        assert!(
+            (16_384..=16_777_215).contains(&max),
+            "max_frame_size must be within 16,384 and 16,777,215"
+        );
+        self.max_frame_size = Some(max);
+        self
+    }
+
+    /// Set maximum size of a request's header list the server is willing to accept, in bytes.
+    ///
+    /// The size is calculated as defined by RFC 7540 §6.5.2, counting 32 bytes of overhead per
+    /// header. By default the header list size is limited to 16MiB.
+    pub fn max_header_list_size(mut self, max: u32) -> Self {
+        self.max_header_list_size = Some(max);
+        self
+    }
+
+    /// Set interval at which keep-alive PING frames are sent to the client.
+    ///
+    /// When enabled, a PING frame is sent after each `interval` and the connection is closed if
+    /// the client does not acknowledge it within the [keep-alive timeout](Self::keep_alive_timeout).
+    /// This allows detecting dead connections that are held open by intermediaries.
+    ///
+    /// By default keep-alive PINGs are disabled.
+    pub fn keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Set time to wait for a keep-alive PING acknowledgement before closing the connection.
+    ///
+    /// Only takes effect if a [keep-alive interval](Self::keep_alive_interval) is set.
+    ///
+    /// By default the timeout is 20 seconds.
+    pub fn keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.keep_alive_timeout = timeout;
+        self
+    }
+
+    /// Returns keep-alive PING interval and acknowledgement timeout, if enabled.
+    pub(crate) fn keep_alive(&self) -> Option<(Duration, Duration)> {
+        self.keep_alive_interval
+            .map(|interval| (interval, self.keep_alive_timeout))
+    }
+
+    /// Start HTTP/2 handshake on `io` using these settings.
+    pub(crate) fn handshake<T>(&self, io: T) -> H2Handshake<T, Bytes>
+    where
+        T: actix_codec::AsyncRead + actix_codec::AsyncWrite + Unpin,
+    {
+        let mut builder = H2Builder::new();
+
+        if let Some(size) = self.initial_window_size {
+            builder.initial_window_size(size);
+        }
+        if let Some(size) = self.initial_connection_window_size {
+            builder.initial_connection_window_size(size);
+        }
+        if let Some(max) = self.max_concurrent_streams {
+            builder.max_concurrent_streams(max);
+        }
+        if let Some(max) = self.max_frame_size {
+            builder.max_frame_size(max);
+        }
+        if let Some(max) = self.max_header_list_size {
+            builder.max_header_list_size(max);
+        }
+
+        builder.handshake(io)
+    }
+}
+
+impl Default for H2Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Http service configuration
 pub struct ServiceConfig(Rc<Inner>);
 
@@ -51,6 +314,8 @@ struct Inner {
     ka_enabled: bool,
     secure: bool,
     local_addr: Option<std::net::SocketAddr>,
+    limits: RequestLimits,
+    h2: H2Config,
     date_service: DateService,
 }
 
@@ -93,10 +358,32 @@ impl ServiceConfig {
             client_disconnect,
             secure,
             local_addr,
+            limits: RequestLimits::default(),
+            h2: H2Config::default(),
             date_service: DateService::new(),
         }))
     }
 
+    /// Set limits applied when parsing request heads.
+    ///
+    /// Only used while building a config, before it is shared with services.
+    pub(crate) fn request_limits(mut self, limits: RequestLimits) -> Self {
+        self.inner_mut().limits = limits;
+        self
+    }
+
+    /// Set HTTP/2 connection settings.
+    ///
+    /// Only used while building a config, before it is shared with services.
+    pub(crate) fn h2(mut self, h2: H2Config) -> Self {
+        self.inner_mut().h2 = h2;
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.0).expect("ServiceConfig is not shared while it is built")
+    }
+
     /// Returns true if connection is secure (HTTPS)
     #[inline]
     pub fn secure(&self) -> bool {
@@ -109,6 +396,18 @@ impl ServiceConfig {
         self.0.local_addr
     }
 
+    /// Returns limits applied when parsing request heads.
+    #[inline]
+    pub fn limits(&self) -> &RequestLimits {
+        &self.0.limits
+    }
+
+    /// Returns HTTP/2 connection settings.
+    #[inline]
+    pub fn h2_config(&self) -> &H2Config {
+        &self.0.h2
+    }
+
     /// Keep alive duration if configured.
     #[inline]
     pub fn keep_alive(&self) -> Option<Duration> {
@@ -328,6 +627,37 @@ mod tests {
 
     use actix_rt::task::yield_now;
 
+    #[test]
+    fn test_h2_config() {
+        let config = H2Config::new();
+        assert_eq!(config, H2Config::default());
+        assert_eq!(config.keep_alive(), None);
+
+        let config = config.keep_alive_interval(Duration::from_secs(10));
+        assert_eq!(
+            config.keep_alive(),
+            Some((Duration::from_secs(10), Duration::from_secs(20)))
+        );
+
+        let config = config.keep_alive_timeout(Duration::from_secs(5));
+        assert_eq!(
+            config.keep_alive(),
+            Some((Duration::from_secs(10), Duration::from_secs(5)))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_h2_config_max_frame_size_too_small() {
+        let _ = H2Config::new().max_frame_size(1024);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_h2_config_window_size_too_large() {
+        let _ = H2Config::new().initial_window_size(1 << 31);
+    }
+
     #[actix_rt::test]
     async fn test_date_service_update() {
         let settings = ServiceConfig::new(KeepAlive::Os, 0, 0, false, None);
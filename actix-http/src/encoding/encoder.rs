@@ -26,7 +26,7 @@ use zstd::stream::write::Encoder as ZstdEncoder;
 use crate::{
     body::{Body, BodySize, BoxAnyBody, MessageBody, ResponseBody},
     http::{
-        header::{ContentEncoding, CONTENT_ENCODING},
+        header::{ContentEncoding, HeaderMap, CONTENT_ENCODING},
         HeaderValue, StatusCode,
     },
     Error, ResponseHead,
@@ -146,6 +146,14 @@ where
             }
         }
     }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        match self.project() {
+            EncoderBodyProj::Bytes(_) => None,
+            EncoderBodyProj::Stream(b) => b.take_trailers(),
+            EncoderBodyProj::BoxedStream(ref mut b) => b.as_pin_mut().take_trailers(),
+        }
+    }
 }
 
 impl<B> MessageBody for Encoder<B>
@@ -229,6 +237,9 @@ where
             }
         }
     }
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        self.project().body.take_trailers()
+    }
 }
 
 fn update_head(encoding: ContentEncoding, head: &mut ResponseHead) {
@@ -177,6 +177,10 @@ pub enum ParseError {
     #[display(fmt = "Message head is too large")]
     TooLarge,
 
+    /// A request URI is longer than the configured limit.
+    #[display(fmt = "URI is too long")]
+    UriTooLong,
+
     /// A message reached EOF, but is not complete.
     #[display(fmt = "Message is incomplete")]
     Incomplete,
@@ -366,6 +370,10 @@ pub enum DispatchError {
     #[display(fmt = "The first request did not complete within the specified timeout")]
     SlowRequestTimeout,
 
+    /// HTTP/2 keep-alive PING was not acknowledged within the specified timeout.
+    #[display(fmt = "HTTP/2 keep-alive PING was not acknowledged in time")]
+    H2KeepAliveTimeout,
+
     /// Disconnect timeout. Makes sense for ssl streams.
     #[display(fmt = "Connection shutdown timeout")]
     DisconnectTimeout,
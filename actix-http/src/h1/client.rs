@@ -94,6 +94,12 @@ impl ClientCodec {
         }
     }
 
+    /// Send request targets in absolute-form, as required when sending requests to a forward
+    /// proxy.
+    pub(crate) fn set_absolute_form(&mut self, val: bool) {
+        self.inner.encoder.absolute_form = val;
+    }
+
     /// Convert message codec to a payload codec
     pub fn into_payload_codec(self) -> ClientPayloadCodec {
         ClientPayloadCodec { inner: self.inner }
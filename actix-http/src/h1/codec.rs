@@ -11,6 +11,7 @@ use super::{Message, MessageType};
 use crate::body::BodySize;
 use crate::config::ServiceConfig;
 use crate::error::ParseError;
+use crate::header::{self, HeaderMap};
 use crate::message::ConnectionType;
 use crate::request::Request;
 use crate::response::Response;
@@ -20,6 +21,7 @@ bitflags! {
         const HEAD              = 0b0000_0001;
         const KEEPALIVE_ENABLED = 0b0000_0010;
         const STREAM            = 0b0000_0100;
+        const TRAILERS          = 0b0000_1000;
     }
 }
 
@@ -30,6 +32,7 @@ pub struct Codec {
     payload: Option<PayloadDecoder>,
     version: Version,
     ctype: ConnectionType,
+    trailers: Option<HeaderMap>,
 
     // encoder part
     flags: Flags,
@@ -53,6 +56,7 @@ impl Codec {
     ///
     /// `keepalive_enabled` how response `connection` header get generated.
     pub fn new(config: ServiceConfig) -> Self {
+        let decoder = decoder::MessageDecoder::new(*config.limits());
         let flags = if config.keep_alive_enabled() {
             Flags::KEEPALIVE_ENABLED
         } else {
@@ -62,10 +66,11 @@ impl Codec {
         Codec {
             config,
             flags,
-            decoder: decoder::MessageDecoder::default(),
+            decoder,
             payload: None,
             version: Version::HTTP_11,
             ctype: ConnectionType::Close,
+            trailers: None,
             encoder: encoder::MessageEncoder::default(),
         }
     }
@@ -104,6 +109,31 @@ impl Codec {
     pub fn config(&self) -> &ServiceConfig {
         &self.config
     }
+
+    /// Take trailer fields sent after the last request's chunked payload.
+    ///
+    /// Trailers are only available once the payload has been fully decoded.
+    #[inline]
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take()
+    }
+
+    /// Encode end of response payload followed by given trailer fields.
+    ///
+    /// Trailers are only written when the response uses chunked transfer encoding and the
+    /// client indicated support for them with the `TE: trailers` request header; otherwise
+    /// they are discarded and a regular end of payload is written.
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        dst: &mut BytesMut,
+    ) -> io::Result<()> {
+        if self.flags.contains(Flags::TRAILERS) {
+            self.encoder.encode_trailers(trailers, dst)
+        } else {
+            self.encoder.encode_eof(dst)
+        }
+    }
 }
 
 impl Decoder for Codec {
@@ -115,6 +145,7 @@ impl Decoder for Codec {
             Ok(match payload.decode(src)? {
                 Some(PayloadItem::Chunk(chunk)) => Some(Message::Chunk(Some(chunk))),
                 Some(PayloadItem::Eof) => {
+                    self.trailers = payload.take_trailers();
                     self.payload.take();
                     Some(Message::Chunk(None))
                 }
@@ -123,6 +154,9 @@ impl Decoder for Codec {
         } else if let Some((req, payload)) = self.decoder.decode(src)? {
             let head = req.head();
             self.flags.set(Flags::HEAD, head.method == Method::HEAD);
+            self.flags
+                .set(Flags::TRAILERS, accepts_trailers(&head.headers));
+            self.trailers = None;
             self.version = head.version;
             self.ctype = head.connection_type();
             if self.ctype == ConnectionType::KeepAlive
@@ -193,6 +227,16 @@ impl Encoder<Message<(Response<()>, BodySize)>> for Codec {
     }
 }
 
+/// Check if `TE` request header contains the `trailers` token.
+fn accepts_trailers(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::TE)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .filter_map(|item| item.split(';').next())
+        .any(|token| token.trim().eq_ignore_ascii_case("trailers"))
+}
+
 #[cfg(test)]
 mod tests {
     use bytes::BytesMut;
@@ -237,4 +281,58 @@ mod tests {
         assert_eq!(*req.method(), Method::POST);
         assert!(req.chunked().unwrap());
     }
+
+    #[actix_rt::test]
+    async fn test_http_request_trailers() {
+        let mut codec = Codec::default();
+
+        let mut buf = BytesMut::from(
+            "POST /test HTTP/1.1\r\n\
+             transfer-encoding: chunked\r\n\r\n\
+             4\r\ndata\r\n0\r\nx-checksum: abc\r\n\r\n",
+        );
+        codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            codec.decode(&mut buf).unwrap().unwrap().chunk().as_ref(),
+            b"data"
+        );
+        assert!(codec.take_trailers().is_none());
+        assert!(codec.decode(&mut buf).unwrap().unwrap().eof());
+
+        let trailers = codec.take_trailers().unwrap();
+        assert_eq!(trailers.get("x-checksum").unwrap(), "abc");
+    }
+
+    #[actix_rt::test]
+    async fn test_encode_trailers() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            header::HeaderName::from_static("x-checksum"),
+            header::HeaderValue::from_static("abc"),
+        );
+
+        for &(te, expected) in &[
+            ("", &b"0\r\n\r\n"[..]),
+            ("gzip, trailers", b"0\r\nx-checksum: abc\r\n\r\n"),
+            ("Trailers;q=1", b"0\r\nx-checksum: abc\r\n\r\n"),
+        ] {
+            let mut codec = Codec::default();
+            let mut buf = BytesMut::from(
+                format!("GET /test HTTP/1.1\r\nte: {}\r\n\r\n", te).as_str(),
+            );
+            codec.decode(&mut buf).unwrap().unwrap();
+
+            let mut buf = BytesMut::new();
+            codec
+                .encode(
+                    Message::Item((Response::ok().drop_body(), BodySize::Stream)),
+                    &mut buf,
+                )
+                .unwrap();
+            buf.clear();
+
+            codec.encode_trailers(&trailers, &mut buf).unwrap();
+            assert_eq!(buf.as_ref(), expected);
+        }
+    }
 }
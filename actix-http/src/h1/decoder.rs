@@ -9,16 +9,30 @@ use http::header::{HeaderName, HeaderValue};
 use http::{header, Method, StatusCode, Uri, Version};
 use log::{debug, error, trace};
 
+use crate::config::RequestLimits;
 use crate::error::ParseError;
 use crate::header::HeaderMap;
 use crate::message::{ConnectionType, ResponseHead};
 use crate::request::Request;
 
 pub(crate) const MAX_BUFFER_SIZE: usize = 131_072;
-const MAX_HEADERS: usize = 96;
+const MAX_HEADERS: usize = RequestLimits::DEFAULT_MAX_HEADERS;
 
 /// Incoming message decoder
-pub(crate) struct MessageDecoder<T: MessageType>(PhantomData<T>);
+pub(crate) struct MessageDecoder<T: MessageType> {
+    limits: RequestLimits,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: MessageType> MessageDecoder<T> {
+    /// Create decoder enforcing given message head limits.
+    pub(crate) fn new(limits: RequestLimits) -> Self {
+        MessageDecoder {
+            limits,
+            _phantom: PhantomData,
+        }
+    }
+}
 
 #[derive(Debug)]
 /// Incoming request type
@@ -30,7 +44,7 @@ pub(crate) enum PayloadType {
 
 impl<T: MessageType> Default for MessageDecoder<T> {
     fn default() -> Self {
-        MessageDecoder(PhantomData)
+        MessageDecoder::new(RequestLimits::default())
     }
 }
 
@@ -39,7 +53,7 @@ impl<T: MessageType> Decoder for MessageDecoder<T> {
     type Error = ParseError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        T::decode(src)
+        T::decode(src, &self.limits)
     }
 }
 
@@ -56,12 +70,16 @@ pub(crate) trait MessageType: Sized {
 
     fn headers_mut(&mut self) -> &mut HeaderMap;
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError>;
+    fn decode(
+        src: &mut BytesMut,
+        limits: &RequestLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError>;
 
     fn set_headers(
         &mut self,
         slice: &Bytes,
         raw_headers: &[HeaderIndex],
+        limits: &RequestLimits,
     ) -> Result<PayloadLength, ParseError> {
         let mut ka = None;
         let mut has_upgrade_websocket = false;
@@ -152,7 +170,7 @@ pub(crate) trait MessageType: Sized {
         if chunked {
             // Chunked encoding
             Ok(PayloadLength::Payload(PayloadType::Payload(
-                PayloadDecoder::chunked(),
+                PayloadDecoder::chunked(limits.headers(), limits.trailer_size()),
             )))
         } else if has_upgrade_websocket {
             Ok(PayloadLength::UpgradeWebSocket)
@@ -182,30 +200,47 @@ impl MessageType for Request {
         &mut self.head_mut().headers
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
-        let mut headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
+    fn decode(
+        src: &mut BytesMut,
+        limits: &RequestLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
+        let mut headers = HeaderBuf::new(EMPTY_HEADER_INDEX, limits.headers());
 
         let (len, method, uri, ver, h_len) = {
-            let mut parsed: [httparse::Header<'_>; MAX_HEADERS] = EMPTY_HEADER_ARRAY;
+            let mut parsed = HeaderBuf::new(httparse::EMPTY_HEADER, limits.headers());
 
-            let mut req = httparse::Request::new(&mut parsed);
+            let mut req = httparse::Request::new(parsed.as_mut());
             match req.parse(src)? {
                 httparse::Status::Complete(len) => {
+                    if len > limits.head_size() {
+                        trace!("Request head size limit exceeded, closing");
+                        return Err(ParseError::TooLarge);
+                    }
+
+                    let path = req.path.unwrap();
+                    if path.len() > limits.uri_len() {
+                        trace!("Request URI length limit exceeded, closing");
+                        return Err(ParseError::UriTooLong);
+                    }
+
                     let method = Method::from_bytes(req.method.unwrap().as_bytes())
                         .map_err(|_| ParseError::Method)?;
-                    let uri = Uri::try_from(req.path.unwrap())?;
+                    let uri = Uri::try_from(path)?;
                     let version = if req.version.unwrap() == 1 {
                         Version::HTTP_11
                     } else {
                         Version::HTTP_10
                     };
-                    HeaderIndex::record(src, req.headers, &mut headers);
+                    HeaderIndex::record(src, req.headers, headers.as_mut());
 
                     (len, method, uri, version, req.headers.len())
                 }
                 httparse::Status::Partial => {
-                    return if src.len() >= MAX_BUFFER_SIZE {
-                        trace!("MAX_BUFFER_SIZE unprocessed data reached, closing");
+                    return if partial_uri_len(src) > limits.uri_len() {
+                        trace!("Request URI length limit exceeded, closing");
+                        Err(ParseError::UriTooLong)
+                    } else if src.len() >= limits.head_size() {
+                        trace!("Request head size limit reached, closing");
                         Err(ParseError::TooLarge)
                     } else {
                         // Return None to notify more read are needed for parsing request
@@ -218,7 +253,11 @@ impl MessageType for Request {
         let mut msg = Request::new();
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length = msg.set_headers(
+            &src.split_to(len).freeze(),
+            &headers.as_mut()[..h_len],
+            limits,
+        )?;
 
         // payload decoder
         let decoder = match length {
@@ -258,13 +297,16 @@ impl MessageType for ResponseHead {
         &mut self.headers
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
-        let mut headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
+    fn decode(
+        src: &mut BytesMut,
+        limits: &RequestLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
+        let mut headers = HeaderBuf::new(EMPTY_HEADER_INDEX, limits.headers());
 
         let (len, ver, status, h_len) = {
-            let mut parsed: [httparse::Header<'_>; MAX_HEADERS] = EMPTY_HEADER_ARRAY;
+            let mut parsed = HeaderBuf::new(httparse::EMPTY_HEADER, limits.headers());
 
-            let mut res = httparse::Response::new(&mut parsed);
+            let mut res = httparse::Response::new(parsed.as_mut());
             match res.parse(src)? {
                 httparse::Status::Complete(len) => {
                     let version = if res.version.unwrap() == 1 {
@@ -274,13 +316,13 @@ impl MessageType for ResponseHead {
                     };
                     let status = StatusCode::from_u16(res.code.unwrap())
                         .map_err(|_| ParseError::Status)?;
-                    HeaderIndex::record(src, res.headers, &mut headers);
+                    HeaderIndex::record(src, res.headers, headers.as_mut());
 
                     (len, version, status, res.headers.len())
                 }
                 httparse::Status::Partial => {
-                    return if src.len() >= MAX_BUFFER_SIZE {
-                        error!("MAX_BUFFER_SIZE unprocessed data reached, closing");
+                    return if src.len() >= limits.head_size() {
+                        error!("Response head size limit reached, closing");
                         Err(ParseError::TooLarge)
                     } else {
                         Ok(None)
@@ -293,7 +335,11 @@ impl MessageType for ResponseHead {
         msg.version = ver;
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length = msg.set_headers(
+            &src.split_to(len).freeze(),
+            &headers.as_mut()[..h_len],
+            limits,
+        )?;
 
         // message payload
         let decoder = if let PayloadLength::Payload(pl) = length {
@@ -326,11 +372,46 @@ pub(crate) const EMPTY_HEADER_INDEX: HeaderIndex = HeaderIndex {
     value: (0, 0),
 };
 
-pub(crate) const EMPTY_HEADER_INDEX_ARRAY: [HeaderIndex; MAX_HEADERS] =
-    [EMPTY_HEADER_INDEX; MAX_HEADERS];
+/// Buffer for parsed headers.
+///
+/// Uses stack storage when the header count limit does not exceed the default.
+enum HeaderBuf<T> {
+    Stack([T; MAX_HEADERS], usize),
+    Heap(Vec<T>),
+}
 
-pub(crate) const EMPTY_HEADER_ARRAY: [httparse::Header<'static>; MAX_HEADERS] =
-    [httparse::EMPTY_HEADER; MAX_HEADERS];
+impl<T: Copy> HeaderBuf<T> {
+    fn new(empty: T, len: usize) -> Self {
+        if len <= MAX_HEADERS {
+            HeaderBuf::Stack([empty; MAX_HEADERS], len)
+        } else {
+            HeaderBuf::Heap(vec![empty; len])
+        }
+    }
+
+    fn as_mut(&mut self) -> &mut [T] {
+        match self {
+            HeaderBuf::Stack(buf, len) => &mut buf[..*len],
+            HeaderBuf::Heap(buf) => buf,
+        }
+    }
+}
+
+/// Returns length of the (possibly incomplete) URI in a partially received request line.
+fn partial_uri_len(src: &[u8]) -> usize {
+    let line = match src.iter().position(|&b| b == b'\n') {
+        Some(end) => &src[..end],
+        None => src,
+    };
+
+    match line.iter().position(|&b| b == b' ') {
+        Some(start) => line[start + 1..]
+            .iter()
+            .take_while(|&&b| b != b' ' && b != b'\r')
+            .count(),
+        None => 0,
+    }
+}
 
 impl HeaderIndex {
     pub(crate) fn record(
@@ -361,26 +442,49 @@ pub enum PayloadItem {
 ///
 /// If a message body does not include a Transfer-Encoding, it *should*
 /// include a Content-Length header.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct PayloadDecoder {
     kind: Kind,
+    trailers: Option<HeaderMap>,
+    /// Maximum number of trailer fields of a chunked payload.
+    max_trailers: usize,
+    /// Maximum size of the trailer section of a chunked payload, in bytes.
+    max_trailer_size: usize,
 }
 
 impl PayloadDecoder {
     pub fn length(x: u64) -> PayloadDecoder {
         PayloadDecoder {
             kind: Kind::Length(x),
+            trailers: None,
+            max_trailers: MAX_HEADERS,
+            max_trailer_size: MAX_BUFFER_SIZE,
         }
     }
 
-    pub fn chunked() -> PayloadDecoder {
+    /// Create decoder for a chunked payload, allowing up to `max_trailers` trailer fields in a
+    /// trailer section of up to `max_trailer_size` bytes.
+    pub fn chunked(max_trailers: usize, max_trailer_size: usize) -> PayloadDecoder {
         PayloadDecoder {
             kind: Kind::Chunked(ChunkedState::Size, 0),
+            trailers: None,
+            max_trailers,
+            max_trailer_size,
         }
     }
 
     pub fn eof() -> PayloadDecoder {
-        PayloadDecoder { kind: Kind::Eof }
+        PayloadDecoder {
+            kind: Kind::Eof,
+            trailers: None,
+            max_trailers: MAX_HEADERS,
+            max_trailer_size: MAX_BUFFER_SIZE,
+        }
+    }
+
+    /// Take trailer fields parsed after the last chunk of a chunked payload.
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take()
     }
 }
 
@@ -417,8 +521,7 @@ enum ChunkedState {
     Body,
     BodyCr,
     BodyLf,
-    EndCr,
-    EndLf,
+    Trailers,
     End,
 }
 
@@ -452,7 +555,13 @@ impl Decoder for PayloadDecoder {
                 loop {
                     let mut buf = None;
                     // advances the chunked state
-                    *state = match state.step(src, size, &mut buf) {
+                    *state = match state.step(
+                        src,
+                        size,
+                        &mut buf,
+                        &mut self.trailers,
+                        (self.max_trailers, self.max_trailer_size),
+                    ) {
                         Poll::Pending => return Ok(None),
                         Poll::Ready(Ok(state)) => state,
                         Poll::Ready(Err(e)) => return Err(e),
@@ -498,6 +607,8 @@ impl ChunkedState {
         body: &mut BytesMut,
         size: &mut u64,
         buf: &mut Option<Bytes>,
+        trailers: &mut Option<HeaderMap>,
+        trailer_limits: (usize, usize),
     ) -> Poll<Result<ChunkedState, io::Error>> {
         use self::ChunkedState::*;
         match *self {
@@ -508,8 +619,7 @@ impl ChunkedState {
             Body => ChunkedState::read_body(body, size, buf),
             BodyCr => ChunkedState::read_body_cr(body),
             BodyLf => ChunkedState::read_body_lf(body),
-            EndCr => ChunkedState::read_end_cr(body),
-            EndLf => ChunkedState::read_end_lf(body),
+            Trailers => ChunkedState::read_trailers(body, trailers, trailer_limits),
             End => Poll::Ready(Ok(ChunkedState::End)),
         }
     }
@@ -570,7 +680,7 @@ impl ChunkedState {
     ) -> Poll<Result<ChunkedState, io::Error>> {
         match byte!(rdr) {
             b'\n' if *size > 0 => Poll::Ready(Ok(ChunkedState::Body)),
-            b'\n' if *size == 0 => Poll::Ready(Ok(ChunkedState::EndCr)),
+            b'\n' if *size == 0 => Poll::Ready(Ok(ChunkedState::Trailers)),
             _ => Poll::Ready(Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Invalid chunk size LF",
@@ -624,22 +734,43 @@ impl ChunkedState {
             ))),
         }
     }
-    fn read_end_cr(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, io::Error>> {
-        match byte!(rdr) {
-            b'\r' => Poll::Ready(Ok(ChunkedState::EndLf)),
-            _ => Poll::Ready(Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Invalid chunk end CR",
-            ))),
-        }
-    }
-    fn read_end_lf(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, io::Error>> {
-        match byte!(rdr) {
-            b'\n' => Poll::Ready(Ok(ChunkedState::End)),
-            _ => Poll::Ready(Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Invalid chunk end LF",
-            ))),
+
+    fn read_trailers(
+        rdr: &mut BytesMut,
+        trailers: &mut Option<HeaderMap>,
+        (max_trailers, max_trailer_size): (usize, usize),
+    ) -> Poll<Result<ChunkedState, io::Error>> {
+        let invalid =
+            || io::Error::new(io::ErrorKind::InvalidInput, "Invalid chunked trailers");
+
+        let mut parsed = HeaderBuf::new(httparse::EMPTY_HEADER, max_trailers);
+
+        match httparse::parse_headers(rdr, parsed.as_mut()) {
+            Ok(httparse::Status::Complete((len, _))) if len > max_trailer_size => {
+                Poll::Ready(Err(invalid()))
+            }
+            Ok(httparse::Status::Complete((len, headers))) => {
+                if !headers.is_empty() {
+                    let mut map = HeaderMap::with_capacity(headers.len());
+
+                    for header in headers.iter() {
+                        let name = HeaderName::from_bytes(header.name.as_bytes())
+                            .map_err(|_| invalid())?;
+                        let value = HeaderValue::from_bytes(header.value)
+                            .map_err(|_| invalid())?;
+                        map.append(name, value);
+                    }
+
+                    *trailers = Some(map);
+                }
+
+                rdr.advance(len);
+                Poll::Ready(Ok(ChunkedState::End))
+            }
+            Ok(httparse::Status::Partial) if rdr.len() < max_trailer_size => {
+                Poll::Pending
+            }
+            _ => Poll::Ready(Err(invalid())),
         }
     }
 }
@@ -1098,6 +1229,85 @@ mod tests {
         assert_eq!(req.path(), "//path");
     }
 
+    #[test]
+    fn test_request_limits_headers() {
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             x-one: 1\r\n\
+             x-two: 2\r\n\r\n",
+        );
+        let mut reader =
+            MessageDecoder::<Request>::new(RequestLimits::new().max_headers(1));
+        assert!(matches!(
+            reader.decode(&mut buf.clone()),
+            Err(ParseError::TooLarge)
+        ));
+
+        let mut reader =
+            MessageDecoder::<Request>::new(RequestLimits::new().max_headers(2));
+        let (req, _) = reader.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(req.headers().len(), 2);
+
+        // more headers than fit in stack storage
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\n");
+        for idx in 0..200 {
+            buf.extend(format!("x-header-{}: {}\r\n", idx, idx).as_bytes());
+        }
+        buf.extend(b"\r\n");
+
+        expect_parse_err!(&mut buf.clone());
+
+        let mut reader =
+            MessageDecoder::<Request>::new(RequestLimits::new().max_headers(200));
+        let (req, _) = reader.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(req.headers().len(), 200);
+    }
+
+    #[test]
+    fn test_request_limits_head_size() {
+        let limits = RequestLimits::new().max_head_size(40);
+
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\nx-test: 1234567890\r\n\r\n");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(matches!(reader.decode(&mut buf), Err(ParseError::TooLarge)));
+
+        let mut buf =
+            BytesMut::from("GET /test HTTP/1.1\r\nx-test: 12345678901234567890");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(matches!(reader.decode(&mut buf), Err(ParseError::TooLarge)));
+
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\nx-test: 1\r\n\r\n");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(reader.decode(&mut buf).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_request_limits_uri_len() {
+        let limits = RequestLimits::new().max_uri_len(8);
+
+        let mut buf = BytesMut::from("GET /12345678 HTTP/1.1\r\n\r\n");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(matches!(
+            reader.decode(&mut buf),
+            Err(ParseError::UriTooLong)
+        ));
+
+        // incomplete request line
+        let mut buf = BytesMut::from("GET /1234567");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        assert!(reader.decode(&mut buf).unwrap().is_none());
+        buf.extend(b"8");
+        assert!(matches!(
+            reader.decode(&mut buf),
+            Err(ParseError::UriTooLong)
+        ));
+
+        let mut buf = BytesMut::from("GET /1234567 HTTP/1.1\r\n\r\n");
+        let mut reader = MessageDecoder::<Request>::new(limits);
+        let (req, _) = reader.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(req.path(), "/1234567");
+    }
+
     #[test]
     fn test_http_request_parser_bad_method() {
         let mut buf = BytesMut::from("!12%()+=~$ /get HTTP/1.1\r\n\r\n");
@@ -1210,6 +1420,98 @@ mod tests {
         assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
     }
 
+    #[test]
+    fn test_http_request_chunked_payload_trailers() {
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             transfer-encoding: chunked\r\n\r\n",
+        );
+        let mut reader = MessageDecoder::<Request>::default();
+        let (_req, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        let mut pl = pl.unwrap();
+
+        buf.extend(b"4\r\ndata\r\n0\r\nx-checksum: abc\r\n");
+        let msg = pl.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(msg.chunk().as_ref(), b"data");
+        assert!(pl.decode(&mut buf).unwrap().is_none());
+
+        buf.extend(b"x-other: 1\r\nx-other: 2\r\n\r\nGET /next HTTP/1.1\r\n\r\n");
+        assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
+
+        let trailers = pl.take_trailers().unwrap();
+        assert_eq!(trailers.len(), 3);
+        assert_eq!(trailers.get("x-checksum").unwrap(), "abc");
+        assert_eq!(trailers.get_all("x-other").count(), 2);
+        assert!(pl.take_trailers().is_none());
+
+        let (req, _) = reader.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(req.path(), "/next");
+    }
+
+    #[test]
+    fn test_http_request_chunked_payload_trailers_limit() {
+        let mut trailers = String::from("0\r\n");
+        for i in 0..120 {
+            trailers.push_str(&format!("x-trailer-{}: {}\r\n", i, i));
+        }
+        trailers.push_str("\r\n");
+
+        let req = "GET /test HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n";
+
+        // trailer count is limited like the header count
+        let mut buf = BytesMut::from(req);
+        let mut reader = MessageDecoder::<Request>::default();
+        let (_req, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        let mut pl = pl.unwrap();
+        buf.extend(trailers.as_bytes());
+        assert!(pl.decode(&mut buf).is_err());
+
+        let mut buf = BytesMut::from(req);
+        let mut reader =
+            MessageDecoder::<Request>::new(RequestLimits::new().max_headers(200));
+        let (_req, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        let mut pl = pl.unwrap();
+        buf.extend(trailers.as_bytes());
+        assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
+        assert_eq!(pl.take_trailers().unwrap().len(), 120);
+
+        // trailer section size is limited like the head size, or by its own limit
+        for limits in &[
+            RequestLimits::new().max_headers(200).max_head_size(1024),
+            RequestLimits::new().max_headers(200).max_trailer_size(1024),
+        ] {
+            let mut buf = BytesMut::from(req);
+            let mut reader = MessageDecoder::<Request>::new(*limits);
+            let (_req, pl) = reader.decode(&mut buf).unwrap().unwrap();
+            let mut pl = pl.unwrap();
+
+            // complete section
+            buf.extend(trailers.as_bytes());
+            assert!(pl.decode(&mut buf).is_err());
+
+            // partial section
+            let mut buf = BytesMut::from(req);
+            let (_req, pl) = reader.decode(&mut buf).unwrap().unwrap();
+            let mut pl = pl.unwrap();
+            buf.extend(&trailers.as_bytes()[..2048]);
+            assert!(pl.decode(&mut buf).is_err());
+        }
+    }
+
+    #[test]
+    fn test_http_request_chunked_payload_invalid_trailers() {
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             transfer-encoding: chunked\r\n\r\n",
+        );
+        let mut reader = MessageDecoder::<Request>::default();
+        let (_req, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        let mut pl = pl.unwrap();
+
+        buf.extend(b"0\r\ninvalid trailer\r\n\r\n");
+        assert!(pl.decode(&mut buf).is_err());
+    }
+
     #[test]
     fn test_parse_chunked_payload_chunk_extension() {
         let mut buf = BytesMut::from(
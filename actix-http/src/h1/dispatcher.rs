@@ -1,4 +1,5 @@
 use std::{
+    cmp,
     collections::VecDeque,
     error::Error as StdError,
     fmt,
@@ -430,8 +431,15 @@ where
                             }
 
                             Poll::Ready(None) => {
-                                this.codec
-                                    .encode(Message::Chunk(None), &mut this.write_buf)?;
+                                match stream.as_mut().take_trailers() {
+                                    Some(trailers) => this
+                                        .codec
+                                        .encode_trailers(&trailers, this.write_buf)?,
+                                    None => this.codec.encode(
+                                        Message::Chunk(None),
+                                        &mut this.write_buf,
+                                    )?,
+                                }
                                 // payload stream finished.
                                 // set state to None and handle next message
                                 this.state.set(State::None);
@@ -465,8 +473,15 @@ where
                             }
 
                             Poll::Ready(None) => {
-                                this.codec
-                                    .encode(Message::Chunk(None), &mut this.write_buf)?;
+                                match stream.as_mut().take_trailers() {
+                                    Some(trailers) => this
+                                        .codec
+                                        .encode_trailers(&trailers, this.write_buf)?,
+                                    None => this.codec.encode(
+                                        Message::Chunk(None),
+                                        &mut this.write_buf,
+                                    )?,
+                                }
                                 // payload stream finished.
                                 // set state to None and handle next message
                                 this.state.set(State::None);
@@ -660,6 +675,9 @@ where
                         }
                         Message::Chunk(None) => {
                             if let Some(mut payload) = this.payload.take() {
+                                if let Some(trailers) = this.codec.take_trailers() {
+                                    payload.feed_trailers(trailers);
+                                }
                                 payload.feed_eof();
                             } else {
                                 error!("Internal server error: unexpected eof");
@@ -697,6 +715,15 @@ where
                     *this.error = Some(ParseError::TooLarge.into());
                     break;
                 }
+                Err(ParseError::UriTooLong) => {
+                    // Requests with URI exceeding configured limit should be responded with 414
+                    this.messages.push_back(DispatcherMessage::Error(
+                        Response::with_body(StatusCode::URI_TOO_LONG, ()),
+                    ));
+                    this.flags.insert(Flags::READ_DISCONNECT);
+                    *this.error = Some(ParseError::UriTooLong.into());
+                    break;
+                }
                 Err(err) => {
                     if let Some(mut payload) = this.payload.take() {
                         payload.set_error(PayloadError::EncodingCorrupted);
@@ -817,9 +844,15 @@ where
 
         let mut read_some = false;
 
+        // allow head size limit to raise read buffer limit above decoder's max buffer size.
+        let max_buf = cmp::max(
+            super::decoder::MAX_BUFFER_SIZE,
+            this.codec.config().limits().head_size(),
+        );
+
         loop {
             // Return early when read buf exceed decoder's max buffer size.
-            if this.read_buf.len() >= super::decoder::MAX_BUFFER_SIZE {
+            if this.read_buf.len() >= max_buf {
                 /*
                  At this point it's not known IO stream is still scheduled
                  to be waked up. so force wake up dispatcher just in case.
@@ -10,7 +10,7 @@ use crate::{
     body::BodySize,
     config::ServiceConfig,
     header::{map::Value, HeaderMap, HeaderName},
-    header::{CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING},
+    header::{CONNECTION, CONTENT_LENGTH, DATE, TRAILER, TRANSFER_ENCODING},
     helpers,
     message::{ConnectionType, RequestHeadType},
     Response, StatusCode, Version,
@@ -22,6 +22,9 @@ const AVERAGE_HEADER_SIZE: usize = 30;
 pub(crate) struct MessageEncoder<T: MessageType> {
     pub length: BodySize,
     pub te: TransferEncoding,
+
+    /// Whether the request target is sent in absolute-form, as required by forward proxies.
+    pub absolute_form: bool,
     _phantom: PhantomData<T>,
 }
 
@@ -30,6 +33,7 @@ impl<T: MessageType> Default for MessageEncoder<T> {
         MessageEncoder {
             length: BodySize::None,
             te: TransferEncoding::empty(),
+            absolute_form: false,
             _phantom: PhantomData,
         }
     }
@@ -50,6 +54,12 @@ pub(crate) trait MessageType: Sized {
 
     fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()>;
 
+    /// Encodes the start line with the request target in absolute-form. Only differs from
+    /// `encode_status` for requests.
+    fn encode_absolute_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode_status(dst)
+    }
+
     fn encode_headers(
         &mut self,
         dst: &mut BytesMut,
@@ -285,12 +295,37 @@ impl MessageType for RequestHeadType {
     }
 
     fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode_request_line(dst, false)
+    }
+
+    fn encode_absolute_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode_request_line(dst, true)
+    }
+}
+
+impl RequestHeadType {
+    fn encode_request_line(
+        &self,
+        dst: &mut BytesMut,
+        absolute_form: bool,
+    ) -> io::Result<()> {
         let head = self.as_ref();
         dst.reserve(256 + head.headers.len() * AVERAGE_HEADER_SIZE);
+
+        // user info must not be sent in the request target
+        let origin = match (absolute_form, head.uri.scheme_str(), head.uri.authority()) {
+            (true, Some(scheme), Some(authority)) => {
+                let host = authority.as_str().rsplit('@').next().unwrap_or("");
+                format!("{}://{}", scheme, host)
+            }
+            _ => String::new(),
+        };
+
         write!(
             helpers::MutWriter(dst),
-            "{} {} {}",
+            "{} {}{} {}",
             head.method,
+            origin,
             head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/"),
             match head.version {
                 Version::HTTP_09 => "HTTP/0.9",
@@ -320,6 +355,15 @@ impl<T: MessageType> MessageEncoder<T> {
         self.te.encode_eof(buf)
     }
 
+    /// Encode eof followed by trailer fields
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        self.te.encode_trailers(trailers, buf)
+    }
+
     pub fn encode(
         &mut self,
         dst: &mut BytesMut,
@@ -349,7 +393,11 @@ impl<T: MessageType> MessageEncoder<T> {
             self.te = TransferEncoding::empty();
         }
 
-        message.encode_status(dst)?;
+        if self.absolute_form {
+            message.encode_absolute_status(dst)?;
+        } else {
+            message.encode_status(dst)?;
+        }
         message.encode_headers(dst, version, length, ctype, config)
     }
 }
@@ -469,6 +517,42 @@ impl TransferEncoding {
             }
         }
     }
+
+    /// Encode eof with trailer fields.
+    ///
+    /// Trailers can only be sent with chunked transfer encoding and are discarded otherwise.
+    /// Fields controlling message framing are never written as trailers.
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        match self.kind {
+            TransferEncodingKind::Chunked(ref mut eof) if !*eof => {
+                *eof = true;
+                buf.extend_from_slice(b"0\r\n");
+
+                for (name, value) in trailers.iter() {
+                    match *name {
+                        CONNECTION | CONTENT_LENGTH | TRANSFER_ENCODING | TRAILER => {
+                            continue
+                        }
+                        _ => {}
+                    }
+
+                    buf.reserve(name.as_str().len() + value.len() + 4);
+                    buf.extend_from_slice(name.as_str().as_bytes());
+                    buf.extend_from_slice(b": ");
+                    buf.extend_from_slice(value.as_bytes());
+                    buf.extend_from_slice(b"\r\n");
+                }
+
+                buf.extend_from_slice(b"\r\n");
+                Ok(())
+            }
+            _ => self.encode_eof(buf),
+        }
+    }
 }
 
 /// # Safety
@@ -514,7 +598,7 @@ mod tests {
 
     use super::*;
     use crate::http::header::{HeaderValue, CONTENT_TYPE};
-    use crate::RequestHead;
+    use crate::{http::Uri, RequestHead};
 
     #[test]
     fn test_chunked_te() {
@@ -530,6 +614,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_chunked_te_trailers() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            HeaderName::from_static("x-checksum"),
+            HeaderValue::from_static("abc"),
+        );
+        trailers.insert(CONTENT_LENGTH, HeaderValue::from_static("4"));
+
+        let mut bytes = BytesMut::new();
+        let mut enc = TransferEncoding::chunked();
+        assert!(!enc.encode(b"test", &mut bytes).unwrap());
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        assert_eq!(
+            bytes.split().freeze(),
+            Bytes::from_static(b"4\r\ntest\r\n0\r\nx-checksum: abc\r\n\r\n")
+        );
+
+        // trailers are discarded without chunked encoding
+        let mut enc = TransferEncoding::length(4);
+        assert!(enc.encode(b"test", &mut bytes).unwrap());
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        assert_eq!(bytes.split().freeze(), Bytes::from_static(b"test"));
+    }
+
     #[actix_rt::test]
     async fn test_camel_case() {
         let mut bytes = BytesMut::with_capacity(2048);
@@ -627,6 +736,24 @@ mod tests {
         assert!(data.contains("date: date\r\n"));
     }
 
+    #[test]
+    fn test_absolute_form() {
+        let mut bytes = BytesMut::with_capacity(2048);
+
+        let mut head = RequestHead::default();
+        head.uri = Uri::from_static("http://user@example.com:8080/path?q=1");
+        let mut head = RequestHeadType::Owned(head);
+
+        head.encode_status(&mut bytes).unwrap();
+        assert_eq!(bytes.split(), "GET /path?q=1 HTTP/1.1");
+
+        head.encode_absolute_status(&mut bytes).unwrap();
+        assert_eq!(
+            bytes.split(),
+            "GET http://example.com:8080/path?q=1 HTTP/1.1"
+        );
+    }
+
     #[actix_rt::test]
     async fn test_no_content_length() {
         let mut bytes = BytesMut::with_capacity(2048);
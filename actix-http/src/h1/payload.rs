@@ -9,6 +9,7 @@ use bytes::Bytes;
 use futures_core::Stream;
 
 use crate::error::PayloadError;
+use crate::header::HeaderMap;
 
 /// max buffer size 32k
 pub(crate) const MAX_BUFFER_SIZE: usize = 32_768;
@@ -85,6 +86,14 @@ impl Payload {
     ) -> Poll<Option<Result<Bytes, PayloadError>>> {
         self.inner.borrow_mut().readany(cx)
     }
+
+    /// Take trailer fields sent after the last chunk of a chunked payload.
+    ///
+    /// Trailers are only available once the payload stream has been read to completion.
+    #[inline]
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.inner.borrow_mut().trailers.take()
+    }
 }
 
 impl Stream for Payload {
@@ -118,6 +127,13 @@ impl PayloadSender {
         }
     }
 
+    #[inline]
+    pub fn feed_trailers(&mut self, trailers: HeaderMap) {
+        if let Some(shared) = self.inner.upgrade() {
+            shared.borrow_mut().trailers = Some(trailers);
+        }
+    }
+
     #[inline]
     pub fn feed_data(&mut self, data: Bytes) {
         if let Some(shared) = self.inner.upgrade() {
@@ -149,6 +165,7 @@ struct Inner {
     err: Option<PayloadError>,
     need_read: bool,
     items: VecDeque<Bytes>,
+    trailers: Option<HeaderMap>,
     task: Option<Waker>,
     io_task: Option<Waker>,
 }
@@ -160,6 +177,7 @@ impl Inner {
             len: 0,
             err: None,
             items: VecDeque::new(),
+            trailers: None,
             need_read: true,
             task: None,
             io_task: None,
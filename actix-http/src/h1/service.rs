@@ -326,6 +326,7 @@ where
                 expect,
                 upgrade,
                 on_connect_ext,
+                None,
             ))
         })
     }
@@ -7,14 +7,19 @@ use std::{
     pin::Pin,
     rc::Rc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use actix_codec::{AsyncRead, AsyncWrite};
+use actix_rt::time::{sleep, Instant, Sleep};
 use actix_service::Service;
 use actix_utils::future::poll_fn;
 use bytes::{Bytes, BytesMut};
 use futures_core::ready;
-use h2::server::{Connection, SendResponse};
+use h2::{
+    server::{Connection, SendResponse},
+    Ping, PingPong,
+};
 use http::header::{HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
 use log::{error, trace};
 use pin_project_lite::pin_project;
@@ -23,7 +28,7 @@ use crate::{
     body::{AnyBody, BodySize, MessageBody},
     config::ServiceConfig,
     service::HttpFlow,
-    OnConnectData, Payload, Request, Response, ResponseHead,
+    OnConnectData, Payload, Request, Response, ResponseHead, ShutdownSignal,
 };
 
 const CHUNK_SIZE: usize = 16_384;
@@ -36,29 +41,101 @@ pin_project! {
         on_connect_data: OnConnectData,
         config: ServiceConfig,
         peer_addr: Option<net::SocketAddr>,
+        ping_pong: Option<H2PingPong>,
+        shutdown: Option<Pin<Box<dyn Future<Output = ()>>>>,
         _phantom: PhantomData<B>,
     }
 }
 
-impl<T, S, B, X, U> Dispatcher<T, S, B, X, U> {
+impl<T, S, B, X, U> Dispatcher<T, S, B, X, U>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
     pub(crate) fn new(
         flow: Rc<HttpFlow<S, X, U>>,
-        connection: Connection<T, Bytes>,
+        mut connection: Connection<T, Bytes>,
         on_connect_data: OnConnectData,
         config: ServiceConfig,
         peer_addr: Option<net::SocketAddr>,
+        shutdown: Option<ShutdownSignal>,
     ) -> Self {
+        let ping_pong =
+            config
+                .h2_config()
+                .keep_alive()
+                .and_then(|(interval, timeout)| {
+                    let ping_pong = connection.ping_pong()?;
+
+                    Some(H2PingPong {
+                        timer: Box::pin(sleep(interval)),
+                        in_flight: false,
+                        interval,
+                        timeout,
+                        ping_pong,
+                    })
+                });
+
         Self {
             flow,
             config,
             peer_addr,
             connection,
             on_connect_data,
+            ping_pong,
+            shutdown: shutdown.as_ref().map(ShutdownSignal::listen),
             _phantom: PhantomData,
         }
     }
 }
 
+/// Keep-alive PING state of a connection.
+struct H2PingPong {
+    /// Fires when the next PING is due, or when an in-flight PING has timed out.
+    timer: Pin<Box<Sleep>>,
+    /// True when a PING has been sent and its acknowledgement has not been received yet.
+    in_flight: bool,
+    interval: Duration,
+    timeout: Duration,
+    ping_pong: PingPong,
+}
+
+impl H2PingPong {
+    /// Send PINGs and check their acknowledgements.
+    ///
+    /// Returns an error if a PING is not acknowledged in time or could not be sent.
+    fn poll(&mut self, cx: &mut Context<'_>) -> Result<(), crate::error::DispatchError> {
+        loop {
+            if self.in_flight {
+                match self.ping_pong.poll_pong(cx)? {
+                    Poll::Ready(_) => {
+                        trace!("HTTP/2 keep-alive PING acknowledged");
+                        self.in_flight = false;
+                        self.timer.as_mut().reset(Instant::now() + self.interval);
+                    }
+                    Poll::Pending => {
+                        return match self.timer.as_mut().poll(cx) {
+                            Poll::Ready(_) => {
+                                trace!("HTTP/2 keep-alive PING timed out");
+                                Err(crate::error::DispatchError::H2KeepAliveTimeout)
+                            }
+                            Poll::Pending => Ok(()),
+                        };
+                    }
+                }
+            } else {
+                match self.timer.as_mut().poll(cx) {
+                    Poll::Ready(_) => {
+                        self.ping_pong.send_ping(Ping::opaque())?;
+                        self.in_flight = true;
+                        self.timer.as_mut().reset(Instant::now() + self.timeout);
+                    }
+                    Poll::Pending => return Ok(()),
+                }
+            }
+        }
+    }
+}
+
 impl<T, S, B, X, U> Future for Dispatcher<T, S, B, X, U>
 where
     T: AsyncRead + AsyncWrite + Unpin,
@@ -77,6 +154,19 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
+        if let Some(ref mut ping_pong) = this.ping_pong {
+            ping_pong.poll(cx)?;
+        }
+
+        if let Some(ref mut shutdown) = this.shutdown {
+            if shutdown.as_mut().poll(cx).is_ready() {
+                // send GOAWAY; in-flight streams are completed and new ones are refused
+                trace!("Gracefully shutting down HTTP/2 connection");
+                this.connection.graceful_shutdown();
+                this.shutdown = None;
+            }
+        }
+
         while let Some((req, tx)) =
             ready!(Pin::new(&mut this.connection).poll_accept(cx)?)
         {
@@ -192,10 +282,16 @@ where
         }
     }
 
-    // response body streaming finished. send end of stream and return.
-    stream
-        .send_data(Bytes::new(), true)
-        .map_err(DispatchError::SendData)?;
+    // response body streaming finished. send trailers or end of stream and return.
+    match body.as_mut().take_trailers() {
+        Some(trailers) => stream
+            .send_trailers(trailers.into())
+            .map_err(DispatchError::SendData)?,
+
+        None => stream
+            .send_data(Bytes::new(), true)
+            .map_err(DispatchError::SendData)?,
+    }
 
     Ok(())
 }
@@ -14,16 +14,27 @@ mod service;
 
 pub use self::dispatcher::Dispatcher;
 pub use self::service::H2Service;
-use crate::error::PayloadError;
+use crate::{error::PayloadError, header::HeaderMap};
 
 /// HTTP/2 peer stream.
 pub struct Payload {
     stream: RecvStream,
+    trailers: Option<HeaderMap>,
 }
 
 impl Payload {
     pub(crate) fn new(stream: RecvStream) -> Self {
-        Self { stream }
+        Self {
+            stream,
+            trailers: None,
+        }
+    }
+
+    /// Take trailer fields sent by the peer in a trailing HEADERS frame.
+    ///
+    /// Trailers are only available once the payload stream has been read to completion.
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take()
     }
 }
 
@@ -46,7 +57,14 @@ impl Stream for Payload {
                 }
             }
             Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
-            None => Poll::Ready(None),
+            None => match ready!(this.stream.poll_trailers(cx)) {
+                Ok(Some(trailers)) => {
+                    this.trailers = Some(trailers.into());
+                    Poll::Ready(None)
+                }
+                Ok(None) => Poll::Ready(None),
+                Err(err) => Poll::Ready(Some(Err(err.into()))),
+            },
         }
     }
 }
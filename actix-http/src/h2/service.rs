@@ -17,7 +17,7 @@ use actix_service::{
 use actix_utils::future::ready;
 use bytes::Bytes;
 use futures_core::{future::LocalBoxFuture, ready};
-use h2::server::{handshake as h2_handshake, Handshake as H2Handshake};
+use h2::server::Handshake as H2Handshake;
 use log::error;
 
 use crate::{
@@ -25,7 +25,7 @@ use crate::{
     config::ServiceConfig,
     error::DispatchError,
     service::HttpFlow,
-    ConnectCallback, OnConnectData, Request, Response,
+    ConnectCallback, OnConnectData, Request, Response, ShutdownSignal,
 };
 
 use super::dispatcher::Dispatcher;
@@ -35,6 +35,7 @@ pub struct H2Service<T, S, B> {
     srv: S,
     cfg: ServiceConfig,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+    shutdown: Option<ShutdownSignal>,
     _phantom: PhantomData<(T, B)>,
 }
 
@@ -56,6 +57,7 @@ where
         H2Service {
             cfg,
             on_connect_ext: None,
+            shutdown: None,
             srv: service.into_factory(),
             _phantom: PhantomData,
         }
@@ -66,6 +68,12 @@ where
         self.on_connect_ext = f;
         self
     }
+
+    /// Set signal for graceful connection shutdown.
+    pub(crate) fn shutdown_signal(mut self, signal: Option<ShutdownSignal>) -> Self {
+        self.shutdown = signal;
+        self
+    }
 }
 
 impl<S, B> H2Service<TcpStream, S, B>
@@ -219,10 +227,16 @@ where
         let service = self.srv.new_service(());
         let cfg = self.cfg.clone();
         let on_connect_ext = self.on_connect_ext.clone();
+        let shutdown = self.shutdown.clone();
 
         Box::pin(async move {
             let service = service.await?;
-            Ok(H2ServiceHandler::new(cfg, on_connect_ext, service))
+            Ok(H2ServiceHandler::new(
+                cfg,
+                on_connect_ext,
+                shutdown,
+                service,
+            ))
         })
     }
 }
@@ -235,6 +249,7 @@ where
     flow: Rc<HttpFlow<S, (), ()>>,
     cfg: ServiceConfig,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+    shutdown: Option<ShutdownSignal>,
     _phantom: PhantomData<B>,
 }
 
@@ -249,12 +264,14 @@ where
     fn new(
         cfg: ServiceConfig,
         on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+        shutdown: Option<ShutdownSignal>,
         service: S,
     ) -> H2ServiceHandler<T, S, B> {
         H2ServiceHandler {
             flow: HttpFlow::new(service, (), None),
             cfg,
             on_connect_ext,
+            shutdown,
             _phantom: PhantomData,
         }
     }
@@ -292,7 +309,8 @@ where
                 Some(self.cfg.clone()),
                 addr,
                 on_connect_data,
-                h2_handshake(io),
+                self.shutdown.clone(),
+                self.cfg.h2_config().handshake(io),
             ),
         }
     }
@@ -309,6 +327,7 @@ where
         Option<ServiceConfig>,
         Option<net::SocketAddr>,
         OnConnectData,
+        Option<ShutdownSignal>,
         H2Handshake<T, Bytes>,
     ),
 }
@@ -345,6 +364,7 @@ where
                 ref mut config,
                 ref peer_addr,
                 ref mut on_connect_data,
+                ref mut shutdown,
                 ref mut handshake,
             ) => match ready!(Pin::new(handshake).poll(cx)) {
                 Ok(conn) => {
@@ -355,6 +375,7 @@ where
                         on_connect_data,
                         config.take().unwrap(),
                         *peer_addr,
+                        shutdown.take(),
                     ));
                     self.poll(cx)
                 }
@@ -67,6 +67,19 @@ impl From<http::HeaderMap> for HeaderMap {
     }
 }
 
+/// Convert our `HeaderMap` to `http::HeaderMap`.
+impl From<HeaderMap> for http::HeaderMap {
+    fn from(map: HeaderMap) -> http::HeaderMap {
+        let mut res = http::HeaderMap::with_capacity(map.len());
+
+        for (name, value) in map {
+            res.append(name, value);
+        }
+
+        res
+    }
+}
+
 /// This encode set is used for HTTP header values and is defined at
 /// https://tools.ietf.org/html/rfc5987#section-3.2.
 pub(crate) const HTTP_VALUE: &AsciiSet = &CONTROLS
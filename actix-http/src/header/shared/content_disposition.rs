@@ -8,10 +8,9 @@
 
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::fmt::{self, Write};
+use std::fmt;
 
-use super::{ExtendedValue, Header, IntoHeaderValue, Writer};
-use crate::http::header;
+use crate::header::{self, ExtendedValue, Header, IntoHeaderValue};
 
 /// Split at the index of the first `needle` if it exists or at the end.
 fn split_once(haystack: &str, needle: char) -> (&str, &str) {
@@ -64,7 +63,7 @@ impl<'a> From<&'a str> for DispositionType {
 ///
 /// # Examples
 /// ```
-/// use actix_web::http::header::DispositionParam;
+/// use actix_http::http::header::DispositionParam;
 ///
 /// let param = DispositionParam::Filename(String::from("sample.txt"));
 /// assert!(param.is_filename());
@@ -241,7 +240,7 @@ impl DispositionParam {
 /// # Example
 ///
 /// ```
-/// use actix_web::http::header::{
+/// use actix_http::http::header::{
 ///     Charset, ContentDisposition, DispositionParam, DispositionType,
 ///     ExtendedValue,
 /// };
@@ -444,9 +443,7 @@ impl IntoHeaderValue for ContentDisposition {
     type Error = header::InvalidHeaderValue;
 
     fn try_into_value(self) -> Result<header::HeaderValue, Self::Error> {
-        let mut writer = Writer::new();
-        let _ = write!(&mut writer, "{}", self);
-        header::HeaderValue::from_maybe_shared(writer.take())
+        header::HeaderValue::from_maybe_shared(self.to_string())
     }
 }
 
@@ -509,19 +506,14 @@ impl fmt::Display for DispositionParam {
         //
         //
         // See also comments in test_from_raw_unnecessary_percent_decode.
-        static RE: Lazy<Regex> =
-            Lazy::new(|| Regex::new("[\x00-\x08\x10-\x1F\x7F\"\\\\]").unwrap());
         match self {
-            DispositionParam::Name(ref value) => write!(f, "name={}", value),
+            DispositionParam::Name(ref value) => write!(f, "name={}", Quoted(value)),
             DispositionParam::Filename(ref value) => {
-                write!(f, "filename=\"{}\"", RE.replace_all(value, "\\$0").as_ref())
+                write!(f, "filename={}", Quoted(value))
+            }
+            DispositionParam::Unknown(ref name, ref value) => {
+                write!(f, "{}={}", name, Quoted(value))
             }
-            DispositionParam::Unknown(ref name, ref value) => write!(
-                f,
-                "{}=\"{}\"",
-                name,
-                &RE.replace_all(value, "\\$0").as_ref()
-            ),
             DispositionParam::FilenameExt(ref ext_value) => {
                 write!(f, "filename*={}", ext_value)
             }
@@ -532,6 +524,21 @@ impl fmt::Display for DispositionParam {
     }
 }
 
+/// Formats a parameter value as a quoted-string.
+///
+/// Line breaks can not be escaped in a quoted-string, so they are percent-encoded like browsers do.
+struct Quoted<'a>(&'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        static RE: Lazy<Regex> =
+            Lazy::new(|| Regex::new("[\x00-\x08\x0B-\x1F\x7F\"\\\\]").unwrap());
+
+        let value = self.0.replace('\r', "%0D").replace('\n', "%0A");
+        write!(f, "\"{}\"", RE.replace_all(&value, "\\$0"))
+    }
+}
+
 impl fmt::Display for ContentDisposition {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.disposition)?;
@@ -544,7 +551,7 @@ impl fmt::Display for ContentDisposition {
 #[cfg(test)]
 mod tests {
     use super::{ContentDisposition, DispositionParam, DispositionType};
-    use crate::http::header::{Charset, ExtendedValue, HeaderValue};
+    use crate::header::{Charset, ExtendedValue, HeaderValue};
 
     #[test]
     fn test_from_raw_basic() {
@@ -606,8 +613,8 @@ mod tests {
                 charset: Charset::Ext(String::from("UTF-8")),
                 language_tag: None,
                 value: vec![
-                    0xc2, 0xa3, 0x20, b'a', b'n', b'd', 0x20, 0xe2, 0x82, 0xac, 0x20, b'r',
-                    b'a', b't', b'e', b's',
+                    0xc2, 0xa3, 0x20, b'a', b'n', b'd', 0x20, 0xe2, 0x82, 0xac, 0x20,
+                    b'r', b'a', b't', b'e', b's',
                 ],
             })],
         };
@@ -623,8 +630,8 @@ mod tests {
                 charset: Charset::Ext(String::from("UTF-8")),
                 language_tag: None,
                 value: vec![
-                    0xc2, 0xa3, 0x20, b'a', b'n', b'd', 0x20, 0xe2, 0x82, 0xac, 0x20, b'r',
-                    b'a', b't', b'e', b's',
+                    0xc2, 0xa3, 0x20, b'a', b'n', b'd', 0x20, 0xe2, 0x82, 0xac, 0x20,
+                    b'r', b'a', b't', b'e', b's',
                 ],
             })],
         };
@@ -686,22 +693,26 @@ mod tests {
 
     #[test]
     fn test_from_raw_only_disp() {
-        let a = ContentDisposition::from_raw(&HeaderValue::from_static("attachment")).unwrap();
+        let a = ContentDisposition::from_raw(&HeaderValue::from_static("attachment"))
+            .unwrap();
         let b = ContentDisposition {
             disposition: DispositionType::Attachment,
             parameters: vec![],
         };
         assert_eq!(a, b);
 
-        let a = ContentDisposition::from_raw(&HeaderValue::from_static("inline ;")).unwrap();
+        let a =
+            ContentDisposition::from_raw(&HeaderValue::from_static("inline ;")).unwrap();
         let b = ContentDisposition {
             disposition: DispositionType::Inline,
             parameters: vec![],
         };
         assert_eq!(a, b);
 
-        let a = ContentDisposition::from_raw(&HeaderValue::from_static("unknown-disp-param"))
-            .unwrap();
+        let a = ContentDisposition::from_raw(&HeaderValue::from_static(
+            "unknown-disp-param",
+        ))
+        .unwrap();
         let b = ContentDisposition {
             disposition: DispositionType::Ext(String::from("unknown-disp-param")),
             parameters: vec![],
@@ -740,8 +751,8 @@ mod tests {
         Mainstream browsers like Firefox (gecko) and Chrome use UTF-8 directly as above.
         (And now, only UTF-8 is handled by this implementation.)
         */
-        let a =
-            HeaderValue::from_str("form-data; name=upload; filename=\"文件.webp\"").unwrap();
+        let a = HeaderValue::from_str("form-data; name=upload; filename=\"文件.webp\"")
+            .unwrap();
         let a: ContentDisposition = ContentDisposition::from_raw(&a).unwrap();
         let b = ContentDisposition {
             disposition: DispositionType::FormData,
@@ -792,7 +803,8 @@ mod tests {
 
     #[test]
     fn test_from_raw_semicolon() {
-        let a = HeaderValue::from_static("form-data; filename=\"A semicolon here;.pdf\"");
+        let a =
+            HeaderValue::from_static("form-data; filename=\"A semicolon here;.pdf\"");
         let a: ContentDisposition = ContentDisposition::from_raw(&a).unwrap();
         let b = ContentDisposition {
             disposition: DispositionType::FormData,
@@ -828,8 +840,9 @@ mod tests {
         };
         assert_eq!(a, b);
 
-        let a =
-            HeaderValue::from_static("form-data; name=photo; filename=\"%74%65%73%74.png\"");
+        let a = HeaderValue::from_static(
+            "form-data; name=photo; filename=\"%74%65%73%74.png\"",
+        );
         let a: ContentDisposition = ContentDisposition::from_raw(&a).unwrap();
         let b = ContentDisposition {
             disposition: DispositionType::FormData,
@@ -874,7 +887,8 @@ mod tests {
 
     #[test]
     fn test_display_extended() {
-        let as_string = "attachment; filename*=UTF-8'en'%C2%A3%20and%20%E2%82%AC%20rates";
+        let as_string =
+            "attachment; filename*=UTF-8'en'%C2%A3%20and%20%E2%82%AC%20rates";
         let a = HeaderValue::from_static(as_string);
         let a: ContentDisposition = ContentDisposition::from_raw(&a).unwrap();
         let display_rendered = format!("{}", a);
@@ -891,7 +905,7 @@ mod tests {
 
     #[test]
     fn test_display_quote() {
-        let as_string = "form-data; name=upload; filename=\"Quote\\\"here.png\"";
+        let as_string = "form-data; name=\"upload\"; filename=\"Quote\\\"here.png\"";
         as_string
             .find(['\\', '\"'].iter().collect::<String>().as_str())
             .unwrap(); // ensure `\"` is there
@@ -903,7 +917,7 @@ mod tests {
 
     #[test]
     fn test_display_space_tab() {
-        let as_string = "form-data; name=upload; filename=\"Space here.png\"";
+        let as_string = "form-data; name=\"upload\"; filename=\"Space here.png\"";
         let a = HeaderValue::from_static(as_string);
         let a: ContentDisposition = ContentDisposition::from_raw(&a).unwrap();
         let display_rendered = format!("{}", a);
@@ -935,6 +949,21 @@ mod tests {
         };
         let display_rendered = format!("{}", a);
         assert_eq!("inline; filename=\"bell\\\x07.png\"", display_rendered);
+
+        let a: ContentDisposition = ContentDisposition {
+            disposition: DispositionType::FormData,
+            parameters: vec![
+                DispositionParam::Name(String::from("a\"b\\c")),
+                DispositionParam::Filename(String::from(
+                    "evil\r\ncontent-type: text/html",
+                )),
+            ],
+        };
+        let display_rendered = format!("{}", a);
+        assert_eq!(
+            "form-data; name=\"a\\\"b\\\\c\"; filename=\"evil%0D%0Acontent-type: text/html\"",
+            display_rendered
+        );
     }
 
     #[test]
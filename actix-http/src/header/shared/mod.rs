@@ -1,12 +1,16 @@
 //! Originally taken from `hyper::header::shared`.
 
 mod charset;
+mod content_disposition;
 mod content_encoding;
 mod extended;
 mod httpdate;
 mod quality_item;
 
 pub use self::charset::Charset;
+pub use self::content_disposition::{
+    ContentDisposition, DispositionParam, DispositionType,
+};
 pub use self::content_encoding::ContentEncoding;
 pub use self::extended::{parse_extended_value, ExtendedValue};
 pub use self::httpdate::HttpDate;
@@ -44,6 +44,7 @@ mod request;
 mod response;
 mod response_builder;
 mod service;
+mod shutdown;
 mod time_parser;
 
 pub mod error;
@@ -53,7 +54,7 @@ pub mod test;
 pub mod ws;
 
 pub use self::builder::HttpServiceBuilder;
-pub use self::config::{KeepAlive, ServiceConfig};
+pub use self::config::{H2Config, KeepAlive, RequestLimits, ServiceConfig};
 pub use self::error::Error;
 pub use self::extensions::Extensions;
 pub use self::header::ContentEncoding;
@@ -65,6 +66,7 @@ pub use self::request::Request;
 pub use self::response::Response;
 pub use self::response_builder::ResponseBuilder;
 pub use self::service::HttpService;
+pub use self::shutdown::ShutdownSignal;
 
 pub use ::http::{uri, uri::Uri};
 pub use ::http::{Method, StatusCode, Version};
@@ -5,7 +5,7 @@ use bytes::Bytes;
 use futures_core::Stream;
 use h2::RecvStream;
 
-use crate::error::PayloadError;
+use crate::{error::PayloadError, header::HeaderMap};
 
 /// Type represent boxed payload
 pub type PayloadStream = Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>>;
@@ -47,6 +47,18 @@ impl<S> Payload<S> {
     pub fn take(&mut self) -> Payload<S> {
         std::mem::replace(self, Payload::None)
     }
+
+    /// Take trailer fields sent by the peer after the payload.
+    ///
+    /// Trailers are only available for HTTP/1.1 chunked and HTTP/2 payloads, once the payload
+    /// stream has been read to completion.
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            Payload::H1(ref mut pl) => pl.take_trailers(),
+            Payload::H2(ref mut pl) => pl.take_trailers(),
+            Payload::None | Payload::Stream(_) => None,
+        }
+    }
 }
 
 impl<S> Stream for Payload<S>
@@ -9,7 +9,7 @@ use std::{
     task::{Context, Poll},
 };
 
-use ::h2::server::{handshake as h2_handshake, Handshake as H2Handshake};
+use ::h2::server::Handshake as H2Handshake;
 use actix_codec::{AsyncRead, AsyncWrite, Framed};
 use actix_rt::net::TcpStream;
 use actix_service::{
@@ -24,7 +24,7 @@ use crate::{
     builder::HttpServiceBuilder,
     config::{KeepAlive, ServiceConfig},
     error::DispatchError,
-    h1, h2, ConnectCallback, OnConnectData, Protocol, Request, Response,
+    h1, h2, ConnectCallback, OnConnectData, Protocol, Request, Response, ShutdownSignal,
 };
 
 /// A `ServiceFactory` for HTTP/1.1 or HTTP/2 protocol.
@@ -34,6 +34,7 @@ pub struct HttpService<T, S, B, X = h1::ExpectHandler, U = h1::UpgradeHandler> {
     expect: X,
     upgrade: Option<U>,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+    shutdown: Option<ShutdownSignal>,
     _phantom: PhantomData<B>,
 }
 
@@ -72,6 +73,7 @@ where
             expect: h1::ExpectHandler,
             upgrade: None,
             on_connect_ext: None,
+            shutdown: None,
             _phantom: PhantomData,
         }
     }
@@ -87,6 +89,7 @@ where
             expect: h1::ExpectHandler,
             upgrade: None,
             on_connect_ext: None,
+            shutdown: None,
             _phantom: PhantomData,
         }
     }
@@ -118,6 +121,7 @@ where
             srv: self.srv,
             upgrade: self.upgrade,
             on_connect_ext: self.on_connect_ext,
+            shutdown: self.shutdown,
             _phantom: PhantomData,
         }
     }
@@ -138,6 +142,7 @@ where
             srv: self.srv,
             expect: self.expect,
             on_connect_ext: self.on_connect_ext,
+            shutdown: self.shutdown,
             _phantom: PhantomData,
         }
     }
@@ -147,6 +152,12 @@ where
         self.on_connect_ext = f;
         self
     }
+
+    /// Set signal for graceful HTTP/2 connection shutdown.
+    pub(crate) fn shutdown_signal(mut self, signal: Option<ShutdownSignal>) -> Self {
+        self.shutdown = signal;
+        self
+    }
 }
 
 impl<S, B, X, U> HttpService<TcpStream, S, B, X, U>
@@ -369,6 +380,7 @@ where
         let expect = self.expect.new_service(());
         let upgrade = self.upgrade.as_ref().map(|s| s.new_service(()));
         let on_connect_ext = self.on_connect_ext.clone();
+        let shutdown = self.shutdown.clone();
         let cfg = self.cfg.clone();
 
         Box::pin(async move {
@@ -396,6 +408,7 @@ where
                 expect,
                 upgrade,
                 on_connect_ext,
+                shutdown,
             ))
         })
     }
@@ -411,6 +424,7 @@ where
     pub(super) flow: Rc<HttpFlow<S, X, U>>,
     pub(super) cfg: ServiceConfig,
     pub(super) on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+    pub(super) shutdown: Option<ShutdownSignal>,
     _phantom: PhantomData<B>,
 }
 
@@ -429,10 +443,12 @@ where
         expect: X,
         upgrade: Option<U>,
         on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+        shutdown: Option<ShutdownSignal>,
     ) -> HttpServiceHandler<T, S, B, X, U> {
         HttpServiceHandler {
             cfg,
             on_connect_ext,
+            shutdown,
             flow: HttpFlow::new(service, expect, upgrade),
             _phantom: PhantomData,
         }
@@ -511,11 +527,12 @@ where
         match proto {
             Protocol::Http2 => HttpServiceHandlerResponse {
                 state: State::H2Handshake(Some((
-                    h2_handshake(io),
+                    self.cfg.h2_config().handshake(io),
                     self.cfg.clone(),
                     self.flow.clone(),
                     on_connect_data,
                     peer_addr,
+                    self.shutdown.clone(),
                 ))),
             },
 
@@ -561,6 +578,7 @@ where
             Rc<HttpFlow<S, X, U>>,
             OnConnectData,
             Option<net::SocketAddr>,
+            Option<ShutdownSignal>,
         )>,
     ),
 }
@@ -615,7 +633,7 @@ where
             StateProj::H2Handshake(data) => {
                 match ready!(Pin::new(&mut data.as_mut().unwrap().0).poll(cx)) {
                     Ok(conn) => {
-                        let (_, cfg, srv, on_connect_data, peer_addr) =
+                        let (_, cfg, srv, on_connect_data, peer_addr, shutdown) =
                             data.take().unwrap();
                         self.as_mut().project().state.set(State::H2(
                             h2::Dispatcher::new(
@@ -624,6 +642,7 @@ where
                                 on_connect_data,
                                 cfg,
                                 peer_addr,
+                                shutdown,
                             ),
                         ));
                         self.poll(cx)
@@ -0,0 +1,86 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use futures_util::future::pending;
+use tokio::sync::watch;
+
+/// Signal for gracefully shutting down HTTP/2 connections.
+///
+/// When triggered, HTTP/2 connections of services built with this signal send a GOAWAY frame to
+/// their clients. Streams that are already in flight are allowed to complete while new streams
+/// are refused, after which the connection is closed.
+///
+/// The signal can be cloned and sent across threads; all clones share the same state.
+#[derive(Debug, Clone)]
+pub struct ShutdownSignal {
+    tx: Arc<watch::Sender<bool>>,
+    rx: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+    /// Create new signal that has not been triggered.
+    pub fn new() -> Self {
+        let (tx, rx) = watch::channel(false);
+
+        Self {
+            tx: Arc::new(tx),
+            rx,
+        }
+    }
+
+    /// Trigger graceful shutdown of all connections listening to this signal.
+    pub fn shutdown(&self) {
+        let _ = self.tx.send(true);
+    }
+
+    /// Returns true if shutdown has been triggered.
+    pub fn is_shutdown(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Returns a future that resolves once shutdown has been triggered.
+    pub(crate) fn listen(&self) -> Pin<Box<dyn Future<Output = ()>>> {
+        let mut rx = self.rx.clone();
+
+        Box::pin(async move {
+            while !*rx.borrow() {
+                if rx.changed().await.is_err() {
+                    // all signal handles are dropped; shutdown can not be triggered anymore
+                    pending::<()>().await;
+                }
+            }
+        })
+    }
+}
+
+impl Default for ShutdownSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures_util::FutureExt as _;
+
+    #[actix_rt::test]
+    async fn test_shutdown_signal() {
+        let signal = ShutdownSignal::new();
+        assert!(!signal.is_shutdown());
+
+        let mut listener = signal.listen();
+        assert!((&mut listener).now_or_never().is_none());
+
+        let handle = signal.clone();
+        std::thread::spawn(move || handle.shutdown())
+            .join()
+            .unwrap();
+        assert!(signal.is_shutdown());
+
+        listener.await;
+
+        // listeners created after shutdown resolve immediately
+        assert!(signal.listen().now_or_never().is_some());
+    }
+}
@@ -3,6 +3,8 @@ use bitflags::bitflags;
 use bytes::{Bytes, BytesMut};
 use bytestring::ByteString;
 
+#[cfg(feature = "compress-gzip")]
+use super::deflate::{DeflateContext, DeflateParams};
 use super::frame::Parser;
 use super::proto::{CloseReason, OpCode};
 use super::ProtocolError;
@@ -63,11 +65,17 @@ pub enum Item {
     Last(Bytes),
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "compress-gzip"), derive(Copy))]
 /// WebSocket protocol codec.
+///
+/// Cloning a codec with permessage-deflate enabled keeps the negotiated parameters but not the
+/// compression state, so clones should be made before any frames are processed.
 pub struct Codec {
     flags: Flags,
     max_size: usize,
+    #[cfg(feature = "compress-gzip")]
+    deflate: Option<Box<DeflateContext>>,
 }
 
 bitflags! {
@@ -75,6 +83,7 @@ bitflags! {
         const SERVER         = 0b0000_0001;
         const CONTINUATION   = 0b0000_0010;
         const W_CONTINUATION = 0b0000_0100;
+        const R_COMPRESSED   = 0b0000_1000;
     }
 }
 
@@ -84,6 +93,8 @@ impl Codec {
         Codec {
             max_size: 65_536,
             flags: Flags::SERVER,
+            #[cfg(feature = "compress-gzip")]
+            deflate: None,
         }
     }
 
@@ -102,6 +113,96 @@ impl Codec {
         self.flags.remove(Flags::SERVER);
         self
     }
+
+    /// Enable permessage-deflate compression with negotiated parameters.
+    ///
+    /// Text and binary messages are compressed and frames with the RSV1 bit set are accepted
+    /// and decompressed. The max frame size also limits the decompressed size of whole
+    /// compressed messages, including all of their fragments.
+    #[cfg(feature = "compress-gzip")]
+    pub fn deflate(mut self, params: DeflateParams) -> Self {
+        self.deflate = Some(Box::new(DeflateContext::new(params)));
+        self
+    }
+
+    /// Write a text, binary or continuation frame, compressing it if deflate is enabled.
+    fn write_data(
+        &mut self,
+        dst: &mut BytesMut,
+        data: &[u8],
+        op: OpCode,
+        fin: bool,
+    ) -> Result<(), ProtocolError> {
+        let mask = !self.flags.contains(Flags::SERVER);
+
+        #[cfg(feature = "compress-gzip")]
+        if let Some(ref mut deflate) = self.deflate {
+            let data = deflate.compress(!mask, data, fin)?;
+
+            // only the first frame of a compressed message is marked with RSV1
+            let rsv1 = op != OpCode::Continue;
+            Parser::write_frame(dst, data, op, fin, rsv1, mask);
+
+            return Ok(());
+        }
+
+        Parser::write_message(dst, data, op, fin, mask);
+        Ok(())
+    }
+
+    /// Parse next frame from buffer, decompressing its payload if it is part of a compressed
+    /// message.
+    fn parse(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<(bool, OpCode, Bytes)>, ProtocolError> {
+        let server = self.flags.contains(Flags::SERVER);
+
+        #[cfg(feature = "compress-gzip")]
+        if let Some(ref mut deflate) = self.deflate {
+            let (finished, rsv1, opcode, payload) =
+                match Parser::parse_frame(src, server, self.max_size, true)? {
+                    Some(frame) => frame,
+                    None => return Ok(None),
+                };
+
+            let compressed = match opcode {
+                OpCode::Text | OpCode::Binary => {
+                    if !finished {
+                        self.flags.set(Flags::R_COMPRESSED, rsv1);
+                    }
+                    rsv1
+                }
+                OpCode::Continue => {
+                    let compressed = self.flags.contains(Flags::R_COMPRESSED);
+                    if finished {
+                        self.flags.remove(Flags::R_COMPRESSED);
+                    }
+                    compressed
+                }
+                _ => false,
+            };
+
+            let payload = if compressed {
+                let payload = payload.unwrap_or_default();
+                deflate.decompress(server, &payload, finished, self.max_size)?
+            } else {
+                payload.map(|pl| pl.freeze()).unwrap_or_default()
+            };
+
+            return Ok(Some((finished, opcode, payload)));
+        }
+
+        Ok(Parser::parse(src, server, self.max_size)?.map(
+            |(finished, opcode, payload)| {
+                (
+                    finished,
+                    opcode,
+                    payload.map(|pl| pl.freeze()).unwrap_or_default(),
+                )
+            },
+        ))
+    }
 }
 
 impl Encoder<Message> for Codec {
@@ -109,20 +210,10 @@ impl Encoder<Message> for Codec {
 
     fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), Self::Error> {
         match item {
-            Message::Text(txt) => Parser::write_message(
-                dst,
-                txt,
-                OpCode::Text,
-                true,
-                !self.flags.contains(Flags::SERVER),
-            ),
-            Message::Binary(bin) => Parser::write_message(
-                dst,
-                bin,
-                OpCode::Binary,
-                true,
-                !self.flags.contains(Flags::SERVER),
-            ),
+            Message::Text(txt) => {
+                self.write_data(dst, txt.as_bytes(), OpCode::Text, true)?
+            }
+            Message::Binary(bin) => self.write_data(dst, &bin, OpCode::Binary, true)?,
             Message::Ping(txt) => Parser::write_message(
                 dst,
                 txt,
@@ -146,13 +237,7 @@ impl Encoder<Message> for Codec {
                         return Err(ProtocolError::ContinuationStarted);
                     } else {
                         self.flags.insert(Flags::W_CONTINUATION);
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Text,
-                            false,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data[..], OpCode::Text, false)?
                     }
                 }
                 Item::FirstBinary(data) => {
@@ -160,24 +245,12 @@ impl Encoder<Message> for Codec {
                         return Err(ProtocolError::ContinuationStarted);
                     } else {
                         self.flags.insert(Flags::W_CONTINUATION);
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Binary,
-                            false,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data[..], OpCode::Binary, false)?
                     }
                 }
                 Item::Continue(data) => {
                     if self.flags.contains(Flags::W_CONTINUATION) {
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Continue,
-                            false,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data[..], OpCode::Continue, false)?
                     } else {
                         return Err(ProtocolError::ContinuationNotStarted);
                     }
@@ -185,13 +258,7 @@ impl Encoder<Message> for Codec {
                 Item::Last(data) => {
                     if self.flags.contains(Flags::W_CONTINUATION) {
                         self.flags.remove(Flags::W_CONTINUATION);
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Continue,
-                            true,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data[..], OpCode::Continue, true)?
                     } else {
                         return Err(ProtocolError::ContinuationNotStarted);
                     }
@@ -208,18 +275,14 @@ impl Decoder for Codec {
     type Error = ProtocolError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        match Parser::parse(src, self.flags.contains(Flags::SERVER), self.max_size) {
+        match self.parse(src) {
             Ok(Some((finished, opcode, payload))) => {
                 // continuation is not supported
                 if !finished {
                     return match opcode {
                         OpCode::Continue => {
                             if self.flags.contains(Flags::CONTINUATION) {
-                                Ok(Some(Frame::Continuation(Item::Continue(
-                                    payload
-                                        .map(|pl| pl.freeze())
-                                        .unwrap_or_else(Bytes::new),
-                                ))))
+                                Ok(Some(Frame::Continuation(Item::Continue(payload))))
                             } else {
                                 Err(ProtocolError::ContinuationNotStarted)
                             }
@@ -227,11 +290,7 @@ impl Decoder for Codec {
                         OpCode::Binary => {
                             if !self.flags.contains(Flags::CONTINUATION) {
                                 self.flags.insert(Flags::CONTINUATION);
-                                Ok(Some(Frame::Continuation(Item::FirstBinary(
-                                    payload
-                                        .map(|pl| pl.freeze())
-                                        .unwrap_or_else(Bytes::new),
-                                ))))
+                                Ok(Some(Frame::Continuation(Item::FirstBinary(payload))))
                             } else {
                                 Err(ProtocolError::ContinuationStarted)
                             }
@@ -239,11 +298,7 @@ impl Decoder for Codec {
                         OpCode::Text => {
                             if !self.flags.contains(Flags::CONTINUATION) {
                                 self.flags.insert(Flags::CONTINUATION);
-                                Ok(Some(Frame::Continuation(Item::FirstText(
-                                    payload
-                                        .map(|pl| pl.freeze())
-                                        .unwrap_or_else(Bytes::new),
-                                ))))
+                                Ok(Some(Frame::Continuation(Item::FirstText(payload))))
                             } else {
                                 Err(ProtocolError::ContinuationStarted)
                             }
@@ -259,34 +314,20 @@ impl Decoder for Codec {
                     OpCode::Continue => {
                         if self.flags.contains(Flags::CONTINUATION) {
                             self.flags.remove(Flags::CONTINUATION);
-                            Ok(Some(Frame::Continuation(Item::Last(
-                                payload.map(|pl| pl.freeze()).unwrap_or_else(Bytes::new),
-                            ))))
+                            Ok(Some(Frame::Continuation(Item::Last(payload))))
                         } else {
                             Err(ProtocolError::ContinuationNotStarted)
                         }
                     }
                     OpCode::Bad => Err(ProtocolError::BadOpCode),
                     OpCode::Close => {
-                        if let Some(ref pl) = payload {
-                            let close_reason = Parser::parse_close_payload(pl);
-                            Ok(Some(Frame::Close(close_reason)))
-                        } else {
-                            Ok(Some(Frame::Close(None)))
-                        }
+                        let close_reason = Parser::parse_close_payload(&payload);
+                        Ok(Some(Frame::Close(close_reason)))
                     }
-                    OpCode::Ping => Ok(Some(Frame::Ping(
-                        payload.map(|pl| pl.freeze()).unwrap_or_else(Bytes::new),
-                    ))),
-                    OpCode::Pong => Ok(Some(Frame::Pong(
-                        payload.map(|pl| pl.freeze()).unwrap_or_else(Bytes::new),
-                    ))),
-                    OpCode::Binary => Ok(Some(Frame::Binary(
-                        payload.map(|pl| pl.freeze()).unwrap_or_else(Bytes::new),
-                    ))),
-                    OpCode::Text => Ok(Some(Frame::Text(
-                        payload.map(|pl| pl.freeze()).unwrap_or_else(Bytes::new),
-                    ))),
+                    OpCode::Ping => Ok(Some(Frame::Ping(payload))),
+                    OpCode::Pong => Ok(Some(Frame::Pong(payload))),
+                    OpCode::Binary => Ok(Some(Frame::Binary(payload))),
+                    OpCode::Text => Ok(Some(Frame::Text(payload))),
                 }
             }
             Ok(None) => Ok(None),
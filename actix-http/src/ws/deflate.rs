@@ -0,0 +1,745 @@
+//! Per-message compression extension (RFC 7692).
+//!
+//! The `permessage-deflate` extension is negotiated with the `Sec-WebSocket-Extensions` header
+//! during the handshake. Use [`DeflateConfig`] to describe local preferences and to produce the
+//! negotiated [`DeflateParams`] which are then passed to [`Codec::deflate`](super::Codec::deflate).
+
+use std::{convert::TryFrom, fmt, io};
+
+use bytes::Bytes;
+use derive_more::{Display, Error};
+use flate2::{
+    Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status,
+};
+
+use super::ProtocolError;
+use crate::header::{HeaderMap, HeaderValue, SEC_WEBSOCKET_EXTENSIONS};
+
+const EXTENSION_NAME: &str = "permessage-deflate";
+
+const SERVER_NO_CONTEXT_TAKEOVER: &str = "server_no_context_takeover";
+const CLIENT_NO_CONTEXT_TAKEOVER: &str = "client_no_context_takeover";
+const SERVER_MAX_WINDOW_BITS: &str = "server_max_window_bits";
+const CLIENT_MAX_WINDOW_BITS: &str = "client_max_window_bits";
+
+/// Largest LZ77 window size; the only size the local compressor can produce.
+const MAX_WINDOW_BITS: u8 = 15;
+const MIN_WINDOW_BITS: u8 = 8;
+
+/// Empty uncompressed deflate block that ends every sync-flushed message.
+const DEFLATE_TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Local preferences for the `permessage-deflate` WebSocket extension.
+///
+/// The same configuration is used on either side of a connection; it is translated to the
+/// role-specific extension parameters during negotiation.
+///
+/// The local compressor always uses the largest (32KiB) LZ77 window, so offers that require
+/// a smaller window for it are declined. Any window size chosen by the peer can be decompressed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeflateConfig {
+    compression_level: u32,
+    no_context_takeover: bool,
+    request_no_context_takeover: bool,
+    max_window_bits: Option<u8>,
+}
+
+impl DeflateConfig {
+    /// Create new configuration with default settings.
+    pub const fn new() -> Self {
+        Self {
+            compression_level: 6,
+            no_context_takeover: false,
+            request_no_context_takeover: false,
+            max_window_bits: None,
+        }
+    }
+
+    /// Set compression level, from 0 (none) to 9 (best).
+    ///
+    /// By default level is set to 6.
+    pub fn compression_level(mut self, level: u32) -> Self {
+        self.compression_level = level.min(9);
+        self
+    }
+
+    /// Reset the local compression context after every message.
+    ///
+    /// This reduces memory held between messages at the cost of compression ratio.
+    pub fn no_context_takeover(mut self) -> Self {
+        self.no_context_takeover = true;
+        self
+    }
+
+    /// Ask the peer to reset its compression context after every message.
+    pub fn request_no_context_takeover(mut self) -> Self {
+        self.request_no_context_takeover = true;
+        self
+    }
+
+    /// Ask the peer to limit its LZ77 window to `2^bits` bytes.
+    ///
+    /// Values are clamped to the 8-15 range allowed by RFC 7692.
+    pub fn max_window_bits(mut self, bits: u8) -> Self {
+        self.max_window_bits = Some(bits.max(MIN_WINDOW_BITS).min(MAX_WINDOW_BITS));
+        self
+    }
+
+    /// Create `Sec-WebSocket-Extensions` header value offering the extension, for use in a
+    /// client handshake request.
+    pub fn offer(&self) -> HeaderValue {
+        let mut offer = String::from(EXTENSION_NAME);
+
+        if self.no_context_takeover {
+            offer.push_str("; ");
+            offer.push_str(CLIENT_NO_CONTEXT_TAKEOVER);
+        }
+
+        if self.request_no_context_takeover {
+            offer.push_str("; ");
+            offer.push_str(SERVER_NO_CONTEXT_TAKEOVER);
+        }
+
+        if let Some(bits) = self.max_window_bits {
+            offer.push_str(&format!("; {}={}", SERVER_MAX_WINDOW_BITS, bits));
+        }
+
+        // offer only contains token characters
+        HeaderValue::try_from(offer).unwrap()
+    }
+
+    /// Select the first acceptable offer from client handshake request headers.
+    ///
+    /// Returns the `Sec-WebSocket-Extensions` header value to send in the handshake response
+    /// and the negotiated parameters. Returns `None` if no offer could be accepted, in which
+    /// case the connection should proceed without compression.
+    pub fn negotiate(
+        &self,
+        headers: &HeaderMap,
+    ) -> Option<(HeaderValue, DeflateParams)> {
+        let offer = extensions(headers)
+            .filter(|(name, _)| name.eq_ignore_ascii_case(EXTENSION_NAME))
+            .filter_map(|(_, params)| Offer::parse(&params))
+            // local compressor only supports the largest window
+            .find(|offer| {
+                offer
+                    .server_max_window_bits
+                    .map_or(true, |bits| bits == MAX_WINDOW_BITS)
+            })?;
+
+        let params = DeflateParams {
+            server_no_context_takeover: offer.server_no_context_takeover
+                || self.no_context_takeover,
+            client_no_context_takeover: offer.client_no_context_takeover
+                || self.request_no_context_takeover,
+            compression_level: self.compression_level,
+        };
+
+        let mut res = String::from(EXTENSION_NAME);
+
+        if params.server_no_context_takeover {
+            res.push_str("; ");
+            res.push_str(SERVER_NO_CONTEXT_TAKEOVER);
+        }
+
+        if params.client_no_context_takeover {
+            res.push_str("; ");
+            res.push_str(CLIENT_NO_CONTEXT_TAKEOVER);
+        }
+
+        // client window can only be limited if the client signalled support for it
+        if let (Some(offered), Some(bits)) =
+            (offer.client_max_window_bits, self.max_window_bits)
+        {
+            res.push_str(&format!(
+                "; {}={}",
+                CLIENT_MAX_WINDOW_BITS,
+                bits.min(offered)
+            ));
+        }
+
+        // parameters above are all tokens
+        let res = HeaderValue::try_from(res).unwrap();
+
+        Some((res, params))
+    }
+
+    /// Validate server handshake response headers against an offer created with
+    /// [`offer`](Self::offer).
+    ///
+    /// Returns `Ok(None)` if the server did not accept the extension.
+    pub fn accept_response(
+        &self,
+        headers: &HeaderMap,
+    ) -> Result<Option<DeflateParams>, DeflateNegotiationError> {
+        let mut negotiated = None;
+
+        for (name, params) in extensions(headers) {
+            // only this extension was offered and it may only be accepted once
+            if !name.eq_ignore_ascii_case(EXTENSION_NAME) || negotiated.is_some() {
+                return Err(DeflateNegotiationError);
+            }
+
+            let res = Offer::parse(&params).ok_or(DeflateNegotiationError)?;
+
+            // window size of the local compressor can not be limited and was not offered
+            if res.client_max_window_bits.is_some() {
+                return Err(DeflateNegotiationError);
+            }
+
+            if let (Some(requested), Some(bits)) =
+                (self.max_window_bits, res.server_max_window_bits)
+            {
+                if bits > requested {
+                    return Err(DeflateNegotiationError);
+                }
+            }
+
+            negotiated = Some(DeflateParams {
+                server_no_context_takeover: res.server_no_context_takeover,
+                client_no_context_takeover: res.client_no_context_takeover
+                    || self.no_context_takeover,
+                compression_level: self.compression_level,
+            });
+        }
+
+        Ok(negotiated)
+    }
+}
+
+impl Default for DeflateConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Negotiated `permessage-deflate` parameters.
+///
+/// Created by [`DeflateConfig::negotiate`] on the server side or
+/// [`DeflateConfig::accept_response`] on the client side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeflateParams {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+    compression_level: u32,
+}
+
+impl DeflateParams {
+    /// Returns true if the server resets its compression context after every message.
+    pub fn server_no_context_takeover(&self) -> bool {
+        self.server_no_context_takeover
+    }
+
+    /// Returns true if the client resets its compression context after every message.
+    pub fn client_no_context_takeover(&self) -> bool {
+        self.client_no_context_takeover
+    }
+}
+
+/// Server handshake response contained an unacceptable `Sec-WebSocket-Extensions` header.
+#[derive(Debug, Display, Error)]
+#[display(fmt = "Invalid permessage-deflate extension response.")]
+pub struct DeflateNegotiationError;
+
+/// Parameters of a single `permessage-deflate` offer or response.
+#[derive(Debug, Default)]
+struct Offer {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+    server_max_window_bits: Option<u8>,
+    client_max_window_bits: Option<u8>,
+}
+
+impl Offer {
+    /// Parse extension parameters, returning `None` if any are unknown, repeated or invalid.
+    fn parse(params: &[(&str, Option<&str>)]) -> Option<Offer> {
+        let mut offer = Offer::default();
+        let mut seen = Vec::with_capacity(params.len());
+
+        for &(key, val) in params {
+            let key = key.to_ascii_lowercase();
+
+            if seen.contains(&key) {
+                return None;
+            }
+
+            match (key.as_str(), val) {
+                (SERVER_NO_CONTEXT_TAKEOVER, None) => {
+                    offer.server_no_context_takeover = true
+                }
+                (CLIENT_NO_CONTEXT_TAKEOVER, None) => {
+                    offer.client_no_context_takeover = true
+                }
+
+                (SERVER_MAX_WINDOW_BITS, Some(bits)) => {
+                    offer.server_max_window_bits = Some(parse_window_bits(bits)?)
+                }
+
+                (CLIENT_MAX_WINDOW_BITS, None) => {
+                    offer.client_max_window_bits = Some(MAX_WINDOW_BITS)
+                }
+                (CLIENT_MAX_WINDOW_BITS, Some(bits)) => {
+                    offer.client_max_window_bits = Some(parse_window_bits(bits)?)
+                }
+
+                _ => return None,
+            }
+
+            seen.push(key);
+        }
+
+        Some(offer)
+    }
+}
+
+fn parse_window_bits(val: &str) -> Option<u8> {
+    val.parse::<u8>()
+        .ok()
+        .filter(|bits| (MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(bits))
+}
+
+/// Iterate over all extensions listed in `Sec-WebSocket-Extensions` headers.
+///
+/// Yields extension names along with their parameters with any quoting removed.
+fn extensions(
+    headers: &HeaderMap,
+) -> impl Iterator<Item = (&str, Vec<(&str, Option<&str>)>)> + '_ {
+    headers
+        .get_all(SEC_WEBSOCKET_EXTENSIONS)
+        .filter_map(|hdr| hdr.to_str().ok())
+        .flat_map(|hdr| hdr.split(','))
+        .filter_map(|ext| {
+            let mut parts = ext.split(';').map(str::trim);
+            let name = parts.next().filter(|name| !name.is_empty())?;
+
+            let params = parts
+                .filter(|param| !param.is_empty())
+                .map(|param| match param.find('=') {
+                    Some(idx) => {
+                        let val = param[idx + 1..].trim().trim_matches('"');
+                        (param[..idx].trim(), Some(val))
+                    }
+                    None => (param, None),
+                })
+                .collect();
+
+            Some((name, params))
+        })
+}
+
+/// Compression state of a codec with negotiated `permessage-deflate` parameters.
+///
+/// Compressor and decompressor are created lazily so that each clone of a codec, typically one
+/// used for encoding and one for decoding, only allocates the state it uses.
+pub(super) struct DeflateContext {
+    params: DeflateParams,
+    compress: Option<Compress>,
+    decompress: Option<Decompress>,
+    /// Decompressed size of the fragments of the current message received so far.
+    message_size: usize,
+}
+
+impl DeflateContext {
+    pub(super) fn new(params: DeflateParams) -> Self {
+        Self {
+            params,
+            compress: None,
+            decompress: None,
+            message_size: 0,
+        }
+    }
+
+    /// Compress a message fragment. When `fin` is set the message is completed.
+    pub(super) fn compress(
+        &mut self,
+        server: bool,
+        payload: &[u8],
+        fin: bool,
+    ) -> Result<Vec<u8>, ProtocolError> {
+        let reset = if server {
+            self.params.server_no_context_takeover
+        } else {
+            self.params.client_no_context_takeover
+        };
+
+        let level = self.params.compression_level;
+        let compress = self
+            .compress
+            .get_or_insert_with(|| Compress::new(Compression::new(level), false));
+
+        let mut out = Vec::with_capacity(payload.len() / 2 + 64);
+        let start = compress.total_in();
+
+        loop {
+            let consumed = (compress.total_in() - start) as usize;
+
+            if out.len() == out.capacity() {
+                out.reserve(payload.len() - consumed + 64);
+            }
+
+            compress
+                .compress_vec(&payload[consumed..], &mut out, FlushCompress::Sync)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+            // sync flush is complete once all input is consumed and output has room left
+            if (compress.total_in() - start) as usize == payload.len()
+                && out.len() < out.capacity()
+            {
+                break;
+            }
+        }
+
+        if fin {
+            if out.ends_with(&DEFLATE_TRAILER) {
+                out.truncate(out.len() - DEFLATE_TRAILER.len());
+            }
+
+            if reset {
+                compress.reset();
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Decompress a message fragment. When `fin` is set the message is completed.
+    ///
+    /// Returns [`ProtocolError::Overflow`] if the decompressed message, including the fragments
+    /// decompressed before, exceeds `max_size`.
+    pub(super) fn decompress(
+        &mut self,
+        server: bool,
+        payload: &[u8],
+        fin: bool,
+        max_size: usize,
+    ) -> Result<Bytes, ProtocolError> {
+        // peer's context takeover determines when decompression context is reset
+        let reset = if server {
+            self.params.client_no_context_takeover
+        } else {
+            self.params.server_no_context_takeover
+        };
+
+        let decompress = self
+            .decompress
+            .get_or_insert_with(|| Decompress::new(false));
+
+        let max_size = max_size.saturating_sub(self.message_size);
+        let mut out = Vec::with_capacity((payload.len() * 2).min(max_size) + 64);
+        inflate(decompress, payload, &mut out, max_size)?;
+
+        if fin {
+            inflate(decompress, &DEFLATE_TRAILER, &mut out, max_size)?;
+
+            if reset {
+                decompress.reset(false);
+            }
+
+            self.message_size = 0;
+        } else {
+            self.message_size += out.len();
+        }
+
+        Ok(Bytes::from(out))
+    }
+}
+
+fn inflate(
+    decompress: &mut Decompress,
+    input: &[u8],
+    out: &mut Vec<u8>,
+    max_size: usize,
+) -> Result<(), ProtocolError> {
+    let start = decompress.total_in();
+
+    loop {
+        let consumed = (decompress.total_in() - start) as usize;
+
+        if out.len() == out.capacity() {
+            out.reserve((input.len() - consumed).max(1024));
+        }
+
+        let before = (decompress.total_in(), decompress.total_out());
+
+        let status = decompress
+            .decompress_vec(&input[consumed..], out, FlushDecompress::Sync)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        if out.len() > max_size {
+            return Err(ProtocolError::Overflow);
+        }
+
+        let done = (decompress.total_in() - start) as usize == input.len()
+            && out.len() < out.capacity();
+        let stalled = before == (decompress.total_in(), decompress.total_out());
+
+        if done || stalled || status == Status::StreamEnd {
+            return Ok(());
+        }
+    }
+}
+
+impl Clone for DeflateContext {
+    /// Cloned context shares negotiated parameters but starts with fresh compression state.
+    fn clone(&self) -> Self {
+        Self::new(self.params)
+    }
+}
+
+impl fmt::Debug for DeflateContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeflateContext")
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(val: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(SEC_WEBSOCKET_EXTENSIONS, HeaderValue::from_static(val));
+        headers
+    }
+
+    #[test]
+    fn test_offer() {
+        assert_eq!(DeflateConfig::new().offer(), "permessage-deflate");
+
+        let offer = DeflateConfig::new()
+            .no_context_takeover()
+            .request_no_context_takeover()
+            .max_window_bits(10)
+            .offer();
+        assert_eq!(
+            offer,
+            "permessage-deflate; client_no_context_takeover; \
+             server_no_context_takeover; server_max_window_bits=10"
+        );
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let config = DeflateConfig::new();
+
+        assert!(config.negotiate(&HeaderMap::new()).is_none());
+        assert!(config
+            .negotiate(&headers("x-webkit-deflate-frame"))
+            .is_none());
+
+        let (res, params) = config
+            .negotiate(&headers("permessage-deflate; client_max_window_bits"))
+            .unwrap();
+        assert_eq!(res, "permessage-deflate");
+        assert!(!params.server_no_context_takeover());
+        assert!(!params.client_no_context_takeover());
+
+        let (res, params) = config
+            .negotiate(&headers(
+                "permessage-deflate; server_no_context_takeover; client_no_context_takeover",
+            ))
+            .unwrap();
+        assert_eq!(
+            res,
+            "permessage-deflate; server_no_context_takeover; client_no_context_takeover"
+        );
+        assert!(params.server_no_context_takeover());
+        assert!(params.client_no_context_takeover());
+
+        // smaller server window can not be honored; falls back to next offer
+        let (res, _) = config
+            .negotiate(&headers(
+                "permessage-deflate; server_max_window_bits=10, permessage-deflate",
+            ))
+            .unwrap();
+        assert_eq!(res, "permessage-deflate");
+
+        // unknown, duplicate and invalid parameters
+        assert!(config
+            .negotiate(&headers("permessage-deflate; foo"))
+            .is_none());
+        assert!(config
+            .negotiate(&headers(
+                "permessage-deflate; server_no_context_takeover; server_no_context_takeover"
+            ))
+            .is_none());
+        assert!(config
+            .negotiate(&headers("permessage-deflate; client_max_window_bits=16"))
+            .is_none());
+
+        let config = DeflateConfig::new()
+            .no_context_takeover()
+            .max_window_bits(12);
+        let (res, params) = config
+            .negotiate(&headers(
+                "permessage-deflate; client_max_window_bits=\"10\"",
+            ))
+            .unwrap();
+        assert_eq!(
+            res,
+            "permessage-deflate; server_no_context_takeover; client_max_window_bits=10"
+        );
+        assert!(params.server_no_context_takeover());
+    }
+
+    #[test]
+    fn test_accept_response() {
+        let config = DeflateConfig::new().max_window_bits(12);
+
+        assert_eq!(config.accept_response(&HeaderMap::new()).unwrap(), None);
+
+        let params = config
+            .accept_response(&headers("permessage-deflate; server_no_context_takeover"))
+            .unwrap()
+            .unwrap();
+        assert!(params.server_no_context_takeover());
+        assert!(!params.client_no_context_takeover());
+
+        assert!(config
+            .accept_response(&headers("permessage-deflate; client_max_window_bits=10"))
+            .is_err());
+        assert!(config
+            .accept_response(&headers("permessage-deflate; server_max_window_bits=10"))
+            .unwrap()
+            .is_some());
+        assert!(config
+            .accept_response(&headers("permessage-deflate; server_max_window_bits=15"))
+            .is_err());
+        assert!(config
+            .accept_response(&headers("permessage-deflate, permessage-deflate"))
+            .is_err());
+        assert!(config.accept_response(&headers("foo")).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let params = DeflateConfig::new()
+            .negotiate(&headers("permessage-deflate"))
+            .unwrap()
+            .1;
+
+        let mut server = DeflateContext::new(params);
+        let mut client = DeflateContext::new(params);
+
+        for _ in 0..3 {
+            let msg = b"Hello, Hello, Hello, Hello, Hello";
+            let compressed = server.compress(true, msg, true).unwrap();
+            assert!(compressed.len() < msg.len());
+            assert!(!compressed.ends_with(&DEFLATE_TRAILER));
+
+            let decompressed =
+                client.decompress(false, &compressed, true, 1024).unwrap();
+            assert_eq!(&decompressed[..], &msg[..]);
+        }
+
+        // fragmented message
+        let first = client.compress(false, b"Hello, ", false).unwrap();
+        let last = client.compress(false, b"World!", true).unwrap();
+        let mut out = server
+            .decompress(true, &first, false, 1024)
+            .unwrap()
+            .to_vec();
+        out.extend(server.decompress(true, &last, true, 1024).unwrap());
+        assert_eq!(out, b"Hello, World!");
+    }
+
+    #[test]
+    fn test_codec() {
+        use actix_codec::{Decoder as _, Encoder as _};
+        use bytes::BytesMut;
+
+        use crate::ws::{Codec, Frame, Item, Message};
+
+        let params = DeflateConfig::new()
+            .negotiate(&headers("permessage-deflate"))
+            .unwrap()
+            .1;
+
+        let mut server = Codec::new().deflate(params);
+        let mut client = Codec::new().client_mode().deflate(params);
+
+        let mut buf = BytesMut::new();
+        let text = "Hello, Hello, Hello, Hello, Hello, Hello";
+        server.encode(Message::Text(text.into()), &mut buf).unwrap();
+        assert_eq!(buf[0] & 0x40, 0x40);
+        assert!(buf.len() < text.len());
+        assert_eq!(
+            client.decode(&mut buf).unwrap().unwrap(),
+            Frame::Text(Bytes::from_static(text.as_bytes()))
+        );
+
+        // control frames are never compressed
+        server
+            .encode(Message::Ping(Bytes::from_static(b"ping")), &mut buf)
+            .unwrap();
+        assert_eq!(buf[0] & 0x40, 0);
+        assert_eq!(
+            client.decode(&mut buf).unwrap().unwrap(),
+            Frame::Ping(Bytes::from_static(b"ping"))
+        );
+
+        let mut buf = BytesMut::new();
+        let first = Item::FirstBinary(Bytes::from_static(b"Hello, "));
+        let last = Item::Last(Bytes::from_static(b"World!"));
+        client
+            .encode(Message::Continuation(first), &mut buf)
+            .unwrap();
+        client
+            .encode(Message::Continuation(last), &mut buf)
+            .unwrap();
+        assert_eq!(
+            server.decode(&mut buf).unwrap().unwrap(),
+            Frame::Continuation(Item::FirstBinary(Bytes::from_static(b"Hello, ")))
+        );
+        assert_eq!(
+            server.decode(&mut buf).unwrap().unwrap(),
+            Frame::Continuation(Item::Last(Bytes::from_static(b"World!")))
+        );
+
+        // uncompressed messages are still accepted
+        let mut buf = BytesMut::new();
+        Codec::new()
+            .client_mode()
+            .encode(Message::Text("plain".into()), &mut buf)
+            .unwrap();
+        assert_eq!(
+            server.decode(&mut buf).unwrap().unwrap(),
+            Frame::Text(Bytes::from_static(b"plain"))
+        );
+    }
+
+    #[test]
+    fn test_decompress_overflow() {
+        let params = DeflateConfig::new()
+            .negotiate(&headers("permessage-deflate"))
+            .unwrap()
+            .1;
+
+        let compressed = DeflateContext::new(params)
+            .compress(true, &[0u8; 4096], true)
+            .unwrap();
+
+        let mut ctx = DeflateContext::new(params);
+        assert!(matches!(
+            ctx.decompress(false, &compressed, true, 1024),
+            Err(ProtocolError::Overflow)
+        ));
+
+        // limit applies to whole fragmented messages
+        let mut compress = DeflateContext::new(params);
+        let mut decompress = DeflateContext::new(params);
+
+        for _ in 0..2 {
+            let fragment = compress.compress(true, &[0u8; 512], false).unwrap();
+            assert!(decompress.decompress(false, &fragment, false, 1024).is_ok());
+        }
+
+        let fragment = compress.compress(true, &[0u8; 512], true).unwrap();
+        assert!(matches!(
+            decompress.decompress(false, &fragment, true, 1024),
+            Err(ProtocolError::Overflow)
+        ));
+    }
+}
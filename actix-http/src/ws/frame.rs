@@ -16,7 +16,8 @@ impl Parser {
         src: &[u8],
         server: bool,
         max_size: usize,
-    ) -> Result<Option<(usize, bool, OpCode, usize, Option<[u8; 4]>)>, ProtocolError>
+        allow_rsv1: bool,
+    ) -> Result<Option<(usize, bool, bool, OpCode, usize, Option<[u8; 4]>)>, ProtocolError>
     {
         let chunk_len = src.len();
 
@@ -44,6 +45,17 @@ impl Parser {
             return Err(ProtocolError::InvalidOpcode(first & 0x0F));
         }
 
+        // reserved bits; only RSV1 has a meaning and only when an extension defines it
+        let rsv1 = first & 0x40 != 0;
+        if first & 0x30 != 0 || (rsv1 && !allow_rsv1) {
+            return Err(ProtocolError::ReservedBitsSet);
+        }
+
+        // RSV1 marks the first frame of a compressed message
+        if rsv1 && !matches!(opcode, OpCode::Text | OpCode::Binary) {
+            return Err(ProtocolError::ReservedBitsSet);
+        }
+
         let len = second & 0x7F;
         let length = if len == 126 {
             if chunk_len < 4 {
@@ -87,18 +99,34 @@ impl Parser {
             None
         };
 
-        Ok(Some((idx, finished, opcode, length, mask)))
+        Ok(Some((idx, finished, rsv1, opcode, length, mask)))
     }
 
     /// Parse the input stream into a frame.
+    ///
+    /// Frames with any reserved bits set are rejected.
     pub fn parse(
         src: &mut BytesMut,
         server: bool,
         max_size: usize,
     ) -> Result<Option<(bool, OpCode, Option<BytesMut>)>, ProtocolError> {
+        Ok(Parser::parse_frame(src, server, max_size, false)?
+            .map(|(finished, _, opcode, payload)| (finished, opcode, payload)))
+    }
+
+    /// Parse the input stream into a frame, optionally allowing the RSV1 bit to be set.
+    ///
+    /// Returns whether the frame is finished and whether RSV1 is set, along with the opcode
+    /// and payload.
+    pub(crate) fn parse_frame(
+        src: &mut BytesMut,
+        server: bool,
+        max_size: usize,
+        allow_rsv1: bool,
+    ) -> Result<Option<(bool, bool, OpCode, Option<BytesMut>)>, ProtocolError> {
         // try to parse ws frame metadata
-        let (idx, finished, opcode, length, mask) =
-            match Parser::parse_metadata(src, server, max_size)? {
+        let (idx, finished, rsv1, opcode, length, mask) =
+            match Parser::parse_metadata(src, server, max_size, allow_rsv1)? {
                 None => return Ok(None),
                 Some(res) => res,
             };
@@ -113,7 +141,7 @@ impl Parser {
 
         // no need for body
         if length == 0 {
-            return Ok(Some((finished, opcode, None)));
+            return Ok(Some((finished, rsv1, opcode, None)));
         }
 
         let mut data = src.split_to(length);
@@ -125,7 +153,7 @@ impl Parser {
             }
             OpCode::Close if length > 125 => {
                 debug!("Received close frame with payload length exceeding 125. Morphing to protocol close frame.");
-                return Ok(Some((true, OpCode::Close, None)));
+                return Ok(Some((true, false, OpCode::Close, None)));
             }
             _ => {}
         }
@@ -135,7 +163,7 @@ impl Parser {
             apply_mask(&mut data, mask);
         }
 
-        Ok(Some((finished, opcode, Some(data))))
+        Ok(Some((finished, rsv1, opcode, Some(data))))
     }
 
     /// Parse the payload of a close frame.
@@ -161,13 +189,27 @@ impl Parser {
         op: OpCode,
         fin: bool,
         mask: bool,
+    ) {
+        Parser::write_frame(dst, pl, op, fin, false, mask)
+    }
+
+    /// Generate binary representation, optionally setting the RSV1 bit.
+    pub(crate) fn write_frame<B: AsRef<[u8]>>(
+        dst: &mut BytesMut,
+        pl: B,
+        op: OpCode,
+        fin: bool,
+        rsv1: bool,
+        mask: bool,
     ) {
         let payload = pl.as_ref();
-        let one: u8 = if fin {
-            0x80 | Into::<u8>::into(op)
-        } else {
-            op.into()
-        };
+        let mut one: u8 = op.into();
+        if fin {
+            one |= 0x80;
+        }
+        if rsv1 {
+            one |= 0x40;
+        }
         let payload_len = payload.len();
         let (two, p_len) = if mask {
             (0x80, payload_len + 4)
@@ -342,6 +384,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_reserved_bits() {
+        let mut buf = BytesMut::from(&[0b0100_0001u8, 0b0000_0001u8][..]);
+        buf.extend(b"1");
+        assert!(matches!(
+            Parser::parse(&mut buf, false, 1024),
+            Err(ProtocolError::ReservedBitsSet)
+        ));
+
+        let mut buf = BytesMut::from(&[0b0100_0001u8, 0b0000_0001u8][..]);
+        buf.extend(b"1");
+        let (finished, rsv1, opcode, _) =
+            Parser::parse_frame(&mut buf, false, 1024, true)
+                .unwrap()
+                .unwrap();
+        assert!(!finished);
+        assert!(rsv1);
+        assert_eq!(opcode, OpCode::Text);
+
+        // RSV2 is never allowed
+        let mut buf = BytesMut::from(&[0b0010_0001u8, 0b0000_0000u8][..]);
+        assert!(Parser::parse_frame(&mut buf, false, 1024, true).is_err());
+
+        // RSV1 on control frames is never allowed
+        let mut buf = BytesMut::from(&[0b1100_1001u8, 0b0000_0000u8][..]);
+        assert!(Parser::parse_frame(&mut buf, false, 1024, true).is_err());
+    }
+
+    #[test]
+    fn test_write_frame_rsv1() {
+        let mut buf = BytesMut::new();
+        Parser::write_frame(&mut buf, "data", OpCode::Text, true, true, false);
+
+        let mut v = vec![0b1100_0001u8, 4u8];
+        v.extend(b"data");
+        assert_eq!(&buf[..], &v[..]);
+    }
+
     #[test]
     fn test_ping_frame() {
         let mut buf = BytesMut::new();
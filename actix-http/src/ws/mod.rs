@@ -14,12 +14,16 @@ use crate::{
 };
 
 mod codec;
+#[cfg(feature = "compress-gzip")]
+mod deflate;
 mod dispatcher;
 mod frame;
 mod mask;
 mod proto;
 
 pub use self::codec::{Codec, Frame, Item, Message};
+#[cfg(feature = "compress-gzip")]
+pub use self::deflate::{DeflateConfig, DeflateNegotiationError, DeflateParams};
 pub use self::dispatcher::Dispatcher;
 pub use self::frame::Parser;
 pub use self::proto::{hash_key, CloseCode, CloseReason, OpCode};
@@ -39,6 +43,10 @@ pub enum ProtocolError {
     #[display(fmt = "Invalid opcode: {}.", _0)]
     InvalidOpcode(#[error(not(source))] u8),
 
+    /// Received a frame with reserved bits set that no negotiated extension defines.
+    #[display(fmt = "Received a frame with unexpected reserved bits set.")]
+    ReservedBitsSet,
+
     /// Invalid control frame length
     #[display(fmt = "Invalid control frame length: {}.", _0)]
     InvalidLength(#[error(not(source))] usize),
@@ -151,6 +159,44 @@ pub fn handshake(req: &RequestHead) -> Result<ResponseBuilder, HandshakeError> {
     Ok(handshake_response(req))
 }
 
+/// Verify WebSocket handshake request and create handshake response, negotiating the
+/// permessage-deflate extension if the client offers it.
+///
+/// Returns the response builder along with a server mode codec that uses the negotiated
+/// compression parameters, if any.
+#[cfg(feature = "compress-gzip")]
+pub fn handshake_with_deflate(
+    req: &RequestHead,
+    config: &DeflateConfig,
+) -> Result<(ResponseBuilder, Codec), HandshakeError> {
+    verify_handshake(req)?;
+
+    let mut res = handshake_response(req);
+    let (codec, extension) = negotiate_deflate(req, config);
+
+    if let Some(extension) = extension {
+        res.insert_header((header::SEC_WEBSOCKET_EXTENSIONS, extension));
+    }
+
+    Ok((res, codec))
+}
+
+/// Negotiate the permessage-deflate extension offered in a handshake request.
+///
+/// Returns a server mode codec that uses the negotiated compression parameters, along with the
+/// `Sec-WebSocket-Extensions` header value to send in the handshake response. If no offer is
+/// acceptable, the codec does not compress and no header value is returned.
+#[cfg(feature = "compress-gzip")]
+pub fn negotiate_deflate(
+    req: &RequestHead,
+    config: &DeflateConfig,
+) -> (Codec, Option<HeaderValue>) {
+    match config.negotiate(req.headers()) {
+        Some((extension, params)) => (Codec::new().deflate(params), Some(extension)),
+        None => (Codec::new(), None),
+    }
+}
+
 /// Verify WebSocket handshake request.
 pub fn verify_handshake(req: &RequestHead) -> Result<(), HandshakeError> {
     // WebSocket accepts only GET
@@ -335,6 +381,44 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "compress-gzip")]
+    #[test]
+    fn test_handshake_with_deflate() {
+        let req = TestRequest::default()
+            .insert_header((header::UPGRADE, "websocket"))
+            .insert_header((header::CONNECTION, "upgrade"))
+            .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+            .insert_header((header::SEC_WEBSOCKET_KEY, "13"))
+            .insert_header((
+                header::SEC_WEBSOCKET_EXTENSIONS,
+                "permessage-deflate; client_max_window_bits",
+            ))
+            .finish();
+
+        let (mut res, _) =
+            handshake_with_deflate(req.head(), &DeflateConfig::new()).unwrap();
+        let res = res.finish();
+        assert_eq!(res.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert_eq!(
+            res.headers().get(header::SEC_WEBSOCKET_EXTENSIONS).unwrap(),
+            "permessage-deflate"
+        );
+
+        let req = TestRequest::default()
+            .insert_header((header::UPGRADE, "websocket"))
+            .insert_header((header::CONNECTION, "upgrade"))
+            .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+            .insert_header((header::SEC_WEBSOCKET_KEY, "13"))
+            .finish();
+
+        let (mut res, _) =
+            handshake_with_deflate(req.head(), &DeflateConfig::new()).unwrap();
+        assert!(!res
+            .finish()
+            .headers()
+            .contains_key(header::SEC_WEBSOCKET_EXTENSIONS));
+    }
+
     #[test]
     fn test_ws_error_http_response() {
         let resp: Response<AnyBody> = HandshakeError::GetMethodRequired.into();
@@ -1,22 +1,26 @@
 use std::{
     convert::Infallible,
     io::{Read, Write},
-    net, thread,
+    mem, net,
+    pin::Pin,
+    task::{Context, Poll},
+    thread,
     time::Duration,
 };
 
 use actix_http::{
-    body::{AnyBody, Body, SizedStream},
+    body::{AnyBody, Body, BodySize, MessageBody, SizedStream},
     header, http, Error, HttpMessage, HttpService, KeepAlive, Request, Response,
-    StatusCode,
+    ShutdownSignal, StatusCode,
 };
 use actix_http_test::test_server;
 use actix_rt::time::sleep;
 use actix_service::fn_service;
 use actix_utils::future::{err, ok, ready};
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use derive_more::{Display, Error};
 use futures_util::{
+    future::{select, Either},
     stream::{once, StreamExt as _},
     FutureExt as _,
 };
@@ -131,6 +135,37 @@ async fn test_expect_continue_h1() {
     assert!(data.starts_with("HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\n"));
 }
 
+#[actix_rt::test]
+async fn test_h1_request_limits() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .max_request_headers(2)
+            .max_uri_len(16)
+            .h1(|_| ok::<_, Infallible>(Response::ok()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\nx-one: 1\r\nx-two: 2\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let _ = stream.read(&mut data);
+    assert!(data.starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ =
+        stream.write_all(b"GET /test HTTP/1.1\r\nx-1: 1\r\nx-2: 2\r\nx-3: 3\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /this/path/is/too/long HTTP/1.1\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 414 URI Too Long\r\n"));
+}
+
 #[actix_rt::test]
 async fn test_chunked_payload() {
     let chunk_sizes = vec![32768, 32, 32768];
@@ -636,6 +671,247 @@ async fn test_h1_body_chunked_implicit() {
     assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
 }
 
+struct TrailersBody {
+    body: Bytes,
+    trailers: Option<http::HeaderMap>,
+}
+
+impl MessageBody for TrailersBody {
+    type Error = Infallible;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let body = mem::take(&mut self.get_mut().body);
+
+        if body.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Ready(Some(Ok(body)))
+        }
+    }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<http::HeaderMap> {
+        self.get_mut().trailers.take()
+    }
+}
+
+#[actix_rt::test]
+async fn test_h1_trailers() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(|mut req: Request| async move {
+                let mut pl = req.take_payload();
+                let mut body = BytesMut::new();
+                while let Some(chunk) = pl.next().await {
+                    body.extend_from_slice(&chunk?);
+                }
+
+                let trailers = pl.take_trailers();
+                Ok::<_, Error>(Response::ok().set_body(TrailersBody {
+                    body: body.freeze(),
+                    trailers,
+                }))
+            })
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"POST /test HTTP/1.1\r\nconnection: close\r\nte: trailers\r\n\
+          transfer-encoding: chunked\r\n\r\n4\r\ndata\r\n0\r\nx-checksum: abc\r\n\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(data.ends_with("\r\n\r\n4\r\ndata\r\n0\r\nx-checksum: abc\r\n\r\n"));
+
+    // trailers are not sent to clients that do not accept them
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"POST /test HTTP/1.1\r\nconnection: close\r\n\
+          transfer-encoding: chunked\r\n\r\n4\r\ndata\r\n0\r\nx-checksum: abc\r\n\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.ends_with("\r\n\r\n4\r\ndata\r\n0\r\n\r\n"));
+}
+
+#[actix_rt::test]
+async fn test_h2_trailers() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2(|mut req: Request| async move {
+                let mut pl = req.take_payload();
+                let mut body = BytesMut::new();
+                while let Some(chunk) = pl.next().await {
+                    body.extend_from_slice(&chunk?);
+                }
+
+                let trailers = pl.take_trailers();
+                Ok::<_, Error>(Response::ok().set_body(TrailersBody {
+                    body: body.freeze(),
+                    trailers,
+                }))
+            })
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    let mut trailers = ::http::HeaderMap::new();
+    trailers.insert("x-checksum", ::http::HeaderValue::from_static("abc"));
+
+    let req = ::http::Request::post("/").body(()).unwrap();
+    let (res, mut stream) = client.send_request(req, false).unwrap();
+    stream
+        .send_data(Bytes::from_static(b"data"), false)
+        .unwrap();
+    stream.send_trailers(trailers).unwrap();
+    let mut body = res.await.unwrap().into_body();
+
+    let chunk = body.data().await.unwrap().unwrap();
+    assert_eq!(chunk, Bytes::from_static(b"data"));
+    assert!(body.data().await.is_none());
+
+    let trailers = body.trailers().await.unwrap().unwrap();
+    assert_eq!(trailers.get("x-checksum").unwrap(), "abc");
+}
+
+#[actix_rt::test]
+async fn test_h2_settings() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_max_concurrent_streams(1)
+            .h2_initial_window_size(1_048_576)
+            .h2_max_frame_size(32_768)
+            .h2(|_| ok::<_, Infallible>(Response::ok()))
+            .tcp()
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, mut conn) = h2::client::handshake(io).await.unwrap();
+
+    let req = ::http::Request::get("/").body(()).unwrap();
+    let (res, _) = client.send_request(req, true).unwrap();
+
+    // drive connection until response is received so that server settings are applied
+    let res = match select(&mut conn, res).await {
+        Either::Right((res, _)) => res.unwrap(),
+        Either::Left(_) => panic!("connection closed before response"),
+    };
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(conn.max_concurrent_send_streams(), 1);
+}
+
+#[actix_rt::test]
+async fn test_h2_keep_alive_ping() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_keep_alive_interval(Duration::from_millis(50))
+            .h2_keep_alive_timeout(Duration::from_millis(100))
+            .h2(|_| ok::<_, Infallible>(Response::ok()))
+            .tcp()
+    })
+    .await;
+
+    // client acknowledges PINGs; connection is kept open
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    sleep(Duration::from_millis(400)).await;
+
+    let req = ::http::Request::get("/").body(()).unwrap();
+    let (res, _) = client.send_request(req, true).unwrap();
+    assert_eq!(res.await.unwrap().status(), StatusCode::OK);
+
+    // client never acknowledges PINGs; connection is closed by server
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    stream
+        .write_all(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n")
+        .unwrap();
+    // empty SETTINGS frame
+    stream.write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0]).unwrap();
+
+    let mut buf = [0; 1024];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => continue,
+            Err(err) if err.kind() == std::io::ErrorKind::ConnectionReset => break,
+            Err(err) => panic!("connection was not closed: {}", err),
+        }
+    }
+}
+
+#[actix_rt::test]
+async fn test_h2_graceful_shutdown() {
+    let signal = ShutdownSignal::new();
+
+    let srv = test_server({
+        let signal = signal.clone();
+        move || {
+            HttpService::build()
+                .shutdown_signal(signal.clone())
+                .h2(|_| async {
+                    sleep(Duration::from_millis(300)).await;
+                    Ok::<_, Infallible>(Response::ok().set_body(STR))
+                })
+                .tcp()
+        }
+    })
+    .await;
+
+    let io = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    let conn = actix_rt::spawn(conn);
+
+    let req = ::http::Request::get("/").body(()).unwrap();
+    let (res, _) = client.send_request(req, true).unwrap();
+
+    // trigger shutdown while request is in flight
+    sleep(Duration::from_millis(100)).await;
+    signal.shutdown();
+
+    // in-flight stream completes
+    let res = res.await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let mut body = res.into_body();
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        bytes.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(bytes, STR.as_bytes());
+
+    // connection is closed after GOAWAY without error
+    let conn = actix_rt::time::timeout(Duration::from_secs(5), conn)
+        .await
+        .expect("connection was not closed");
+    assert!(conn.unwrap().is_ok());
+
+    // new streams are refused
+    let req = ::http::Request::get("/").body(()).unwrap();
+    assert!(client.send_request(req, true).is_err());
+}
+
 #[actix_rt::test]
 async fn test_h1_response_http_error_handling() {
     let mut srv = test_server(|| {
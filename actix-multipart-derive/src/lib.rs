@@ -0,0 +1,345 @@
+//! Multipart form derive macro for Actix Web.
+//!
+//! See [`macro@MultipartForm`] for usage examples.
+
+#![deny(rust_2018_idioms)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned as _, Data, DeriveInput, Fields, Lit, Meta, NestedMeta,
+};
+
+/// Implements `MultipartCollect` for a struct so that it can be used with the `MultipartForm`
+/// extractor.
+///
+/// # Basic Use
+/// Each field type should implement the `FieldReader` trait:
+///
+/// ```
+/// use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// struct ImageUpload {
+///     description: Text<String>,
+///     timestamp: Text<i64>,
+///     image: TempFile,
+/// }
+/// ```
+///
+/// # Optional and List Fields
+/// You can also use `Vec<T>` and `Option<T>` provided that `T: FieldReader`.
+///
+/// A [`Vec`] field corresponds to an upload with multiple parts under the same field name.
+///
+/// ```
+/// use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// struct Form {
+///     category: Option<Text<String>>,
+///     files: Vec<TempFile>,
+/// }
+/// ```
+///
+/// # Field Renaming
+/// You can use the `#[multipart(rename = "foo")]` attribute to receive a field by a different
+/// name.
+///
+/// ```
+/// use actix_multipart::form::{tempfile::TempFile, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// struct Form {
+///     #[multipart(rename = "files[]")]
+///     files: Vec<TempFile>,
+/// }
+/// ```
+///
+/// # Field Limits
+/// You can use the `#[multipart(limit = "<size>")]` attribute to set field level limits. The limit
+/// string is a number of bytes with an optional unit, such as `"100"`, `"10 KB"` or `"2 MiB"`.
+///
+/// For `Vec` fields, the limit applies to each part individually.
+///
+/// ```
+/// use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// struct Form {
+///     #[multipart(limit = "2 KiB")]
+///     description: Text<String>,
+///
+///     #[multipart(limit = "512 MiB")]
+///     files: Vec<TempFile>,
+/// }
+/// ```
+///
+/// # Unknown Fields
+/// By default, fields that are not part of the struct are ignored, although their data still
+/// counts towards the total limit of the form. Use the `#[multipart(deny_unknown_fields)]`
+/// attribute to reject them with an `UnsupportedField` error instead.
+///
+/// ```
+/// use actix_multipart::form::{text::Text, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// #[multipart(deny_unknown_fields)]
+/// struct Form {
+///     name: Text<String>,
+/// }
+/// ```
+///
+/// # Duplicate Fields
+/// Fields that are not a `Vec` may only be sent once; duplicates are rejected with a
+/// `DuplicateField` error.
+#[proc_macro_derive(MultipartForm, attributes(multipart))]
+pub fn impl_multipart_form(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct FormField {
+    ident: syn::Ident,
+    ty: syn::Type,
+    name: String,
+    limit: Option<usize>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "MultipartForm can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "MultipartForm can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut deny_unknown_fields = false;
+
+    for meta in multipart_attrs(&input.attrs)? {
+        match meta {
+            NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("deny_unknown_fields") => {
+                deny_unknown_fields = true;
+            }
+            meta => return Err(syn::Error::new(meta.span(), "unknown multipart attribute")),
+        }
+    }
+
+    let mut form_fields = Vec::with_capacity(fields.len());
+
+    for field in fields {
+        let ident = field.ident.clone().expect("named fields have identifiers");
+
+        let mut name = ident.to_string();
+        let mut limit = None;
+
+        for meta in multipart_attrs(&field.attrs)? {
+            match meta {
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("rename") => {
+                    match nv.lit {
+                        Lit::Str(ref lit) => name = lit.value(),
+                        ref lit => return Err(syn::Error::new(lit.span(), "expected string")),
+                    }
+                }
+
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("limit") => {
+                    let size = match nv.lit {
+                        Lit::Str(ref lit) => parse_size(&lit.value()),
+                        Lit::Int(ref lit) => lit.base10_parse().ok(),
+                        _ => None,
+                    };
+
+                    match size {
+                        Some(size) => limit = Some(size),
+                        None => {
+                            return Err(syn::Error::new(
+                                nv.lit.span(),
+                                "invalid limit, expected a size such as \"512 KiB\"",
+                            ))
+                        }
+                    }
+                }
+
+                meta => {
+                    return Err(syn::Error::new(meta.span(), "unknown multipart attribute"))
+                }
+            }
+        }
+
+        if form_fields.iter().any(|f: &FormField| f.name == name) {
+            return Err(syn::Error::new(
+                field.span(),
+                format!("multiple fields are named `{}`", name),
+            ));
+        }
+
+        form_fields.push(FormField {
+            ident,
+            ty: field.ty.clone(),
+            name,
+            limit,
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let limits = form_fields.iter().filter_map(|field| {
+        let name = &field.name;
+        field
+            .limit
+            .map(|limit| quote!(#name => ::std::option::Option::Some(#limit),))
+    });
+
+    let handlers = form_fields.iter().map(|field| {
+        let name = &field.name;
+        let ty = &field.ty;
+        quote! {
+            #name => <#ty as ::actix_multipart::form::FieldGroupReader>::handle_field(
+                req, field, limits, state,
+            ),
+        }
+    });
+
+    let unknown_field = if deny_unknown_fields {
+        quote! {
+            ::std::boxed::Box::pin(async move {
+                ::std::result::Result::Err(
+                    ::actix_multipart::MultipartError::UnsupportedField(field_name),
+                )
+            })
+        }
+    } else {
+        quote!(::std::boxed::Box::pin(::actix_multipart::form::skip_field(
+            field, limits
+        ),))
+    };
+
+    let initializers = form_fields.iter().map(|field| {
+        let ident = &field.ident;
+        let name = &field.name;
+        let ty = &field.ty;
+        quote! {
+            #ident: <#ty as ::actix_multipart::form::FieldGroupReader>::from_state(
+                #name, &mut state,
+            )?,
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::actix_multipart::form::MultipartCollect for #ident #ty_generics
+        #where_clause
+        {
+            fn limit(field_name: &str) -> ::std::option::Option<usize> {
+                match field_name {
+                    #(#limits)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn handle_field<'t>(
+                req: &'t ::actix_web::HttpRequest,
+                field: ::actix_multipart::Field,
+                limits: &'t mut ::actix_multipart::form::Limits,
+                state: &'t mut ::actix_multipart::form::State,
+            ) -> ::std::pin::Pin<
+                ::std::boxed::Box<
+                    dyn ::std::future::Future<
+                            Output = ::std::result::Result<
+                                (),
+                                ::actix_multipart::MultipartError,
+                            >,
+                        > + 't,
+                >,
+            > {
+                let field_name = field.name().unwrap_or_default().to_owned();
+
+                match field_name.as_str() {
+                    #(#handlers)*
+                    _ => #unknown_field,
+                }
+            }
+
+            fn from_state(
+                mut state: ::actix_multipart::form::State,
+            ) -> ::std::result::Result<Self, ::actix_multipart::MultipartError> {
+                ::std::result::Result::Ok(Self {
+                    #(#initializers)*
+                })
+            }
+        }
+    })
+}
+
+/// Collects the items of all `#[multipart(...)]` attributes.
+fn multipart_attrs(attrs: &[syn::Attribute]) -> syn::Result<Vec<NestedMeta>> {
+    let mut items = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("multipart")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => items.extend(list.nested),
+            meta => {
+                return Err(syn::Error::new(
+                    meta.span(),
+                    "expected #[multipart(...)] attribute",
+                ))
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+/// Parses a size such as `"512"`, `"10 KB"` or `"2 MiB"` into a number of bytes.
+fn parse_size(size: &str) -> Option<usize> {
+    let size = size.trim();
+    let unit_start = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(unit_start);
+
+    let multiplier: usize = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "kib" => 1 << 10,
+        "mb" => 1_000_000,
+        "mib" => 1 << 20,
+        "gb" => 1_000_000_000,
+        "gib" => 1 << 30,
+        _ => return None,
+    };
+
+    number.parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("10 KB"), Some(10_000));
+        assert_eq!(parse_size("2MiB"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_size(" 1 gib "), Some(1 << 30));
+        assert_eq!(parse_size("MiB"), None);
+        assert_eq!(parse_size("2 MiBs"), None);
+        assert_eq!(parse_size("-1"), None);
+    }
+}
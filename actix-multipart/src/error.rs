@@ -1,5 +1,7 @@
 //! Error and Result module
-use actix_web::error::{ParseError, PayloadError};
+use std::io;
+
+use actix_web::error::{Error, ParseError, PayloadError};
 use actix_web::http::StatusCode;
 use actix_web::ResponseError;
 use derive_more::{Display, From};
@@ -31,14 +33,55 @@ pub enum MultipartError {
     /// Not consumed
     #[display(fmt = "Multipart stream is not consumed")]
     NotConsumed,
+    /// Field has no name in its `Content-Disposition` header
+    #[display(fmt = "Content-Disposition name parameter is not found")]
+    ContentDispositionNameMissing,
+    /// Form field is missing
+    #[display(fmt = "Field `{}` is missing", _0)]
+    #[from(ignore)]
+    MissingField(String),
+    /// Form field is sent more than once
+    #[display(fmt = "Field `{}` is sent more than once", _0)]
+    #[from(ignore)]
+    DuplicateField(String),
+    /// Form field is not expected
+    #[display(fmt = "Field `{}` is not supported", _0)]
+    #[from(ignore)]
+    UnsupportedField(String),
+    /// Form field is larger than its size limit
+    #[display(fmt = "Field `{}` exceeds its size limit", _0)]
+    #[from(ignore)]
+    FieldLimitExceeded(String),
+    /// Form is larger than the configured size limits
+    #[display(fmt = "Multipart form exceeds its size limit")]
+    LimitExceeded,
+    /// Form field could not be read
+    #[display(fmt = "Field `{}` could not be read: {}", name, source)]
+    #[from(ignore)]
+    Field {
+        /// Name of the field.
+        name: String,
+        /// Error that occurred.
+        source: Error,
+    },
+    /// I/O error while storing a form field
+    #[display(fmt = "{}", _0)]
+    Io(io::Error),
 }
 
 impl std::error::Error for MultipartError {}
 
-/// Return `BadRequest` for `MultipartError`
+/// Return `BadRequest` for `MultipartError`, unless the error calls for a more specific status code
 impl ResponseError for MultipartError {
     fn status_code(&self) -> StatusCode {
-        StatusCode::BAD_REQUEST
+        match self {
+            MultipartError::FieldLimitExceeded(_) | MultipartError::LimitExceeded => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            MultipartError::Field { source, .. } => source.as_response_error().status_code(),
+            MultipartError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
     }
 }
 
@@ -50,5 +93,8 @@ mod tests {
     fn test_multipart_error() {
         let resp = MultipartError::Boundary.error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = MultipartError::FieldLimitExceeded("file".to_owned()).error_response();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
     }
 }
@@ -0,0 +1,45 @@
+//! Reads a field into memory.
+
+use actix_web::HttpRequest;
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    form::{read_field_bytes, FieldReader, Limits},
+    Field, MultipartError,
+};
+
+/// Field reader that reads a field, usually a small file, into memory.
+///
+/// The field counts towards the form's memory limit; use [`TempFile`](super::tempfile::TempFile)
+/// for large files.
+#[derive(Debug, Clone)]
+pub struct Bytes {
+    /// The data.
+    pub data: ::bytes::Bytes,
+
+    /// The value of the `Content-Type` header.
+    pub content_type: mime::Mime,
+
+    /// The `filename` parameter of the `Content-Disposition` header.
+    pub file_name: Option<String>,
+}
+
+impl FieldReader for Bytes {
+    fn read_field<'t>(
+        _req: &'t HttpRequest,
+        mut field: Field,
+        limits: &'t mut Limits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartError>> {
+        Box::pin(async move {
+            let data = read_field_bytes(&mut field, limits).await?;
+
+            Ok(Bytes {
+                data: data.freeze(),
+                content_type: field.content_type().clone(),
+                file_name: field
+                    .content_disposition()
+                    .and_then(|cd| cd.get_filename().map(ToOwned::to_owned)),
+            })
+        })
+    }
+}
@@ -0,0 +1,79 @@
+//! Reads a field into a value deserialized from JSON.
+
+use std::ops;
+
+use actix_web::{error::JsonPayloadError, HttpRequest};
+use futures_core::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    form::{read_field_bytes, FieldReader, Limits},
+    Field, MultipartError,
+};
+
+/// Field reader that deserializes the JSON content of a field.
+///
+/// The field is read into memory, so it counts towards the form's memory limit. Its content type
+/// is not checked, since browsers do not set one for text fields.
+///
+/// # Examples
+/// ```
+/// use actix_multipart::form::{json::Json, MultipartForm};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Metadata {
+///     title: String,
+///     tags: Vec<String>,
+/// }
+///
+/// #[derive(MultipartForm)]
+/// struct Form {
+///     metadata: Json<Metadata>,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    /// Unwraps into the inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Json<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> FieldReader for Json<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    fn read_field<'t>(
+        _req: &'t HttpRequest,
+        mut field: Field,
+        limits: &'t mut Limits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartError>> {
+        Box::pin(async move {
+            let data = read_field_bytes(&mut field, limits).await?;
+
+            serde_json::from_slice(&data)
+                .map(Json)
+                .map_err(|err| MultipartError::Field {
+                    name: field.name().unwrap_or_default().to_owned(),
+                    source: JsonPayloadError::Deserialize(err).into(),
+                })
+        })
+    }
+}
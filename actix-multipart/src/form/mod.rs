@@ -0,0 +1,655 @@
+//! Typed multipart form extraction.
+//!
+//! A struct deriving [`MultipartForm`](derive@MultipartForm) can be extracted from a
+//! `multipart/form-data` request using the [`MultipartForm`](struct@MultipartForm) extractor.
+//! Each of its fields is read by a [`FieldReader`], such as [`Text`](text::Text),
+//! [`Json`](json::Json), [`Bytes`](bytes::Bytes) or [`TempFile`](tempfile::TempFile).
+
+use std::{any::Any, collections::HashMap, ops, sync::Arc};
+
+use ::bytes::BytesMut;
+use actix_web::{dev::Payload, error::Error, web, FromRequest, HttpRequest};
+use futures_core::future::LocalBoxFuture;
+use futures_util::stream::TryStreamExt as _;
+
+use crate::{Field, Multipart, MultipartError};
+
+pub mod bytes;
+pub mod json;
+pub mod tempfile;
+pub mod text;
+
+#[cfg(feature = "derive")]
+pub use actix_multipart_derive::MultipartForm;
+
+/// Values read so far by a form's [`FieldGroupReader`]s, keyed by field name.
+pub type State = HashMap<String, Box<dyn Any>>;
+
+/// Reads a single field of a multipart form.
+pub trait FieldReader: Sized + 'static {
+    /// Reads the field from the multipart stream, consuming its size from `limits`.
+    fn read_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartError>>;
+}
+
+/// Collects the parts sent under one field name.
+///
+/// Implemented for any `T: FieldReader`, which must be sent exactly once, `Option<T>`, which may
+/// be sent at most once, and `Vec<T>`, which may be sent any number of times.
+pub trait FieldGroupReader: Sized {
+    /// Reads a part of this field group into `state`.
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+        state: &'t mut State,
+    ) -> LocalBoxFuture<'t, Result<(), MultipartError>>;
+
+    /// Takes the field group out of `state` once the whole form has been read.
+    fn from_state(name: &str, state: &mut State) -> Result<Self, MultipartError>;
+}
+
+impl<T: FieldReader> FieldGroupReader for T {
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+        state: &'t mut State,
+    ) -> LocalBoxFuture<'t, Result<(), MultipartError>> {
+        Box::pin(async move {
+            let name = field.name().unwrap_or_default().to_owned();
+
+            if state.contains_key(&name) {
+                return Err(MultipartError::DuplicateField(name));
+            }
+
+            let value = T::read_field(req, field, limits).await?;
+            state.insert(name, Box::new(value));
+            Ok(())
+        })
+    }
+
+    fn from_state(name: &str, state: &mut State) -> Result<Self, MultipartError> {
+        match state.remove(name) {
+            Some(value) => Ok(*value.downcast::<T>().expect("field group type mismatch")),
+            None => Err(MultipartError::MissingField(name.to_owned())),
+        }
+    }
+}
+
+impl<T: FieldReader> FieldGroupReader for Option<T> {
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+        state: &'t mut State,
+    ) -> LocalBoxFuture<'t, Result<(), MultipartError>> {
+        T::handle_field(req, field, limits, state)
+    }
+
+    fn from_state(name: &str, state: &mut State) -> Result<Self, MultipartError> {
+        match T::from_state(name, state) {
+            Ok(value) => Ok(Some(value)),
+            Err(MultipartError::MissingField(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl<T: FieldReader> FieldGroupReader for Vec<T> {
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+        state: &'t mut State,
+    ) -> LocalBoxFuture<'t, Result<(), MultipartError>> {
+        Box::pin(async move {
+            let name = field.name().unwrap_or_default().to_owned();
+            let value = T::read_field(req, field, limits).await?;
+
+            state
+                .entry(name)
+                .or_insert_with(|| Box::new(Vec::<T>::new()))
+                .downcast_mut::<Vec<T>>()
+                .expect("field group type mismatch")
+                .push(value);
+
+            Ok(())
+        })
+    }
+
+    fn from_state(name: &str, state: &mut State) -> Result<Self, MultipartError> {
+        match state.remove(name) {
+            Some(values) => Ok(*values
+                .downcast::<Vec<T>>()
+                .expect("field group type mismatch")),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// A form that can be collected from a multipart stream, field by field.
+///
+/// Usually implemented with `#[derive(MultipartForm)]`.
+pub trait MultipartCollect: Sized {
+    /// Returns the size limit of the field with the given name, if it has one.
+    fn limit(field_name: &str) -> Option<usize>;
+
+    /// Reads a field of the form into `state`.
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut Limits,
+        state: &'t mut State,
+    ) -> LocalBoxFuture<'t, Result<(), MultipartError>>;
+
+    /// Builds the form once all of its fields have been read.
+    fn from_state(state: State) -> Result<Self, MultipartError>;
+}
+
+/// Size limits of a multipart form that is being read.
+#[derive(Debug)]
+pub struct Limits {
+    total_remaining: usize,
+    memory_remaining: usize,
+    field_remaining: Option<usize>,
+}
+
+impl Limits {
+    /// Constructs limits allowing `total_limit` bytes overall, of which at most `memory_limit`
+    /// bytes may be held in memory.
+    pub fn new(total_limit: usize, memory_limit: usize) -> Self {
+        Limits {
+            total_remaining: total_limit,
+            memory_remaining: memory_limit,
+            field_remaining: None,
+        }
+    }
+
+    /// Consumes `bytes` of the limits for a chunk of the field called `field_name`.
+    ///
+    /// Returns `FieldLimitExceeded` if the field's own limit is exceeded, or `LimitExceeded` if
+    /// the total or memory limit of the form is exceeded.
+    pub fn try_consume_limits(
+        &mut self,
+        field_name: &str,
+        bytes: usize,
+        in_memory: bool,
+    ) -> Result<(), MultipartError> {
+        let field_remaining =
+            match self.field_remaining {
+                Some(remaining) => Some(remaining.checked_sub(bytes).ok_or_else(|| {
+                    MultipartError::FieldLimitExceeded(field_name.to_owned())
+                })?),
+                None => None,
+            };
+
+        let total_remaining = self
+            .total_remaining
+            .checked_sub(bytes)
+            .ok_or(MultipartError::LimitExceeded)?;
+
+        let memory_remaining = if in_memory {
+            self.memory_remaining
+                .checked_sub(bytes)
+                .ok_or(MultipartError::LimitExceeded)?
+        } else {
+            self.memory_remaining
+        };
+
+        self.field_remaining = field_remaining;
+        self.total_remaining = total_remaining;
+        self.memory_remaining = memory_remaining;
+
+        Ok(())
+    }
+}
+
+/// Reads the remaining data of a field into memory.
+pub(crate) async fn read_field_bytes(
+    field: &mut Field,
+    limits: &mut Limits,
+) -> Result<BytesMut, MultipartError> {
+    let name = field.name().unwrap_or_default().to_owned();
+    let mut data = BytesMut::new();
+
+    while let Some(chunk) = field.try_next().await? {
+        limits.try_consume_limits(&name, chunk.len(), true)?;
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(data)
+}
+
+/// Reads and discards the remaining data of a field that is not part of the form.
+///
+/// The data of skipped fields still counts towards the total limit of the form.
+#[doc(hidden)]
+pub async fn skip_field(mut field: Field, limits: &mut Limits) -> Result<(), MultipartError> {
+    let name = field.name().unwrap_or_default().to_owned();
+
+    while let Some(chunk) = field.try_next().await? {
+        limits.try_consume_limits(&name, chunk.len(), false)?;
+    }
+
+    Ok(())
+}
+
+/// Typed `multipart/form-data` extractor.
+///
+/// `T` is usually a struct deriving [`MultipartForm`](derive@MultipartForm). Size limits and
+/// error handling can be configured with [`MultipartFormConfig`].
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, Responder};
+/// use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// struct Upload {
+///     description: Text<String>,
+///     files: Vec<TempFile>,
+/// }
+///
+/// #[post("/upload")]
+/// async fn upload(form: MultipartForm<Upload>) -> impl Responder {
+///     format!("received {} files: {}", form.files.len(), *form.description)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MultipartForm<T: MultipartCollect>(pub T);
+
+impl<T: MultipartCollect> MultipartForm<T> {
+    /// Unwraps into the inner form.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: MultipartCollect> ops::Deref for MultipartForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: MultipartCollect> ops::DerefMut for MultipartForm<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> FromRequest for MultipartForm<T>
+where
+    T: MultipartCollect + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    type Config = MultipartFormConfig;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = MultipartFormConfig::from_req(req);
+        let mut limits = Limits::new(config.total_limit, config.memory_limit);
+        let err_handler = config.err_handler.clone();
+
+        let mut multipart = Multipart::new(req.headers(), payload.take());
+        let req = req.clone();
+
+        Box::pin(async move {
+            let mut state = State::new();
+
+            let res = async {
+                while let Some(field) = multipart.try_next().await? {
+                    let name = field
+                        .name()
+                        .ok_or(MultipartError::ContentDispositionNameMissing)?
+                        .to_owned();
+
+                    limits.field_remaining = T::limit(&name);
+                    T::handle_field(&req, field, &mut limits, &mut state).await?;
+                }
+
+                T::from_state(state)
+            }
+            .await;
+
+            match res {
+                Ok(form) => Ok(MultipartForm(form)),
+                Err(err) => Err(match err_handler {
+                    Some(err_handler) => (err_handler)(err, &req),
+                    None => err.into(),
+                }),
+            }
+        })
+    }
+}
+
+type MultipartFormErrorHandler =
+    Option<Arc<dyn Fn(MultipartError, &HttpRequest) -> Error + Send + Sync>>;
+
+/// [`MultipartForm`] extractor configuration.
+///
+/// # Examples
+/// ```
+/// use actix_web::{error, web, App, HttpResponse};
+/// use actix_multipart::form::MultipartFormConfig;
+///
+/// let form_cfg = MultipartFormConfig::default()
+///     // allow forms of up to 100 MiB, at most 4 MiB of which are read into memory
+///     .total_limit(100 * 1024 * 1024)
+///     .memory_limit(4 * 1024 * 1024)
+///     .error_handler(|err, _req| {
+///         error::InternalError::from_response(err, HttpResponse::Conflict().into()).into()
+///     });
+///
+/// App::new().app_data(form_cfg);
+/// ```
+#[derive(Clone)]
+pub struct MultipartFormConfig {
+    total_limit: usize,
+    memory_limit: usize,
+    err_handler: MultipartFormErrorHandler,
+}
+
+impl MultipartFormConfig {
+    /// Set maximum accepted size of a whole form. By default this limit is 50MiB.
+    pub fn total_limit(mut self, total_limit: usize) -> Self {
+        self.total_limit = total_limit;
+        self
+    }
+
+    /// Set maximum size of the form fields that are read into memory, such as text fields. By
+    /// default this limit is 2MiB.
+    pub fn memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Set custom error handler.
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(MultipartError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.err_handler = Some(Arc::new(f));
+        self
+    }
+
+    /// Extract form config from app data. Check both `T` and `Data<T>`, in that order, and fall
+    /// back to the default form config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs used as default.
+const DEFAULT_CONFIG: MultipartFormConfig = MultipartFormConfig {
+    total_limit: 52_428_800, // 50 MiB
+    memory_limit: 2_097_152, // 2 MiB
+    err_handler: None,
+};
+
+impl Default for MultipartFormConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+
+    use actix_web::{
+        http::{header, StatusCode},
+        test::{self, TestRequest},
+        web, App, HttpResponse, Responder,
+    };
+
+    use super::{bytes::Bytes, json::Json, tempfile::TempFile, text::Text, *};
+
+    const BOUNDARY: &str = "abbc761f78ff4d7cb7573b5a23f96ef0";
+
+    /// Form parts as `(name, file name, content)`.
+    type Parts<'a> = &'a [(&'a str, Option<&'a str>, &'a str)];
+
+    /// Builds a multipart request body from its parts.
+    fn form_body(parts: Parts<'_>) -> String {
+        let mut body = String::new();
+
+        for (name, file_name, content) in parts {
+            body.push_str(&format!("--{}\r\n", BOUNDARY));
+
+            match file_name {
+                Some(file_name) => body.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+                     Content-Type: text/plain\r\n",
+                    name, file_name
+                )),
+                None => body.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{}\"\r\n",
+                    name
+                )),
+            }
+
+            body.push_str(&format!("\r\n{}\r\n", content));
+        }
+
+        body.push_str(&format!("--{}--\r\n", BOUNDARY));
+        body
+    }
+
+    fn form_request(parts: Parts<'_>) -> TestRequest {
+        TestRequest::post()
+            .uri("/")
+            .insert_header((
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", BOUNDARY),
+            ))
+            .set_payload(form_body(parts))
+    }
+
+    #[derive(MultipartForm)]
+    struct Upload {
+        title: Text<String>,
+        count: Option<Text<u32>>,
+        #[multipart(rename = "meta")]
+        metadata: Json<HashMap<String, String>>,
+        #[multipart(limit = "16 B")]
+        files: Vec<TempFile>,
+        raw: Option<Bytes>,
+    }
+
+    async fn upload(form: MultipartForm<Upload>) -> impl Responder {
+        let mut files = Vec::new();
+
+        for file in &form.files {
+            let mut content = String::new();
+            (&file.file).read_to_string(&mut content).unwrap();
+            files.push(format!("{:?}:{}", file.file_name, content));
+        }
+
+        format!(
+            "{} {:?} {} [{}] {:?}",
+            *form.title,
+            form.count.as_ref().map(|count| **count),
+            form.metadata["key"],
+            files.join(","),
+            form.raw.as_ref().map(|raw| raw.data.clone()),
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_multipart_form() {
+        let srv = test::init_service(App::new().route("/", web::post().to(upload))).await;
+
+        let req = form_request(&[
+            ("title", None, "Hello"),
+            ("files", Some("a.txt"), "first file"),
+            ("meta", None, "{\"key\": \"value\"}"),
+            ("files", Some("b.txt"), "second file"),
+            ("unknown", None, "ignored"),
+            ("count", None, "3"),
+        ])
+        .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = test::read_body(res).await;
+        assert_eq!(
+            body,
+            "Hello Some(3) value [Some(\"a.txt\"):first file,Some(\"b.txt\"):second file] None"
+        );
+
+        let req = form_request(&[
+            ("meta", None, "{\"key\": \"value\"}"),
+            ("title", None, "Hi"),
+            ("raw", Some("data.bin"), "raw data"),
+        ])
+        .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = test::read_body(res).await;
+        assert_eq!(body, "Hi None value [] Some(b\"raw data\")");
+    }
+
+    #[actix_rt::test]
+    async fn test_multipart_form_errors() {
+        let srv = test::init_service(App::new().route("/", web::post().to(upload))).await;
+
+        let cases: &[(Parts<'_>, StatusCode)] = &[
+            // missing field
+            (&[("title", None, "Hello")], StatusCode::BAD_REQUEST),
+            // duplicate field
+            (
+                &[
+                    ("title", None, "Hello"),
+                    ("title", None, "Hello"),
+                    ("meta", None, "{}"),
+                ],
+                StatusCode::BAD_REQUEST,
+            ),
+            // unparsable text field
+            (
+                &[
+                    ("title", None, "Hello"),
+                    ("count", None, "three"),
+                    ("meta", None, "{}"),
+                ],
+                StatusCode::BAD_REQUEST,
+            ),
+            // invalid json
+            (
+                &[("title", None, "Hello"), ("meta", None, "{")],
+                StatusCode::BAD_REQUEST,
+            ),
+            // field limit
+            (
+                &[
+                    ("title", None, "Hello"),
+                    ("meta", None, "{}"),
+                    ("files", Some("a.txt"), "more than sixteen bytes"),
+                ],
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+        ];
+
+        for (parts, status) in cases {
+            let req = form_request(parts).to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), *status, "{:?}", parts);
+        }
+    }
+
+    #[derive(MultipartForm)]
+    #[multipart(deny_unknown_fields)]
+    struct Strict {
+        name: Text<String>,
+    }
+
+    #[actix_rt::test]
+    async fn test_deny_unknown_fields() {
+        let (req, mut payload) =
+            form_request(&[("name", None, "a"), ("other", None, "b")]).to_http_parts();
+        let err = MultipartForm::<Strict>::from_request(&req, &mut payload)
+            .await
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Field `other` is not supported");
+
+        let (req, mut payload) = form_request(&[("name", None, "a")]).to_http_parts();
+        let form = MultipartForm::<Strict>::from_request(&req, &mut payload)
+            .await
+            .unwrap();
+        assert_eq!(&*form.name, "a");
+    }
+
+    #[actix_rt::test]
+    async fn test_limits() {
+        async fn strict(form: MultipartForm<Strict>) -> HttpResponse {
+            HttpResponse::Ok().body(form.into_inner().name.into_inner())
+        }
+
+        let srv = test::init_service(
+            App::new()
+                .app_data(
+                    MultipartFormConfig::default()
+                        .memory_limit(8)
+                        .error_handler(|err, _| {
+                            let status = match err {
+                                MultipartError::LimitExceeded => StatusCode::IM_A_TEAPOT,
+                                _ => StatusCode::BAD_REQUEST,
+                            };
+                            actix_web::error::InternalError::new(err, status).into()
+                        }),
+                )
+                .route("/", web::post().to(strict)),
+        )
+        .await;
+
+        let req = form_request(&[("name", None, "short")]).to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = form_request(&[("name", None, "too long to keep in memory")]).to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::IM_A_TEAPOT);
+
+        // ignored fields count towards the total limit
+        let (req, mut payload) = form_request(&[
+            ("title", None, "Hi"),
+            ("meta", None, "{}"),
+            ("unknown", None, "too long to be ignored"),
+        ])
+        .app_data(MultipartFormConfig::default().total_limit(16))
+        .to_http_parts();
+        let err = MultipartForm::<Upload>::from_request(&req, &mut payload)
+            .await
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(err.to_string(), MultipartError::LimitExceeded.to_string());
+
+        let mut limits = Limits::new(10, 4);
+        limits.field_remaining = Some(6);
+        limits.try_consume_limits("a", 4, false).unwrap();
+        assert!(matches!(
+            limits.try_consume_limits("a", 3, false),
+            Err(MultipartError::FieldLimitExceeded(ref name)) if name == "a"
+        ));
+
+        let mut limits = Limits::new(10, 4);
+        limits.try_consume_limits("a", 4, true).unwrap();
+        assert!(matches!(
+            limits.try_consume_limits("b", 1, true),
+            Err(MultipartError::LimitExceeded)
+        ));
+        limits.try_consume_limits("b", 6, false).unwrap();
+        assert!(matches!(
+            limits.try_consume_limits("c", 1, false),
+            Err(MultipartError::LimitExceeded)
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+//! Writes a field to a temporary file on disk.
+
+use std::{
+    collections::hash_map::RandomState,
+    env, fmt,
+    fs::{self, File, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::{self, Seek as _, SeekFrom, Write as _},
+    path::{Path, PathBuf},
+};
+
+use actix_web::{web, HttpRequest};
+use futures_core::future::LocalBoxFuture;
+use futures_util::stream::TryStreamExt as _;
+
+use crate::{
+    form::{FieldReader, Limits},
+    Field, MultipartError,
+};
+
+/// Field reader that streams a field to a temporary file.
+///
+/// The file is created in the directory set by [`TempFileConfig`], or the system's temporary
+/// directory by default, and is deleted when the `TempFile` is dropped unless it has been
+/// [persisted](Self::persist). Temporary files do not count towards the form's memory limit.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, Error, HttpResponse};
+/// use actix_multipart::form::{tempfile::TempFile, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// struct Upload {
+///     file: TempFile,
+/// }
+///
+/// #[post("/upload")]
+/// async fn upload(form: MultipartForm<Upload>) -> Result<HttpResponse, Error> {
+///     let mut file = form.into_inner().file;
+///     file.persist("./uploads/latest")?;
+///     Ok(HttpResponse::Ok().finish())
+/// }
+/// ```
+pub struct TempFile {
+    /// The file, opened for reading and writing and positioned at its start.
+    pub file: File,
+
+    /// The value of the `Content-Type` header.
+    pub content_type: mime::Mime,
+
+    /// The `filename` parameter of the `Content-Disposition` header.
+    pub file_name: Option<String>,
+
+    /// The size of the file in bytes.
+    pub size: usize,
+
+    path: PathBuf,
+    persisted: bool,
+}
+
+impl TempFile {
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Moves the file to `path` so that it is kept after the `TempFile` is dropped.
+    ///
+    /// The file is renamed, so `path` must be on the same file system as the temporary
+    /// directory.
+    pub fn persist(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::rename(&self.path, path.as_ref())?;
+        self.path = path.as_ref().to_owned();
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for TempFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TempFile")
+            .field("path", &self.path)
+            .field("content_type", &self.content_type)
+            .field("file_name", &self.file_name)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl FieldReader for TempFile {
+    fn read_field<'t>(
+        req: &'t HttpRequest,
+        mut field: Field,
+        limits: &'t mut Limits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartError>> {
+        Box::pin(async move {
+            let name = field.name().unwrap_or_default().to_owned();
+            let dir = TempFileConfig::from_req(req)
+                .directory
+                .unwrap_or_else(env::temp_dir);
+
+            let (file, path) = web::block(move || create_file(&dir))
+                .await
+                .map_err(|_| blocking_error())??;
+
+            // removes the file if reading the field fails
+            let mut temp_file = TempFile {
+                file,
+                content_type: field.content_type().clone(),
+                file_name: field
+                    .content_disposition()
+                    .and_then(|cd| cd.get_filename().map(ToOwned::to_owned)),
+                size: 0,
+                path,
+                persisted: false,
+            };
+
+            while let Some(chunk) = field.try_next().await? {
+                limits.try_consume_limits(&name, chunk.len(), false)?;
+                temp_file.size += chunk.len();
+
+                let mut file = temp_file.file.try_clone()?;
+                web::block(move || file.write_all(&chunk))
+                    .await
+                    .map_err(|_| blocking_error())??;
+            }
+
+            // rewind so that the file can be read from the start
+            temp_file.file.seek(SeekFrom::Start(0))?;
+
+            Ok(temp_file)
+        })
+    }
+}
+
+/// Creates a new file with a random name in `dir`.
+fn create_file(dir: &Path) -> io::Result<(File, PathBuf)> {
+    loop {
+        // hashers are seeded with random keys
+        let random = RandomState::new().build_hasher().finish();
+        let path = dir.join(format!("actix-multipart-{:016x}", random));
+
+        match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => return Ok((file, path)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn blocking_error() -> MultipartError {
+    MultipartError::Io(io::Error::new(
+        io::ErrorKind::Other,
+        "blocking thread pool is gone",
+    ))
+}
+
+/// [`TempFile`] field reader configuration.
+///
+/// # Examples
+/// ```
+/// use actix_web::App;
+/// use actix_multipart::form::tempfile::TempFileConfig;
+///
+/// App::new().app_data(TempFileConfig::default().directory("./uploads"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TempFileConfig {
+    directory: Option<PathBuf>,
+}
+
+impl TempFileConfig {
+    /// Set directory in which temporary files are created. By default, the system's temporary
+    /// directory is used.
+    pub fn directory(mut self, dir: impl AsRef<Path>) -> Self {
+        self.directory = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Extract temp file config from app data. Check both `T` and `Data<T>`, in that order, and
+    /// fall back to the default temp file config.
+    fn from_req(req: &HttpRequest) -> Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,78 @@
+//! Reads a field into a value parsed from its text.
+
+use std::{fmt, ops, str::FromStr};
+
+use actix_web::{error::ErrorBadRequest, HttpRequest};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    form::{read_field_bytes, FieldReader, Limits},
+    Field, MultipartError,
+};
+
+/// Field reader that parses the UTF-8 text of a field using [`FromStr`].
+///
+/// The field is read into memory, so it counts towards the form's memory limit.
+///
+/// # Examples
+/// ```
+/// use actix_multipart::form::{text::Text, MultipartForm};
+///
+/// #[derive(MultipartForm)]
+/// struct Form {
+///     name: Text<String>,
+///     age: Text<u8>,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Text<T>(pub T);
+
+impl<T> Text<T> {
+    /// Unwraps into the inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Text<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Text<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> FieldReader for Text<T>
+where
+    T: FromStr + 'static,
+    T::Err: fmt::Display,
+{
+    fn read_field<'t>(
+        _req: &'t HttpRequest,
+        mut field: Field,
+        limits: &'t mut Limits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartError>> {
+        Box::pin(async move {
+            let data = read_field_bytes(&mut field, limits).await?;
+            let name = field.name().unwrap_or_default();
+
+            let text = std::str::from_utf8(&data).map_err(|err| MultipartError::Field {
+                name: name.to_owned(),
+                source: ErrorBadRequest(err),
+            })?;
+
+            text.parse()
+                .map(Text)
+                .map_err(|err: T::Err| MultipartError::Field {
+                    name: name.to_owned(),
+                    source: ErrorBadRequest(err.to_string()),
+                })
+        })
+    }
+}
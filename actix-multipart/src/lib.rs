@@ -3,8 +3,13 @@
 #![deny(rust_2018_idioms)]
 #![allow(clippy::borrow_interior_mutable_const)]
 
+// allows the derive macro's generated paths to resolve within this crate
+#[cfg(test)]
+extern crate self as actix_multipart;
+
 mod error;
 mod extractor;
+pub mod form;
 mod server;
 
 pub use self::error::MultipartError;
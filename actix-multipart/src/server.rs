@@ -371,6 +371,7 @@ impl Drop for InnerMultipart {
 /// A single field in a multipart stream
 pub struct Field {
     ct: mime::Mime,
+    content_disposition: Option<ContentDisposition>,
     headers: HeaderMap,
     inner: Rc<RefCell<InnerField>>,
     safety: Safety,
@@ -383,8 +384,15 @@ impl Field {
         ct: mime::Mime,
         inner: Rc<RefCell<InnerField>>,
     ) -> Self {
+        // RFC 7578: 'Each part MUST contain a Content-Disposition header field
+        // where the disposition type is "form-data".'
+        let content_disposition = headers
+            .get(&header::CONTENT_DISPOSITION)
+            .and_then(|cd| ContentDisposition::from_raw(cd).ok());
+
         Field {
             ct,
+            content_disposition,
             headers,
             inner,
             safety,
@@ -403,13 +411,15 @@ impl Field {
 
     /// Get the content disposition of the field, if it exists
     pub fn content_disposition(&self) -> Option<ContentDisposition> {
-        // RFC 7578: 'Each part MUST contain a Content-Disposition header field
-        // where the disposition type is "form-data".'
-        if let Some(content_disposition) = self.headers.get(&header::CONTENT_DISPOSITION) {
-            ContentDisposition::from_raw(content_disposition).ok()
-        } else {
-            None
-        }
+        self.content_disposition.clone()
+    }
+
+    /// Get the name of the field, from its `form-data` content disposition
+    pub fn name(&self) -> Option<&str> {
+        self.content_disposition
+            .as_ref()
+            .filter(|cd| cd.is_form_data())
+            .and_then(|cd| cd.get_name())
     }
 }
 
@@ -39,9 +39,10 @@ use actix_http::{
     http::{HeaderMap, Method},
     ws, HttpService, Request, Response,
 };
+use actix_server::Server;
 use actix_service::{map_config, IntoServiceFactory, ServiceFactory, ServiceFactoryExt as _};
 use actix_web::{
-    dev::{AppConfig, MessageBody, Server, Service},
+    dev::{AppConfig, MessageBody, Service},
     rt, web, Error,
 };
 use awc::{error::PayloadError, Client, ClientRequest, ClientResponse, Connector};
@@ -18,9 +18,11 @@ use actix_codec::{Decoder, Encoder};
 pub use actix_http::ws::{
     CloseCode, CloseReason, Frame, HandshakeError, Message, ProtocolError,
 };
+#[cfg(feature = "compress-gzip")]
+pub use actix_http::ws::{DeflateConfig, DeflateParams};
 use actix_http::{
     http::HeaderValue,
-    ws::{hash_key, Codec},
+    ws::{self, hash_key, Codec},
 };
 use actix_web::{
     error::{Error, PayloadError},
@@ -33,13 +35,15 @@ use futures_core::Stream;
 use tokio::sync::oneshot::Sender;
 
 /// Perform WebSocket handshake and start actor.
+///
+/// With the `compress-gzip` feature, which is enabled by default, the permessage-deflate
+/// extension is negotiated with the default [`DeflateConfig`] if the client offers it.
 pub fn start<A, T>(actor: A, req: &HttpRequest, stream: T) -> Result<HttpResponse, Error>
 where
     A: Actor<Context = WebsocketContext<A>> + StreamHandler<Result<Message, ProtocolError>>,
     T: Stream<Item = Result<Bytes, PayloadError>> + 'static,
 {
-    let mut res = handshake(req)?;
-    Ok(res.streaming(WebsocketContext::create(actor, stream)))
+    start_with_protocols(actor, &[], req, stream)
 }
 
 /// Perform WebSocket handshake and start actor.
@@ -62,8 +66,8 @@ where
     A: Actor<Context = WebsocketContext<A>> + StreamHandler<Result<Message, ProtocolError>>,
     T: Stream<Item = Result<Bytes, PayloadError>> + 'static,
 {
-    let mut res = handshake(req)?;
-    let (addr, out_stream) = WebsocketContext::create_with_addr(actor, stream);
+    let (mut res, codec) = handshake_with_codec(req, &[])?;
+    let (addr, out_stream) = WebsocketContext::create_with_codec(actor, stream, codec);
     Ok((addr, res.streaming(out_stream)))
 }
 
@@ -80,8 +84,59 @@ where
     A: Actor<Context = WebsocketContext<A>> + StreamHandler<Result<Message, ProtocolError>>,
     T: Stream<Item = Result<Bytes, PayloadError>> + 'static,
 {
-    let mut res = handshake_with_protocols(req, protocols)?;
-    Ok(res.streaming(WebsocketContext::create(actor, stream)))
+    let (mut res, codec) = handshake_with_codec(req, protocols)?;
+    Ok(res.streaming(WebsocketContext::with_codec(actor, stream, codec)))
+}
+
+/// Do WebSocket handshake, negotiating the permessage-deflate extension with `config`, and start
+/// ws actor.
+///
+/// Messages are compressed if the client offered the extension with acceptable parameters,
+/// otherwise the connection proceeds uncompressed.
+#[cfg(feature = "compress-gzip")]
+pub fn start_with_deflate<A, T>(
+    actor: A,
+    config: &DeflateConfig,
+    req: &HttpRequest,
+    stream: T,
+) -> Result<HttpResponse, Error>
+where
+    A: Actor<Context = WebsocketContext<A>> + StreamHandler<Result<Message, ProtocolError>>,
+    T: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+{
+    let (mut res, codec) = negotiate_deflate(handshake(req)?, req, config);
+    Ok(res.streaming(WebsocketContext::with_codec(actor, stream, codec)))
+}
+
+/// Prepare WebSocket handshake response along with the codec for the connection, negotiating
+/// the permessage-deflate extension if the `compress-gzip` feature is enabled.
+fn handshake_with_codec(
+    req: &HttpRequest,
+    protocols: &[&str],
+) -> Result<(HttpResponseBuilder, Codec), HandshakeError> {
+    let res = handshake_with_protocols(req, protocols)?;
+
+    #[cfg(feature = "compress-gzip")]
+    let (res, codec) = negotiate_deflate(res, req, &DeflateConfig::default());
+    #[cfg(not(feature = "compress-gzip"))]
+    let codec = Codec::new();
+
+    Ok((res, codec))
+}
+
+#[cfg(feature = "compress-gzip")]
+fn negotiate_deflate(
+    mut res: HttpResponseBuilder,
+    req: &HttpRequest,
+    config: &DeflateConfig,
+) -> (HttpResponseBuilder, Codec) {
+    let (codec, extension) = ws::negotiate_deflate(req.head(), config);
+
+    if let Some(extension) = extension {
+        res.insert_header((header::SEC_WEBSOCKET_EXTENSIONS, extension));
+    }
+
+    (res, codec)
 }
 
 /// Prepare WebSocket handshake response.
@@ -267,6 +322,20 @@ where
         actor: A,
         stream: S,
     ) -> (Addr<A>, impl Stream<Item = Result<Bytes, Error>>)
+    where
+        A: StreamHandler<Result<Message, ProtocolError>>,
+        S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+    {
+        WebsocketContext::create_with_codec(actor, stream, Codec::new())
+    }
+
+    /// Create a new Websocket context from a request, an actor, and a codec, returning the addr
+    /// of the created actor along with the response stream.
+    fn create_with_codec<S>(
+        actor: A,
+        stream: S,
+        codec: Codec,
+    ) -> (Addr<A>, impl Stream<Item = Result<Bytes, Error>>)
     where
         A: StreamHandler<Result<Message, ProtocolError>>,
         S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
@@ -276,11 +345,12 @@ where
             inner: ContextParts::new(mb.sender_producer()),
             messages: VecDeque::new(),
         };
-        ctx.add_stream(WsStream::new(stream, Codec::new()));
+        #[allow(clippy::clone_on_copy)]
+        ctx.add_stream(WsStream::new(stream, codec.clone()));
 
         let addr = ctx.address();
 
-        (addr, WebsocketContextFut::new(ctx, actor, mb, Codec::new()))
+        (addr, WebsocketContextFut::new(ctx, actor, mb, codec))
     }
 
     #[inline]
@@ -294,14 +364,8 @@ where
         A: StreamHandler<Result<Message, ProtocolError>>,
         S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
     {
-        let mb = Mailbox::default();
-        let mut ctx = WebsocketContext {
-            inner: ContextParts::new(mb.sender_producer()),
-            messages: VecDeque::new(),
-        };
-        ctx.add_stream(WsStream::new(stream, codec));
-
-        WebsocketContextFut::new(ctx, actor, mb, codec)
+        let (_, stream) = WebsocketContext::create_with_codec(actor, stream, codec);
+        stream
     }
 
     /// Create a new Websocket context
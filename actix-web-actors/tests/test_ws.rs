@@ -107,3 +107,45 @@ async fn test_with_credentials() {
     let item = framed.next().await.unwrap().unwrap();
     assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
 }
+
+#[cfg(feature = "compress-gzip")]
+#[actix_rt::test]
+async fn test_deflate() {
+    let srv = actix_test::start(|| {
+        App::new().service(web::resource("/").to(
+            |req: HttpRequest, stream: web::Payload| async move { ws::start(Ws, &req, stream) },
+        ))
+    });
+
+    // extension is negotiated without any configuration when offered
+    let (res, mut framed) = awc::Client::new()
+        .ws(srv.url("/"))
+        .deflate(ws::DeflateConfig::new())
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(
+        res.headers().get(header::SEC_WEBSOCKET_EXTENSIONS).unwrap(),
+        "permessage-deflate"
+    );
+
+    for _ in 0..3 {
+        framed
+            .send(ws::Message::Text("text text text text".into()))
+            .await
+            .unwrap();
+        let item = framed.next().await.unwrap().unwrap();
+        assert_eq!(
+            item,
+            ws::Frame::Text(Bytes::from_static(b"text text text text"))
+        );
+    }
+
+    // clients not offering it are not sent compressed messages
+    let (res, mut framed) = awc::Client::new().ws(srv.url("/")).connect().await.unwrap();
+    assert!(!res.headers().contains_key(header::SEC_WEBSOCKET_EXTENSIONS));
+
+    framed.send(ws::Message::Text("text".into())).await.unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"text")));
+}
@@ -1,17 +1,21 @@
 use std::convert::TryFrom;
 use std::fmt;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::rc::Rc;
 use std::time::Duration;
 
 use actix_http::{
-    client::{Connector, ConnectorService, TcpConnect, TcpConnectError, TcpConnection},
+    client::{
+        Connector, ConnectorService, Proxy, Resolve, TcpConnect, TcpConnectError, TcpConnection,
+    },
     http::{self, header, Error as HttpError, HeaderMap, HeaderName, Uri},
 };
 use actix_rt::net::{ActixStream, TcpStream};
 use actix_service::{boxed, Service};
 
 use crate::connect::DefaultConnector;
+#[cfg(feature = "cookies")]
+use crate::cookie_store::CookieStore;
 use crate::error::SendRequestError;
 use crate::middleware::{NestTransform, Redirect, Transform};
 use crate::{Client, ClientConfig, ConnectRequest, ConnectResponse};
@@ -119,6 +123,82 @@ where
         self
     }
 
+    /// Send requests to both HTTP and HTTPS hosts through `proxy`.
+    ///
+    /// See [`Connector::proxy`] for details. No proxy is used by default. Proxies are set on the
+    /// current connector, so a custom [`connector`](Self::connector) must be set first.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.connector = self.connector.proxy(proxy);
+        self
+    }
+
+    /// Send requests to HTTP hosts through `proxy`.
+    pub fn http_proxy(mut self, proxy: Proxy) -> Self {
+        self.connector = self.connector.http_proxy(proxy);
+        self
+    }
+
+    /// Send requests to HTTPS hosts through `proxy`.
+    pub fn https_proxy(mut self, proxy: Proxy) -> Self {
+        self.connector = self.connector.https_proxy(proxy);
+        self
+    }
+
+    /// Set hosts that requests are sent to directly instead of through a proxy.
+    ///
+    /// See [`Connector::no_proxy`] for the accepted host patterns.
+    pub fn no_proxy<I, H>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = H>,
+        H: AsRef<str>,
+    {
+        self.connector = self.connector.no_proxy(hosts);
+        self
+    }
+
+    /// Read proxy configuration from the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment
+    /// variables.
+    ///
+    /// See [`Connector::proxy_from_env`] for details.
+    pub fn proxy_from_env(mut self) -> Self {
+        self.connector = self.connector.proxy_from_env();
+        self
+    }
+
+    /// Use a custom resolver to look up the addresses of hosts.
+    ///
+    /// See [`Connector::resolver`] for details. Like proxies, the resolver is set on the current
+    /// connector, so a custom [`connector`](Self::connector) must be set first.
+    pub fn resolver(mut self, resolver: impl Resolve + 'static) -> Self {
+        self.connector = self.connector.resolver(resolver);
+        self
+    }
+
+    /// Connect to `addr` when sending requests to `port` of `host`, instead of resolving it.
+    ///
+    /// See [`Connector::resolve`] for details.
+    pub fn resolve(mut self, host: &str, port: u16, addr: SocketAddr) -> Self {
+        self.connector = self.connector.resolve(host, port, addr);
+        self
+    }
+
+    /// Set the delay before the next address of a host is tried while connecting to the previous
+    /// one is still pending.
+    ///
+    /// See [`Connector::happy_eyeballs_delay`] for details.
+    pub fn happy_eyeballs_delay(mut self, delay: Duration) -> Self {
+        self.connector = self.connector.happy_eyeballs_delay(delay);
+        self
+    }
+
+    /// Set the max time to connect to each address of a host.
+    ///
+    /// See [`Connector::address_timeout`] for details.
+    pub fn address_timeout(mut self, timeout: Duration) -> Self {
+        self.connector = self.connector.address_timeout(timeout);
+        self
+    }
+
     /// Do not follow redirects.
     ///
     /// Redirects are allowed by default.
@@ -230,6 +310,23 @@ where
         }
     }
 
+    /// Stores the cookies set by responses in `store` and sends them with later requests,
+    /// including the requests of redirects.
+    ///
+    /// The store is applied inside middleware registered before this call and outside middleware
+    /// registered after it.
+    #[cfg(feature = "cookies")]
+    pub fn cookie_store<S1>(
+        self,
+        store: CookieStore,
+    ) -> ClientBuilder<S, NestTransform<M, CookieStore, S1, ConnectRequest>>
+    where
+        M: Transform<S1, ConnectRequest>,
+        CookieStore: Transform<M::Transform, ConnectRequest>,
+    {
+        self.wrap(store)
+    }
+
     /// Finish build process and create `Client` instance.
     pub fn finish(self) -> Client
     where
@@ -85,14 +85,12 @@ where
     fn call(&self, req: ConnectRequest) -> Self::Future {
         // connect to the host
         let fut = match req {
-            ConnectRequest::Client(ref head, .., addr) => self.connector.call(ClientConnect {
-                uri: head.as_ref().uri.clone(),
-                addr,
-            }),
-            ConnectRequest::Tunnel(ref head, addr) => self.connector.call(ClientConnect {
-                uri: head.uri.clone(),
-                addr,
-            }),
+            ConnectRequest::Client(ref head, .., addr) => self
+                .connector
+                .call(ClientConnect::new(head.as_ref().uri.clone(), addr)),
+            ConnectRequest::Tunnel(ref head, addr) => self
+                .connector
+                .call(ClientConnect::new(head.uri.clone(), addr)),
         };
 
         ConnectRequestFuture::Connection {
@@ -0,0 +1,554 @@
+use std::{
+    cell::RefCell,
+    cmp::Reverse,
+    net::IpAddr,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use actix_http::{
+    client::SendRequestError,
+    http::{
+        header::{self, HeaderValue},
+        HeaderMap, Uri,
+    },
+    RequestHead, RequestHeadType,
+};
+use actix_service::Service;
+use futures_core::future::LocalBoxFuture;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::connect::{ConnectRequest, ConnectResponse};
+use crate::cookie::Cookie;
+use crate::middleware::Transform;
+
+/// Cookie store shared by the requests of a [`Client`](crate::Client).
+///
+/// Cookies set by responses are stored and sent with later requests to matching URLs, following
+/// the domain, path, `Secure` and expiry rules of [RFC 6265]. Since the store is consulted for
+/// each request sent, cookies set by redirect responses are sent with the redirected request.
+///
+/// The store is a handle; clones share the same cookies. It can be serialized and deserialized
+/// with `serde`, e.g. to load cookies from a test fixture. Public suffixes are not recognized, so
+/// domain cookies are accepted for any parent domain of the responding host.
+///
+/// # Examples
+/// ```
+/// use awc::{Client, CookieStore};
+///
+/// let store = CookieStore::new();
+/// let client = Client::builder().cookie_store(store.clone()).finish();
+///
+/// // persist the cookies of the session
+/// let json = serde_json::to_string(&store).unwrap();
+/// let store: CookieStore = serde_json::from_str(&json).unwrap();
+/// ```
+///
+/// [RFC 6265]: https://datatracker.ietf.org/doc/html/rfc6265
+#[derive(Debug, Clone, Default)]
+pub struct CookieStore {
+    cookies: Rc<RefCell<Vec<StoredCookie>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct StoredCookie {
+    name: String,
+    value: String,
+    domain: String,
+    /// Whether the cookie is only sent to the host that set it, rather than also to its
+    /// subdomains.
+    host_only: bool,
+    path: String,
+    secure: bool,
+    /// Expiry time in seconds since the Unix epoch, if the cookie is not a session cookie.
+    expires: Option<u64>,
+}
+
+impl StoredCookie {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires.map_or(false, |expires| expires <= now)
+    }
+
+    fn matches(&self, url: &Url<'_>) -> bool {
+        let domain_match = if self.host_only {
+            url.host == self.domain
+        } else {
+            domain_match(&url.host, &self.domain)
+        };
+
+        domain_match && path_match(url.path, &self.path) && (url.secure || !self.secure)
+    }
+}
+
+impl CookieStore {
+    /// Constructs an empty cookie store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `cookie` as if it was set by a response to a request for `url`.
+    ///
+    /// The cookie is ignored if its attributes are not valid for `url`, and an expired cookie
+    /// removes the stored cookie it replaces.
+    pub fn insert(&self, cookie: Cookie<'_>, url: &Uri) {
+        let url = match Url::new(url) {
+            Some(url) => url,
+            None => return,
+        };
+
+        let now = now();
+
+        let (domain, host_only) = match cookie.domain() {
+            Some(domain) if !domain.is_empty() => {
+                let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+
+                if !domain_match(&url.host, &domain) {
+                    return;
+                }
+
+                (domain, false)
+            }
+            _ => (url.host.clone(), true),
+        };
+
+        let path = match cookie.path() {
+            Some(path) if path.starts_with('/') => path.to_owned(),
+            _ => default_path(url.path).to_owned(),
+        };
+
+        let secure = cookie.secure().unwrap_or(false);
+
+        // a secure cookie cannot be set from an insecure URL
+        if secure && !url.secure {
+            return;
+        }
+
+        // the Max-Age attribute takes precedence over Expires
+        let expires = match cookie.max_age() {
+            Some(max_age) => Some(now.saturating_add(max_age.whole_seconds().max(0) as u64)),
+            None => cookie
+                .expires()
+                .and_then(|expires| expires.datetime())
+                .map(|expires| expires.unix_timestamp().max(0) as u64),
+        };
+
+        let cookie = StoredCookie {
+            name: cookie.name().to_owned(),
+            value: cookie.value().to_owned(),
+            domain,
+            host_only,
+            path,
+            secure,
+            expires,
+        };
+
+        let mut cookies = self.cookies.borrow_mut();
+        cookies.retain(|stored| {
+            !stored.is_expired(now)
+                && (stored.name != cookie.name
+                    || stored.domain != cookie.domain
+                    || stored.path != cookie.path)
+        });
+
+        if !cookie.is_expired(now) {
+            cookies.push(cookie);
+        }
+    }
+
+    /// Returns the cookies that are sent with requests for `url`, in the order they are sent.
+    pub fn cookies(&self, url: &Uri) -> Vec<Cookie<'static>> {
+        let url = match Url::new(url) {
+            Some(url) => url,
+            None => return Vec::new(),
+        };
+
+        let now = now();
+
+        let mut cookies = self
+            .cookies
+            .borrow()
+            .iter()
+            .filter(|cookie| !cookie.is_expired(now) && cookie.matches(&url))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // cookies with longer paths are sent first
+        cookies.sort_by_key(|cookie| Reverse(cookie.path.len()));
+
+        cookies
+            .into_iter()
+            .map(|cookie| {
+                let mut builder = Cookie::build(cookie.name, cookie.value)
+                    .path(cookie.path)
+                    .secure(cookie.secure);
+
+                if !cookie.host_only {
+                    builder = builder.domain(cookie.domain);
+                }
+
+                builder.finish()
+            })
+            .collect()
+    }
+
+    /// Removes all cookies from the store.
+    pub fn clear(&self) {
+        self.cookies.borrow_mut().clear();
+    }
+
+    /// Stores the cookies set by a response to a request for `url`.
+    fn store_response(&self, url: &Uri, headers: &HeaderMap) {
+        for value in headers.get_all(header::SET_COOKIE) {
+            if let Some(cookie) = value.to_str().ok().and_then(|v| Cookie::parse(v).ok()) {
+                self.insert(cookie, url);
+            }
+        }
+    }
+
+    /// Returns the `Cookie` header for a request for `url` that sets the `existing` header, with
+    /// the stored cookies added. Cookies the request already sets take precedence.
+    fn cookie_header(&self, url: &Uri, existing: Option<&HeaderValue>) -> Option<HeaderValue> {
+        let cookies = self.cookies(url);
+
+        if cookies.is_empty() {
+            return None;
+        }
+
+        let mut value = existing
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+            .to_owned();
+
+        let set = value
+            .split(';')
+            .filter_map(|pair| pair.split('=').next())
+            .map(|name| name.trim().to_owned())
+            .collect::<Vec<_>>();
+
+        for cookie in cookies
+            .iter()
+            .filter(|c| !set.iter().any(|n| n == c.name()))
+        {
+            if !value.is_empty() {
+                value.push_str("; ");
+            }
+
+            value.push_str(cookie.name());
+            value.push('=');
+            value.push_str(cookie.value());
+        }
+
+        HeaderValue::from_str(&value).ok()
+    }
+
+    /// Adds the stored cookies for the request's URL to its `Cookie` header.
+    fn add_cookie_header(&self, head: &mut RequestHeadType) {
+        match head {
+            RequestHeadType::Owned(head) => self.add_owned_cookie_header(head),
+            RequestHeadType::Rc(head, extra_headers) => {
+                let extra_headers = extra_headers.get_or_insert_with(HeaderMap::new);
+                let existing = extra_headers
+                    .get(header::COOKIE)
+                    .or_else(|| head.headers.get(header::COOKIE));
+
+                if let Some(value) = self.cookie_header(&head.uri, existing) {
+                    extra_headers.insert(header::COOKIE, value);
+                }
+            }
+        }
+    }
+
+    fn add_owned_cookie_header(&self, head: &mut RequestHead) {
+        if let Some(value) = self.cookie_header(&head.uri, head.headers.get(header::COOKIE)) {
+            head.headers.insert(header::COOKIE, value);
+        }
+    }
+}
+
+impl Serialize for CookieStore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let now = now();
+
+        self.cookies
+            .borrow()
+            .iter()
+            .filter(|cookie| !cookie.is_expired(now))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CookieStore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(CookieStore {
+            cookies: Rc::new(RefCell::new(Vec::deserialize(deserializer)?)),
+        })
+    }
+}
+
+impl<S> Transform<S, ConnectRequest> for CookieStore
+where
+    S: Service<ConnectRequest, Response = ConnectResponse, Error = SendRequestError> + 'static,
+{
+    type Transform = CookieStoreService<S>;
+
+    fn new_transform(self, service: S) -> Self::Transform {
+        CookieStoreService {
+            store: self,
+            connector: service,
+        }
+    }
+}
+
+pub struct CookieStoreService<S> {
+    store: CookieStore,
+    connector: S,
+}
+
+impl<S> Service<ConnectRequest> for CookieStoreService<S>
+where
+    S: Service<ConnectRequest, Response = ConnectResponse, Error = SendRequestError> + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = LocalBoxFuture<'static, Result<ConnectResponse, SendRequestError>>;
+
+    actix_service::forward_ready!(connector);
+
+    fn call(&self, req: ConnectRequest) -> Self::Future {
+        let (uri, req) = match req {
+            ConnectRequest::Client(mut head, body, addr) => {
+                self.store.add_cookie_header(&mut head);
+                let uri = head.as_ref().uri.clone();
+                (uri, ConnectRequest::Client(head, body, addr))
+            }
+            ConnectRequest::Tunnel(mut head, addr) => {
+                self.store.add_owned_cookie_header(&mut head);
+                (head.uri.clone(), ConnectRequest::Tunnel(head, addr))
+            }
+        };
+
+        let store = self.store.clone();
+        let fut = self.connector.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+
+            match res {
+                ConnectResponse::Client(ref res) => store.store_response(&uri, res.headers()),
+                ConnectResponse::Tunnel(ref head, _) => {
+                    store.store_response(&uri, &head.headers)
+                }
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+/// The parts of a request URL that cookies are matched against.
+struct Url<'a> {
+    host: String,
+    path: &'a str,
+    secure: bool,
+}
+
+impl<'a> Url<'a> {
+    fn new(uri: &'a Uri) -> Option<Self> {
+        Some(Url {
+            host: uri.host()?.to_ascii_lowercase(),
+            path: uri.path(),
+            secure: matches!(uri.scheme_str(), Some("https") | Some("wss")),
+        })
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
+/// Whether `host` is `domain` or one of its subdomains. IP addresses have no subdomains.
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_err())
+}
+
+/// Whether a request for `path` is within the cookie path `cookie_path`.
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The path of cookies set without a `Path` attribute: the directory of the request's path.
+fn default_path(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(idx) => &path[..idx],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{cookie::Cookie as WebCookie, web, App, HttpRequest, HttpResponse};
+
+    use super::*;
+    use crate::ClientBuilder;
+
+    fn names(store: &CookieStore, url: &'static str) -> Vec<String> {
+        store
+            .cookies(&Uri::from_static(url))
+            .iter()
+            .map(|cookie| cookie.name().to_owned())
+            .collect()
+    }
+
+    fn insert(store: &CookieStore, cookie: &str, url: &'static str) {
+        store.insert(Cookie::parse(cookie).unwrap(), &Uri::from_static(url));
+    }
+
+    #[test]
+    fn test_matching() {
+        let store = CookieStore::new();
+
+        insert(&store, "host=1", "http://example.com/a/b");
+        insert(
+            &store,
+            "domain=1; Domain=.Example.com; Path=/",
+            "http://example.com/",
+        );
+        insert(&store, "root=1; Path=/", "http://www.example.com/x");
+        insert(&store, "deep=1; Path=/a/b", "http://example.com/");
+        insert(&store, "secure=1; Secure; Path=/", "https://example.com/");
+        insert(&store, "insecure=1; Secure", "http://example.com/");
+        insert(&store, "other=1; Domain=other.com", "http://example.com/");
+
+        assert_eq!(names(&store, "http://example.com/"), vec!["domain"]);
+        assert_eq!(
+            names(&store, "http://example.com/a/b/c"),
+            vec!["deep", "host", "domain"]
+        );
+        assert_eq!(
+            names(&store, "http://example.com/a/bc"),
+            vec!["host", "domain"]
+        );
+        assert_eq!(
+            names(&store, "https://www.example.com/"),
+            vec!["domain", "root"]
+        );
+        assert_eq!(
+            names(&store, "https://example.com/"),
+            vec!["domain", "secure"]
+        );
+        assert!(names(&store, "http://example.org/").is_empty());
+
+        let cookies = store.cookies(&Uri::from_static("http://sub.example.com/"));
+        assert_eq!(cookies[0].domain(), Some("example.com"));
+        assert_eq!(cookies[0].path(), Some("/"));
+    }
+
+    #[test]
+    fn test_expiry() {
+        let store = CookieStore::new();
+
+        insert(&store, "a=1; Max-Age=60", "http://example.com/");
+        insert(
+            &store,
+            "b=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+            "http://example.com/",
+        );
+        insert(
+            &store,
+            "c=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=60",
+            "http://example.com/",
+        );
+        insert(&store, "d=1", "http://example.com/");
+        assert_eq!(names(&store, "http://example.com/"), vec!["a", "c", "d"]);
+
+        // cookies are replaced and removed by name, domain and path
+        insert(&store, "a=2", "http://example.com/");
+        insert(&store, "c=; Max-Age=0", "http://example.com/");
+        let cookies = store.cookies(&Uri::from_static("http://example.com/"));
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].name_value(), ("d", "1"));
+        assert_eq!(cookies[1].name_value(), ("a", "2"));
+
+        store.clear();
+        assert!(names(&store, "http://example.com/").is_empty());
+    }
+
+    #[test]
+    fn test_serde() {
+        let store = CookieStore::new();
+        insert(
+            &store,
+            "a=1; Domain=example.com; Secure; Max-Age=60",
+            "https://example.com/",
+        );
+        insert(&store, "b=2; Path=/b", "https://example.com/");
+        insert(&store, "c=3; Max-Age=0", "https://example.com/");
+
+        let json = serde_json::to_string(&store).unwrap();
+        let loaded: CookieStore = serde_json::from_str(&json).unwrap();
+        assert_eq!(*loaded.cookies.borrow(), *store.cookies.borrow());
+        assert_eq!(names(&loaded, "https://www.example.com/b"), vec!["a"]);
+
+        let loaded: CookieStore = serde_json::from_str(
+            r#"[{"name":"id","value":"7","domain":"localhost","host_only":true,
+                 "path":"/","secure":false,"expires":null}]"#,
+        )
+        .unwrap();
+        assert_eq!(names(&loaded, "http://localhost/x"), vec!["id"]);
+    }
+
+    #[actix_rt::test]
+    async fn test_client_cookie_store() {
+        let srv = actix_test::start(|| {
+            App::new()
+                .service(web::resource("/login").to(|| {
+                    HttpResponse::Found()
+                        .cookie(WebCookie::build("session", "abc").path("/").finish())
+                        .insert_header((header::LOCATION, "/whoami"))
+                        .finish()
+                }))
+                .service(web::resource("/whoami").to(|req: HttpRequest| {
+                    let cookie = req
+                        .headers()
+                        .get(header::COOKIE)
+                        .map_or("", |cookie| cookie.to_str().unwrap())
+                        .to_owned();
+
+                    HttpResponse::Ok().body(cookie)
+                }))
+        });
+
+        let store = CookieStore::new();
+        let client = ClientBuilder::new().cookie_store(store.clone()).finish();
+
+        // the cookie is sent with the redirected request
+        let mut res = client.get(srv.url("/login")).send().await.unwrap();
+        assert_eq!(res.body().await.unwrap(), "session=abc");
+
+        // cookies set on the request take precedence
+        let mut res = client
+            .get(srv.url("/whoami"))
+            .cookie(Cookie::new("session", "override"))
+            .cookie(Cookie::new("extra", "1"))
+            .send()
+            .await
+            .unwrap();
+        let body = res.body().await.unwrap();
+        assert!(body == "session=override; extra=1" || body == "extra=1; session=override");
+
+        let mut res = client.get(srv.url("/whoami")).send().await.unwrap();
+        assert_eq!(res.body().await.unwrap(), "session=abc");
+    }
+}
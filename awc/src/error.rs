@@ -38,6 +38,10 @@ pub enum WsClientError {
     #[display(fmt = "Invalid challenge response")]
     InvalidChallengeResponse([u8; 28], HeaderValue),
 
+    /// Invalid Sec-Websocket-Extensions header
+    #[display(fmt = "Invalid Sec-Websocket-Extensions header")]
+    InvalidExtensionsHeader,
+
     /// Protocol error
     #[display(fmt = "{}", _0)]
     Protocol(WsProtocolError),
@@ -76,3 +80,22 @@ pub enum JsonPayloadError {
 }
 
 impl std::error::Error for JsonPayloadError {}
+
+/// A set of errors that can occur while reading server-sent events
+#[derive(Debug, Display, From)]
+pub enum SseError {
+    /// Content type error
+    #[display(fmt = "Content type error")]
+    ContentType,
+
+    /// Event is bigger than allowed
+    #[display(fmt = "Event has exceeded limit ({} bytes)", limit)]
+    #[from(ignore)]
+    Overflow { limit: usize },
+
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+impl std::error::Error for SseError {}
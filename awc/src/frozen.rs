@@ -11,6 +11,7 @@ use actix_http::{
 };
 
 use crate::{
+    multipart::Form,
     sender::{RequestSender, SendClientRequest},
     ClientConfig,
 };
@@ -78,6 +79,17 @@ impl FrozenClientRequest {
         )
     }
 
+    /// Send a `multipart/form-data` body.
+    pub fn send_multipart(&self, form: Form) -> SendClientRequest {
+        RequestSender::Rc(self.head.clone(), None).send_multipart(
+            self.addr,
+            self.response_decompress,
+            self.timeout,
+            &self.config,
+            form,
+        )
+    }
+
     /// Send a streaming body.
     pub fn send_stream<S, E>(&self, stream: S) -> SendClientRequest
     where
@@ -203,6 +215,21 @@ impl FrozenSendBuilder {
         )
     }
 
+    /// Complete request construction and send a `multipart/form-data` body.
+    pub fn send_multipart(self, form: Form) -> SendClientRequest {
+        if let Some(e) = self.err {
+            return e.into();
+        }
+
+        RequestSender::Rc(self.req.head, Some(self.extra_headers)).send_multipart(
+            self.req.addr,
+            self.req.response_decompress,
+            self.req.timeout,
+            &self.req.config,
+            form,
+        )
+    }
+
     /// Complete request construction and send a streaming body.
     pub fn send_stream<S, E>(self, stream: S) -> SendClientRequest
     where
@@ -109,7 +109,10 @@ use std::{convert::TryFrom, rc::Rc, time::Duration};
 #[cfg(feature = "cookies")]
 pub use cookie;
 
-pub use actix_http::{client::Connector, http};
+pub use actix_http::{
+    client::{Connector, Proxy, Resolve, Socks5Connector},
+    http,
+};
 
 use actix_http::{
     client::{TcpConnect, TcpConnectError, TcpConnection},
@@ -121,17 +124,23 @@ use actix_service::Service;
 
 mod builder;
 mod connect;
+#[cfg(feature = "cookies")]
+mod cookie_store;
 pub mod error;
 mod frozen;
 pub mod middleware;
+pub mod multipart;
 mod request;
 mod response;
 mod sender;
+pub mod sse;
 pub mod test;
 pub mod ws;
 
 pub use self::builder::ClientBuilder;
 pub use self::connect::{BoxConnectorService, BoxedSocket, ConnectRequest, ConnectResponse};
+#[cfg(feature = "cookies")]
+pub use self::cookie_store::CookieStore;
 pub use self::frozen::{FrozenClientRequest, FrozenSendBuilder};
 pub use self::request::ClientRequest;
 pub use self::response::{ClientResponse, JsonBody, MessageBody};
@@ -0,0 +1,516 @@
+use std::{
+    rc::Rc,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+use actix_http::{
+    body::Body,
+    client::{ConnectError, SendRequestError},
+    http::{
+        header::{self, HttpDate},
+        Method, StatusCode,
+    },
+    RequestHeadType,
+};
+use actix_rt::time::{sleep, timeout};
+use actix_service::Service;
+use futures_core::future::LocalBoxFuture;
+use rand::Rng;
+
+use super::Transform;
+
+use crate::connect::{ConnectRequest, ConnectResponse};
+use crate::ClientResponse;
+
+/// Middleware that retries failed requests, waiting longer after each attempt.
+///
+/// Requests are retried when connecting to the host fails for a reason other than the URL having no
+/// host to resolve, when an attempt exceeds the [attempt timeout](Self::attempt_timeout) and when
+/// the response status is one of the [retried statuses](Self::statuses) (`429`, `502`, `503` and
+/// `504` by default). The delay before a retry grows exponentially from the
+/// [base delay](Self::backoff) with random jitter, unless the response has a `Retry-After` header,
+/// which is honored when it is no longer than the maximum delay.
+///
+/// Only requests with bodies that can be sent again, i.e. no body or a body of bytes, are retried.
+/// Timeouts and responses are only retried for idempotent methods, while connection errors are
+/// retried for any method since the request was not sent.
+///
+/// The client's [timeout](crate::ClientBuilder::timeout) bounds the total time of all attempts of a
+/// request and the delays between them, so the request fails with a timeout error once it is
+/// exceeded, even if retries are left. Set an attempt timeout shorter than the client's timeout to
+/// retry attempts that take too long.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use awc::{middleware::Retry, ClientBuilder};
+///
+/// let client = ClientBuilder::new()
+///     .wrap(
+///         Retry::new()
+///             .max_retries(5)
+///             .backoff(Duration::from_millis(50), Duration::from_secs(1))
+///             .attempt_timeout(Duration::from_secs(2)),
+///     )
+///     .timeout(Duration::from_secs(10))
+///     .finish();
+/// ```
+pub struct Retry {
+    config: RetryConfig,
+}
+
+struct RetryConfig {
+    max_retries: u8,
+    base_delay: Duration,
+    max_delay: Duration,
+    attempt_timeout: Option<Duration>,
+    statuses: Vec<StatusCode>,
+    methods: Vec<Method>,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Retry {
+    /// Constructs a middleware that retries requests up to 3 times, with a base delay of 100ms and
+    /// a maximum delay of 2 seconds, and no attempt timeout.
+    ///
+    /// The maximum delay keeps retries within the client's default timeout of 5 seconds.
+    pub fn new() -> Self {
+        Self {
+            config: RetryConfig {
+                max_retries: 3,
+                base_delay: Duration::from_millis(100),
+                max_delay: Duration::from_secs(2),
+                attempt_timeout: None,
+                statuses: vec![
+                    StatusCode::TOO_MANY_REQUESTS,
+                    StatusCode::BAD_GATEWAY,
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    StatusCode::GATEWAY_TIMEOUT,
+                ],
+                methods: vec![
+                    Method::GET,
+                    Method::HEAD,
+                    Method::PUT,
+                    Method::DELETE,
+                    Method::OPTIONS,
+                    Method::TRACE,
+                ],
+            },
+        }
+    }
+
+    /// Sets the maximum number of times a request is retried.
+    pub fn max_retries(mut self, times: u8) -> Self {
+        self.config.max_retries = times;
+        self
+    }
+
+    /// Sets the delay before the first retry, which doubles with each retry up to `max_delay`.
+    pub fn backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.config.base_delay = base_delay;
+        self.config.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the time after which an attempt is abandoned and, for idempotent methods, retried.
+    ///
+    /// An attempt is complete once the response head is received.
+    pub fn attempt_timeout(mut self, timeout: Duration) -> Self {
+        self.config.attempt_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the response statuses that requests are retried on.
+    pub fn statuses<I>(mut self, statuses: I) -> Self
+    where
+        I: IntoIterator<Item = StatusCode>,
+    {
+        self.config.statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Sets the methods considered idempotent, whose requests are retried on timeouts and
+    /// responses.
+    ///
+    /// `GET`, `HEAD`, `PUT`, `DELETE`, `OPTIONS` and `TRACE` by default.
+    pub fn methods<I>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = Method>,
+    {
+        self.config.methods = methods.into_iter().collect();
+        self
+    }
+}
+
+impl<S> Transform<S, ConnectRequest> for Retry
+where
+    S: Service<ConnectRequest, Response = ConnectResponse, Error = SendRequestError> + 'static,
+{
+    type Transform = RetryService<S>;
+
+    fn new_transform(self, service: S) -> Self::Transform {
+        RetryService {
+            config: Rc::new(self.config),
+            connector: Rc::new(service),
+        }
+    }
+}
+
+pub struct RetryService<S> {
+    config: Rc<RetryConfig>,
+    connector: Rc<S>,
+}
+
+impl<S> Service<ConnectRequest> for RetryService<S>
+where
+    S: Service<ConnectRequest, Response = ConnectResponse, Error = SendRequestError> + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = LocalBoxFuture<'static, Result<ConnectResponse, SendRequestError>>;
+
+    actix_service::forward_ready!(connector);
+
+    fn call(&self, req: ConnectRequest) -> Self::Future {
+        let connector = self.connector.clone();
+
+        let (head, body, addr) = match req {
+            ConnectRequest::Client(head, body, addr) => (head, body, addr),
+            ConnectRequest::Tunnel(..) => {
+                return Box::pin(async move { connector.call(req).await })
+            }
+        };
+
+        let body = match body {
+            // streaming bodies can only be sent once
+            Body::Message(_) => {
+                let req = ConnectRequest::Client(head, body, addr);
+                return Box::pin(async move { connector.call(req).await });
+            }
+            body => body,
+        };
+
+        let config = self.config.clone();
+
+        // the head is shared between attempts
+        let (head, extra_headers) = match head {
+            RequestHeadType::Owned(head) => (Rc::new(head), None),
+            RequestHeadType::Rc(head, extra_headers) => (head, extra_headers),
+        };
+        let idempotent = config.methods.contains(&head.method);
+
+        Box::pin(async move {
+            let mut retries = 0;
+
+            loop {
+                let req = ConnectRequest::Client(
+                    RequestHeadType::Rc(head.clone(), extra_headers.clone()),
+                    clone_body(&body),
+                    addr,
+                );
+
+                let res = match config.attempt_timeout {
+                    Some(dur) => timeout(dur, connector.call(req))
+                        .await
+                        .unwrap_or(Err(SendRequestError::Timeout)),
+                    None => connector.call(req).await,
+                };
+
+                if retries == config.max_retries {
+                    return res;
+                }
+
+                let delay = match res {
+                    Err(SendRequestError::Connect(ref err)) if is_transient(err) => {
+                        Some(config.backoff(retries))
+                    }
+                    Err(SendRequestError::Timeout) if idempotent => {
+                        Some(config.backoff(retries))
+                    }
+                    Ok(ConnectResponse::Client(ref res))
+                        if idempotent && config.statuses.contains(&res.status()) =>
+                    {
+                        match retry_after(res) {
+                            // the response is returned if the server asks to wait too long
+                            Some(delay) if delay > config.max_delay => None,
+                            Some(delay) => Some(delay),
+                            None => Some(config.backoff(retries)),
+                        }
+                    }
+                    _ => None,
+                };
+
+                let delay = match delay {
+                    Some(delay) => delay,
+                    None => return res,
+                };
+
+                sleep(delay).await;
+                retries += 1;
+            }
+        })
+    }
+}
+
+impl RetryConfig {
+    /// Returns the delay before retrying after `retries` previous retries: the exponential
+    /// backoff delay, reduced by up to half at random.
+    fn backoff(&self, retries: u8) -> Duration {
+        let delay = self
+            .base_delay
+            .checked_mul(1u32 << retries.min(31))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+
+        delay - delay.mul_f64(rand::thread_rng().gen_range(0.0..0.5))
+    }
+}
+
+/// Returns whether connecting may succeed when retried.
+fn is_transient(err: &ConnectError) -> bool {
+    !matches!(
+        err,
+        ConnectError::Unresolved | ConnectError::SslIsNotSupported
+    )
+}
+
+fn clone_body(body: &Body) -> Body {
+    match body {
+        Body::None => Body::None,
+        Body::Empty => Body::Empty,
+        Body::Bytes(bytes) => Body::Bytes(bytes.clone()),
+        Body::Message(_) => unreachable!("streaming bodies are not retried"),
+    }
+}
+
+/// Returns the delay requested in the response's `Retry-After` header, if any.
+fn retry_after(res: &ClientResponse) -> Option<Duration> {
+    let value = res
+        .headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    match value.parse() {
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => {
+            let date = SystemTime::from(HttpDate::from_str(value).ok()?);
+            Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, io};
+
+    use actix_rt::time::Instant;
+    use actix_service::fn_service;
+    use actix_web::{web, App, HttpResponse};
+
+    use super::*;
+    use crate::ClientBuilder;
+
+    fn retry() -> Retry {
+        Retry::new().backoff(Duration::from_millis(1), Duration::from_millis(10))
+    }
+
+    #[actix_rt::test]
+    async fn test_retry_status() {
+        let srv = actix_test::start(|| {
+            let count = Rc::new(Cell::new(0));
+
+            App::new().service(web::resource("/").to(move || {
+                count.set(count.get() + 1);
+
+                if count.get() < 3 {
+                    HttpResponse::ServiceUnavailable().body(count.get().to_string())
+                } else {
+                    HttpResponse::Ok().body(count.get().to_string())
+                }
+            }))
+        });
+
+        let client = ClientBuilder::new().wrap(retry()).finish();
+
+        let mut res = client.get(srv.url("/")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body().await.unwrap(), "3");
+
+        // non-idempotent requests are not retried
+        let srv = actix_test::start(|| {
+            let count = Rc::new(Cell::new(0));
+
+            App::new().service(web::resource("/").to(move || {
+                count.set(count.get() + 1);
+                HttpResponse::ServiceUnavailable().body(count.get().to_string())
+            }))
+        });
+
+        let mut res = client.post(srv.url("/")).send_body("body").await.unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.body().await.unwrap(), "1");
+    }
+
+    #[actix_rt::test]
+    async fn test_retry_after() {
+        let srv = actix_test::start(|| {
+            let count = Rc::new(Cell::new(0));
+
+            App::new()
+                .service(web::resource("/soon").to(move || {
+                    count.set(count.get() + 1);
+
+                    if count.get() == 1 {
+                        HttpResponse::TooManyRequests()
+                            .insert_header((header::RETRY_AFTER, "0"))
+                            .finish()
+                    } else {
+                        HttpResponse::Ok().finish()
+                    }
+                }))
+                .service(web::resource("/later").to(|| {
+                    HttpResponse::TooManyRequests()
+                        .insert_header((header::RETRY_AFTER, "120"))
+                        .finish()
+                }))
+        });
+
+        let client = ClientBuilder::new().wrap(retry()).finish();
+
+        let res = client.get(srv.url("/soon")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client.get(srv.url("/later")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[actix_rt::test]
+    async fn test_retry_connect_error() {
+        let count = Rc::new(Cell::new(0));
+
+        let service = {
+            let count = count.clone();
+
+            retry().max_retries(2).new_transform(fn_service(move |req| {
+                count.set(count.get() + 1);
+
+                let res = match req {
+                    ConnectRequest::Client(_, Body::Bytes(ref body), _)
+                        if body == "refused" =>
+                    {
+                        Err(SendRequestError::Connect(ConnectError::Io(
+                            io::ErrorKind::ConnectionRefused.into(),
+                        )))
+                    }
+                    ConnectRequest::Client(_, Body::Bytes(ref body), _)
+                        if body == "unresolved" =>
+                    {
+                        Err(SendRequestError::Connect(ConnectError::Unresolved))
+                    }
+                    _ => Err(SendRequestError::Timeout),
+                };
+
+                async { res }
+            }))
+        };
+
+        let mut head = actix_http::RequestHead::default();
+        head.method = Method::POST;
+
+        let req = ConnectRequest::Client(head.into(), Body::from("refused"), None);
+        assert!(matches!(
+            service.call(req).await,
+            Err(SendRequestError::Connect(ConnectError::Io(_)))
+        ));
+        assert_eq!(count.get(), 3);
+
+        // permanent connection errors are not retried
+        count.set(0);
+        let head = actix_http::RequestHead::default();
+
+        let req = ConnectRequest::Client(head.into(), Body::from("unresolved"), None);
+        assert!(matches!(
+            service.call(req).await,
+            Err(SendRequestError::Connect(ConnectError::Unresolved))
+        ));
+        assert_eq!(count.get(), 1);
+
+        // timeouts of non-idempotent requests are not retried
+        count.set(0);
+        let mut head = actix_http::RequestHead::default();
+        head.method = Method::POST;
+
+        let req = ConnectRequest::Client(head.into(), Body::Empty, None);
+        assert!(matches!(
+            service.call(req).await,
+            Err(SendRequestError::Timeout)
+        ));
+        assert_eq!(count.get(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_attempt_timeout() {
+        let srv = actix_test::start(|| {
+            let count = Rc::new(Cell::new(0));
+
+            App::new()
+                .service(web::resource("/").to(move || {
+                    count.set(count.get() + 1);
+                    let count = count.get();
+
+                    async move {
+                        if count == 1 {
+                            sleep(Duration::from_secs(2)).await;
+                        }
+
+                        count.to_string()
+                    }
+                }))
+                .service(web::resource("/slow").to(|| async {
+                    sleep(Duration::from_secs(2)).await;
+                    "slow"
+                }))
+        });
+
+        let client = ClientBuilder::new()
+            .wrap(retry().attempt_timeout(Duration::from_millis(200)))
+            .finish();
+
+        let mut res = client.get(srv.url("/")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body().await.unwrap(), "2");
+
+        // the client's timeout bounds all attempts
+        let client = ClientBuilder::new()
+            .wrap(
+                retry()
+                    .max_retries(10)
+                    .attempt_timeout(Duration::from_millis(200)),
+            )
+            .timeout(Duration::from_millis(500))
+            .finish();
+
+        let start = Instant::now();
+        let res = client.get(srv.url("/slow")).send().await;
+        assert!(matches!(res, Err(SendRequestError::Timeout)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = retry().config;
+
+        for retries in 0..40 {
+            let delay = config.backoff(retries);
+            let max = Duration::from_millis(1 << retries.min(31)).min(config.max_delay);
+            assert!(delay <= max && delay >= max / 2);
+        }
+    }
+}
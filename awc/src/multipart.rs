@@ -0,0 +1,424 @@
+//! `multipart/form-data` request bodies.
+//!
+//! # Examples
+//! ```no_run
+//! use awc::multipart::{Form, Part};
+//!
+//! # #[actix_rt::main]
+//! # async fn main() -> Result<(), awc::error::SendRequestError> {
+//! let form = Form::new().text("title", "Holiday").part(
+//!     "photo",
+//!     Part::bytes(vec![0xFF, 0xD8, 0xFF])
+//!         .file_name("beach.jpg")
+//!         .content_type(mime::IMAGE_JPEG),
+//! );
+//!
+//! let res = awc::Client::new()
+//!     .post("http://www.rust-lang.org")
+//!     .send_multipart(form)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    error::Error as StdError,
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_http::{
+    body::{AnyBody, BodySize, BodyStream, MessageBody, SizedStream},
+    http::{
+        header::{
+            self, ContentDisposition, DispositionParam, DispositionType, HeaderMap,
+            IntoHeaderPair,
+        },
+        Error as HttpError,
+    },
+    Error,
+};
+use bytes::{Bytes, BytesMut};
+use futures_core::{ready, Stream};
+use rand::{distributions::Alphanumeric, Rng as _};
+
+/// A `multipart/form-data` request body, defined in
+/// [RFC7578](https://tools.ietf.org/html/rfc7578).
+///
+/// Parts are streamed in the order they were added. When the size of every part is known, the
+/// request is sent with a `Content-Length` header; otherwise it is sent chunked.
+pub struct Form {
+    boundary: String,
+    parts: Vec<(String, Part)>,
+}
+
+impl Form {
+    /// Constructs an empty form with a random boundary.
+    pub fn new() -> Self {
+        let boundary = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+
+        Form {
+            boundary,
+            parts: Vec::new(),
+        }
+    }
+
+    /// Returns the boundary that delimits the parts of the form.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Returns the `Content-Type` header value of the form, including its boundary.
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Adds a text field.
+    pub fn text(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.part(name, Part::text(value))
+    }
+
+    /// Adds a part under the field `name`.
+    ///
+    /// Several parts may be added under the same name.
+    pub fn part(mut self, name: impl Into<String>, part: Part) -> Self {
+        self.parts.push((name.into(), part));
+        self
+    }
+
+    /// Returns the size of the encoded form, if the size of every part is known.
+    pub fn content_length(&self) -> Option<u64> {
+        self.parts.iter().try_fold(
+            self.closing_delimiter().len() as u64,
+            |size, (name, part)| {
+                let body_size = match part.body.size() {
+                    BodySize::None | BodySize::Empty => 0,
+                    BodySize::Sized(size) => size,
+                    BodySize::Stream => return None,
+                };
+
+                let head = self.part_head(name, part);
+                Some(size + head.len() as u64 + body_size + 2)
+            },
+        )
+    }
+
+    fn closing_delimiter(&self) -> String {
+        format!("--{}--\r\n", self.boundary)
+    }
+
+    /// Encodes the delimiter and headers preceding the body of a part.
+    fn part_head(&self, name: &str, part: &Part) -> Bytes {
+        let mut parameters = vec![DispositionParam::Name(name.to_owned())];
+        if let Some(ref file_name) = part.file_name {
+            parameters.push(DispositionParam::Filename(file_name.clone()));
+        }
+
+        let content_disposition = ContentDisposition {
+            disposition: DispositionType::FormData,
+            parameters,
+        };
+
+        let mut head = BytesMut::new();
+        head.extend_from_slice(format!("--{}\r\n", self.boundary).as_bytes());
+        head.extend_from_slice(
+            format!("content-disposition: {}\r\n", content_disposition).as_bytes(),
+        );
+
+        for (name, value) in part.headers.iter() {
+            head.extend_from_slice(name.as_str().as_bytes());
+            head.extend_from_slice(b": ");
+            head.extend_from_slice(value.as_bytes());
+            head.extend_from_slice(b"\r\n");
+        }
+
+        head.extend_from_slice(b"\r\n");
+        head.freeze()
+    }
+
+    /// Encodes the form into a request body, failing if a part has an invalid header.
+    pub(crate) fn into_body(mut self) -> Result<FormBody, HttpError> {
+        for (_, part) in &mut self.parts {
+            if let Some(err) = part.err.take() {
+                return Err(err);
+            }
+        }
+
+        let size = match self.content_length() {
+            Some(size) => BodySize::Sized(size),
+            None => BodySize::Stream,
+        };
+
+        let heads = self
+            .parts
+            .iter()
+            .map(|(name, part)| self.part_head(name, part))
+            .collect::<Vec<_>>();
+
+        let closing_delimiter = self.closing_delimiter();
+        let mut chunks = VecDeque::with_capacity(self.parts.len() * 3 + 1);
+
+        for (head, (_, part)) in heads.into_iter().zip(self.parts) {
+            chunks.push_back(FormChunk::Bytes(head));
+            chunks.push_back(FormChunk::Body(part.body));
+            chunks.push_back(FormChunk::Bytes(Bytes::from_static(b"\r\n")));
+        }
+
+        chunks.push_back(FormChunk::Bytes(Bytes::from(closing_delimiter)));
+
+        Ok(FormBody { chunks, size })
+    }
+}
+
+impl Default for Form {
+    fn default() -> Self {
+        Form::new()
+    }
+}
+
+impl fmt::Debug for Form {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Form")
+            .field("boundary", &self.boundary)
+            .field("parts", &self.parts)
+            .finish()
+    }
+}
+
+/// A part of a multipart [`Form`].
+pub struct Part {
+    body: AnyBody,
+    file_name: Option<String>,
+    headers: HeaderMap,
+    err: Option<HttpError>,
+}
+
+impl Part {
+    fn new(body: AnyBody) -> Self {
+        Part {
+            body,
+            file_name: None,
+            headers: HeaderMap::new(),
+            err: None,
+        }
+    }
+
+    /// Constructs a part containing text.
+    pub fn text(value: impl Into<String>) -> Self {
+        Part::new(AnyBody::from(value.into()))
+    }
+
+    /// Constructs a part containing bytes.
+    pub fn bytes(data: impl Into<Bytes>) -> Self {
+        Part::new(AnyBody::Bytes(data.into()))
+    }
+
+    /// Constructs a part that streams its content, such as a file, from `stream`.
+    ///
+    /// The size of the part is unknown, so the form is sent chunked.
+    pub fn stream<S, E>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, E>> + 'static,
+        E: Into<Box<dyn StdError>> + 'static,
+    {
+        Part::new(AnyBody::from_message(BodyStream::new(stream)))
+    }
+
+    /// Constructs a part that streams `size` bytes from `stream`.
+    pub fn sized_stream<S, E>(size: u64, stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, E>> + 'static,
+        E: Into<Box<dyn StdError>> + 'static,
+    {
+        Part::new(AnyBody::from_message(SizedStream::new(size, stream)))
+    }
+
+    /// Sets the file name of the part, sent in its `Content-Disposition` header.
+    pub fn file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    /// Sets the `Content-Type` header of the part.
+    pub fn content_type(self, content_type: mime::Mime) -> Self {
+        self.insert_header((header::CONTENT_TYPE, content_type.to_string()))
+    }
+
+    /// Inserts a header into the part, replacing any that were set with an equivalent field
+    /// name.
+    pub fn insert_header<H>(mut self, header: H) -> Self
+    where
+        H: IntoHeaderPair,
+    {
+        match header.try_into_header_pair() {
+            Ok((key, value)) => {
+                self.headers.insert(key, value);
+            }
+            Err(e) => self.err = Some(e.into()),
+        }
+
+        self
+    }
+}
+
+impl fmt::Debug for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Part")
+            .field("size", &self.body.size())
+            .field("file_name", &self.file_name)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+enum FormChunk {
+    Bytes(Bytes),
+    Body(AnyBody),
+}
+
+/// An encoded [`Form`], streamed part by part.
+pub(crate) struct FormBody {
+    chunks: VecDeque<FormChunk>,
+    size: BodySize,
+}
+
+impl MessageBody for FormBody {
+    type Error = Error;
+
+    fn size(&self) -> BodySize {
+        self.size
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        loop {
+            match self.chunks.front_mut() {
+                None => return Poll::Ready(None),
+
+                Some(FormChunk::Bytes(_)) => match self.chunks.pop_front() {
+                    Some(FormChunk::Bytes(bytes)) => return Poll::Ready(Some(Ok(bytes))),
+                    _ => unreachable!(),
+                },
+
+                Some(FormChunk::Body(body)) => match ready!(Pin::new(body).poll_next(cx)) {
+                    Some(Ok(chunk)) if chunk.is_empty() => {}
+                    Some(res) => return Poll::Ready(Some(res)),
+                    None => {
+                        self.chunks.pop_front();
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use actix_http::body::to_bytes;
+    use futures_util::stream;
+
+    use super::*;
+
+    fn chunks() -> impl Stream<Item = Result<Bytes, Infallible>> {
+        stream::iter(vec![
+            Ok(Bytes::from_static(b"first ")),
+            Ok(Bytes::from_static(b"second")),
+        ])
+    }
+
+    #[actix_rt::test]
+    async fn test_form_encoding() {
+        let form = Form::new().text("title", "Holiday").part(
+            "photo",
+            Part::bytes(&b"\xFF\xD8\xFF"[..])
+                .file_name("beach \"1\".jpg")
+                .content_type(mime::IMAGE_JPEG),
+        );
+        let boundary = form.boundary().to_owned();
+        assert_eq!(boundary.len(), 32);
+        assert_eq!(
+            form.content_type(),
+            format!("multipart/form-data; boundary={}", boundary)
+        );
+
+        let mut expected = format!(
+            "--{b}\r\n\
+             content-disposition: form-data; name=\"title\"\r\n\
+             \r\n\
+             Holiday\r\n\
+             --{b}\r\n\
+             content-disposition: form-data; name=\"photo\"; filename=\"beach \\\"1\\\".jpg\"\r\n\
+             content-type: image/jpeg\r\n\
+             \r\n",
+            b = boundary
+        )
+        .into_bytes();
+        expected.extend_from_slice(b"\xFF\xD8\xFF");
+        expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        assert_eq!(form.content_length(), Some(expected.len() as u64));
+
+        let body = form.into_body().unwrap();
+        assert_eq!(body.size(), BodySize::Sized(expected.len() as u64));
+        assert_eq!(to_bytes(body).await.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_form_escaping() {
+        let form = Form::new().part(
+            "a\"b\\c",
+            Part::bytes("").file_name("evil\r\ncontent-type: text/html"),
+        );
+
+        let head = form.part_head(&form.parts[0].0, &form.parts[0].1);
+        assert_eq!(
+            head,
+            format!(
+                "--{}\r\n\
+                 content-disposition: form-data; name=\"a\\\"b\\\\c\"; \
+                 filename=\"evil%0D%0Acontent-type: text/html\"\r\n\
+                 \r\n",
+                form.boundary()
+            )
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_form_stream() {
+        let form = Form::new().part("file", Part::stream(chunks()));
+        assert_eq!(form.content_length(), None);
+        let boundary = form.boundary().to_owned();
+
+        let body = form.into_body().unwrap();
+        assert_eq!(body.size(), BodySize::Stream);
+        assert_eq!(
+            to_bytes(body).await.unwrap(),
+            format!(
+                "--{b}\r\n\
+                 content-disposition: form-data; name=\"file\"\r\n\
+                 \r\n\
+                 first second\r\n\
+                 --{b}--\r\n",
+                b = boundary
+            )
+        );
+
+        let form = Form::new().part("file", Part::sized_stream(12, chunks()));
+        let size = form.content_length().unwrap();
+        let body = form.into_body().unwrap();
+        assert_eq!(to_bytes(body).await.unwrap().len() as u64, size);
+
+        let form = Form::new().part("file", Part::text("a").insert_header(("bad\n", "b")));
+        assert!(form.into_body().is_err());
+    }
+}
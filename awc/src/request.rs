@@ -18,6 +18,7 @@ use crate::cookie::{Cookie, CookieJar};
 use crate::{
     error::{FreezeRequestError, InvalidUrl},
     frozen::FrozenClientRequest,
+    multipart::Form,
     sender::{PrepForSendingError, RequestSender, SendClientRequest},
     ClientConfig,
 };
@@ -400,6 +401,25 @@ impl ClientRequest {
         )
     }
 
+    /// Set a `multipart/form-data` body and generate `ClientRequest`.
+    ///
+    /// The `Content-Type` header is set to include the boundary of the form, unless it was
+    /// already set.
+    pub fn send_multipart(self, form: Form) -> SendClientRequest {
+        let slf = match self.prep_for_sending() {
+            Ok(slf) => slf,
+            Err(e) => return e.into(),
+        };
+
+        RequestSender::Owned(slf.head).send_multipart(
+            slf.addr,
+            slf.response_decompress,
+            slf.timeout,
+            &slf.config,
+            form,
+        )
+    }
+
     /// Set an streaming body and generate `ClientRequest`.
     pub fn send_stream<S, E>(self, stream: S) -> SendClientRequest
     where
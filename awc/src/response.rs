@@ -21,7 +21,7 @@ use serde::de::DeserializeOwned;
 
 #[cfg(feature = "cookies")]
 use crate::cookie::{Cookie, ParseError as CookieParseError};
-use crate::error::JsonPayloadError;
+use crate::{error::JsonPayloadError, sse::EventStream};
 
 /// Client Response
 pub struct ClientResponse<S = PayloadStream> {
@@ -213,6 +213,13 @@ where
     pub fn json<T: DeserializeOwned>(&mut self) -> JsonBody<S, T> {
         JsonBody::new(self)
     }
+
+    /// Reads a `text/event-stream` encoded body as a stream of server-sent events.
+    ///
+    /// Returns a stream that yields an error if the content type is not `text/event-stream`.
+    pub fn sse(&mut self) -> EventStream<S> {
+        EventStream::new(self)
+    }
 }
 
 impl<S> Stream for ClientResponse<S>
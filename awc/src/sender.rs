@@ -27,6 +27,7 @@ use actix_http::{encoding::Decoder, http::header::ContentEncoding, Payload, Payl
 
 use crate::{
     error::{FreezeRequestError, InvalidUrl, SendRequestError},
+    multipart::Form,
     ClientConfig, ClientResponse, ConnectRequest, ConnectResponse,
 };
 
@@ -269,6 +270,33 @@ impl RequestSender {
         )
     }
 
+    pub(crate) fn send_multipart(
+        mut self,
+        addr: Option<net::SocketAddr>,
+        response_decompress: bool,
+        timeout: Option<Duration>,
+        config: &ClientConfig,
+        form: Form,
+    ) -> SendClientRequest {
+        // set content-type, including the boundary of the form
+        if let Err(e) = self.set_header_if_none(header::CONTENT_TYPE, form.content_type()) {
+            return e.into();
+        }
+
+        let body = match form.into_body() {
+            Ok(body) => body,
+            Err(e) => return e.into(),
+        };
+
+        self.send_body(
+            addr,
+            response_decompress,
+            timeout,
+            config,
+            Body::from_message(body),
+        )
+    }
+
     pub(crate) fn send_stream<S, E>(
         self,
         addr: Option<net::SocketAddr>,
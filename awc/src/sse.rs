@@ -0,0 +1,352 @@
+//! Server-sent events client.
+//!
+//! Responses with the `text/event-stream` content type are read as a stream of [`Event`]s with
+//! [`ClientResponse::sse`](crate::ClientResponse::sse).
+//!
+//! # Examples
+//! ```no_run
+//! use futures_util::stream::StreamExt as _;
+//!
+//! # #[actix_rt::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut res = awc::Client::new()
+//!     .get("http://localhost:8080/events")
+//!     .insert_header(("Accept", "text/event-stream"))
+//!     .send()
+//!     .await?;
+//!
+//! let mut events = res.sse();
+//!
+//! while let Some(event) = events.next().await {
+//!     let event = event?;
+//!     println!("{}: {}", event.event, event.data);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_http::{error::PayloadError, HttpMessage as _, Payload};
+use bytes::{Buf as _, Bytes, BytesMut};
+use futures_core::{ready, Stream};
+
+use crate::{error::SseError, ClientResponse};
+
+const DEFAULT_EVENT_LIMIT: usize = 262_144;
+
+/// An event received from a server-sent event stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// The event name, `message` if the server did not set one.
+    pub event: String,
+
+    /// The event data. Data sent in several `data` fields is joined with line feeds.
+    pub data: String,
+
+    /// The ID of the last event that set one, if any.
+    pub last_event_id: Option<String>,
+}
+
+/// Stream of the [`Event`]s in a `text/event-stream` response, parsed as defined in the
+/// [HTML Living Standard](https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation).
+///
+/// Comments and events without data are skipped, and events left incomplete when the response
+/// ends are discarded. The response timeout does not apply to event streams.
+pub struct EventStream<S> {
+    payload: Payload<S>,
+    buf: BytesMut,
+    limit: usize,
+    err: Option<SseError>,
+    done: bool,
+
+    /// Whether the first line has been read, after which a byte order mark is not expected.
+    started: bool,
+
+    event: String,
+    data: String,
+    last_event_id: String,
+    retry: Option<Duration>,
+}
+
+impl<S> EventStream<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+{
+    /// Constructs a stream of the events in the response body.
+    pub fn new(res: &mut ClientResponse<S>) -> Self {
+        let event_stream = match res.mime_type() {
+            Ok(Some(mime)) => mime.type_() == mime::TEXT && mime.subtype() == "event-stream",
+            _ => false,
+        };
+
+        EventStream {
+            payload: res.take_payload(),
+            buf: BytesMut::new(),
+            limit: DEFAULT_EVENT_LIMIT,
+            err: if event_stream {
+                None
+            } else {
+                Some(SseError::ContentType)
+            },
+            done: false,
+            started: false,
+            event: String::new(),
+            data: String::new(),
+            last_event_id: String::new(),
+            retry: None,
+        }
+    }
+
+    /// Change max size of an event. By default max size is 256kB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Returns the ID of the last event that set one, which should be sent in the
+    /// `Last-Event-ID` header when reconnecting.
+    pub fn last_event_id(&self) -> Option<&str> {
+        if self.last_event_id.is_empty() {
+            None
+        } else {
+            Some(&self.last_event_id)
+        }
+    }
+
+    /// Returns the reconnection time last set by the server, if any.
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Takes the next complete line from the buffer, without its line break.
+    fn next_line(&mut self) -> Option<Bytes> {
+        let idx = self.buf.iter().position(|&b| b == b'\r' || b == b'\n')?;
+
+        let break_len = if self.buf[idx] == b'\n' {
+            1
+        } else {
+            match self.buf.get(idx + 1) {
+                Some(b'\n') => 2,
+                Some(_) => 1,
+                // a `\r` may be followed by a `\n` in the next chunk
+                None => return None,
+            }
+        };
+
+        let line = self.buf.split_to(idx).freeze();
+        self.buf.advance(break_len);
+        Some(line)
+    }
+
+    /// Processes a line, returning an event if it completes one.
+    fn process_line(&mut self, line: &[u8]) -> Option<Event> {
+        let mut line = String::from_utf8_lossy(line);
+
+        if !self.started {
+            self.started = true;
+
+            if let Some(stripped) = line.strip_prefix('\u{feff}') {
+                line = stripped.to_owned().into();
+            }
+        }
+
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        let (field, value) = match line.find(':') {
+            Some(0) => return None,
+            Some(idx) => {
+                let value = &line[idx + 1..];
+                (&line[..idx], value.strip_prefix(' ').unwrap_or(value))
+            }
+            None => (&*line, ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_owned(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.last_event_id = value.to_owned(),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(ms) = value.parse() {
+                    self.retry = Some(Duration::from_millis(ms));
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Completes the pending event, if it has data.
+    fn dispatch(&mut self) -> Option<Event> {
+        let event = std::mem::take(&mut self.event);
+        let mut data = std::mem::take(&mut self.data);
+
+        if data.is_empty() {
+            return None;
+        }
+
+        data.pop();
+
+        Some(Event {
+            event: if event.is_empty() {
+                "message".to_owned()
+            } else {
+                event
+            },
+            data,
+            last_event_id: self.last_event_id().map(ToOwned::to_owned),
+        })
+    }
+}
+
+impl<S> Unpin for EventStream<S> {}
+
+impl<S> Stream for EventStream<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<Event, SseError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            if let Some(err) = this.err.take() {
+                this.done = true;
+                return Poll::Ready(Some(Err(err)));
+            }
+
+            while let Some(line) = this.next_line() {
+                if let Some(event) = this.process_line(&line) {
+                    return Poll::Ready(Some(Ok(event)));
+                }
+            }
+
+            if this.buf.len() + this.data.len() > this.limit {
+                this.err = Some(SseError::Overflow { limit: this.limit });
+                continue;
+            }
+
+            match ready!(Pin::new(&mut this.payload).poll_next(cx)) {
+                Some(Ok(chunk)) => this.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => this.err = Some(err.into()),
+
+                // an incomplete event is discarded
+                None => this.done = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::http::header;
+    use futures_util::stream::{self, StreamExt as _, TryStreamExt as _};
+
+    use super::*;
+    use crate::test::TestResponse;
+
+    fn event_stream(
+        body: &'static str,
+        chunk_size: usize,
+    ) -> EventStream<actix_http::PayloadStream> {
+        let chunks = body
+            .as_bytes()
+            .chunks(chunk_size)
+            .map(|chunk| Ok(Bytes::from_static(chunk)))
+            .collect::<Vec<_>>();
+
+        let mut res = TestResponse::with_header((header::CONTENT_TYPE, "text/event-stream"))
+            .finish()
+            .map_body(|_, _| {
+                let stream: actix_http::PayloadStream = Box::pin(stream::iter(chunks));
+                Payload::Stream(stream)
+            });
+
+        res.sse()
+    }
+
+    fn event(event: &str, data: &str, id: Option<&str>) -> Event {
+        Event {
+            event: event.to_owned(),
+            data: data.to_owned(),
+            last_event_id: id.map(ToOwned::to_owned),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_parse_events() {
+        let body = "\u{feff}: comment\n\
+                    data: first\r\n\
+                    data:  second\r\
+                    \r\n\
+                    event: update\n\
+                    id: 7\n\
+                    retry: 2500\n\
+                    data\n\
+                    data:third\n\
+                    \n\
+                    id: 8\n\
+                    \n\
+                    retry: soon\n\
+                    data: last\n\
+                    \n\
+                    data: incomplete\n";
+
+        for &chunk_size in &[1, 2, 7, body.len()] {
+            let mut events = event_stream(body, chunk_size);
+
+            assert_eq!(
+                events.next().await.unwrap().unwrap(),
+                event("message", "first\n second", None)
+            );
+            assert_eq!(
+                events.next().await.unwrap().unwrap(),
+                event("update", "\nthird", Some("7"))
+            );
+            assert_eq!(events.retry(), Some(Duration::from_millis(2500)));
+            assert_eq!(
+                events.next().await.unwrap().unwrap(),
+                event("message", "last", Some("8"))
+            );
+            assert!(events.next().await.is_none());
+            assert_eq!(events.last_event_id(), Some("8"));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_errors() {
+        let mut res = TestResponse::with_header((header::CONTENT_TYPE, "text/plain"))
+            .set_payload("data: a\n\n")
+            .finish();
+        let events = res.sse().collect::<Vec<_>>().await;
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], Err(SseError::ContentType)));
+
+        let events = event_stream("data: short\n\ndata: a long line\n\n", 4).limit(12);
+        let events = events.collect::<Vec<_>>().await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            *events[0].as_ref().unwrap(),
+            event("message", "short", None)
+        );
+        assert!(matches!(events[1], Err(SseError::Overflow { limit: 12 })));
+
+        let events = event_stream("data: a\n\n", 1).try_collect::<Vec<_>>().await;
+        assert_eq!(events.unwrap(), vec![event("message", "a", None)]);
+    }
+}
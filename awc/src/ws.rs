@@ -36,6 +36,8 @@ use actix_rt::time::timeout;
 use actix_service::Service;
 
 pub use actix_http::ws::{CloseCode, CloseReason, Codec, Frame, Message};
+#[cfg(feature = "compress-gzip")]
+pub use actix_http::ws::{DeflateConfig, DeflateParams};
 
 use crate::connect::{BoxedSocket, ConnectRequest};
 #[cfg(feature = "cookies")]
@@ -59,6 +61,9 @@ pub struct WebsocketsRequest {
 
     #[cfg(feature = "cookies")]
     cookies: Option<CookieJar>,
+
+    #[cfg(feature = "compress-gzip")]
+    deflate: Option<DeflateConfig>,
 }
 
 impl WebsocketsRequest {
@@ -94,6 +99,8 @@ impl WebsocketsRequest {
             server_mode: false,
             #[cfg(feature = "cookies")]
             cookies: None,
+            #[cfg(feature = "compress-gzip")]
+            deflate: None,
         }
     }
 
@@ -160,6 +167,16 @@ impl WebsocketsRequest {
         self
     }
 
+    /// Offer the permessage-deflate extension to the server.
+    ///
+    /// If the server accepts the offer, the returned codec compresses messages using the
+    /// negotiated parameters.
+    #[cfg(feature = "compress-gzip")]
+    pub fn deflate(mut self, config: DeflateConfig) -> Self {
+        self.deflate = Some(config);
+        self
+    }
+
     /// Append a header.
     ///
     /// Header gets appended to existing header.
@@ -312,6 +329,13 @@ impl WebsocketsRequest {
             );
         }
 
+        #[cfg(feature = "compress-gzip")]
+        if let Some(ref deflate) = self.deflate {
+            self.head
+                .headers
+                .insert(header::SEC_WEBSOCKET_EXTENSIONS, deflate.offer());
+        }
+
         // Generate a random key for the `Sec-WebSocket-Key` header.
         // a base64-encoded (see Section 4 of [RFC4648]) value that,
         // when decoded, is 16 bytes in length (RFC 6455)
@@ -398,16 +422,29 @@ impl WebsocketsRequest {
             return Err(WsClientError::MissingWebSocketAcceptHeader);
         };
 
+        let mut codec = if server_mode {
+            ws::Codec::new().max_size(max_size)
+        } else {
+            ws::Codec::new().max_size(max_size).client_mode()
+        };
+
+        #[cfg(feature = "compress-gzip")]
+        if let Some(deflate) = self.deflate {
+            match deflate.accept_response(&head.headers) {
+                Ok(Some(params)) => codec = codec.deflate(params),
+                Ok(None) => {}
+                Err(_) => {
+                    log::trace!("Invalid Sec-WebSocket-Extensions header");
+                    return Err(WsClientError::InvalidExtensionsHeader);
+                }
+            }
+        }
+
         // response and ws framed
         Ok((
             ClientResponse::new(head, Payload::None),
-            framed.into_map_codec(|_| {
-                if server_mode {
-                    ws::Codec::new().max_size(max_size)
-                } else {
-                    ws::Codec::new().max_size(max_size).client_mode()
-                }
-            }),
+            #[allow(clippy::clone_on_copy)]
+            framed.into_map_codec(move |_| codec.clone()),
         ))
     }
 }
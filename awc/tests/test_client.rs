@@ -112,6 +112,295 @@ async fn test_form() {
     assert!(response.status().is_success());
 }
 
+#[actix_rt::test]
+async fn test_multipart() {
+    let srv = actix_test::start(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, body: Bytes| {
+                let header = |name| {
+                    req.headers()
+                        .get(name)
+                        .map_or("-", |value: &header::HeaderValue| value.to_str().unwrap())
+                        .to_owned()
+                };
+
+                HttpResponse::Ok()
+                    .insert_header(("x-content-type", header(header::CONTENT_TYPE)))
+                    .insert_header(("x-content-length", header(header::CONTENT_LENGTH)))
+                    .body(body)
+            },
+        )))
+    });
+
+    let form = awc::multipart::Form::new().text("key", "TEST").part(
+        "file",
+        awc::multipart::Part::bytes("file content").file_name("test.txt"),
+    );
+    let boundary = form.boundary().to_owned();
+    let size = form.content_length().unwrap();
+
+    let mut res = srv.post("/").send_multipart(form).await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(
+        res.headers().get("x-content-type").unwrap(),
+        &format!("multipart/form-data; boundary={}", boundary)
+    );
+    assert_eq!(
+        res.headers().get("x-content-length").unwrap(),
+        &size.to_string()
+    );
+
+    let body = res.body().await.unwrap();
+    assert_eq!(
+        body,
+        format!(
+            "--{b}\r\ncontent-disposition: form-data; name=\"key\"\r\n\r\nTEST\r\n\
+             --{b}\r\ncontent-disposition: form-data; name=\"file\"; filename=\"test.txt\"\r\n\r\n\
+             file content\r\n--{b}--\r\n",
+            b = boundary
+        )
+    );
+
+    // streamed parts are sent chunked
+    let stream = stream::iter(vec![Ok::<_, Error>(Bytes::from_static(b"streamed"))]);
+    let form = awc::multipart::Form::new().part("file", awc::multipart::Part::stream(stream));
+
+    let mut res = srv.post("/").send_multipart(form).await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.headers().get("x-content-length").unwrap(), "-");
+    let body = res.body().await.unwrap();
+    let part = &b"\r\n\r\nstreamed\r\n--"[..];
+    assert!(body.windows(part.len()).any(|w| w == part));
+}
+
+#[actix_rt::test]
+async fn test_http_proxy() {
+    // the proxy echoes the request target and credentials it received
+    let proxy = actix_test::start(|| {
+        App::new().default_service(web::to(|req: HttpRequest| {
+            let auth = req
+                .headers()
+                .get(header::PROXY_AUTHORIZATION)
+                .map_or("", |auth| auth.to_str().unwrap())
+                .to_owned();
+
+            HttpResponse::Ok().body(format!("{} {}", req.uri(), auth))
+        }))
+    });
+
+    let client = awc::Client::builder()
+        .proxy(
+            awc::Proxy::new(proxy.url("/"))
+                .unwrap()
+                .basic_auth("user", Some("pass")),
+        )
+        .finish();
+
+    let mut res = client
+        .get("http://example.invalid/path?q=1")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let body = res.body().await.unwrap();
+    assert_eq!(
+        body,
+        "http://example.invalid/path?q=1 Basic dXNlcjpwYXNz".as_bytes()
+    );
+
+    // hosts excluded from proxying are connected to directly
+    let client = awc::Client::builder()
+        .proxy(awc::Proxy::new(proxy.url("/")).unwrap())
+        .no_proxy(vec!["invalid"])
+        .finish();
+
+    let res = client.get("http://example.invalid/").send().await;
+    assert!(matches!(res, Err(SendRequestError::Connect(_))));
+}
+
+#[actix_rt::test]
+async fn test_socks5_proxy() {
+    use std::{
+        io,
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    let srv = actix_test::start(|| {
+        App::new().default_service(web::to(|req: HttpRequest| {
+            let host = req.headers().get(header::HOST).unwrap().to_str().unwrap();
+            HttpResponse::Ok().body(host.to_owned())
+        }))
+    });
+
+    // the proxy connects every requested host name to the test server
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy = listener.local_addr().unwrap();
+    let target = srv.addr();
+
+    thread::spawn(move || {
+        for client in listener.incoming() {
+            let mut client = client.unwrap();
+
+            let mut greeting = [0; 3];
+            client.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [5, 1, 0]);
+            client.write_all(&[5, 0]).unwrap();
+
+            let mut request = [0; 5];
+            client.read_exact(&mut request).unwrap();
+            assert_eq!(request[..4], [5, 1, 0, 3]);
+            let mut host = vec![0; request[4] as usize + 2];
+            client.read_exact(&mut host).unwrap();
+            client.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+
+            let mut server = TcpStream::connect(target).unwrap();
+            let mut client_read = client.try_clone().unwrap();
+            let mut server_write = server.try_clone().unwrap();
+
+            thread::spawn(move || io::copy(&mut client_read, &mut server_write));
+            thread::spawn(move || io::copy(&mut server, &mut client));
+        }
+    });
+
+    let socks5 = awc::Socks5Connector::new(format!("socks5h://{}", proxy)).unwrap();
+    let client = awc::Client::builder()
+        .connector(awc::Connector::new().connector(socks5))
+        .finish();
+
+    let url = format!("http://socks.invalid:{}/", target.port());
+    for _ in 0..2 {
+        let mut res = client.get(&url).send().await.unwrap();
+        assert!(res.status().is_success());
+
+        let body = res.body().await.unwrap();
+        assert_eq!(body, format!("socks.invalid:{}", target.port()).as_bytes());
+    }
+}
+
+#[actix_rt::test]
+async fn test_resolve() {
+    use std::{
+        cell::RefCell,
+        net::{SocketAddr, TcpListener},
+        rc::Rc,
+    };
+
+    use futures_core::future::LocalBoxFuture;
+
+    let srv = actix_test::start(|| {
+        App::new().default_service(web::to(|req: HttpRequest| {
+            let host = req.headers().get(header::HOST).unwrap().to_str().unwrap();
+            HttpResponse::Ok().body(host.to_owned())
+        }))
+    });
+
+    // addresses that refuse connections are skipped
+    let refused = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap()
+    };
+
+    let client = awc::Client::builder()
+        .resolve("static.invalid", 80, refused)
+        .resolve("static.invalid", 80, srv.addr())
+        .finish();
+
+    let mut res = client.get("http://static.invalid/").send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), "static.invalid".as_bytes());
+
+    // custom resolvers look up hosts without static addresses
+    struct TestResolver {
+        addr: SocketAddr,
+        lookups: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl awc::Resolve for TestResolver {
+        fn lookup<'a>(
+            &'a self,
+            host: &'a str,
+            port: u16,
+        ) -> LocalBoxFuture<'a, Result<Vec<SocketAddr>, Box<dyn std::error::Error>>> {
+            self.lookups.borrow_mut().push(format!("{}:{}", host, port));
+
+            let addr = self.addr;
+            Box::pin(async move { Ok(vec![addr]) })
+        }
+    }
+
+    let lookups = Rc::new(RefCell::new(Vec::new()));
+    let client = awc::Client::builder()
+        .resolver(TestResolver {
+            addr: srv.addr(),
+            lookups: lookups.clone(),
+        })
+        .resolve("static.invalid", 80, srv.addr())
+        .finish();
+
+    let res = client
+        .get("http://custom.invalid:8080/")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let res = client.get("http://static.invalid/").send().await.unwrap();
+    assert!(res.status().is_success());
+
+    // static addresses only apply to their port
+    let res = client
+        .get("http://static.invalid:8080/")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    assert_eq!(
+        *lookups.borrow(),
+        vec!["custom.invalid:8080", "static.invalid:8080"]
+    );
+}
+
+#[actix_rt::test]
+async fn test_sse() {
+    use futures_util::stream::StreamExt as _;
+
+    let srv = actix_test::start(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |last_event_id: Option<web::Header<header::LastEventId>>| async move {
+                let start = last_event_id.map_or(0, |id| id.0.parse::<u32>().unwrap() + 1);
+                let events = stream::iter((start..3).map(|id| {
+                    web::SseEvent::data(format!("event {}\nof 3", id)).id(id.to_string())
+                }));
+
+                web::Sse::new(events).keep_alive(Duration::from_secs(1))
+            },
+        )))
+    });
+
+    let mut res = srv.get("/").send().await.unwrap();
+    assert!(res.status().is_success());
+
+    let mut events = res.sse();
+    let first = events.next().await.unwrap().unwrap();
+    assert_eq!(first.event, "message");
+    assert_eq!(first.data, "event 0\nof 3");
+    assert_eq!(first.last_event_id.as_deref(), Some("0"));
+    assert_eq!(events.last_event_id(), Some("0"));
+
+    // resumes after the last received event
+    let mut res = srv
+        .get("/")
+        .insert_header(("last-event-id", "1"))
+        .send()
+        .await
+        .unwrap();
+    let events = res.sse().collect::<Vec<_>>().await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].as_ref().unwrap().data, "event 2\nof 3");
+}
+
 #[actix_rt::test]
 async fn test_timeout() {
     let srv = actix_test::start(|| {
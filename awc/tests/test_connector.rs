@@ -2,11 +2,23 @@
 
 extern crate tls_openssl as openssl;
 
-use actix_http::HttpService;
+use std::{
+    io::{self, Read as _, Write as _},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::mpsc,
+    thread,
+};
+
+use actix_codec::Framed;
+use actix_http::{body::BodySize, client::ConnectError, h1, ws, Error, HttpService, Request};
 use actix_http_test::test_server;
 use actix_service::{map_config, ServiceFactoryExt};
-use actix_web::http::Version;
+use actix_utils::future::ok;
+use actix_web::http::{StatusCode, Version};
 use actix_web::{dev::AppConfig, web, App, HttpResponse};
+use awc::error::SendRequestError;
+use bytes::Bytes;
+use futures_util::{SinkExt as _, StreamExt as _};
 use openssl::{
     pkey::PKey,
     ssl::{SslAcceptor, SslConnector, SslMethod, SslVerifyMode},
@@ -68,3 +80,142 @@ async fn test_connection_window_size() {
     assert!(response.status().is_success());
     assert_eq!(response.version(), Version::HTTP_2);
 }
+
+/// Starts a proxy that responds to `CONNECT` requests with `status`, sending the request heads it
+/// receives to the returned channel. Successful tunnels are connected to `target`.
+fn connect_proxy(target: SocketAddr, status: u16) -> (SocketAddr, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        for client in listener.incoming() {
+            let mut client = client.unwrap();
+
+            let mut head = Vec::new();
+            let mut byte = [0];
+            while !head.ends_with(b"\r\n\r\n") {
+                client.read_exact(&mut byte).unwrap();
+                head.push(byte[0]);
+            }
+            tx.send(String::from_utf8(head).unwrap()).unwrap();
+
+            write!(client, "HTTP/1.1 {} Proxy Response\r\n\r\n", status).unwrap();
+            if status != 200 {
+                continue;
+            }
+
+            let mut server = TcpStream::connect(target).unwrap();
+            let mut client_read = client.try_clone().unwrap();
+            let mut server_write = server.try_clone().unwrap();
+
+            thread::spawn(move || io::copy(&mut client_read, &mut server_write));
+            thread::spawn(move || io::copy(&mut server, &mut client));
+        }
+    });
+
+    (addr, rx)
+}
+
+#[actix_rt::test]
+async fn test_https_proxy_tunnel() {
+    let srv = test_server(move || {
+        HttpService::build()
+            .h2(map_config(
+                App::new().service(web::resource("/").route(web::to(HttpResponse::Ok))),
+                |_| AppConfig::default(),
+            ))
+            .openssl(tls_config())
+            .map_err(|_| ())
+    })
+    .await;
+
+    let client = |proxy: SocketAddr| {
+        let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        builder.set_alpn_protos(b"\x02h2\x08http/1.1").unwrap();
+
+        let proxy = awc::Proxy::new(format!("http://{}", proxy))
+            .unwrap()
+            .basic_auth("user", Some("pass"));
+
+        awc::Client::builder()
+            .connector(awc::Connector::new().ssl(builder.build()))
+            .proxy(proxy)
+            .finish()
+    };
+
+    let port = srv.addr().port();
+    let expected_head = format!(
+        "CONNECT localhost:{0} HTTP/1.1\r\nHost: localhost:{0}\r\n\
+         Proxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n",
+        port
+    );
+
+    let (proxy, heads) = connect_proxy(srv.addr(), 200);
+    let url = format!("https://localhost:{}/", port);
+
+    let res = client(proxy).get(&url).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.version(), Version::HTTP_2);
+    assert_eq!(heads.recv().unwrap(), expected_head);
+
+    let (proxy, heads) = connect_proxy(srv.addr(), 407);
+
+    let res = client(proxy).get(&url).send().await;
+    assert!(matches!(
+        res,
+        Err(SendRequestError::Connect(ConnectError::ProxyTunnel(
+            StatusCode::PROXY_AUTHENTICATION_REQUIRED
+        )))
+    ));
+    assert_eq!(heads.recv().unwrap(), expected_head);
+}
+
+#[actix_rt::test]
+async fn test_ws_proxy_tunnel() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| async move {
+                let res = ws::handshake_response(req.head()).finish();
+                framed
+                    .send(h1::Message::Item((res.drop_body(), BodySize::None)))
+                    .await
+                    .unwrap();
+
+                let mut framed = framed.replace_codec(ws::Codec::new());
+                if let Some(Ok(ws::Frame::Text(text))) = framed.next().await {
+                    framed.send(ws::Message::Binary(text)).await.unwrap();
+                }
+
+                Ok::<_, Error>(())
+            })
+            .finish(|_| ok::<_, Error>(actix_http::Response::not_found()))
+            .tcp()
+    })
+    .await;
+
+    let (proxy, heads) = connect_proxy(srv.addr(), 200);
+    let client = awc::Client::builder()
+        .proxy(awc::Proxy::new(format!("http://{}", proxy)).unwrap())
+        .finish();
+
+    let port = srv.addr().port();
+    let (res, mut framed) = client
+        .ws(format!("ws://localhost:{}/", port))
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::SWITCHING_PROTOCOLS);
+    assert_eq!(
+        heads.recv().unwrap(),
+        format!(
+            "CONNECT localhost:{0} HTTP/1.1\r\nHost: localhost:{0}\r\n\r\n",
+            port
+        )
+    );
+
+    framed.send(ws::Message::Text("text".into())).await.unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Binary(Bytes::from_static(b"text")));
+}
@@ -66,3 +66,72 @@ async fn test_simple() {
     let item = framed.next().await.unwrap().unwrap();
     assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
 }
+
+#[cfg(feature = "compress-gzip")]
+#[actix_rt::test]
+async fn test_deflate() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| {
+                async move {
+                    let (mut res, codec) =
+                        ws::handshake_with_deflate(req.head(), &ws::DeflateConfig::new())
+                            .unwrap();
+
+                    // send handshake response
+                    framed
+                        .send(h1::Message::Item((
+                            res.finish().drop_body(),
+                            BodySize::None,
+                        )))
+                        .await?;
+
+                    // start WebSocket service
+                    let framed = framed.replace_codec(codec);
+                    ws::Dispatcher::with(framed, ws_service).await
+                }
+            })
+            .finish(|_| ok::<_, Error>(Response::not_found()))
+            .tcp()
+    })
+    .await;
+
+    let (res, mut framed) = awc::Client::new()
+        .ws(srv.url("/"))
+        .deflate(awc::ws::DeflateConfig::new().no_context_takeover())
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(
+        res.headers()
+            .get(actix_http::http::header::SEC_WEBSOCKET_EXTENSIONS)
+            .unwrap(),
+        "permessage-deflate; client_no_context_takeover"
+    );
+
+    for _ in 0..3 {
+        framed
+            .send(ws::Message::Text("text text text text".into()))
+            .await
+            .unwrap();
+        let item = framed.next().await.unwrap().unwrap();
+        assert_eq!(
+            item,
+            ws::Frame::Text(Bytes::from_static(b"text text text text"))
+        );
+    }
+
+    framed
+        .send(ws::Message::Binary("text".into()))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Binary(Bytes::from_static(b"text")));
+
+    framed
+        .send(ws::Message::Close(Some(ws::CloseCode::Normal.into())))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
+}
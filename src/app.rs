@@ -17,6 +17,7 @@ use crate::config::ServiceConfig;
 use crate::data::{Data, DataFactory, FnDataFactory};
 use crate::dev::ResourceDef;
 use crate::error::Error;
+use crate::info::TrustedProxies;
 use crate::resource::Resource;
 use crate::route::Route;
 use crate::service::{
@@ -36,6 +37,7 @@ pub struct App<T, B> {
     data_factories: Vec<FnDataFactory>,
     external: Vec<ResourceDef>,
     extensions: Extensions,
+    trusted_proxies: Option<TrustedProxies>,
     _phantom: PhantomData<B>,
 }
 
@@ -53,6 +55,7 @@ impl App<AppEntry, Body> {
             factory_ref,
             external: Vec::new(),
             extensions: Extensions::new(),
+            trusted_proxies: None,
             _phantom: PhantomData,
         }
     }
@@ -339,6 +342,29 @@ where
         self
     }
 
+    /// Set proxies trusted to report client information through forwarding headers.
+    ///
+    /// Overrides the configuration set with
+    /// [`HttpServer::trusted_proxies`](crate::HttpServer::trusted_proxies). Check
+    /// [TrustedProxies](crate::dev::TrustedProxies) documentation for more information.
+    ///
+    /// ```
+    /// use actix_web::{dev::TrustedProxies, web, App, HttpRequest};
+    ///
+    /// async fn index(req: HttpRequest) -> String {
+    ///     // address of the client, as reported by the load balancer
+    ///     req.connection_info().realip_remote_addr().unwrap_or("-").to_owned()
+    /// }
+    ///
+    /// let app = App::new()
+    ///     .trusted_proxies(TrustedProxies::new().add_network("10.0.0.0/8"))
+    ///     .route("/", web::get().to(index));
+    /// ```
+    pub fn trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = Some(trusted_proxies);
+        self
+    }
+
     /// Registers middleware, in the form of a middleware component (type),
     /// that runs during inbound and/or outbound processing in the request
     /// life-cycle (request -> response), modifying request/response as
@@ -400,6 +426,7 @@ where
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            trusted_proxies: self.trusted_proxies,
             _phantom: PhantomData,
         }
     }
@@ -461,6 +488,7 @@ where
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            trusted_proxies: self.trusted_proxies,
             _phantom: PhantomData,
         }
     }
@@ -487,6 +515,7 @@ where
             default: self.default,
             factory_ref: self.factory_ref,
             extensions: RefCell::new(Some(self.extensions)),
+            trusted_proxies: self.trusted_proxies,
         }
     }
 }
@@ -13,6 +13,7 @@ use crate::{
     config::{AppConfig, AppService},
     data::FnDataFactory,
     guard::Guard,
+    info::TrustedProxies,
     request::{HttpRequest, HttpRequestPool},
     rmap::ResourceMap,
     service::{AppServiceFactory, ServiceRequest, ServiceResponse},
@@ -42,6 +43,7 @@ where
     pub(crate) default: Option<Rc<HttpNewService>>,
     pub(crate) factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     pub(crate) external: RefCell<Vec<ResourceDef>>,
+    pub(crate) trusted_proxies: Option<TrustedProxies>,
 }
 
 impl<T, B> ServiceFactory<Request> for AppInit<T, B>
@@ -62,7 +64,11 @@ where
     type InitError = T::InitError;
     type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
 
-    fn new_service(&self, config: AppConfig) -> Self::Future {
+    fn new_service(&self, mut config: AppConfig) -> Self::Future {
+        if let Some(ref trusted_proxies) = self.trusted_proxies {
+            config.set_trusted_proxies(trusted_proxies.clone());
+        }
+
         // set AppService's default service to 404 NotFound
         // if no user defined default service exists.
         let default = self.default.clone().unwrap_or_else(|| {
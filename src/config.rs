@@ -8,6 +8,7 @@ use actix_service::{boxed, IntoServiceFactory, ServiceFactory};
 use crate::data::Data;
 use crate::error::Error;
 use crate::guard::Guard;
+use crate::info::TrustedProxies;
 use crate::resource::Resource;
 use crate::rmap::ResourceMap;
 use crate::route::Route;
@@ -111,17 +112,28 @@ pub struct AppConfig {
     secure: bool,
     host: String,
     addr: SocketAddr,
+    trusted_proxies: TrustedProxies,
 }
 
 impl AppConfig {
-    pub(crate) fn new(secure: bool, host: String, addr: SocketAddr) -> Self {
-        AppConfig { secure, host, addr }
+    pub(crate) fn new(
+        secure: bool,
+        host: String,
+        addr: SocketAddr,
+        trusted_proxies: TrustedProxies,
+    ) -> Self {
+        AppConfig {
+            secure,
+            host,
+            addr,
+            trusted_proxies,
+        }
     }
 
     /// Needed in actix-test crate. Semver exempt.
     #[doc(hidden)]
     pub fn __priv_test_new(secure: bool, host: String, addr: SocketAddr) -> Self {
-        AppConfig::new(secure, host, addr)
+        AppConfig::new(secure, host, addr, TrustedProxies::default())
     }
 
     /// Server host name.
@@ -145,6 +157,18 @@ impl AppConfig {
         self.addr
     }
 
+    /// Proxies trusted to report client information through forwarding headers.
+    ///
+    /// Check [ConnectionInfo](super::dev::ConnectionInfo::realip_remote_addr())
+    /// documentation for more information.
+    pub fn trusted_proxies(&self) -> &TrustedProxies {
+        &self.trusted_proxies
+    }
+
+    pub(crate) fn set_trusted_proxies(&mut self, trusted_proxies: TrustedProxies) {
+        self.trusted_proxies = trusted_proxies;
+    }
+
     #[cfg(test)]
     pub(crate) fn set_host(&mut self, host: &str) {
         self.host = host.to_owned();
@@ -157,6 +181,7 @@ impl Default for AppConfig {
             false,
             "localhost:8080".to_owned(),
             "127.0.0.1:8080".parse().unwrap(),
+            TrustedProxies::default(),
         )
     }
 }
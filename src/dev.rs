@@ -12,8 +12,9 @@
 pub use crate::config::{AppConfig, AppService};
 #[doc(hidden)]
 pub use crate::handler::Handler;
-pub use crate::info::{ConnectionInfo, PeerAddr};
+pub use crate::info::{ConnectionInfo, PeerAddr, TrustedProxies};
 pub use crate::rmap::ResourceMap;
+pub use crate::server::Server;
 pub use crate::service::{HttpServiceFactory, ServiceRequest, ServiceResponse, WebService};
 
 pub use crate::types::form::UrlEncoded;
@@ -25,9 +26,10 @@ pub use actix_http::body::{AnyBody, Body, BodySize, MessageBody, ResponseBody, S
 #[cfg(feature = "__compress")]
 pub use actix_http::encoding::Decoder as Decompress;
 pub use actix_http::ResponseBuilder as BaseHttpResponseBuilder;
-pub use actix_http::{Extensions, Payload, PayloadStream, RequestHead, ResponseHead};
+pub use actix_http::{
+    Extensions, Payload, PayloadStream, RequestHead, ResponseHead, ShutdownSignal,
+};
 pub use actix_router::{Path, ResourceDef, ResourcePath, Url};
-pub use actix_server::Server;
 pub use actix_service::{
     always_ready, fn_factory, fn_service, forward_ready, Service, Transform,
 };
@@ -1,5 +1,7 @@
 //! Error and Result module
 
+use std::fmt;
+
 pub use actix_http::error::*;
 use derive_more::{Display, Error, From};
 use serde_json::error::Error as JsonError;
@@ -7,7 +9,14 @@ use serde_urlencoded::de::Error as FormDeError;
 use serde_urlencoded::ser::Error as FormError;
 use url::ParseError as UrlParseError;
 
-use crate::http::StatusCode;
+use crate::{
+    http::{
+        header::{self, Challenge, WwwAuthenticate},
+        StatusCode,
+    },
+    middleware::RateLimitStatus,
+    HttpResponse,
+};
 
 #[allow(clippy::module_inception)]
 mod error;
@@ -112,6 +121,10 @@ pub enum JsonPayloadError {
     #[display(fmt = "JSON payload has exceeded limit ({} bytes).", limit)]
     Overflow { limit: usize },
 
+    /// Item of a JSON stream is bigger than allowed. (default: 256kB)
+    #[display(fmt = "JSON stream item has exceeded limit ({} bytes).", limit)]
+    ItemOverflow { limit: usize },
+
     /// Content type error
     #[display(fmt = "Content type error")]
     ContentType,
@@ -143,6 +156,7 @@ impl ResponseError for JsonPayloadError {
                 limit: _,
             } => StatusCode::PAYLOAD_TOO_LARGE,
             Self::Overflow { limit: _ } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::ItemOverflow { limit: _ } => StatusCode::PAYLOAD_TOO_LARGE,
             Self::Serialize(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::Payload(err) => err.status_code(),
             _ => StatusCode::BAD_REQUEST,
@@ -211,6 +225,126 @@ impl ResponseError for ReadlinesError {
     }
 }
 
+/// Errors that can occur when processing CORS preflight requests.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum CorsError {
+    /// Request origin is not allowed.
+    #[display(fmt = "Origin is not allowed to make this request")]
+    OriginNotAllowed,
+
+    /// Method in `Access-Control-Request-Method` header is not allowed.
+    #[display(fmt = "Requested method is not allowed")]
+    MethodNotAllowed,
+
+    /// One or more headers in `Access-Control-Request-Headers` header are not allowed.
+    #[display(fmt = "One or more requested headers are not allowed")]
+    HeadersNotAllowed,
+
+    /// `Access-Control-Request-Method` or `Access-Control-Request-Headers` header is malformed.
+    #[display(fmt = "Malformed CORS preflight request headers")]
+    BadRequestHeaders,
+}
+
+/// Return `BadRequest` for `CorsError`
+impl ResponseError for CorsError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+/// Error returned when a request could not be authenticated.
+///
+/// Responds with the status code of its challenge, `401 Unauthorized` by default, and a
+/// `WWW-Authenticate` header containing the challenge.
+#[derive(Debug)]
+pub struct AuthenticationError<C: Challenge> {
+    challenge: C,
+}
+
+impl<C: Challenge> AuthenticationError<C> {
+    /// Constructs an authentication error with the given challenge.
+    pub fn new(challenge: C) -> Self {
+        AuthenticationError { challenge }
+    }
+
+    /// Returns the challenge sent to the client.
+    pub fn challenge(&self) -> &C {
+        &self.challenge
+    }
+
+    /// Returns mutable reference to the challenge sent to the client.
+    pub fn challenge_mut(&mut self) -> &mut C {
+        &mut self.challenge
+    }
+}
+
+impl<C: Challenge> From<C> for AuthenticationError<C> {
+    fn from(challenge: C) -> Self {
+        AuthenticationError::new(challenge)
+    }
+}
+
+impl<C: Challenge> fmt::Display for AuthenticationError<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.challenge.status_code(), f)
+    }
+}
+
+impl<C: Challenge> std::error::Error for AuthenticationError<C> {}
+
+/// Return challenge status code and `WWW-Authenticate` header for `AuthenticationError`
+impl<C: Challenge> ResponseError for AuthenticationError<C> {
+    fn status_code(&self) -> StatusCode {
+        self.challenge.status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .insert_header((
+                header::WWW_AUTHENTICATE,
+                WwwAuthenticate(self.challenge.clone()),
+            ))
+            .finish()
+    }
+}
+
+/// Error returned when a client exceeds its rate limit.
+///
+/// Responds with `429 Too Many Requests` and `Retry-After` and `RateLimit-*` headers.
+#[derive(Debug, Display)]
+#[display(fmt = "Rate limit exceeded")]
+pub struct RateLimitError {
+    status: RateLimitStatus,
+}
+
+impl RateLimitError {
+    /// Constructs a rate limit error from the status of the rejected request.
+    pub fn new(status: RateLimitStatus) -> Self {
+        RateLimitError { status }
+    }
+
+    /// Returns status of the rejected request.
+    pub fn status(&self) -> &RateLimitStatus {
+        &self.status
+    }
+}
+
+impl std::error::Error for RateLimitError {}
+
+/// Return `TooManyRequests` and rate limit headers for `RateLimitError`
+impl ResponseError for RateLimitError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::TOO_MANY_REQUESTS
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::new(self.status_code());
+        self.status.insert_headers(res.headers_mut());
+        res
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,6 +369,8 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
         let resp = JsonPayloadError::Overflow { limit: 0 }.error_response();
         assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp = JsonPayloadError::ItemOverflow { limit: 0 }.error_response();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
         let resp = JsonPayloadError::ContentType.error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
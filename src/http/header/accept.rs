@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 
 use mime::Mime;
 
-use super::{qitem, QualityItem};
+use super::{q, qitem, Quality, QualityItem};
 use crate::http::header;
 
 crate::http::header::common_header! {
@@ -208,6 +208,75 @@ impl Accept {
         let types = self.mime_precedence();
         types.first().cloned()
     }
+
+    /// Chooses the most preferable of the `available` mime types, accounting for
+    /// [q-factor weighting] and specificity.
+    ///
+    /// Each available type is given the q-factor of the most specific media range matching it
+    /// (eg. `text/html` over `text/*` over `*/*`); parameters other than the q-factor are
+    /// ignored. Types with a q-factor of 0 are not acceptable. Ties are resolved in favor of the
+    /// type listed first in `available`.
+    ///
+    /// Returns `None` if none of the available types are acceptable.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::http::header::{q, qitem, Accept, QualityItem};
+    ///
+    /// let accept = Accept(vec![
+    ///     QualityItem::new(mime::TEXT_STAR, q(0.5)),
+    ///     qitem(mime::APPLICATION_JSON),
+    /// ]);
+    /// let available = [mime::TEXT_PLAIN, mime::APPLICATION_JSON, mime::IMAGE_PNG];
+    ///
+    /// assert_eq!(accept.negotiate(&available), Some(&mime::APPLICATION_JSON));
+    /// ```
+    ///
+    /// [q-factor weighting]: https://tools.ietf.org/html/rfc7231#section-5.3.2
+    pub fn negotiate<'a>(&self, available: &'a [Mime]) -> Option<&'a Mime> {
+        let mut best: Option<(&Mime, Quality)> = None;
+
+        for mime in available {
+            let quality = match self.quality_of(mime) {
+                Some(quality) if quality > q(0) => quality,
+                _ => continue,
+            };
+
+            if best.map_or(true, |(_, best)| quality > best) {
+                best = Some((mime, quality));
+            }
+        }
+
+        best.map(|(mime, _)| mime)
+    }
+
+    /// Returns the q-factor of the most specific media range matching `mime`, if any.
+    fn quality_of(&self, mime: &Mime) -> Option<Quality> {
+        let mut best: Option<(u8, Quality)> = None;
+
+        for range in &self.0 {
+            let specificity = if range.item.type_() == mime::STAR {
+                0
+            } else if range.item.type_() != mime.type_() {
+                continue;
+            } else if range.item.subtype() == mime::STAR {
+                1
+            } else if range.item.subtype() == mime.subtype()
+                && range.item.suffix() == mime.suffix()
+            {
+                2
+            } else {
+                continue;
+            };
+
+            // the first of equally specific ranges is used
+            if best.map_or(true, |(best, _)| specificity > best) {
+                best = Some((specificity, range.quality));
+            }
+        }
+
+        best.map(|(_, quality)| quality)
+    }
 }
 
 #[cfg(test)]
@@ -269,4 +338,37 @@ mod tests {
         ]);
         assert_eq!(test.mime_preference(), Some(mime::IMAGE_PNG));
     }
+
+    #[test]
+    fn test_negotiate() {
+        let available = [mime::APPLICATION_JSON, mime::TEXT_PLAIN_UTF_8];
+
+        let test = Accept::star();
+        assert_eq!(test.negotiate(&available), Some(&mime::APPLICATION_JSON));
+
+        let test = Accept(vec![]);
+        assert_eq!(test.negotiate(&available), None);
+
+        let test = Accept(vec![
+            QualityItem::new(mime::APPLICATION_JSON, q(0.5)),
+            QualityItem::new(mime::TEXT_STAR, q(0.8)),
+        ]);
+        assert_eq!(test.negotiate(&available), Some(&mime::TEXT_PLAIN_UTF_8));
+
+        // more specific ranges override less specific ones
+        let test = Accept(vec![
+            qitem(mime::STAR_STAR),
+            QualityItem::new(mime::APPLICATION_JSON, q(0)),
+        ]);
+        assert_eq!(test.negotiate(&available), Some(&mime::TEXT_PLAIN_UTF_8));
+
+        let test = Accept(vec![
+            QualityItem::new(mime::TEXT_STAR, q(0)),
+            qitem(mime::TEXT_PLAIN),
+        ]);
+        assert_eq!(test.negotiate(&available), Some(&mime::TEXT_PLAIN_UTF_8));
+
+        let test = Accept(vec![qitem(mime::IMAGE_STAR)]);
+        assert_eq!(test.negotiate(&available), None);
+    }
 }
@@ -0,0 +1,32 @@
+use super::HeaderName;
+
+crate::http::header::common_header! {
+    /// `Last-Event-ID` header, defined in the
+    /// [HTML Living Standard](https://html.spec.whatwg.org/multipage/server-sent-events.html#last-event-id)
+    ///
+    /// The `Last-Event-ID` header field is sent by clients reconnecting to a server-sent event
+    /// stream. It contains the ID of the last event they received, so that the server can resume
+    /// the stream after it.
+    ///
+    /// # Example values
+    ///
+    /// * `42`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use actix_web::{http::header::LastEventId, web};
+    ///
+    /// async fn events(last_event_id: Option<web::Header<LastEventId>>) -> String {
+    ///     match last_event_id {
+    ///         Some(id) => format!("resuming after event {}", id.0),
+    ///         None => "starting from the first event".to_owned(),
+    ///     }
+    /// }
+    /// ```
+    (LastEventId, HeaderName::from_static("last-event-id")) => [String]
+
+    test_last_event_id {
+        crate::http::header::common_header_test!(test1, vec![b"42"]);
+    }
+}
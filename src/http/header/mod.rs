@@ -14,8 +14,8 @@ pub use actix_http::http::header::*;
 pub use self::accept::Accept;
 pub use self::accept_language::AcceptLanguage;
 pub use self::allow::Allow;
+pub use self::authorization::{Authorization, Basic, Bearer, Scheme};
 pub use self::cache_control::{CacheControl, CacheDirective};
-pub use self::content_disposition::{ContentDisposition, DispositionParam, DispositionType};
 pub use self::content_language::ContentLanguage;
 pub use self::content_range::{ContentRange, ContentRangeSpec};
 pub use self::content_type::ContentType;
@@ -29,7 +29,11 @@ pub use self::if_modified_since::IfModifiedSince;
 pub use self::if_none_match::IfNoneMatch;
 pub use self::if_range::IfRange;
 pub use self::if_unmodified_since::IfUnmodifiedSince;
+pub use self::last_event_id::LastEventId;
 pub use self::last_modified::LastModified;
+pub use self::www_authenticate::{
+    BasicChallenge, BearerChallenge, BearerError, Challenge, WwwAuthenticate,
+};
 //pub use self::range::{Range, ByteRangeSpec};
 pub(crate) use actix_http::http::header::{
     fmt_comma_delimited, from_comma_delimited, from_one_raw_str,
@@ -68,8 +72,8 @@ mod accept_charset;
 mod accept;
 mod accept_language;
 mod allow;
+mod authorization;
 mod cache_control;
-mod content_disposition;
 mod content_language;
 mod content_range;
 mod content_type;
@@ -83,7 +87,9 @@ mod if_modified_since;
 mod if_none_match;
 mod if_range;
 mod if_unmodified_since;
+mod last_event_id;
 mod last_modified;
+mod www_authenticate;
 
 mod macros;
 #[cfg(test)]
@@ -0,0 +1,270 @@
+use std::{borrow::Cow, fmt, fmt::Write as _};
+
+use super::{HeaderValue, IntoHeaderValue, InvalidHeaderValue, Writer};
+use crate::http::StatusCode;
+
+/// Authentication challenge of a [`WwwAuthenticate`] header.
+pub trait Challenge: fmt::Display + fmt::Debug + Clone {
+    /// Returns status code of responses carrying this challenge.
+    ///
+    /// Defaults to `401 Unauthorized`.
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+}
+
+/// `WWW-Authenticate` header, defined in
+/// [RFC7235](https://tools.ietf.org/html/rfc7235#section-4.1)
+///
+/// The `WWW-Authenticate` header field indicates the authentication scheme(s) and parameters
+/// applicable to the target resource. It is sent with `401 Unauthorized` responses.
+///
+/// # ABNF
+///
+/// ```text
+/// WWW-Authenticate = 1#challenge
+/// ```
+///
+/// # Example values
+///
+/// * `Basic realm="Restricted area"`
+/// * `Bearer realm="example", error="invalid_token"`
+///
+/// # Examples
+///
+/// ```
+/// use actix_web::HttpResponse;
+/// use actix_web::http::header::{BasicChallenge, WwwAuthenticate, WWW_AUTHENTICATE};
+///
+/// let mut builder = HttpResponse::Unauthorized();
+/// builder.insert_header((
+///     WWW_AUTHENTICATE,
+///     WwwAuthenticate(BasicChallenge::new().realm("Restricted area")),
+/// ));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WwwAuthenticate<C: Challenge>(pub C);
+
+impl<C: Challenge> fmt::Display for WwwAuthenticate<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<C: Challenge> IntoHeaderValue for WwwAuthenticate<C> {
+    type Error = InvalidHeaderValue;
+
+    fn try_into_value(self) -> Result<HeaderValue, Self::Error> {
+        let mut writer = Writer::new();
+        let _ = write!(&mut writer, "{}", self.0);
+        HeaderValue::from_maybe_shared(writer.take())
+    }
+}
+
+/// Writes an auth-param with its value as a quoted-string.
+fn fmt_param(
+    f: &mut fmt::Formatter<'_>,
+    first: &mut bool,
+    name: &str,
+    value: &str,
+) -> fmt::Result {
+    f.write_str(if *first { " " } else { ", " })?;
+    *first = false;
+
+    write!(f, "{}=\"", name)?;
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char('"')
+}
+
+/// Challenge of the `Basic` authentication scheme, defined in
+/// [RFC7617](https://tools.ietf.org/html/rfc7617#section-2)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BasicChallenge {
+    realm: Option<Cow<'static, str>>,
+}
+
+impl BasicChallenge {
+    /// Constructs a `Basic` challenge without a realm.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets protection space the challenge applies to.
+    pub fn realm(mut self, realm: impl Into<Cow<'static, str>>) -> Self {
+        self.realm = Some(realm.into());
+        self
+    }
+}
+
+impl Challenge for BasicChallenge {}
+
+impl fmt::Display for BasicChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Basic")?;
+
+        if let Some(ref realm) = self.realm {
+            fmt_param(f, &mut true, "realm", realm)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Error code of a `Bearer` challenge, defined in
+/// [RFC6750](https://tools.ietf.org/html/rfc6750#section-3.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BearerError {
+    /// Request is malformed; responded to with `400 Bad Request`.
+    InvalidRequest,
+
+    /// Token is expired, revoked or otherwise invalid; responded to with `401 Unauthorized`.
+    InvalidToken,
+
+    /// Token does not grant the required scope; responded to with `403 Forbidden`.
+    InsufficientScope,
+}
+
+impl BearerError {
+    /// Returns status code of responses carrying this error.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            BearerError::InvalidRequest => StatusCode::BAD_REQUEST,
+            BearerError::InvalidToken => StatusCode::UNAUTHORIZED,
+            BearerError::InsufficientScope => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+impl fmt::Display for BearerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BearerError::InvalidRequest => "invalid_request",
+            BearerError::InvalidToken => "invalid_token",
+            BearerError::InsufficientScope => "insufficient_scope",
+        })
+    }
+}
+
+/// Challenge of the `Bearer` authentication scheme, defined in
+/// [RFC6750](https://tools.ietf.org/html/rfc6750#section-3)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BearerChallenge {
+    realm: Option<Cow<'static, str>>,
+    scope: Option<Cow<'static, str>>,
+    error: Option<BearerError>,
+    error_description: Option<Cow<'static, str>>,
+    error_uri: Option<Cow<'static, str>>,
+}
+
+impl BearerChallenge {
+    /// Constructs a `Bearer` challenge without parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets protection space the challenge applies to.
+    pub fn realm(mut self, realm: impl Into<Cow<'static, str>>) -> Self {
+        self.realm = Some(realm.into());
+        self
+    }
+
+    /// Sets space-delimited list of scopes required to access the resource.
+    pub fn scope(mut self, scope: impl Into<Cow<'static, str>>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Sets error code explaining why access was denied.
+    ///
+    /// The error code determines the response status code.
+    pub fn error(mut self, error: BearerError) -> Self {
+        self.error = Some(error);
+        self
+    }
+
+    /// Sets human-readable explanation of the error.
+    pub fn error_description(mut self, description: impl Into<Cow<'static, str>>) -> Self {
+        self.error_description = Some(description.into());
+        self
+    }
+
+    /// Sets URI of a web page explaining the error.
+    pub fn error_uri(mut self, uri: impl Into<Cow<'static, str>>) -> Self {
+        self.error_uri = Some(uri.into());
+        self
+    }
+}
+
+impl Challenge for BearerChallenge {
+    fn status_code(&self) -> StatusCode {
+        self.error
+            .map_or(StatusCode::UNAUTHORIZED, |err| err.status_code())
+    }
+}
+
+impl fmt::Display for BearerChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Bearer")?;
+
+        let mut first = true;
+
+        if let Some(ref realm) = self.realm {
+            fmt_param(f, &mut first, "realm", realm)?;
+        }
+        if let Some(ref scope) = self.scope {
+            fmt_param(f, &mut first, "scope", scope)?;
+        }
+        if let Some(error) = self.error {
+            fmt_param(f, &mut first, "error", &error.to_string())?;
+        }
+        if let Some(ref description) = self.error_description {
+            fmt_param(f, &mut first, "error_description", description)?;
+        }
+        if let Some(ref uri) = self.error_uri {
+            fmt_param(f, &mut first, "error_uri", uri)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_challenge() {
+        let challenge = BasicChallenge::new();
+        assert_eq!(challenge.to_string(), "Basic");
+
+        let challenge = BasicChallenge::new().realm("Restricted \"area\"");
+        assert_eq!(
+            WwwAuthenticate(challenge).try_into_value().unwrap(),
+            r#"Basic realm="Restricted \"area\"""#
+        );
+    }
+
+    #[test]
+    fn test_bearer_challenge() {
+        let challenge = BearerChallenge::new();
+        assert_eq!(challenge.to_string(), "Bearer");
+        assert_eq!(challenge.status_code(), StatusCode::UNAUTHORIZED);
+
+        let challenge = BearerChallenge::new()
+            .realm("example")
+            .scope("read write")
+            .error(BearerError::InsufficientScope)
+            .error_description("Write access required");
+        assert_eq!(challenge.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            WwwAuthenticate(challenge).try_into_value().unwrap(),
+            "Bearer realm=\"example\", scope=\"read write\", \
+             error=\"insufficient_scope\", error_description=\"Write access required\""
+        );
+    }
+}
@@ -1,2 +1,5 @@
 pub mod header;
 pub use actix_http::http::*;
+
+mod precondition;
+pub use self::precondition::Precondition;
@@ -0,0 +1,241 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    http::{
+        header::{
+            EntityTag, HttpDate, IfMatch, IfModifiedSince, IfNoneMatch, IfUnmodifiedSince,
+        },
+        Method, StatusCode,
+    },
+    HttpMessage as _, HttpRequest,
+};
+
+/// Outcome of evaluating the conditional headers of a request, as defined in
+/// [RFC7232 §6](https://tools.ietf.org/html/rfc7232#section-6).
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     http::{header::EntityTag, Precondition},
+///     HttpRequest, HttpResponse,
+/// };
+///
+/// async fn index(req: HttpRequest) -> HttpResponse {
+///     let etag = EntityTag::strong("v1".to_owned());
+///
+///     match Precondition::evaluate(&req, Some(&etag), None).status_code() {
+///         Some(status) => HttpResponse::build(status).finish(),
+///         None => HttpResponse::Ok().body("version 1"),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precondition {
+    /// All conditions hold and the request should be processed normally.
+    Passed,
+
+    /// The selected representation has not been modified. A `304 Not Modified` response should
+    /// be sent.
+    NotModified,
+
+    /// A condition evaluated to false. A `412 Precondition Failed` response should be sent and
+    /// the method must not be performed.
+    Failed,
+}
+
+impl Precondition {
+    /// Evaluates the `If-Match`, `If-Unmodified-Since`, `If-None-Match` and `If-Modified-Since`
+    /// headers of `req` against the validators of the selected representation.
+    ///
+    /// The representation is assumed to exist, so `If-Match: *` holds and `If-None-Match: *`
+    /// does not. When `etag` is `None`, `If-Match` only holds for `*`. Dates are compared with
+    /// a resolution of one second.
+    pub fn evaluate(
+        req: &HttpRequest,
+        etag: Option<&EntityTag>,
+        last_modified: Option<HttpDate>,
+    ) -> Self {
+        let if_match = req.get_header::<IfMatch>();
+
+        match if_match {
+            Some(IfMatch::Any) | None => {}
+            Some(IfMatch::Items(ref items)) => {
+                let matched =
+                    etag.map_or(false, |etag| items.iter().any(|i| i.strong_eq(etag)));
+
+                if !matched {
+                    return Precondition::Failed;
+                }
+            }
+        }
+
+        // `If-Unmodified-Since` is only evaluated in the absence of `If-Match`
+        if if_match.is_none() {
+            if let (Some(IfUnmodifiedSince(since)), Some(last_modified)) =
+                (req.get_header(), last_modified)
+            {
+                if secs(last_modified) > secs(since) {
+                    return Precondition::Failed;
+                }
+            }
+        }
+
+        let get_or_head = matches!(*req.method(), Method::GET | Method::HEAD);
+        let not_modified = if get_or_head {
+            Precondition::NotModified
+        } else {
+            Precondition::Failed
+        };
+
+        match req.get_header::<IfNoneMatch>() {
+            Some(IfNoneMatch::Any) => return not_modified,
+            Some(IfNoneMatch::Items(items)) => {
+                let matched = etag.map_or(false, |etag| items.iter().any(|i| i.weak_eq(etag)));
+
+                return if matched {
+                    not_modified
+                } else {
+                    Precondition::Passed
+                };
+            }
+            None => {}
+        }
+
+        // `If-Modified-Since` is only evaluated in the absence of `If-None-Match`
+        if get_or_head {
+            if let (Some(IfModifiedSince(since)), Some(last_modified)) =
+                (req.get_header(), last_modified)
+            {
+                if secs(last_modified) <= secs(since) {
+                    return Precondition::NotModified;
+                }
+            }
+        }
+
+        Precondition::Passed
+    }
+
+    /// Returns the status code of the response that should be sent instead of processing the
+    /// request, if any.
+    pub fn status_code(&self) -> Option<StatusCode> {
+        match self {
+            Precondition::Passed => None,
+            Precondition::NotModified => Some(StatusCode::NOT_MODIFIED),
+            Precondition::Failed => Some(StatusCode::PRECONDITION_FAILED),
+        }
+    }
+}
+
+/// Returns the number of whole seconds between the Unix epoch and `date`.
+fn secs(date: HttpDate) -> u64 {
+    SystemTime::from(date)
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |dur| dur.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{http::header, test::TestRequest};
+
+    #[test]
+    fn test_etag_conditions() {
+        let etag = EntityTag::strong("v1".to_owned());
+        let weak = EntityTag::weak("v1".to_owned());
+
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(
+            Precondition::evaluate(&req, Some(&etag), None),
+            Precondition::Passed
+        );
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "W/\"v1\""))
+            .to_http_request();
+        assert_eq!(
+            Precondition::evaluate(&req, Some(&etag), None),
+            Precondition::NotModified
+        );
+
+        let req = TestRequest::post()
+            .insert_header((header::IF_NONE_MATCH, "*"))
+            .to_http_request();
+        assert_eq!(
+            Precondition::evaluate(&req, None, None),
+            Precondition::Failed
+        );
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, "\"v1\""))
+            .to_http_request();
+        assert_eq!(
+            Precondition::evaluate(&req, Some(&etag), None),
+            Precondition::Passed
+        );
+        assert_eq!(
+            Precondition::evaluate(&req, Some(&weak), None),
+            Precondition::Failed
+        );
+        assert_eq!(
+            Precondition::evaluate(&req, None, None),
+            Precondition::Failed
+        );
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, "*"))
+            .to_http_request();
+        assert_eq!(
+            Precondition::evaluate(&req, None, None),
+            Precondition::Passed
+        );
+    }
+
+    #[test]
+    fn test_date_conditions() {
+        let now = SystemTime::now();
+        let modified = HttpDate::from(now - Duration::from_secs(60));
+
+        let req = TestRequest::default()
+            .insert_header(header::IfModifiedSince(now.into()))
+            .to_http_request();
+        assert_eq!(
+            Precondition::evaluate(&req, None, Some(modified)),
+            Precondition::NotModified
+        );
+
+        // `If-None-Match` takes precedence over `If-Modified-Since`
+        let req = TestRequest::default()
+            .insert_header(header::IfModifiedSince(now.into()))
+            .insert_header((header::IF_NONE_MATCH, "\"v0\""))
+            .to_http_request();
+        let etag = EntityTag::strong("v1".to_owned());
+        assert_eq!(
+            Precondition::evaluate(&req, Some(&etag), Some(modified)),
+            Precondition::Passed
+        );
+
+        // `If-Modified-Since` only applies to `GET` and `HEAD`
+        let req = TestRequest::put()
+            .insert_header(header::IfModifiedSince(now.into()))
+            .to_http_request();
+        assert_eq!(
+            Precondition::evaluate(&req, None, Some(modified)),
+            Precondition::Passed
+        );
+
+        let since = now - Duration::from_secs(3600);
+        let req = TestRequest::put()
+            .insert_header(header::IfUnmodifiedSince(since.into()))
+            .to_http_request();
+        assert_eq!(
+            Precondition::evaluate(&req, None, Some(modified)),
+            Precondition::Failed
+        );
+        assert_eq!(
+            Precondition::Failed.status_code(),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+    }
+}
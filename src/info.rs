@@ -1,7 +1,12 @@
-use std::{cell::Ref, convert::Infallible, net::SocketAddr};
+use std::{
+    cell::Ref,
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
 
 use actix_utils::future::{err, ok, Ready};
 use derive_more::{Display, Error};
+use ipnet::IpNet;
 use once_cell::sync::Lazy;
 
 use crate::{
@@ -58,7 +63,8 @@ fn first_header_value<'a>(req: &'a RequestHead, name: &'_ HeaderName) -> Option<
 /// [obfuscated][rfc7239-63] or [unknown][rfc7239-62].
 ///
 /// If the older, related headers are also present (eg. `X-Forwarded-For`), then `Forwarded`
-/// is preferred.
+/// is preferred. These headers are only honored when sent by a proxy trusted by the
+/// [`TrustedProxies`] configuration of the app.
 ///
 /// [rfc7239]: https://datatracker.ietf.org/doc/html/rfc7239
 /// [rfc7239-62]: https://datatracker.ietf.org/doc/html/rfc7239#section-6.2
@@ -85,62 +91,81 @@ impl ConnectionInfo {
         let mut scheme = None;
         let mut realip_remote_addr = None;
 
-        for (name, val) in req
-            .headers
-            .get_all(&header::FORWARDED)
-            .into_iter()
-            .filter_map(|hdr| hdr.to_str().ok())
-            // "for=1.2.3.4, for=5.6.7.8; scheme=https"
-            .flat_map(|val| val.split(';'))
-            // ["for=1.2.3.4, for=5.6.7.8", " scheme=https"]
-            .flat_map(|vals| vals.split(','))
-            // ["for=1.2.3.4", " for=5.6.7.8", " scheme=https"]
-            .flat_map(|pair| {
-                let mut items = pair.trim().splitn(2, '=');
-                Some((items.next()?, items.next()?))
-            })
-        {
-            // [(name , val      ), ...                                    ]
-            // [("for", "1.2.3.4"), ("for", "5.6.7.8"), ("scheme", "https")]
-
-            // taking the first value for each property is correct because spec states that first
-            // "for" value is client and rest are proxies; multiple values other properties have
-            // no defined semantics
-            //
-            // > In a chain of proxy servers where this is fully utilized, the first
-            // > "for" parameter will disclose the client where the request was first
-            // > made, followed by any subsequent proxy identifiers.
-            // --- https://datatracker.ietf.org/doc/html/rfc7239#section-5.2
-
-            match name.trim().to_lowercase().as_str() {
-                "for" => realip_remote_addr.get_or_insert_with(|| unquote(val)),
-                "proto" => scheme.get_or_insert_with(|| unquote(val)),
-                "host" => host.get_or_insert_with(|| unquote(val)),
-                "by" => {
-                    // TODO: implement https://datatracker.ietf.org/doc/html/rfc7239#section-5.1
-                    continue;
-                }
-                _ => continue,
-            };
+        let trusted_proxies = cfg.trusted_proxies();
+
+        if trusted_proxies.trusts_peer(req.peer_addr) {
+            let mut forwarded_for = Vec::new();
+
+            for (name, val) in req
+                .headers
+                .get_all(&header::FORWARDED)
+                .into_iter()
+                .filter_map(|hdr| hdr.to_str().ok())
+                // "for=1.2.3.4; proto=https, for=5.6.7.8"
+                .flat_map(|val| val.split(','))
+                // ["for=1.2.3.4; proto=https", " for=5.6.7.8"]
+                .flat_map(|element| element.split(';'))
+                // ["for=1.2.3.4", " proto=https", " for=5.6.7.8"]
+                .flat_map(|pair| {
+                    let mut items = pair.trim().splitn(2, '=');
+                    Some((items.next()?, items.next()?))
+                })
+            {
+                // [(name , val      ), ...                                      ]
+                // [("for", "1.2.3.4"), ("proto", "https"), ("for", "5.6.7.8")]
+
+                // each proxy appends an element, so "for" values are ordered from the client to
+                // the nearest proxy; multiple values of other properties have no defined semantics
+                //
+                // > In a chain of proxy servers where this is fully utilized, the first
+                // > "for" parameter will disclose the client where the request was first
+                // > made, followed by any subsequent proxy identifiers.
+                // --- https://datatracker.ietf.org/doc/html/rfc7239#section-5.2
+
+                match name.trim().to_lowercase().as_str() {
+                    "for" => forwarded_for.push(unquote(val)),
+                    "proto" => {
+                        scheme.get_or_insert_with(|| unquote(val));
+                    }
+                    "host" => {
+                        host.get_or_insert_with(|| unquote(val));
+                    }
+                    // "by" identifies the interface of the proxy that received the request and
+                    // tells nothing about the client
+                    _ => {}
+                };
+            }
+
+            scheme = scheme.or_else(|| first_header_value(req, &*X_FORWARDED_PROTO));
+            host = host.or_else(|| first_header_value(req, &*X_FORWARDED_HOST));
+
+            if forwarded_for.is_empty() {
+                forwarded_for = req
+                    .headers
+                    .get_all(&*X_FORWARDED_FOR)
+                    .filter_map(|hdr| hdr.to_str().ok())
+                    .flat_map(|val| val.split(','))
+                    .map(str::trim)
+                    .filter(|val| !val.is_empty())
+                    .collect();
+            }
+
+            realip_remote_addr = trusted_proxies.client_addr(&forwarded_for);
         }
 
         let scheme = scheme
-            .or_else(|| first_header_value(req, &*X_FORWARDED_PROTO))
             .or_else(|| req.uri.scheme().map(Scheme::as_str))
             .or_else(|| Some("https").filter(|_| cfg.secure()))
             .unwrap_or("http")
             .to_owned();
 
         let host = host
-            .or_else(|| first_header_value(req, &*X_FORWARDED_HOST))
             .or_else(|| req.headers.get(&header::HOST)?.to_str().ok())
             .or_else(|| req.uri.authority().map(Authority::as_str))
             .unwrap_or(cfg.host())
             .to_owned();
 
-        let realip_remote_addr = realip_remote_addr
-            .or_else(|| first_header_value(req, &*X_FORWARDED_FOR))
-            .map(str::to_owned);
+        let realip_remote_addr = realip_remote_addr.map(str::to_owned);
 
         let remote_addr = req.peer_addr.map(|addr| addr.to_string());
 
@@ -192,10 +217,14 @@ impl ConnectionInfo {
     /// - X-Forwarded-For
     /// - remote_addr name of opened socket
     ///
+    /// The `for` chain of the header is walked right-to-left, skipping trusted proxies. See
+    /// [`TrustedProxies`] for details.
+    ///
     /// # Security
-    /// Do not use this function for security purposes, unless you can ensure the Forwarded and
-    /// X-Forwarded-For headers cannot be spoofed by the client. If you want the client's socket
-    /// address explicitly, use [`HttpRequest::peer_addr()`][peer_addr] instead.
+    /// Do not use this function for security purposes, unless trusted proxies are configured or
+    /// you can ensure the Forwarded and X-Forwarded-For headers cannot be spoofed by the client.
+    /// By default all peers are trusted. If you want the client's socket address explicitly, use
+    /// [`HttpRequest::peer_addr()`][peer_addr] instead.
     ///
     /// [peer_addr]: crate::web::HttpRequest::peer_addr()
     #[inline]
@@ -216,6 +245,139 @@ impl FromRequest for ConnectionInfo {
     }
 }
 
+/// Proxies trusted to report client information through forwarding headers.
+///
+/// [`ConnectionInfo`] only honors the `Forwarded`, `X-Forwarded-For`, `X-Forwarded-Proto` and
+/// `X-Forwarded-Host` headers of requests whose peer is a trusted proxy. The real client address is
+/// then found by walking the `for` chain right-to-left, from the nearest proxy towards the client,
+/// and taking the first address that is not trusted.
+///
+/// Proxies can be trusted by network, or by counting hops when the number of proxies in front of
+/// the server is known but their addresses are not. Requests without a peer address, such as ones
+/// received over Unix domain sockets, are only trusted by [`all`](Self::all) and
+/// [`hops`](Self::hops).
+///
+/// Defaults to trusting all peers, which lets clients spoof their address; configure trusted
+/// proxies using [`App::trusted_proxies`](crate::App::trusted_proxies) or
+/// [`HttpServer::trusted_proxies`](crate::HttpServer::trusted_proxies).
+///
+/// # Examples
+/// ```
+/// use actix_web::{dev::TrustedProxies, App};
+///
+/// // load balancer in a private network
+/// let app = App::new().trusted_proxies(TrustedProxies::new().add_network("10.0.0.0/8"));
+///
+/// // single reverse proxy on an unknown address
+/// let app = App::new().trusted_proxies(TrustedProxies::hops(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TrustedProxies(TrustedProxiesInner);
+
+#[derive(Debug, Clone)]
+enum TrustedProxiesInner {
+    All,
+    Networks(Vec<IpNet>),
+    Hops(usize),
+}
+
+impl TrustedProxies {
+    /// Constructs configuration trusting no proxies; forwarding headers are ignored.
+    pub fn new() -> Self {
+        TrustedProxies(TrustedProxiesInner::Networks(Vec::new()))
+    }
+
+    /// Constructs configuration trusting all peers and proxies.
+    ///
+    /// The client address is the first `for` value. Only use this when the server is not reachable
+    /// other than through proxies that overwrite forwarding headers.
+    pub fn all() -> Self {
+        TrustedProxies(TrustedProxiesInner::All)
+    }
+
+    /// Constructs configuration trusting the `hops` proxies nearest to the server.
+    ///
+    /// The peer counts as the first hop, so `hops(1)` takes the last `for` value as the client
+    /// address. `hops(0)` trusts no proxies.
+    pub fn hops(hops: usize) -> Self {
+        TrustedProxies(TrustedProxiesInner::Hops(hops))
+    }
+
+    /// Adds a trusted network in CIDR notation (e.g. `10.0.0.0/8` or `fd00::/8`) or a single IP
+    /// address.
+    ///
+    /// Replaces [`all`](Self::all) and [`hops`](Self::hops) configuration.
+    ///
+    /// # Panics
+    /// Panics if `network` is not a valid network or IP address.
+    pub fn add_network(mut self, network: &str) -> Self {
+        let network = network
+            .parse::<IpNet>()
+            .or_else(|_| network.parse::<IpAddr>().map(IpNet::from))
+            .unwrap_or_else(|_| panic!("invalid trusted proxy network: {}", network));
+
+        match self.0 {
+            TrustedProxiesInner::Networks(ref mut networks) => networks.push(network),
+            _ => self.0 = TrustedProxiesInner::Networks(vec![network]),
+        }
+
+        self
+    }
+
+    /// Returns true if forwarding headers of requests from `peer` are honored.
+    fn trusts_peer(&self, peer: Option<SocketAddr>) -> bool {
+        match self.0 {
+            TrustedProxiesInner::All => true,
+            TrustedProxiesInner::Hops(hops) => hops > 0,
+            TrustedProxiesInner::Networks(_) => {
+                peer.map_or(false, |addr| self.contains(addr.ip()))
+            }
+        }
+    }
+
+    fn contains(&self, addr: IpAddr) -> bool {
+        match self.0 {
+            TrustedProxiesInner::Networks(ref networks) => {
+                networks.iter().any(|network| network.contains(&addr))
+            }
+            _ => false,
+        }
+    }
+
+    /// Picks the client address from a `for` chain ordered from the client to the nearest proxy.
+    fn client_addr<'a>(&self, chain: &[&'a str]) -> Option<&'a str> {
+        let addr = match self.0 {
+            TrustedProxiesInner::All => None,
+
+            // the peer is the first hop and not part of the chain
+            TrustedProxiesInner::Hops(hops) => chain.get(chain.len().saturating_sub(hops)),
+
+            // unparsable values (e.g. obfuscated identifiers) can not be trusted
+            TrustedProxiesInner::Networks(_) => chain
+                .iter()
+                .rev()
+                .find(|addr| !parse_ip(addr).map_or(false, |ip| self.contains(ip))),
+        };
+
+        // when the whole chain is trusted, the first value is the client
+        addr.or_else(|| chain.first()).copied()
+    }
+}
+
+impl Default for TrustedProxies {
+    fn default() -> Self {
+        TrustedProxies::all()
+    }
+}
+
+/// Parses IP address from a forwarded `for` value, with optional port and IPv6 brackets.
+fn parse_ip(addr: &str) -> Option<IpAddr> {
+    addr.parse::<IpAddr>()
+        .ok()
+        .or_else(|| addr.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| addr.strip_prefix('[')?.strip_suffix(']')?.parse().ok())
+}
+
 /// Extractor for peer's socket address.
 ///
 /// Also see [`HttpRequest::peer_addr`].
@@ -402,6 +564,138 @@ mod tests {
         assert_eq!(info.realip_remote_addr(), Some("192.0.2.60"));
     }
 
+    fn trusted_info(
+        trusted_proxies: TrustedProxies,
+        peer_addr: Option<&str>,
+        headers: &[(&str, &str)],
+    ) -> ConnectionInfo {
+        let mut req = TestRequest::default();
+        if let Some(addr) = peer_addr {
+            req = req.peer_addr(addr.parse().unwrap());
+        }
+        for &header in headers {
+            req = req.append_header(header);
+        }
+        let req = req.to_http_request();
+
+        let mut cfg = AppConfig::default();
+        cfg.set_trusted_proxies(trusted_proxies);
+        ConnectionInfo::new(req.head(), &cfg)
+    }
+
+    #[test]
+    fn trusted_proxies_networks() {
+        let proxies = || {
+            TrustedProxies::new()
+                .add_network("10.0.0.0/8")
+                .add_network("2001:db8::1")
+        };
+        let headers = [
+            (X_FORWARDED_FOR, "203.0.113.7, 198.51.100.17, 10.0.0.2"),
+            (X_FORWARDED_PROTO, "https"),
+            (X_FORWARDED_HOST, "rust-lang.org"),
+        ];
+
+        // untrusted peer; headers are ignored
+        let info = trusted_info(proxies(), Some("192.0.2.1:1234"), &headers);
+        assert_eq!(info.realip_remote_addr(), Some("192.0.2.1:1234"));
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), "localhost:8080");
+
+        let info = trusted_info(proxies(), None, &headers);
+        assert_eq!(info.realip_remote_addr(), None);
+        assert_eq!(info.scheme(), "http");
+
+        // trusted peer; chain is walked right-to-left up to the first untrusted address
+        let info = trusted_info(proxies(), Some("10.1.2.3:1234"), &headers);
+        assert_eq!(info.realip_remote_addr(), Some("198.51.100.17"));
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "rust-lang.org");
+
+        let info = trusted_info(
+            proxies(),
+            Some("[2001:db8::1]:1234"),
+            &[(
+                "forwarded",
+                r#"for=192.0.2.60, for="[2001:db8::1]:4711";proto=https, for=10.0.0.3"#,
+            )],
+        );
+        assert_eq!(info.realip_remote_addr(), Some("192.0.2.60"));
+        assert_eq!(info.scheme(), "https");
+
+        // obfuscated identifiers are never trusted
+        let info = trusted_info(
+            proxies(),
+            Some("10.1.2.3:1234"),
+            &[("forwarded", "for=192.0.2.60, for=_hidden, for=10.0.0.3")],
+        );
+        assert_eq!(info.realip_remote_addr(), Some("_hidden"));
+
+        // fully trusted chain
+        let info = trusted_info(
+            proxies(),
+            Some("10.1.2.3:1234"),
+            &[(X_FORWARDED_FOR, "10.0.0.4"), (X_FORWARDED_FOR, "10.0.0.3")],
+        );
+        assert_eq!(info.realip_remote_addr(), Some("10.0.0.4"));
+
+        let info = trusted_info(TrustedProxies::new(), Some("10.1.2.3:1234"), &headers);
+        assert_eq!(info.realip_remote_addr(), Some("10.1.2.3:1234"));
+    }
+
+    #[test]
+    fn trusted_proxies_hops() {
+        let headers = [(X_FORWARDED_FOR, "203.0.113.7, 198.51.100.17, 10.0.0.2")];
+
+        let info = trusted_info(TrustedProxies::hops(0), Some("10.1.2.3:1234"), &headers);
+        assert_eq!(info.realip_remote_addr(), Some("10.1.2.3:1234"));
+
+        let info = trusted_info(TrustedProxies::hops(1), Some("10.1.2.3:1234"), &headers);
+        assert_eq!(info.realip_remote_addr(), Some("10.0.0.2"));
+
+        let info = trusted_info(TrustedProxies::hops(2), None, &headers);
+        assert_eq!(info.realip_remote_addr(), Some("198.51.100.17"));
+
+        let info = trusted_info(TrustedProxies::hops(5), None, &headers);
+        assert_eq!(info.realip_remote_addr(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid trusted proxy network")]
+    fn trusted_proxies_invalid_network() {
+        TrustedProxies::new().add_network("10.0.0.0/33");
+    }
+
+    #[actix_rt::test]
+    async fn trusted_proxies_app() {
+        use crate::{test, web, App};
+
+        let srv = test::init_service(
+            App::new()
+                .trusted_proxies(TrustedProxies::new().add_network("127.0.0.1"))
+                .route(
+                    "/",
+                    web::get().to(|req: HttpRequest| async move {
+                        let info = req.connection_info();
+                        info.realip_remote_addr().unwrap().to_owned()
+                    }),
+                ),
+        )
+        .await;
+
+        for &(peer, expected) in &[
+            ("127.0.0.1:1234", "192.0.2.60"),
+            ("192.0.2.1:1234", "192.0.2.1:1234"),
+        ] {
+            let req = TestRequest::default()
+                .peer_addr(peer.parse().unwrap())
+                .insert_header((X_FORWARDED_FOR, "192.0.2.60"))
+                .to_request();
+            let body = test::read_response(&srv, req).await;
+            assert_eq!(body, expected);
+        }
+    }
+
     #[test]
     fn scheme_from_uri() {
         let req = TestRequest::get()
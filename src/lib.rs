@@ -63,6 +63,7 @@
 //! * `openssl` - HTTPS support via `openssl` crate, supports `HTTP/2`
 //! * `rustls` - HTTPS support via `rustls` crate, supports `HTTP/2`
 //! * `secure-cookies` - secure cookies support
+//! * `session` - session middleware with cookie and in-memory stores
 
 #![deny(rust_2018_idioms, nonstandard_style)]
 #![allow(clippy::needless_doctest_main, clippy::type_complexity)]
@@ -92,6 +93,8 @@ mod route;
 mod scope;
 mod server;
 mod service;
+#[cfg(feature = "session")]
+pub mod session;
 pub mod test;
 pub(crate) mod types;
 pub mod web;
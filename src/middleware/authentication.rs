@@ -0,0 +1,250 @@
+//! For middleware documentation, see [`HttpAuthentication`].
+
+use std::{future::Future, marker::PhantomData, rc::Rc};
+
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    dev::{Service, Transform},
+    error::Error,
+    extract::FromRequest,
+    service::{ServiceRequest, ServiceResponse},
+    web::{BasicAuth, BearerAuth},
+};
+
+/// Middleware for validating credentials of every request.
+///
+/// Credentials are extracted with `T`, usually [`BasicAuth`] or [`BearerAuth`]. Requests without
+/// valid credentials are rejected with `401 Unauthorized` and a `WWW-Authenticate` challenge,
+/// configurable using [`BasicAuthConfig`](crate::web::BasicAuthConfig) or
+/// [`BearerAuthConfig`](crate::web::BearerAuthConfig) app data.
+///
+/// Extracted credentials are passed to the async validator along with the request. The validator
+/// returns the request to continue processing it, or an error to reject it; returning an
+/// [`AuthenticationError`](crate::error::AuthenticationError) responds with its challenge.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     dev::ServiceRequest,
+///     error::AuthenticationError,
+///     http::header::{BearerChallenge, BearerError},
+///     middleware::HttpAuthentication,
+///     web::BearerAuth,
+///     App, Error,
+/// };
+///
+/// async fn validator(req: ServiceRequest, auth: BearerAuth) -> Result<ServiceRequest, Error> {
+///     if auth.token() == "secret" {
+///         Ok(req)
+///     } else {
+///         let challenge = BearerChallenge::new()
+///             .realm("example")
+///             .error(BearerError::InvalidToken);
+///         Err(AuthenticationError::new(challenge).into())
+///     }
+/// }
+///
+/// let app = App::new().wrap(HttpAuthentication::bearer(validator));
+/// ```
+pub struct HttpAuthentication<T, F> {
+    validator: Rc<F>,
+    _extractor: PhantomData<T>,
+}
+
+impl<T, F, O> HttpAuthentication<T, F>
+where
+    T: FromRequest,
+    F: Fn(ServiceRequest, T) -> O,
+    O: Future<Output = Result<ServiceRequest, Error>>,
+{
+    /// Constructs authentication middleware that extracts credentials with `T`.
+    pub fn with_fn(validator: F) -> Self {
+        HttpAuthentication {
+            validator: Rc::new(validator),
+            _extractor: PhantomData,
+        }
+    }
+}
+
+impl<F, O> HttpAuthentication<BasicAuth, F>
+where
+    F: Fn(ServiceRequest, BasicAuth) -> O,
+    O: Future<Output = Result<ServiceRequest, Error>>,
+{
+    /// Constructs authentication middleware for the `Basic` scheme.
+    pub fn basic(validator: F) -> Self {
+        Self::with_fn(validator)
+    }
+}
+
+impl<F, O> HttpAuthentication<BearerAuth, F>
+where
+    F: Fn(ServiceRequest, BearerAuth) -> O,
+    O: Future<Output = Result<ServiceRequest, Error>>,
+{
+    /// Constructs authentication middleware for the `Bearer` scheme.
+    pub fn bearer(validator: F) -> Self {
+        Self::with_fn(validator)
+    }
+}
+
+impl<S, B, T, F, O> Transform<S, ServiceRequest> for HttpAuthentication<T, F>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    T: FromRequest + 'static,
+    F: Fn(ServiceRequest, T) -> O + 'static,
+    O: Future<Output = Result<ServiceRequest, Error>> + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = AuthenticationMiddleware<S, T, F>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthenticationMiddleware {
+            service: Rc::new(service),
+            validator: Rc::clone(&self.validator),
+            _extractor: PhantomData,
+        }))
+    }
+}
+
+pub struct AuthenticationMiddleware<S, T, F> {
+    service: Rc<S>,
+    validator: Rc<F>,
+    _extractor: PhantomData<T>,
+}
+
+impl<S, B, T, F, O> Service<ServiceRequest> for AuthenticationMiddleware<S, T, F>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    T: FromRequest + 'static,
+    F: Fn(ServiceRequest, T) -> O + 'static,
+    O: Future<Output = Result<ServiceRequest, Error>> + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let validator = Rc::clone(&self.validator);
+
+        Box::pin(async move {
+            let (http_req, mut payload) = req.into_parts();
+            let credentials = T::from_request(&http_req, &mut payload)
+                .await
+                .map_err(Into::into)?;
+            let req = ServiceRequest::from_parts(http_req, payload);
+
+            let req = validator(req, credentials).await?;
+            service.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::AuthenticationError,
+        http::{
+            header::{self, BasicChallenge},
+            StatusCode,
+        },
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    async fn basic_validator(
+        req: ServiceRequest,
+        auth: BasicAuth,
+    ) -> Result<ServiceRequest, Error> {
+        if auth.user_id() == "admin" && auth.password() == Some("secret") {
+            Ok(req)
+        } else {
+            let challenge = BasicChallenge::new().realm("Admin");
+            Err(AuthenticationError::new(challenge).into())
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_basic_authentication() {
+        let srv = test::init_service(
+            App::new()
+                .app_data(web::BasicAuthConfig::default().realm("Admin"))
+                .wrap(HttpAuthentication::basic(basic_validator))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        // admin:secret
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Basic YWRtaW46c2VjcmV0"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // admin:wrong; middleware errors are turned into responses by the server
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Basic YWRtaW46d3Jvbmc="))
+            .to_request();
+        let res = srv.call(req).await.unwrap_err().error_response();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Basic realm=\"Admin\""
+        );
+
+        let req = TestRequest::default().to_request();
+        let res = srv.call(req).await.unwrap_err().error_response();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Basic realm=\"Admin\""
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_bearer_authentication() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(HttpAuthentication::bearer(
+                    |req, auth: BearerAuth| async move {
+                        if auth.token() == "token" {
+                            Ok(req)
+                        } else {
+                            Err(crate::error::ErrorForbidden("invalid token"))
+                        }
+                    },
+                ))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer token"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer other"))
+            .to_request();
+        let res = srv.call(req).await.unwrap_err().error_response();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let req = TestRequest::default().to_request();
+        let res = srv.call(req).await.unwrap_err().error_response();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer"
+        );
+    }
+}
@@ -0,0 +1,1077 @@
+//! For middleware documentation, see [`ResponseCache`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error as StdError,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use actix_http::body::{to_bytes, AnyBody, BodySize, MessageBody};
+use actix_utils::future::{ready, Ready};
+use bytes::Bytes;
+use futures_core::future::LocalBoxFuture;
+
+use super::conditional::{self, validators};
+use crate::{
+    dev::{Service, Transform},
+    error::{Error, ErrorInternalServerError},
+    http::{
+        header::{
+            self, from_comma_delimited, CacheDirective, HeaderMap, HeaderName, HeaderValue,
+            HttpDate,
+        },
+        Method, Precondition, StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    HttpResponse,
+};
+
+/// Shared storage of responses cached by [`ResponseCache`].
+///
+/// Clones of the store share the same entries, so a store created outside of the
+/// [`HttpServer`](crate::HttpServer) factory closure is used by all workers. It can also be kept
+/// to [purge](Self::purge) entries when the resources they represent change.
+///
+/// The total size of cached bodies and headers is bounded by [`max_size`](Self::max_size); when
+/// it is exceeded, expired entries are removed first, then the least recently used ones.
+#[derive(Debug, Clone)]
+pub struct ResponseCacheStore {
+    inner: Arc<StoreInner>,
+}
+
+#[derive(Debug)]
+struct StoreInner {
+    max_size: usize,
+    max_entry_size: usize,
+    state: Mutex<StoreState>,
+}
+
+#[derive(Debug, Default)]
+struct StoreState {
+    /// Entries keyed by host, path and query of their requests; each key may have entries for
+    /// several methods and variants.
+    entries: HashMap<String, Vec<Entry>>,
+
+    /// Keys of entries, by the tick they were last used at. Ticks are unique, so they identify
+    /// entries among the variants of their key.
+    lru: BTreeMap<u64, String>,
+
+    /// Keys of entries, by expiry and the tick they were last used at.
+    expiry: BTreeMap<(Instant, u64), String>,
+
+    /// Total size of all entries.
+    size: usize,
+
+    /// Counter used to order entries by last use.
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    method: Method,
+
+    /// Values of the request headers listed in the response's `Vary` header.
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+
+    stored: Instant,
+    expires: Instant,
+    last_used: u64,
+}
+
+impl Entry {
+    fn size(&self) -> usize {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| name.as_str().len() + value.len())
+            .sum::<usize>();
+
+        self.body.len() + headers
+    }
+
+    fn matches(&self, method: &Method, headers: &HeaderMap) -> bool {
+        self.method == method
+            && self
+                .vary
+                .iter()
+                .all(|(name, value)| headers.get(name) == value.as_ref())
+    }
+}
+
+impl ResponseCacheStore {
+    /// Constructs a new, empty store.
+    ///
+    /// By default, up to 64MiB of responses are stored, each of at most 1MiB.
+    pub fn new() -> Self {
+        ResponseCacheStore {
+            inner: Arc::new(StoreInner {
+                max_size: 64 * 1024 * 1024,
+                max_entry_size: 1024 * 1024,
+                state: Mutex::new(StoreState::default()),
+            }),
+        }
+    }
+
+    /// Sets maximum total size of cached responses, in bytes.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        Arc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .max_size = max_size;
+        self
+    }
+
+    /// Sets maximum size of a single cached response, in bytes. Larger responses are not cached.
+    pub fn max_entry_size(mut self, max_entry_size: usize) -> Self {
+        Arc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .max_entry_size = max_entry_size;
+        self
+    }
+
+    /// Removes all cached responses stored under `key`, for any method and variant.
+    ///
+    /// Responses are stored under the host of their request, as given by
+    /// [`ConnectionInfo::host`](crate::dev::ConnectionInfo::host), followed by the request's path
+    /// and query. For example, a response to a request for `https://example.com/items?page=2` is
+    /// purged with the key `example.com/items?page=2`.
+    ///
+    /// Returns `true` if any responses were removed.
+    pub fn purge(&self, key: &str) -> bool {
+        let mut state = self.inner.state.lock().unwrap();
+
+        match state.entries.remove(key) {
+            Some(entries) => {
+                for entry in &entries {
+                    state.unlink(entry);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes all cached responses.
+    pub fn purge_all(&self) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.entries.clear();
+        state.lru.clear();
+        state.expiry.clear();
+        state.size = 0;
+    }
+
+    /// Returns number of cached responses, including expired ones that have not been removed yet.
+    pub fn len(&self) -> usize {
+        let state = self.inner.state.lock().unwrap();
+        state.entries.values().map(Vec::len).sum()
+    }
+
+    /// Returns `true` if no responses are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Looks up a fresh response to a request, returning it along with its age.
+    fn get(
+        &self,
+        key: &str,
+        method: &Method,
+        headers: &HeaderMap,
+        directives: &[CacheDirective],
+        now: Instant,
+    ) -> Option<(StatusCode, HeaderMap, Bytes, Duration)> {
+        let mut state = self.inner.state.lock().unwrap();
+        let state = &mut *state;
+        state.tick += 1;
+        let tick = state.tick;
+
+        let entry = state
+            .entries
+            .get_mut(key)?
+            .iter_mut()
+            .find(|entry| entry.matches(method, headers))?;
+
+        let age = now.saturating_duration_since(entry.stored);
+        let remaining = entry.expires.saturating_duration_since(now);
+
+        if remaining == Duration::from_secs(0) {
+            return None;
+        }
+
+        // request may ask for fresher responses than the cache would serve
+        for directive in directives {
+            match *directive {
+                CacheDirective::MaxAge(max_age) if age.as_secs() > u64::from(max_age) => {
+                    return None
+                }
+                CacheDirective::MinFresh(min_fresh)
+                    if remaining.as_secs() < u64::from(min_fresh) =>
+                {
+                    return None
+                }
+                _ => {}
+            }
+        }
+
+        let key = state.lru.remove(&entry.last_used)?;
+        state.expiry.remove(&(entry.expires, entry.last_used));
+        state.expiry.insert((entry.expires, tick), key.clone());
+        state.lru.insert(tick, key);
+
+        entry.last_used = tick;
+        Some((entry.status, entry.headers.clone(), entry.body.clone(), age))
+    }
+
+    fn insert(&self, key: String, mut entry: Entry, now: Instant) {
+        let entry_size = entry.size();
+        if entry_size > self.inner.max_entry_size || entry_size > self.inner.max_size {
+            return;
+        }
+
+        let mut state = self.inner.state.lock().unwrap();
+        state.tick += 1;
+        entry.last_used = state.tick;
+
+        state.lru.insert(entry.last_used, key.clone());
+        state
+            .expiry
+            .insert((entry.expires, entry.last_used), key.clone());
+        state.size += entry_size;
+
+        let variants = state.entries.entry(key).or_default();
+
+        // replace previous response for the same variant
+        let vary = &entry.vary;
+        let replaced = variants
+            .iter()
+            .position(|old| old.method == entry.method && old.vary == *vary)
+            .map(|idx| variants.swap_remove(idx));
+
+        variants.push(entry);
+
+        if let Some(replaced) = replaced {
+            state.unlink(&replaced);
+        }
+
+        if state.size > self.inner.max_size {
+            state.evict(self.inner.max_size, now);
+        }
+    }
+}
+
+impl StoreState {
+    /// Removes an entry that was taken out of `entries` from the indexes and the total size.
+    fn unlink(&mut self, entry: &Entry) {
+        self.lru.remove(&entry.last_used);
+        self.expiry.remove(&(entry.expires, entry.last_used));
+        self.size -= entry.size();
+    }
+
+    /// Removes the entry stored under `key` that was last used at `tick`.
+    fn remove(&mut self, key: &str, tick: u64) {
+        let variants = match self.entries.get_mut(key) {
+            Some(variants) => variants,
+            None => return,
+        };
+
+        if let Some(idx) = variants.iter().position(|entry| entry.last_used == tick) {
+            let entry = variants.swap_remove(idx);
+
+            if variants.is_empty() {
+                self.entries.remove(key);
+            }
+
+            self.unlink(&entry);
+        }
+    }
+
+    /// Removes expired entries, then least recently used ones, until at most `max_size` bytes
+    /// are stored.
+    fn evict(&mut self, max_size: usize, now: Instant) {
+        while self.size > max_size {
+            let next = match self.expiry.iter().next() {
+                Some((&(expires, tick), key)) if expires <= now => Some((key.clone(), tick)),
+                _ => self
+                    .lru
+                    .iter()
+                    .next()
+                    .map(|(&tick, key)| (key.clone(), tick)),
+            };
+
+            match next {
+                Some((key, tick)) => self.remove(&key, tick),
+                None => break,
+            }
+        }
+    }
+}
+
+impl Default for ResponseCacheStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Middleware for caching responses in server memory.
+///
+/// Responses to `GET` and `HEAD` requests are stored in a [`ResponseCacheStore`], keyed by method,
+/// host, path and query and the values of the request headers listed in the response's `Vary`
+/// header, and are
+/// served from the store while they are fresh. Cached responses include an `Age` header. If the
+/// request's `If-None-Match` or `If-Modified-Since` header matches a cached response, a
+/// `304 Not Modified` response is served instead, and if its `If-Match` or `If-Unmodified-Since`
+/// header does not, a `412 Precondition Failed` response is served.
+///
+/// Responses are stored only if:
+/// - their status is 200, 203, 204, 300, 301, 308, 404 or 410;
+/// - they have no `Cache-Control: no-store`, `no-cache` or `private` directive, no `Set-Cookie`
+///   header and no `Vary: *` header;
+/// - their body has a known size within the store's limit;
+/// - they are fresh for some time, as given by the `s-maxage` or `max-age` directive or the
+///   `Expires` header, or by the [default TTL](Self::default_ttl) if none of these are present.
+///
+/// The request's `Cache-Control: no-store`, `no-cache`, `max-age`, `min-fresh` and
+/// `only-if-cached` directives are honored. Requests with an `Authorization` header bypass the
+/// cache. Successful responses to unsafe methods, such as `POST`, purge the responses cached for
+/// the same host, path and query.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use actix_web::{
+///     http::header::{CacheControl, CacheDirective},
+///     middleware::{ResponseCache, ResponseCacheStore},
+///     web, App, HttpResponse,
+/// };
+///
+/// let store = ResponseCacheStore::new().max_size(16 * 1024 * 1024);
+///
+/// let app = App::new()
+///     .wrap(ResponseCache::new(store.clone()).max_ttl(Duration::from_secs(3600)))
+///     .route(
+///         "/",
+///         web::get().to(|| {
+///             HttpResponse::Ok()
+///                 .insert_header(CacheControl(vec![CacheDirective::MaxAge(60)]))
+///                 .body("cached for a minute")
+///         }),
+///     );
+/// ```
+pub struct ResponseCache {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    store: ResponseCacheStore,
+    default_ttl: Option<Duration>,
+    max_ttl: Option<Duration>,
+}
+
+impl ResponseCache {
+    /// Constructs response caching middleware storing responses in `store`.
+    pub fn new(store: ResponseCacheStore) -> Self {
+        ResponseCache {
+            inner: Rc::new(Inner {
+                store,
+                default_ttl: None,
+                max_ttl: None,
+            }),
+        }
+    }
+
+    /// Sets time for which responses without explicit freshness information are cached.
+    ///
+    /// By default, such responses are not cached.
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .default_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets maximum time for which responses are cached, regardless of their freshness
+    /// information.
+    pub fn max_ttl(mut self, ttl: Duration) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .max_ttl = Some(ttl);
+        self
+    }
+}
+
+impl Inner {
+    /// Returns the time for which a response may be cached, if it may be cached at all.
+    fn ttl<B>(&self, res: &ServiceResponse<B>) -> Option<Duration> {
+        match res.status().as_u16() {
+            200 | 203 | 204 | 300 | 301 | 308 | 404 | 410 => {}
+            _ => return None,
+        }
+
+        let headers = res.headers();
+
+        if res.response().error().is_some() || headers.contains_key(header::SET_COOKIE) {
+            return None;
+        }
+
+        if vary_names(headers)?.is_empty() && headers.contains_key(header::VARY) {
+            return None;
+        }
+
+        let directives = cache_directives(headers)?;
+        let mut max_age = None;
+        let mut s_maxage = None;
+
+        for directive in &directives {
+            match *directive {
+                CacheDirective::NoStore | CacheDirective::NoCache | CacheDirective::Private => {
+                    return None
+                }
+                CacheDirective::MaxAge(secs) => max_age = Some(secs),
+                CacheDirective::SMaxAge(secs) => s_maxage = Some(secs),
+                _ => {}
+            }
+        }
+
+        let ttl = match s_maxage.or(max_age) {
+            Some(secs) => Duration::from_secs(u64::from(secs)),
+            None => match headers.get(header::EXPIRES) {
+                // invalid dates, such as "0", mean the response has already expired
+                Some(expires) => expires
+                    .to_str()
+                    .ok()
+                    .and_then(|expires| expires.parse::<HttpDate>().ok())
+                    .and_then(|expires| {
+                        SystemTime::from(expires)
+                            .duration_since(SystemTime::now())
+                            .ok()
+                    })
+                    .unwrap_or_else(|| Duration::from_secs(0)),
+                None => self.default_ttl?,
+            },
+        };
+
+        let ttl = match self.max_ttl {
+            Some(max_ttl) => ttl.min(max_ttl),
+            None => ttl,
+        };
+
+        if ttl == Duration::from_secs(0) {
+            None
+        } else {
+            Some(ttl)
+        }
+    }
+}
+
+/// Returns the key under which responses to `req` are stored: the host of the request followed by
+/// its path and query.
+///
+/// The URI of HTTP/2 requests is absolute, so it can not be used as the key directly.
+fn cache_key(req: &ServiceRequest) -> String {
+    let path = req
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+
+    format!("{}{}", req.connection_info().host(), path)
+}
+
+/// Parses `Cache-Control` directives, returning `None` if they are invalid.
+fn cache_directives(headers: &HeaderMap) -> Option<Vec<CacheDirective>> {
+    from_comma_delimited(headers.get_all(header::CACHE_CONTROL)).ok()
+}
+
+/// Parses the header names listed in `Vary`, returning `None` if they are invalid.
+///
+/// An empty list is returned for `Vary: *`.
+fn vary_names(headers: &HeaderMap) -> Option<Vec<HeaderName>> {
+    let mut names = Vec::new();
+
+    for value in headers.get_all(header::VARY) {
+        for name in value.to_str().ok()?.split(',').map(str::trim) {
+            match name {
+                "" => {}
+                "*" => return Some(Vec::new()),
+                name => names.push(HeaderName::from_bytes(name.as_bytes()).ok()?),
+            }
+        }
+    }
+
+    Some(names)
+}
+
+/// Builds a response from a cache entry.
+fn cached_response(
+    req: &ServiceRequest,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    age: Duration,
+) -> HttpResponse {
+    let age = HeaderValue::from(age.as_secs());
+    let (etag, last_modified) = validators(&headers);
+
+    match Precondition::evaluate(req.request(), etag.as_ref(), last_modified) {
+        Precondition::Passed => {
+            let mut res = HttpResponse::with_body(status, AnyBody::Bytes(body));
+            *res.headers_mut() = headers;
+            res.headers_mut().insert(header::AGE, age);
+            res
+        }
+
+        Precondition::NotModified => conditional::not_modified(&headers)
+            .insert_header((header::AGE, age))
+            .finish(),
+
+        Precondition::Failed => HttpResponse::PreconditionFailed().finish(),
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ResponseCache
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = ResponseCacheMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ResponseCacheMiddleware {
+            service: Rc::new(service),
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct ResponseCacheMiddleware<S> {
+    service: Rc<S>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for ResponseCacheMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let inner = Rc::clone(&self.inner);
+
+        Box::pin(async move {
+            let key = cache_key(&req);
+            let method = req.method().clone();
+
+            if method != Method::GET && method != Method::HEAD {
+                let res = service.call(req).await?;
+
+                // responses to unsafe methods invalidate cached responses
+                let unsafe_method = method != Method::OPTIONS && method != Method::TRACE;
+                if unsafe_method && (res.status().is_success() || res.status().is_redirection())
+                {
+                    inner.store.purge(&key);
+                }
+
+                return Ok(res.map_body(|_, body| AnyBody::from_message(body)));
+            }
+
+            let directives = cache_directives(req.headers()).unwrap_or_default();
+
+            if req.headers().contains_key(header::AUTHORIZATION)
+                || directives.contains(&CacheDirective::NoStore)
+            {
+                let res = service.call(req).await?;
+                return Ok(res.map_body(|_, body| AnyBody::from_message(body)));
+            }
+
+            if !directives.contains(&CacheDirective::NoCache) {
+                let now = Instant::now();
+                let cached = inner
+                    .store
+                    .get(&key, &method, req.headers(), &directives, now);
+
+                if let Some((status, headers, body, age)) = cached {
+                    let res = cached_response(&req, status, headers, body, age);
+                    return Ok(req.into_response(res));
+                }
+
+                if directives.contains(&CacheDirective::OnlyIfCached) {
+                    return Ok(req.into_response(HttpResponse::GatewayTimeout().finish()));
+                }
+            }
+
+            // values of the headers listed in `Vary` are taken once the response is known
+            let req_headers = req.headers().clone();
+            let res = service.call(req).await?;
+
+            let ttl = match inner.ttl(&res) {
+                Some(ttl) => ttl,
+                None => return Ok(res.map_body(|_, body| AnyBody::from_message(body))),
+            };
+
+            match res.response().body().size() {
+                BodySize::Sized(size) if size <= inner.store.inner.max_entry_size as u64 => {}
+                BodySize::None | BodySize::Empty => {}
+                _ => return Ok(res.map_body(|_, body| AnyBody::from_message(body))),
+            }
+
+            let vary = vary_names(res.headers())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|name| {
+                    let value = req_headers.get(&name).cloned();
+                    (name, value)
+                })
+                .collect();
+
+            let mut body = None;
+            let res = res.map_body(|_, b| {
+                body = Some(b);
+                AnyBody::Empty
+            });
+
+            let body = to_bytes(body.unwrap())
+                .await
+                .map_err(|err| ErrorInternalServerError(err.into()))?;
+
+            let now = Instant::now();
+            let entry = Entry {
+                method,
+                vary,
+                status: res.status(),
+                headers: res.headers().clone(),
+                body: body.clone(),
+                stored: now,
+                expires: now + ttl,
+                last_used: 0,
+            };
+            inner.store.insert(key, entry, now);
+
+            Ok(res.map_body(|_, _| AnyBody::Bytes(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{
+        http::header::CacheControl,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    /// Handler that counts its calls and responds with the given headers.
+    fn handler(
+        calls: &Arc<AtomicUsize>,
+        headers: Vec<(HeaderName, &'static str)>,
+    ) -> impl Fn() -> HttpResponse + Clone {
+        let calls = Arc::clone(calls);
+
+        move || {
+            let count = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            let mut res = HttpResponse::Ok();
+            for (name, value) in &headers {
+                res.append_header((name.clone(), *value));
+            }
+            res.body(format!("response {}", count))
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_cache_hit() {
+        let store = ResponseCacheStore::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let srv = test::init_service(
+            App::new().wrap(ResponseCache::new(store.clone())).service(
+                web::resource("/")
+                    .route(web::get().to(handler(
+                        &calls,
+                        vec![
+                            (header::CACHE_CONTROL, "max-age=60"),
+                            (header::ETAG, "\"v1\""),
+                        ],
+                    )))
+                    .route(web::post().to(HttpResponse::Ok)),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get(header::AGE).is_none());
+        assert_eq!(test::read_body(res).await, "response 1");
+
+        let req = TestRequest::with_uri("/").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::AGE).unwrap(), "0");
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"v1\"");
+        assert_eq!(test::read_body(res).await, "response 1");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // conditional requests are answered from the cache
+        let req = TestRequest::with_uri("/")
+            .insert_header((header::IF_NONE_MATCH, "W/\"v1\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"v1\"");
+        assert_eq!(test::read_body(res).await, "");
+
+        let req = TestRequest::with_uri("/")
+            .insert_header((header::IF_NONE_MATCH, "\"v0\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let req = TestRequest::with_uri("/")
+            .insert_header((header::IF_MATCH, "\"v0\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(test::read_body(res).await, "");
+
+        let req = TestRequest::with_uri("/")
+            .insert_header((header::IF_MATCH, "\"v1\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // unsafe methods purge cached responses
+        let req = TestRequest::post().uri("/").to_request();
+        test::call_service(&srv, req).await;
+        assert!(store.is_empty());
+
+        let req = TestRequest::with_uri("/").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "response 2");
+        assert_eq!(store.len(), 1);
+
+        assert!(store.purge("localhost:8080/"));
+        assert!(!store.purge("localhost:8080/"));
+
+        let req = TestRequest::with_uri("/").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "response 3");
+    }
+
+    #[actix_rt::test]
+    async fn test_cache_key() {
+        let store = ResponseCacheStore::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let srv = test::init_service(App::new().wrap(ResponseCache::new(store.clone())).route(
+            "/items",
+            web::get().to(handler(&calls, vec![(header::CACHE_CONTROL, "max-age=60")])),
+        ))
+        .await;
+
+        // absolute URI, as sent over HTTP/2
+        let req = TestRequest::with_uri("https://example.com/items?page=2").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "response 1");
+
+        let req = TestRequest::with_uri("/items?page=2")
+            .insert_header((header::HOST, "example.com"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "response 1");
+
+        // other hosts and queries are cached separately
+        let req = TestRequest::with_uri("/items?page=2")
+            .insert_header((header::HOST, "example.org"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "response 2");
+
+        let req = TestRequest::with_uri("https://example.com/items?page=3").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "response 3");
+        assert_eq!(store.len(), 3);
+
+        assert!(store.purge("example.com/items?page=2"));
+        assert!(!store.purge("/items?page=2"));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_not_cacheable() {
+        let store = ResponseCacheStore::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let srv = test::init_service(
+            App::new()
+                .wrap(ResponseCache::new(store.clone()))
+                .route("/none", web::get().to(handler(&calls, vec![])))
+                .route(
+                    "/no-store",
+                    web::get().to(handler(
+                        &calls,
+                        vec![(header::CACHE_CONTROL, "max-age=60, no-store")],
+                    )),
+                )
+                .route(
+                    "/private",
+                    web::get().to(handler(
+                        &calls,
+                        vec![(header::CACHE_CONTROL, "private, max-age=60")],
+                    )),
+                )
+                .route(
+                    "/cookie",
+                    web::get().to(handler(
+                        &calls,
+                        vec![
+                            (header::CACHE_CONTROL, "max-age=60"),
+                            (header::SET_COOKIE, "id=1"),
+                        ],
+                    )),
+                )
+                .route(
+                    "/vary-all",
+                    web::get().to(handler(
+                        &calls,
+                        vec![(header::CACHE_CONTROL, "max-age=60"), (header::VARY, "*")],
+                    )),
+                )
+                .route(
+                    "/expired",
+                    web::get().to(handler(&calls, vec![(header::EXPIRES, "0")])),
+                ),
+        )
+        .await;
+
+        for path in &[
+            "/none",
+            "/no-store",
+            "/private",
+            "/cookie",
+            "/vary-all",
+            "/expired",
+        ] {
+            for _ in 0..2 {
+                let req = TestRequest::with_uri(path).to_request();
+                let res = test::call_service(&srv, req).await;
+                assert_eq!(res.status(), StatusCode::OK);
+            }
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 12);
+        assert!(store.is_empty());
+
+        // authorized requests bypass the cache
+        let srv = test::init_service(
+            App::new()
+                .wrap(ResponseCache::new(store.clone()).default_ttl(Duration::from_secs(60)))
+                .route("/", web::get().to(handler(&calls, vec![]))),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/")
+            .insert_header((header::AUTHORIZATION, "Bearer token"))
+            .to_request();
+        test::call_service(&srv, req).await;
+        assert!(store.is_empty());
+
+        let req = TestRequest::with_uri("/").to_request();
+        test::call_service(&srv, req).await;
+        assert_eq!(store.len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_vary() {
+        let store = ResponseCacheStore::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let srv = test::init_service(App::new().wrap(ResponseCache::new(store.clone())).route(
+            "/",
+            web::get().to(handler(
+                &calls,
+                vec![
+                    (header::CACHE_CONTROL, "max-age=60"),
+                    (header::VARY, "Accept-Language"),
+                ],
+            )),
+        ))
+        .await;
+
+        let requests = [
+            (Some("en"), "response 1"),
+            (Some("de"), "response 2"),
+            (None, "response 3"),
+            (Some("en"), "response 1"),
+            (Some("de"), "response 2"),
+            (None, "response 3"),
+        ];
+
+        for (lang, body) in &requests {
+            let mut req = TestRequest::with_uri("/");
+            if let Some(lang) = lang {
+                req = req.insert_header((header::ACCEPT_LANGUAGE, *lang));
+            }
+
+            let res = test::call_service(&srv, req.to_request()).await;
+            assert_eq!(test::read_body(res).await, *body);
+        }
+
+        assert_eq!(store.len(), 3);
+    }
+
+    #[actix_rt::test]
+    async fn test_request_directives() {
+        let store = ResponseCacheStore::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let srv = test::init_service(App::new().wrap(ResponseCache::new(store.clone())).route(
+            "/",
+            web::get().to(handler(&calls, vec![(header::CACHE_CONTROL, "max-age=60")])),
+        ))
+        .await;
+
+        let only_if_cached = CacheControl(vec![CacheDirective::OnlyIfCached]);
+        let req = TestRequest::with_uri("/")
+            .insert_header(only_if_cached.clone())
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        // no-store neither reads nor writes the cache
+        let req = TestRequest::with_uri("/")
+            .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+            .to_request();
+        test::call_service(&srv, req).await;
+        assert!(store.is_empty());
+
+        let req = TestRequest::with_uri("/").to_request();
+        test::call_service(&srv, req).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let req = TestRequest::with_uri("/")
+            .insert_header(only_if_cached)
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "response 2");
+
+        // no-cache refreshes the cached response
+        let req = TestRequest::with_uri("/")
+            .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "response 3");
+
+        let req = TestRequest::with_uri("/").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "response 3");
+
+        let req = TestRequest::with_uri("/")
+            .insert_header(CacheControl(vec![CacheDirective::MinFresh(120)]))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(test::read_body(res).await, "response 4");
+    }
+
+    fn entry(body: &'static str, now: Instant, ttl: Duration) -> Entry {
+        Entry {
+            method: Method::GET,
+            vary: Vec::new(),
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(body.as_bytes()),
+            stored: now,
+            expires: now + ttl,
+            last_used: 0,
+        }
+    }
+
+    #[test]
+    fn test_store_expiry_and_eviction() {
+        let store = ResponseCacheStore::new().max_size(10).max_entry_size(5);
+        let headers = HeaderMap::new();
+        let now = Instant::now();
+        let min = Duration::from_secs(60);
+
+        store.insert("/a".to_owned(), entry("aaaa", now, min), now);
+        assert!(store.get("/a", &Method::GET, &headers, &[], now).is_some());
+        assert!(store.get("/a", &Method::HEAD, &headers, &[], now).is_none());
+        assert!(store
+            .get("/a", &Method::GET, &headers, &[], now + min)
+            .is_none());
+
+        let max_age = [CacheDirective::MaxAge(10)];
+        let later = now + Duration::from_secs(20);
+        assert!(store
+            .get("/a", &Method::GET, &headers, &max_age, later)
+            .is_none());
+
+        // too large
+        store.insert("/big".to_owned(), entry("bigger", now, min), now);
+        assert!(store
+            .get("/big", &Method::GET, &headers, &[], now)
+            .is_none());
+
+        // least recently used entry is evicted
+        store.insert("/b".to_owned(), entry("bbbb", now, min), now);
+        assert!(store.get("/a", &Method::GET, &headers, &[], now).is_some());
+        store.insert("/c".to_owned(), entry("cccc", now, min), now);
+        assert!(store.get("/a", &Method::GET, &headers, &[], now).is_some());
+        assert!(store.get("/b", &Method::GET, &headers, &[], now).is_none());
+        assert_eq!(store.len(), 2);
+
+        // expired entries are evicted first
+        store.insert(
+            "/d".to_owned(),
+            entry("dd", now, Duration::from_secs(1)),
+            now,
+        );
+        let later = now + Duration::from_secs(2);
+        store.insert("/e".to_owned(), entry("eeee", later, min), later);
+        assert!(store
+            .get("/a", &Method::GET, &headers, &[], later)
+            .is_some());
+        assert!(store
+            .get("/c", &Method::GET, &headers, &[], later)
+            .is_none());
+        assert!(store
+            .get("/e", &Method::GET, &headers, &[], later)
+            .is_some());
+        assert_eq!(store.len(), 2);
+
+        // replaced and purged entries are removed from the indexes
+        store.insert("/e".to_owned(), entry("ee", later, min), later);
+        assert_eq!(store.len(), 2);
+        assert!(store.purge("/a"));
+
+        {
+            let state = store.inner.state.lock().unwrap();
+            assert_eq!(state.lru.len(), 1);
+            assert_eq!(state.expiry.len(), 1);
+            assert_eq!(state.size, 2);
+        }
+
+        store.purge_all();
+        assert!(store.is_empty());
+    }
+}
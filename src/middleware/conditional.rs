@@ -0,0 +1,485 @@
+//! For middleware documentation, see [`ConditionalRequest`].
+
+use std::{error::Error as StdError, fmt::Write as _, rc::Rc};
+
+use actix_http::body::{to_bytes, AnyBody, BodySize, MessageBody};
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+use sha1::{Digest as _, Sha1};
+
+use crate::{
+    dev::{Service, Transform},
+    error::{Error, ErrorInternalServerError},
+    http::{
+        header::{self, EntityTag, HeaderMap, HttpDate},
+        Method, Precondition, StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    HttpResponse, HttpResponseBuilder,
+};
+
+type Validator = dyn Fn(&ServiceRequest) -> (Option<EntityTag>, Option<HttpDate>);
+
+/// Middleware for answering conditional requests, as defined in
+/// [RFC7232](https://tools.ietf.org/html/rfc7232).
+///
+/// `200 OK` responses to `GET` and `HEAD` requests are validated against the request's
+/// `If-Match`, `If-Unmodified-Since`, `If-None-Match` and `If-Modified-Since` headers using
+/// [`Precondition::evaluate`]. Matching requests receive a `304 Not Modified` response, or a
+/// `412 Precondition Failed` response if a condition does not hold.
+///
+/// If the handler sets an `ETag` or `Last-Modified` header, those validators are used. Otherwise,
+/// bodies with a known size up to [`max_body_size`](Self::max_body_size) are buffered and a
+/// strong `ETag` is computed from the SHA-1 digest of their content, so that all workers and
+/// server instances compute the same `ETag` for the same body. Use [`weak`](Self::weak) to compute weak
+/// `ETag`s instead, such as when an outer middleware (e.g., [`Compress`]) changes the encoding of
+/// the body.
+///
+/// Unsafe methods, such as `PUT` or `DELETE`, are checked before the handler is called so that
+/// the request is not performed if a condition does not hold. The current `ETag` and
+/// `Last-Modified` date of the target resource are given by the function set with
+/// [`validator`](Self::validator); without it, requests that have an `If-Match` header listing
+/// entity tags are always rejected.
+///
+/// [`Compress`]: super::Compress
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header::EntityTag, middleware::ConditionalRequest, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(ConditionalRequest::new().validator(|req| {
+///         // look up the current version of the resource
+///         (Some(EntityTag::strong(format!("{}-v1", req.path()))), None)
+///     }))
+///     .route("/", web::get().to(|| HttpResponse::Ok().body("Hello world")));
+/// ```
+pub struct ConditionalRequest {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    weak: bool,
+    max_body_size: u64,
+    validator: Option<Box<Validator>>,
+}
+
+impl ConditionalRequest {
+    /// Constructs conditional request middleware that computes strong `ETag`s for bodies of up
+    /// to 1MiB.
+    pub fn new() -> Self {
+        ConditionalRequest {
+            inner: Rc::new(Inner {
+                weak: false,
+                max_body_size: 1024 * 1024,
+                validator: None,
+            }),
+        }
+    }
+
+    /// Compute weak `ETag`s instead of strong ones.
+    pub fn weak(mut self) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .weak = true;
+        self
+    }
+
+    /// Set maximum size of bodies that are buffered to compute an `ETag`.
+    ///
+    /// By default, the limit is 1MiB.
+    pub fn max_body_size(mut self, size: u64) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .max_body_size = size;
+        self
+    }
+
+    /// Set function that returns the current `ETag` and `Last-Modified` date of the resource
+    /// targeted by a request with an unsafe method. Either is `None` if the resource has none.
+    pub fn validator<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> (Option<EntityTag>, Option<HttpDate>) + 'static,
+    {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .validator = Some(Box::new(validator));
+        self
+    }
+}
+
+impl Default for ConditionalRequest {
+    fn default() -> Self {
+        ConditionalRequest::new()
+    }
+}
+
+/// Returns the `ETag` and `Last-Modified` validators of a response.
+pub(super) fn validators(headers: &HeaderMap) -> (Option<EntityTag>, Option<HttpDate>) {
+    let etag = headers
+        .get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .and_then(|etag| etag.parse().ok());
+
+    let last_modified = headers
+        .get(header::LAST_MODIFIED)
+        .and_then(|date| date.to_str().ok())
+        .and_then(|date| date.parse().ok());
+
+    (etag, last_modified)
+}
+
+/// Starts a `304 Not Modified` response with the headers of the `200 OK` response it replaces
+/// that must be kept.
+pub(super) fn not_modified(headers: &HeaderMap) -> HttpResponseBuilder {
+    let mut res = HttpResponse::NotModified();
+
+    for name in &[
+        header::CACHE_CONTROL,
+        header::CONTENT_LOCATION,
+        header::DATE,
+        header::ETAG,
+        header::EXPIRES,
+        header::LAST_MODIFIED,
+        header::VARY,
+    ] {
+        for value in headers.get_all(name) {
+            res.append_header((name.clone(), value.clone()));
+        }
+    }
+
+    res
+}
+
+/// Computes an entity tag from the content of a body.
+fn body_etag(body: &[u8], weak: bool) -> EntityTag {
+    let mut tag = format!("{:x}-", body.len());
+    for byte in Sha1::digest(body) {
+        let _ = write!(tag, "{:02x}", byte);
+    }
+
+    EntityTag::new(weak, tag)
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConditionalRequest
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = ConditionalRequestMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ConditionalRequestMiddleware {
+            service: Rc::new(service),
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct ConditionalRequestMiddleware<S> {
+    service: Rc<S>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for ConditionalRequestMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let inner = Rc::clone(&self.inner);
+
+        Box::pin(async move {
+            let method = req.method().clone();
+
+            if method != Method::GET && method != Method::HEAD {
+                let safe_method = method == Method::OPTIONS || method == Method::TRACE;
+                let conditional = [
+                    header::IF_MATCH,
+                    header::IF_NONE_MATCH,
+                    header::IF_UNMODIFIED_SINCE,
+                ]
+                .iter()
+                .any(|name| req.headers().contains_key(name));
+
+                if !safe_method && conditional {
+                    let (etag, last_modified) = match inner.validator {
+                        Some(ref validator) => validator(&req),
+                        None => (None, None),
+                    };
+
+                    if let Precondition::Failed =
+                        Precondition::evaluate(req.request(), etag.as_ref(), last_modified)
+                    {
+                        let res = HttpResponse::PreconditionFailed().finish();
+                        return Ok(req.into_response(res));
+                    }
+                }
+
+                let res = service.call(req).await?;
+                return Ok(res.map_body(|_, body| AnyBody::from_message(body)));
+            }
+
+            let res = service.call(req).await?;
+
+            if res.status() != StatusCode::OK {
+                return Ok(res.map_body(|_, body| AnyBody::from_message(body)));
+            }
+
+            let (mut etag, last_modified) = validators(res.headers());
+
+            let res = if etag.is_none() && last_modified.is_none() {
+                match res.response().body().size() {
+                    BodySize::Sized(size) if size <= inner.max_body_size => {}
+                    BodySize::None | BodySize::Empty => {}
+                    _ => return Ok(res.map_body(|_, body| AnyBody::from_message(body))),
+                }
+
+                let mut body = None;
+                let mut res = res.map_body(|_, b| {
+                    body = Some(b);
+                    AnyBody::Empty
+                });
+
+                let body = to_bytes(body.unwrap())
+                    .await
+                    .map_err(|err| ErrorInternalServerError(err.into()))?;
+
+                let tag = body_etag(&body, inner.weak);
+                res.headers_mut().insert(
+                    header::ETAG,
+                    tag.to_string()
+                        .parse()
+                        .expect("entity tags are valid headers"),
+                );
+                etag = Some(tag);
+
+                res.map_body(|_, _| AnyBody::Bytes(body))
+            } else {
+                res.map_body(|_, body| AnyBody::from_message(body))
+            };
+
+            match Precondition::evaluate(res.request(), etag.as_ref(), last_modified) {
+                Precondition::Passed => Ok(res),
+
+                Precondition::NotModified => {
+                    let not_modified = not_modified(res.headers()).finish();
+                    Ok(res.into_response(not_modified))
+                }
+
+                Precondition::Failed => {
+                    let failed = HttpResponse::PreconditionFailed().finish();
+                    Ok(res.into_response(failed))
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+    use crate::{
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    #[actix_rt::test]
+    async fn test_computed_etag() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(ConditionalRequest::new())
+                .route("/", web::get().to(|| HttpResponse::Ok().body("hello"))),
+        )
+        .await;
+
+        let res = test::call_service(&srv, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        // stable across processes
+        assert_eq!(etag, "\"5-aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d\"");
+        assert_eq!(test::read_body(res).await, "hello");
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG), Some(&etag));
+        assert!(test::read_body(res).await.is_empty());
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"other\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, "\"other\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[actix_rt::test]
+    async fn test_weak_etag() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(ConditionalRequest::new().weak())
+                .route("/", web::get().to(|| HttpResponse::Ok().body("hello"))),
+        )
+        .await;
+
+        let res = test::call_service(&srv, TestRequest::default().to_request()).await;
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        assert!(etag.to_str().unwrap().starts_with("W/"));
+
+        // weak entity tags never match `If-Match`
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, etag))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[actix_rt::test]
+    async fn test_handler_validators() {
+        let modified = SystemTime::now() - Duration::from_secs(3600);
+
+        let srv = test::init_service(
+            App::new()
+                .wrap(ConditionalRequest::new().max_body_size(0))
+                .route(
+                    "/etag",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .insert_header(header::ETag(EntityTag::weak("v1".to_owned())))
+                            .body("hello")
+                    }),
+                )
+                .route(
+                    "/date",
+                    web::get().to(move || {
+                        HttpResponse::Ok()
+                            .insert_header(header::LastModified(modified.into()))
+                            .body("hello")
+                    }),
+                )
+                .route("/large", web::get().to(|| HttpResponse::Ok().body("hello"))),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/etag")
+            .insert_header((header::IF_NONE_MATCH, "\"v1\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::with_uri("/date")
+            .insert_header(header::IfModifiedSince(SystemTime::now().into()))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert!(res.headers().contains_key(header::LAST_MODIFIED));
+        assert!(!res.headers().contains_key(header::ETAG));
+
+        let since = modified - Duration::from_secs(60);
+        let req = TestRequest::with_uri("/date")
+            .insert_header(header::IfModifiedSince(since.into()))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // bodies over the limit are not buffered
+        let res = test::call_service(&srv, TestRequest::with_uri("/large").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::ETAG));
+        assert_eq!(test::read_body(res).await, "hello");
+    }
+
+    #[actix_rt::test]
+    async fn test_unsafe_methods() {
+        thread_local! {
+            static CALLS: Cell<usize> = Cell::new(0);
+        }
+
+        let modified = SystemTime::now() - Duration::from_secs(3600);
+
+        let srv = test::init_service(
+            App::new()
+                .wrap(ConditionalRequest::new().validator(move |_| {
+                    (
+                        Some(EntityTag::strong("v2".to_owned())),
+                        Some(modified.into()),
+                    )
+                }))
+                .route(
+                    "/",
+                    web::put().to(|| {
+                        CALLS.with(|calls| calls.set(calls.get() + 1));
+                        HttpResponse::NoContent()
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::put()
+            .insert_header((header::IF_MATCH, "\"v1\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(CALLS.with(Cell::get), 0);
+
+        let req = TestRequest::put()
+            .insert_header((header::IF_NONE_MATCH, "*"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(CALLS.with(Cell::get), 0);
+
+        let req = TestRequest::put()
+            .insert_header((header::IF_MATCH, "\"v1\", \"v2\""))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(CALLS.with(Cell::get), 1);
+
+        let res = test::call_service(&srv, TestRequest::put().to_request()).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(CALLS.with(Cell::get), 2);
+
+        // resource was modified after the date known to the client
+        let since = modified - Duration::from_secs(60);
+        let req = TestRequest::put()
+            .insert_header(header::IfUnmodifiedSince(since.into()))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(CALLS.with(Cell::get), 2);
+
+        let req = TestRequest::put()
+            .insert_header(header::IfUnmodifiedSince(SystemTime::now().into()))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(CALLS.with(Cell::get), 3);
+    }
+}
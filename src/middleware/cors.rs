@@ -0,0 +1,900 @@
+//! For middleware documentation, see [`Cors`].
+
+use std::{
+    collections::HashSet, convert::TryFrom, error::Error as StdError, fmt, rc::Rc, str::FromStr,
+};
+
+use actix_http::body::{AnyBody, MessageBody};
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    dev::{RequestHead, Service, Transform},
+    error::{CorsError, Error, HttpError},
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        Method,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    HttpResponse,
+};
+
+type OriginFn = dyn Fn(&HeaderValue, &RequestHead) -> bool;
+
+/// Set of values where every value may be allowed.
+enum AllOrSome<T> {
+    All,
+    Some(HashSet<T>),
+}
+
+impl<T: Eq + std::hash::Hash> AllOrSome<T> {
+    fn contains(&self, item: &T) -> bool {
+        match self {
+            AllOrSome::All => true,
+            AllOrSome::Some(set) => set.contains(item),
+        }
+    }
+
+    fn insert(&mut self, item: T) {
+        match self {
+            AllOrSome::All => {}
+            AllOrSome::Some(set) => {
+                set.insert(item);
+            }
+        }
+    }
+}
+
+/// Middleware for Cross-Origin Resource Sharing (CORS).
+///
+/// Adds `Access-Control-Allow-*` headers to responses of requests from allowed origins and
+/// responds to preflight requests directly, without calling the wrapped service. Preflight
+/// requests are `OPTIONS` requests with `Origin` and `Access-Control-Request-Method` headers;
+/// answering them in the middleware means resources do not need to register `OPTIONS` routes.
+/// Preflight requests that do not pass validation are responded to with `400 Bad Request`
+/// (see [`CorsError`]). Other requests from disallowed origins are passed on to the wrapped
+/// service but receive no CORS headers, so that browsers block access to the response.
+///
+/// Whenever the `Access-Control-Allow-Origin` header depends on the request origin, responses
+/// get a `Vary: Origin` header so that caches do not serve them to other origins.
+///
+/// `Cors::default()` does not allow any origin and has to be configured with one of the
+/// `allowed_origin*` methods or [`allow_any_origin`](Self::allow_any_origin).
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header, middleware::Cors, web, App, HttpResponse};
+///
+/// let cors = Cors::default()
+///     .allowed_origin("https://www.rust-lang.org")
+///     .allowed_origin_fn(|origin, _req_head| origin.as_bytes().ends_with(b".rust-lang.org"))
+///     .allowed_methods(vec!["GET", "POST"])
+///     .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT])
+///     .allowed_header(header::CONTENT_TYPE)
+///     .max_age(3600);
+///
+/// let app = App::new()
+///     .wrap(cors)
+///     .service(web::resource("/index.html").route(web::get().to(HttpResponse::Ok)));
+/// ```
+pub struct Cors {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    allowed_origins: AllOrSome<HeaderValue>,
+    allowed_origin_fns: Vec<Box<OriginFn>>,
+    allowed_methods: AllOrSome<Method>,
+    allowed_headers: AllOrSome<HeaderName>,
+    expose_headers: AllOrSome<HeaderName>,
+    max_age: Option<usize>,
+    supports_credentials: bool,
+    preflight: bool,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        let methods = [
+            Method::GET,
+            Method::HEAD,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+            Method::OPTIONS,
+        ];
+
+        Cors {
+            inner: Rc::new(Inner {
+                allowed_origins: AllOrSome::Some(HashSet::new()),
+                allowed_origin_fns: Vec::new(),
+                allowed_methods: AllOrSome::Some(methods.iter().cloned().collect()),
+                allowed_headers: AllOrSome::Some(HashSet::new()),
+                expose_headers: AllOrSome::Some(HashSet::new()),
+                max_age: None,
+                supports_credentials: false,
+                preflight: true,
+            }),
+        }
+    }
+}
+
+impl Cors {
+    /// Constructs a very permissive set of defaults.
+    ///
+    /// Allows any origin, method and request header and exposes all response headers. Should
+    /// only be used during development.
+    pub fn permissive() -> Self {
+        Cors::default()
+            .allow_any_origin()
+            .allow_any_method()
+            .allow_any_header()
+            .expose_any_header()
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Adds an origin that is allowed to make requests.
+    ///
+    /// Origins are compared exactly, e.g. `https://www.rust-lang.org`. Passing `"*"` allows any
+    /// origin, the same as [`allow_any_origin`](Self::allow_any_origin).
+    ///
+    /// # Panics
+    /// Panics if `origin` is not a valid header value.
+    pub fn allowed_origin(mut self, origin: &str) -> Self {
+        if origin == "*" {
+            return self.allow_any_origin();
+        }
+
+        let origin = HeaderValue::from_str(origin).expect("Can not create origin header value");
+        self.inner_mut().allowed_origins.insert(origin);
+        self
+    }
+
+    /// Adds a predicate that decides whether an origin is allowed to make requests.
+    ///
+    /// The predicate receives the `Origin` request header and the request head. An origin is
+    /// allowed if it was added with [`allowed_origin`](Self::allowed_origin) or any of the
+    /// predicates return true.
+    pub fn allowed_origin_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HeaderValue, &RequestHead) -> bool + 'static,
+    {
+        self.inner_mut().allowed_origin_fns.push(Box::new(f));
+        self
+    }
+
+    /// Allows requests from any origin.
+    ///
+    /// Responses use a wildcard `Access-Control-Allow-Origin: *` header, unless
+    /// [credentials are supported](Self::supports_credentials), in which case the request origin
+    /// is echoed back.
+    pub fn allow_any_origin(mut self) -> Self {
+        self.inner_mut().allowed_origins = AllOrSome::All;
+        self
+    }
+
+    /// Sets methods that are allowed in preflight requests.
+    ///
+    /// Replaces the default set of `GET`, `HEAD`, `POST`, `PUT`, `PATCH`, `DELETE` and `OPTIONS`.
+    ///
+    /// # Panics
+    /// Panics if any of the methods is not a valid method.
+    pub fn allowed_methods<U, M>(mut self, methods: U) -> Self
+    where
+        U: IntoIterator<Item = M>,
+        Method: TryFrom<M>,
+        <Method as TryFrom<M>>::Error: Into<HttpError>,
+    {
+        let methods = methods
+            .into_iter()
+            .map(|method| match Method::try_from(method) {
+                Ok(method) => method,
+                Err(_) => panic!("Can not create method"),
+            })
+            .collect();
+
+        self.inner_mut().allowed_methods = AllOrSome::Some(methods);
+        self
+    }
+
+    /// Allows any method in preflight requests.
+    pub fn allow_any_method(mut self) -> Self {
+        self.inner_mut().allowed_methods = AllOrSome::All;
+        self
+    }
+
+    /// Adds a header that is allowed in preflight requests.
+    ///
+    /// # Panics
+    /// Panics if `header` is not a valid header name.
+    pub fn allowed_header<H>(mut self, header: H) -> Self
+    where
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        let header = header_name(header);
+        self.inner_mut().allowed_headers.insert(header);
+        self
+    }
+
+    /// Adds headers that are allowed in preflight requests.
+    ///
+    /// # Panics
+    /// Panics if any of the headers is not a valid header name.
+    pub fn allowed_headers<U, H>(mut self, headers: U) -> Self
+    where
+        U: IntoIterator<Item = H>,
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        for header in headers {
+            let header = header_name(header);
+            self.inner_mut().allowed_headers.insert(header);
+        }
+        self
+    }
+
+    /// Allows any header in preflight requests.
+    pub fn allow_any_header(mut self) -> Self {
+        self.inner_mut().allowed_headers = AllOrSome::All;
+        self
+    }
+
+    /// Adds response headers that browsers are allowed to expose to scripts.
+    ///
+    /// # Panics
+    /// Panics if any of the headers is not a valid header name.
+    pub fn expose_headers<U, H>(mut self, headers: U) -> Self
+    where
+        U: IntoIterator<Item = H>,
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        for header in headers {
+            let header = header_name(header);
+            self.inner_mut().expose_headers.insert(header);
+        }
+        self
+    }
+
+    /// Allows browsers to expose all response headers to scripts.
+    pub fn expose_any_header(mut self) -> Self {
+        self.inner_mut().expose_headers = AllOrSome::All;
+        self
+    }
+
+    /// Sets how long, in seconds, the results of a preflight request may be cached.
+    ///
+    /// Pass `None` to omit the `Access-Control-Max-Age` header. By default the header is omitted.
+    pub fn max_age(mut self, max_age: impl Into<Option<usize>>) -> Self {
+        self.inner_mut().max_age = max_age.into();
+        self
+    }
+
+    /// Allows requests with credentials, such as cookies or authorization headers.
+    ///
+    /// Adds an `Access-Control-Allow-Credentials: true` header to responses. Since wildcard
+    /// values are not allowed together with credentials, any origin, header and exposed header
+    /// wildcards are replaced with the actual values of the request or response.
+    pub fn supports_credentials(mut self) -> Self {
+        self.inner_mut().supports_credentials = true;
+        self
+    }
+
+    /// Disables automatic handling of preflight requests.
+    ///
+    /// Preflight requests are then passed to the wrapped service like any other request.
+    pub fn disable_preflight(mut self) -> Self {
+        self.inner_mut().preflight = false;
+        self
+    }
+}
+
+fn header_name<H>(header: H) -> HeaderName
+where
+    HeaderName: TryFrom<H>,
+    <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+{
+    match HeaderName::try_from(header) {
+        Ok(header) => header,
+        Err(_) => panic!("Can not create header name"),
+    }
+}
+
+impl fmt::Debug for Cors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cors")
+            .field("max_age", &self.inner.max_age)
+            .field("supports_credentials", &self.inner.supports_credentials)
+            .field("preflight", &self.inner.preflight)
+            .finish()
+    }
+}
+
+impl Inner {
+    fn is_origin_allowed(&self, origin: &HeaderValue, head: &RequestHead) -> bool {
+        self.allowed_origins.contains(origin)
+            || self.allowed_origin_fns.iter().any(|f| f(origin, head))
+    }
+
+    /// Returns true if `Access-Control-Allow-Origin` depends on the request origin.
+    fn varies_by_origin(&self) -> bool {
+        match self.allowed_origins {
+            AllOrSome::All => self.supports_credentials,
+            AllOrSome::Some(_) => true,
+        }
+    }
+
+    fn allow_origin(&self, origin: &HeaderValue) -> HeaderValue {
+        if self.varies_by_origin() {
+            origin.clone()
+        } else {
+            HeaderValue::from_static("*")
+        }
+    }
+
+    fn preflight_response(&self, head: &RequestHead) -> Result<HttpResponse, CorsError> {
+        let origin = head.headers().get(header::ORIGIN).unwrap();
+        if !self.is_origin_allowed(origin, head) {
+            return Err(CorsError::OriginNotAllowed);
+        }
+
+        let method = head
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|method| method.to_str().ok())
+            .and_then(|method| Method::from_str(method).ok())
+            .ok_or(CorsError::BadRequestHeaders)?;
+        if !self.allowed_methods.contains(&method) {
+            return Err(CorsError::MethodNotAllowed);
+        }
+
+        let requested_headers = match head.headers().get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+        {
+            Some(value) => {
+                let value = value.to_str().map_err(|_| CorsError::BadRequestHeaders)?;
+
+                let mut headers = HashSet::new();
+                for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                    let name =
+                        HeaderName::from_str(name).map_err(|_| CorsError::BadRequestHeaders)?;
+                    if !self.allowed_headers.contains(&name) {
+                        return Err(CorsError::HeadersNotAllowed);
+                    }
+                    headers.insert(name);
+                }
+
+                Some(headers)
+            }
+            None => None,
+        };
+
+        let mut res = HttpResponse::Ok();
+        res.insert_header((
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            self.allow_origin(origin),
+        ));
+
+        let methods = match self.allowed_methods {
+            AllOrSome::All => method.to_string(),
+            AllOrSome::Some(ref methods) => join(methods.iter().map(Method::as_str)),
+        };
+        res.insert_header((header::ACCESS_CONTROL_ALLOW_METHODS, methods));
+
+        if let Some(requested_headers) = requested_headers {
+            let headers = match self.allowed_headers {
+                AllOrSome::All => join(requested_headers.iter().map(HeaderName::as_str)),
+                AllOrSome::Some(ref headers) => join(headers.iter().map(HeaderName::as_str)),
+            };
+            res.insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, headers));
+        }
+
+        if let Some(max_age) = self.max_age {
+            res.insert_header((header::ACCESS_CONTROL_MAX_AGE, max_age));
+        }
+
+        if self.supports_credentials {
+            res.insert_header((header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true"));
+        }
+
+        let mut res = res.finish();
+        add_vary(
+            res.headers_mut(),
+            "Origin, Access-Control-Request-Method, Access-Control-Request-Headers",
+        );
+
+        Ok(res)
+    }
+
+    fn add_response_headers(&self, origin: &HeaderValue, headers: &mut HeaderMap) {
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            self.allow_origin(origin),
+        );
+
+        if self.supports_credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+
+        let expose = match self.expose_headers {
+            AllOrSome::All if self.supports_credentials => {
+                let names = headers
+                    .keys()
+                    .map(HeaderName::as_str)
+                    .collect::<HashSet<_>>();
+                join(names.into_iter())
+            }
+            AllOrSome::All => "*".to_owned(),
+            AllOrSome::Some(ref names) => join(names.iter().map(HeaderName::as_str)),
+        };
+
+        if !expose.is_empty() {
+            if let Ok(expose) = HeaderValue::from_str(&expose) {
+                headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, expose);
+            }
+        }
+    }
+}
+
+/// Joins values into a sorted, comma separated list.
+fn join<'a>(values: impl Iterator<Item = &'a str>) -> String {
+    let mut values = values.collect::<Vec<_>>();
+    values.sort_unstable();
+    values.join(", ")
+}
+
+/// Appends `value` to the `Vary` header unless it is already covered.
+fn add_vary(headers: &mut HeaderMap, value: &'static str) {
+    let vary = match headers.get(header::VARY) {
+        Some(vary) => match vary.to_str() {
+            Ok(vary) if vary.trim() == "*" => return,
+            Ok("") => HeaderValue::from_static(value),
+            Ok(vary) => {
+                let existing = vary.split(',').map(str::trim).collect::<Vec<_>>();
+
+                let missing = value
+                    .split(", ")
+                    .filter(|v| !existing.iter().any(|e| e.eq_ignore_ascii_case(v)))
+                    .collect::<Vec<_>>();
+
+                if missing.is_empty() {
+                    return;
+                }
+
+                match HeaderValue::from_str(&format!("{}, {}", vary, missing.join(", "))) {
+                    Ok(vary) => vary,
+                    Err(_) => return,
+                }
+            }
+            Err(_) => return,
+        },
+        None => HeaderValue::from_static(value),
+    };
+
+    headers.insert(header::VARY, vary);
+}
+
+fn is_preflight(head: &RequestHead) -> bool {
+    head.method == Method::OPTIONS
+        && head.headers().contains_key(header::ORIGIN)
+        && head
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Cors
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = CorsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CorsMiddleware {
+            service: Rc::new(service),
+            inner: self.inner.clone(),
+        }))
+    }
+}
+
+pub struct CorsMiddleware<S> {
+    service: Rc<S>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for CorsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let inner = self.inner.clone();
+
+        if inner.preflight && is_preflight(req.head()) {
+            let res = match inner.preflight_response(req.head()) {
+                Ok(res) => req.into_response(res),
+                Err(err) => req.error_response(err),
+            };
+
+            return Box::pin(ready(Ok(res)));
+        }
+
+        let origin = req
+            .headers()
+            .get(header::ORIGIN)
+            .filter(|origin| inner.is_origin_allowed(origin, req.head()))
+            .cloned();
+
+        // kept for responding to errors of the inner service, which must carry CORS headers too
+        let http_req = req.parts_mut().0.detached_copy();
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = match fut.await {
+                Ok(res) => res.map_body(|_, body| AnyBody::from_message(body)),
+                Err(err) => ServiceResponse::new(http_req, HttpResponse::from_error(err)),
+            };
+
+            if let Some(ref origin) = origin {
+                inner.add_response_headers(origin, res.headers_mut());
+            }
+
+            if inner.varies_by_origin() {
+                add_vary(res.headers_mut(), "Origin");
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_service::IntoService;
+    use actix_utils::future::ok;
+
+    use super::*;
+    use crate::{
+        error::ErrorUnauthorized,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    async fn cors_service(
+        cors: Cors,
+    ) -> impl Service<ServiceRequest, Response = ServiceResponse, Error = Error> {
+        let srv = |req: ServiceRequest| {
+            ok(req.into_response(
+                HttpResponse::Ok()
+                    .insert_header(("x-custom", "value"))
+                    .finish(),
+            ))
+        };
+        cors.new_transform(srv.into_service()).await.unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_no_origin() {
+        let srv = cors_service(Cors::default().allowed_origin("https://example.com")).await;
+
+        let req = TestRequest::default().to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Origin");
+    }
+
+    #[actix_rt::test]
+    async fn test_exact_origin() {
+        let srv = cors_service(
+            Cors::default()
+                .allowed_origin("https://example.com")
+                .expose_headers(vec!["x-custom"])
+                .supports_credentials(),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+                .unwrap(),
+            "true"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_EXPOSE_HEADERS)
+                .unwrap(),
+            "x-custom"
+        );
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Origin");
+
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "https://other.com"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[actix_rt::test]
+    async fn test_service_error() {
+        let srv = |_: ServiceRequest| async {
+            Err::<ServiceResponse, _>(ErrorUnauthorized("unauthorized"))
+        };
+        let srv = Cors::default()
+            .allowed_origin("https://example.com")
+            .supports_credentials()
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+                .unwrap(),
+            "true"
+        );
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Origin");
+    }
+
+    #[actix_rt::test]
+    async fn test_origin_fn() {
+        let srv = cors_service(
+            Cors::default()
+                .allowed_origin_fn(|origin, _| origin.as_bytes().ends_with(b".example.com")),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "https://api.example.com"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://api.example.com"
+        );
+
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "https://example.org"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[actix_rt::test]
+    async fn test_wildcard_origin() {
+        let srv = cors_service(Cors::permissive()).await;
+
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "*"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_EXPOSE_HEADERS)
+                .unwrap(),
+            "*"
+        );
+        assert!(!res.headers().contains_key(header::VARY));
+
+        // wildcards are replaced when credentials are supported
+        let srv = cors_service(Cors::permissive().supports_credentials()).await;
+
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://example.com"
+        );
+        let expose = res
+            .headers()
+            .get(header::ACCESS_CONTROL_EXPOSE_HEADERS)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(expose.contains("x-custom"));
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Origin");
+    }
+
+    #[actix_rt::test]
+    async fn test_preflight() {
+        let srv = cors_service(
+            Cors::default()
+                .allowed_origin("https://example.com")
+                .allowed_methods(vec!["GET", "POST"])
+                .allowed_headers(vec![header::AUTHORIZATION, header::CONTENT_TYPE])
+                .max_age(3600),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "Content-Type"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key("x-custom"));
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_METHODS)
+                .unwrap(),
+            "GET, POST"
+        );
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_HEADERS)
+                .unwrap(),
+            "authorization, content-type"
+        );
+        assert_eq!(
+            res.headers().get(header::ACCESS_CONTROL_MAX_AGE).unwrap(),
+            "3600"
+        );
+        assert_eq!(
+            res.headers().get(header::VARY).unwrap(),
+            "Origin, Access-Control-Request-Method, Access-Control-Request-Headers"
+        );
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://other.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "DELETE"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "x-unknown"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_preflight_disabled() {
+        let srv = cors_service(Cors::permissive().disable_preflight()).await;
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+            .to_srv_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.headers().get("x-custom").unwrap(), "value");
+    }
+
+    #[actix_rt::test]
+    async fn test_vary_merge() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+        add_vary(&mut headers, "Origin");
+        assert_eq!(
+            headers.get(header::VARY).unwrap(),
+            "Accept-Encoding, Origin"
+        );
+
+        add_vary(&mut headers, "Origin");
+        assert_eq!(
+            headers.get(header::VARY).unwrap(),
+            "Accept-Encoding, Origin"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_resource_preflight() {
+        let srv = test::init_service(
+            App::new().service(
+                web::resource("/test")
+                    .wrap(Cors::default().allowed_origin("https://example.com"))
+                    .route(web::get().to(HttpResponse::Ok)),
+            ),
+        )
+        .await;
+
+        // preflight is answered even though resource has no OPTIONS route
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/test")
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://example.com"
+        );
+
+        let req = TestRequest::get()
+            .uri("/test")
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://example.com"
+        );
+    }
+}
@@ -21,10 +21,10 @@ use regex::{Regex, RegexSet};
 use time::OffsetDateTime;
 
 use crate::{
-    dev::{BodySize, MessageBody},
-    http::{HeaderName, StatusCode},
+    dev::{BodySize, MessageBody, ResponseHead},
+    http::{HeaderMap, HeaderName, StatusCode},
     service::{ServiceRequest, ServiceResponse},
-    Error, HttpResponse, Result,
+    Error, HttpRequest, HttpResponse, Result,
 };
 
 /// Middleware for logging request and response summaries to the terminal.
@@ -71,6 +71,7 @@ use crate::{
 /// `%{FOO}o` | `response.headers["FOO"]`
 /// `%{FOO}e` | `env_var["FOO"]`
 /// `%{FOO}xi` | [Custom request replacement](Logger::custom_request_replace) labelled "FOO"
+/// `%{FOO}xo` | [Custom response replacement](Logger::custom_response_replace) labelled "FOO"
 ///
 /// # Security
 /// **\*** "Real IP" remote address is calculated using
@@ -156,6 +157,54 @@ impl Logger {
 
         self
     }
+
+    /// Register a function that receives the request and the head of its response and returns a
+    /// String for use in the log line. The label passed as the first argument should match a
+    /// replacement substring in the logger format like `%{label}xo`.
+    ///
+    /// Unlike [`custom_request_replace`](Self::custom_request_replace), the function is called
+    /// after the response is produced, so it can read request extensions set by handlers and
+    /// inner middleware.
+    ///
+    /// It is convention to print "-" to indicate no output instead of an empty string.
+    ///
+    /// # Example
+    /// ```
+    /// use actix_web::middleware::{Logger, TimedOut};
+    ///
+    /// Logger::new("%r %s %{TIMEOUT}xo").custom_response_replace("TIMEOUT", |req, _res| {
+    ///     match req.extensions().get::<TimedOut>() {
+    ///         Some(timed_out) => format!("timed out after {:?}", timed_out.0),
+    ///         None => "-".to_owned(),
+    ///     }
+    /// });
+    /// ```
+    pub fn custom_response_replace(
+        mut self,
+        label: &str,
+        f: impl Fn(&HttpRequest, &ResponseHead) -> String + 'static,
+    ) -> Self {
+        let inner = Rc::get_mut(&mut self.0).unwrap();
+
+        let ft = inner.format.0.iter_mut().find(
+            |ft| matches!(ft, FormatText::CustomResponse(unit_label, _) if label == unit_label),
+        );
+
+        if let Some(FormatText::CustomResponse(_, response_fn)) = ft {
+            // replace into None or previously registered fn using same label
+            response_fn.replace(CustomResponseFn {
+                inner_fn: Rc::new(f),
+            });
+        } else {
+            // non-printed response replacement function diagnostic
+            debug!(
+                "Attempted to register custom response logging function for nonexistent label: {}",
+                label
+            );
+        }
+
+        self
+    }
 }
 
 impl Default for Logger {
@@ -193,6 +242,14 @@ where
                     label
                 );
             }
+
+            // missing response replacement function diagnostic
+            if let FormatText::CustomResponse(label, None) = unit {
+                warn!(
+                    "No custom response replacement function was registered for label \"{}\".",
+                    label
+                );
+            }
         }
 
         ok(LoggerMiddleware {
@@ -283,6 +340,7 @@ where
         if let Some(ref mut format) = this.format {
             for unit in &mut format.0 {
                 unit.render_response(res.response());
+                unit.render_custom_response(res.request(), res.response().head());
             }
         }
 
@@ -351,6 +409,10 @@ where
             None => Poll::Ready(None),
         }
     }
+
+    fn take_trailers(self: Pin<&mut Self>) -> Option<HeaderMap> {
+        self.project().body.take_trailers()
+    }
 }
 
 /// A formatting style for the `Logger` consisting of multiple concatenated `FormatText` items.
@@ -370,7 +432,7 @@ impl Format {
     /// Returns `None` if the format string syntax is incorrect.
     pub fn new(s: &str) -> Format {
         log::trace!("Access log format: {}", s);
-        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([aioe]|xi)|[%atPrUsbTD]?)").unwrap();
+        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([aioe]|xi|xo)|[%atPrUsbTD]?)").unwrap();
 
         let mut idx = 0;
         let mut results = Vec::new();
@@ -399,6 +461,7 @@ impl Format {
                     }
                     "e" => FormatText::EnvironHeader(key.as_str().to_owned()),
                     "xi" => FormatText::CustomRequest(key.as_str().to_owned(), None),
+                    "xo" => FormatText::CustomResponse(key.as_str().to_owned(), None),
                     _ => unreachable!(),
                 })
             } else {
@@ -446,6 +509,7 @@ enum FormatText {
     ResponseHeader(HeaderName),
     EnvironHeader(String),
     CustomRequest(String, Option<CustomRequestFn>),
+    CustomResponse(String, Option<CustomResponseFn>),
 }
 
 #[derive(Clone)]
@@ -465,6 +529,23 @@ impl fmt::Debug for CustomRequestFn {
     }
 }
 
+#[derive(Clone)]
+struct CustomResponseFn {
+    inner_fn: Rc<dyn Fn(&HttpRequest, &ResponseHead) -> String>,
+}
+
+impl CustomResponseFn {
+    fn call(&self, req: &HttpRequest, head: &ResponseHead) -> String {
+        (self.inner_fn)(req, head)
+    }
+}
+
+impl fmt::Debug for CustomResponseFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("custom_response_fn")
+    }
+}
+
 impl FormatText {
     fn render(
         &self,
@@ -518,6 +599,15 @@ impl FormatText {
         }
     }
 
+    fn render_custom_response(&mut self, req: &HttpRequest, head: &ResponseHead) {
+        if let FormatText::CustomResponse(_, response_fn) = self {
+            *self = match response_fn {
+                Some(f) => FormatText::Str(f.call(req, head)),
+                None => FormatText::Str("-".to_owned()),
+            };
+        }
+    }
+
     fn render_request(&mut self, now: OffsetDateTime, req: &ServiceRequest) {
         match self {
             FormatText::RequestLine => {
@@ -830,6 +920,35 @@ mod tests {
         assert_eq!(log_output, "custom_log");
     }
 
+    #[actix_rt::test]
+    async fn test_custom_response_closure_log() {
+        let mut logger = Logger::new("test %{CUSTOM}xo").custom_response_replace(
+            "CUSTOM",
+            |req: &HttpRequest, head: &ResponseHead| -> String {
+                format!("{} {}", req.path(), head.status.as_u16())
+            },
+        );
+        let mut unit = Rc::get_mut(&mut logger.0).unwrap().format.0[1].clone();
+
+        let label = match &unit {
+            FormatText::CustomResponse(label, _) => label,
+            ft => panic!("expected CustomResponse, found {:?}", ft),
+        };
+
+        assert_eq!(label, "CUSTOM");
+
+        let req = TestRequest::with_uri("/test").to_http_request();
+        let res = HttpResponse::NotFound().finish();
+        let now = OffsetDateTime::now_utc();
+
+        unit.render_custom_response(&req, res.head());
+
+        let render = |fmt: &mut fmt::Formatter<'_>| unit.render(fmt, 1024, now);
+
+        let log_output = FormatDisplay(&render).to_string();
+        assert_eq!(log_output, "/test 404");
+    }
+
     #[actix_rt::test]
     async fn test_closure_logger_in_middleware() {
         let captured = "custom log replacement";
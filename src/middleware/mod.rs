@@ -1,18 +1,32 @@
 //! Commonly used middleware.
 
+mod authentication;
+mod cache;
 mod compat;
 mod condition;
+mod conditional;
+mod cors;
 mod default_headers;
 mod err_handlers;
 mod logger;
 mod normalize;
+mod rate_limit;
+mod timeout;
 
+pub use self::authentication::HttpAuthentication;
+pub use self::cache::{ResponseCache, ResponseCacheStore};
 pub use self::compat::Compat;
 pub use self::condition::Condition;
+pub use self::conditional::ConditionalRequest;
+pub use self::cors::Cors;
 pub use self::default_headers::DefaultHeaders;
 pub use self::err_handlers::{ErrorHandlerResponse, ErrorHandlers};
 pub use self::logger::Logger;
 pub use self::normalize::{NormalizePath, TrailingSlash};
+pub use self::rate_limit::{
+    MemoryRateLimitStore, Quota, RateLimit, RateLimitAlgorithm, RateLimitStatus, RateLimitStore,
+};
+pub use self::timeout::{TimedOut, Timeout};
 
 #[cfg(feature = "__compress")]
 mod compress;
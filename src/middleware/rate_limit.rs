@@ -0,0 +1,703 @@
+//! For middleware documentation, see [`RateLimit`].
+
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt,
+    net::SocketAddr,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    dev::{Service, Transform},
+    error::{Error, RateLimitError},
+    http::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
+    service::{ServiceRequest, ServiceResponse},
+};
+
+/// Algorithm used to enforce a [`Quota`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitAlgorithm {
+    /// Each client has a bucket holding up to `limit` tokens that refills continuously over
+    /// `period`. Every request takes a token; requests are rejected when the bucket is empty.
+    ///
+    /// Allows bursts of up to `limit` requests.
+    TokenBucket,
+
+    /// Requests are counted in fixed windows of length `period`; the count of the previous window
+    /// is weighted by how much of it overlaps the sliding window ending now.
+    ///
+    /// Smooths out bursts at window boundaries.
+    SlidingWindow,
+}
+
+/// Number of requests allowed per time period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    limit: u32,
+    period: Duration,
+    algorithm: RateLimitAlgorithm,
+}
+
+impl Quota {
+    /// Constructs a quota of `limit` requests per `period`, enforced by a token bucket.
+    ///
+    /// # Panics
+    /// Panics if `limit` or `period` is zero.
+    pub fn new(limit: u32, period: Duration) -> Self {
+        assert!(limit > 0, "Quota limit must be greater than zero");
+        assert!(
+            period > Duration::from_secs(0),
+            "Quota period must be non-zero"
+        );
+
+        Quota {
+            limit,
+            period,
+            algorithm: RateLimitAlgorithm::TokenBucket,
+        }
+    }
+
+    /// Constructs a quota of `limit` requests per second.
+    pub fn per_second(limit: u32) -> Self {
+        Quota::new(limit, Duration::from_secs(1))
+    }
+
+    /// Constructs a quota of `limit` requests per minute.
+    pub fn per_minute(limit: u32) -> Self {
+        Quota::new(limit, Duration::from_secs(60))
+    }
+
+    /// Constructs a quota of `limit` requests per hour.
+    pub fn per_hour(limit: u32) -> Self {
+        Quota::new(limit, Duration::from_secs(60 * 60))
+    }
+
+    /// Sets algorithm used to enforce the quota.
+    pub fn with_algorithm(mut self, algorithm: RateLimitAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Returns number of requests allowed per period.
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// Returns length of the period.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns algorithm used to enforce the quota.
+    pub fn algorithm(&self) -> RateLimitAlgorithm {
+        self.algorithm
+    }
+}
+
+/// Outcome of checking a request against a [`Quota`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Whether the request is allowed.
+    pub allowed: bool,
+
+    /// Number of requests allowed per period.
+    pub limit: u32,
+
+    /// Number of requests remaining before requests are rejected.
+    pub remaining: u32,
+
+    /// Time until the quota is fully restored.
+    pub reset: Duration,
+
+    /// Time until the next request will be allowed, if this one was rejected.
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitStatus {
+    /// Inserts `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` headers, as well as
+    /// `Retry-After` if the request was rejected.
+    pub(crate) fn insert_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(
+            HeaderName::from_static("ratelimit-limit"),
+            HeaderValue::from(self.limit),
+        );
+        headers.insert(
+            HeaderName::from_static("ratelimit-remaining"),
+            HeaderValue::from(self.remaining),
+        );
+        headers.insert(
+            HeaderName::from_static("ratelimit-reset"),
+            HeaderValue::from(ceil_secs(self.reset)),
+        );
+
+        if let Some(retry_after) = self.retry_after {
+            headers.insert(RETRY_AFTER, HeaderValue::from(ceil_secs(retry_after)));
+        }
+    }
+}
+
+/// Rounds duration up to whole seconds, as used by rate limit headers.
+fn ceil_secs(dur: Duration) -> u64 {
+    if dur.subsec_nanos() > 0 {
+        dur.as_secs() + 1
+    } else {
+        dur.as_secs()
+    }
+}
+
+/// Backend that tracks request counts of rate limited clients.
+///
+/// Stores implement the quota's [algorithm](RateLimitAlgorithm) themselves so that checking and
+/// counting a request can be done atomically.
+pub trait RateLimitStore {
+    /// Counts a request by the client identified by `key` and checks it against `quota`.
+    ///
+    /// Rejected requests should not be counted.
+    fn check(
+        &self,
+        key: &str,
+        quota: &Quota,
+    ) -> LocalBoxFuture<'static, Result<RateLimitStatus, Error>>;
+}
+
+/// Rate limit store that keeps request counts in server memory.
+///
+/// Clones of the store share the same counts, so a store created outside of the
+/// [`HttpServer`](crate::HttpServer) factory closure applies to all workers. Counts are not shared
+/// between server instances. Idle clients are forgotten periodically.
+#[derive(Debug, Clone)]
+pub struct MemoryRateLimitStore {
+    inner: Arc<Mutex<MemoryInner>>,
+}
+
+#[derive(Debug)]
+struct MemoryInner {
+    entries: HashMap<String, Entry>,
+    last_prune: Instant,
+}
+
+#[derive(Debug)]
+struct Entry {
+    state: EntryState,
+
+    /// Time after which the entry is equivalent to a fresh one.
+    expires: Instant,
+}
+
+#[derive(Debug)]
+enum EntryState {
+    TokenBucket {
+        tokens: f64,
+        updated: Instant,
+    },
+
+    SlidingWindow {
+        start: Instant,
+        current: u32,
+        previous: u32,
+    },
+}
+
+impl MemoryRateLimitStore {
+    /// Interval at which expired entries are removed.
+    const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Constructs a new, empty memory rate limit store.
+    pub fn new() -> Self {
+        MemoryRateLimitStore {
+            inner: Arc::new(Mutex::new(MemoryInner {
+                entries: HashMap::new(),
+                last_prune: Instant::now(),
+            })),
+        }
+    }
+
+    fn check_at(&self, key: &str, quota: &Quota, now: Instant) -> RateLimitStatus {
+        let mut inner = self.inner.lock().unwrap();
+
+        if now.saturating_duration_since(inner.last_prune) >= Self::PRUNE_INTERVAL {
+            inner.entries.retain(|_, entry| entry.expires > now);
+            inner.last_prune = now;
+        }
+
+        let entry = inner
+            .entries
+            .entry(key.to_owned())
+            .or_insert_with(|| Entry {
+                state: EntryState::new(quota, now),
+                expires: now,
+            });
+
+        // reset state if the quota's algorithm changed
+        if !entry.state.matches(quota.algorithm) {
+            entry.state = EntryState::new(quota, now);
+        }
+
+        let (status, expires) = entry.state.check(quota, now);
+        entry.expires = expires;
+        status
+    }
+}
+
+impl Default for MemoryRateLimitStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    fn check(
+        &self,
+        key: &str,
+        quota: &Quota,
+    ) -> LocalBoxFuture<'static, Result<RateLimitStatus, Error>> {
+        let status = self.check_at(key, quota, Instant::now());
+        Box::pin(ready(Ok(status)))
+    }
+}
+
+impl EntryState {
+    fn new(quota: &Quota, now: Instant) -> Self {
+        match quota.algorithm {
+            RateLimitAlgorithm::TokenBucket => EntryState::TokenBucket {
+                tokens: f64::from(quota.limit),
+                updated: now,
+            },
+            RateLimitAlgorithm::SlidingWindow => EntryState::SlidingWindow {
+                start: now,
+                current: 0,
+                previous: 0,
+            },
+        }
+    }
+
+    fn matches(&self, algorithm: RateLimitAlgorithm) -> bool {
+        matches!(
+            (self, algorithm),
+            (
+                EntryState::TokenBucket { .. },
+                RateLimitAlgorithm::TokenBucket
+            ) | (
+                EntryState::SlidingWindow { .. },
+                RateLimitAlgorithm::SlidingWindow
+            )
+        )
+    }
+
+    /// Counts a request and returns its status along with the time the state expires.
+    fn check(&mut self, quota: &Quota, now: Instant) -> (RateLimitStatus, Instant) {
+        let limit = f64::from(quota.limit);
+        let period = quota.period.as_secs_f64();
+
+        match self {
+            EntryState::TokenBucket { tokens, updated } => {
+                let elapsed = now.saturating_duration_since(*updated).as_secs_f64();
+                *tokens = (*tokens + elapsed * limit / period).min(limit);
+                *updated = now;
+
+                let allowed = *tokens >= 1.0;
+                let retry_after = if allowed {
+                    *tokens -= 1.0;
+                    None
+                } else {
+                    Some(Duration::from_secs_f64((1.0 - *tokens) * period / limit))
+                };
+
+                let reset = Duration::from_secs_f64((limit - *tokens) * period / limit);
+
+                let status = RateLimitStatus {
+                    allowed,
+                    limit: quota.limit,
+                    remaining: *tokens as u32,
+                    reset,
+                    retry_after,
+                };
+
+                (status, now + reset)
+            }
+
+            EntryState::SlidingWindow {
+                start,
+                current,
+                previous,
+            } => {
+                // advance fixed window, forgetting counts older than one period
+                let windows = now.saturating_duration_since(*start).as_secs_f64() / period;
+                if windows >= 2.0 {
+                    *previous = 0;
+                    *current = 0;
+                    *start = now;
+                } else if windows >= 1.0 {
+                    *previous = *current;
+                    *current = 0;
+                    *start += quota.period;
+                }
+
+                let elapsed = now.saturating_duration_since(*start);
+                let weight = 1.0 - elapsed.as_secs_f64() / period;
+                let estimate = f64::from(*previous) * weight + f64::from(*current);
+
+                let allowed = estimate + 1.0 <= limit;
+                let retry_after = if allowed {
+                    *current += 1;
+                    None
+                } else {
+                    // time until enough of the previous window has slid out, if it is enough
+                    let spare = limit - 1.0 - f64::from(*current);
+                    let until_window_end = quota.period - elapsed;
+
+                    if *previous > 0 && spare >= 0.0 {
+                        let target = 1.0 - spare / f64::from(*previous);
+                        Some(Duration::from_secs_f64(
+                            (target * period - elapsed.as_secs_f64()).max(0.0),
+                        ))
+                    } else {
+                        Some(until_window_end)
+                    }
+                };
+
+                let estimate = f64::from(*previous) * weight + f64::from(*current);
+                let remaining = (limit - estimate.ceil()).max(0.0) as u32;
+
+                // all counts have slid out one period after the current window ends
+                let expires = *start + quota.period * 2;
+
+                let status = RateLimitStatus {
+                    allowed,
+                    limit: quota.limit,
+                    remaining,
+                    reset: expires - now,
+                    retry_after,
+                };
+
+                (status, expires)
+            }
+        }
+    }
+}
+
+/// Middleware for limiting the rate of requests by each client.
+///
+/// Clients are identified by their real IP address, as reported by
+/// [`ConnectionInfo::realip_remote_addr`](crate::dev::ConnectionInfo::realip_remote_addr), by
+/// default. Use [`key_header`](Self::key_header) or [`key_fn`](Self::key_fn) to identify clients
+/// differently; requests for which no key can be determined are not limited.
+///
+/// Responses to limited requests include `RateLimit-Limit`, `RateLimit-Remaining` and
+/// `RateLimit-Reset` headers. Requests exceeding the quota are rejected with a
+/// [`RateLimitError`], which responds with `429 Too Many Requests` and a `Retry-After` header.
+///
+/// Counts are kept in a [`RateLimitStore`]. The store should be created outside of the
+/// `HttpServer` factory closure so that all workers share it.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{MemoryRateLimitStore, Quota, RateLimit, RateLimitAlgorithm},
+///     App,
+/// };
+///
+/// let store = MemoryRateLimitStore::new();
+///
+/// let app = App::new().wrap(
+///     RateLimit::new(
+///         store,
+///         Quota::per_minute(60).with_algorithm(RateLimitAlgorithm::SlidingWindow),
+///     )
+///     .key_header("x-api-key"),
+/// );
+/// ```
+pub struct RateLimit<Store> {
+    inner: Rc<Inner<Store>>,
+}
+
+struct Inner<Store> {
+    store: Store,
+    quota: Quota,
+    key: Box<dyn Fn(&ServiceRequest) -> Option<String>>,
+}
+
+impl<Store: RateLimitStore> RateLimit<Store> {
+    /// Constructs rate limiting middleware enforcing `quota` for each client.
+    pub fn new(store: Store, quota: Quota) -> Self {
+        RateLimit {
+            inner: Rc::new(Inner {
+                store,
+                quota,
+                key: Box::new(realip_key),
+            }),
+        }
+    }
+
+    /// Identifies clients by the value of request header `name`.
+    pub fn key_header<K>(self, name: K) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: fmt::Debug,
+    {
+        let name = HeaderName::try_from(name).expect("Invalid header name");
+
+        self.key_fn(move |req| {
+            req.headers()
+                .get(&name)
+                .and_then(|value| value.to_str().ok())
+                .map(ToOwned::to_owned)
+        })
+    }
+
+    /// Identifies clients by the key returned from `f`.
+    ///
+    /// Requests for which `f` returns `None` are not limited.
+    pub fn key_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Option<String> + 'static,
+    {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .key = Box::new(f);
+        self
+    }
+}
+
+impl<Store> fmt::Debug for RateLimit<Store> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("quota", &self.inner.quota)
+            .finish()
+    }
+}
+
+/// Returns IP address part of the real remote address.
+fn realip_key(req: &ServiceRequest) -> Option<String> {
+    let conn_info = req.connection_info();
+    let addr = conn_info.realip_remote_addr()?;
+
+    match addr.parse::<SocketAddr>() {
+        Ok(addr) => Some(addr.ip().to_string()),
+        Err(_) => Some(addr.to_owned()),
+    }
+}
+
+impl<S, B, Store> Transform<S, ServiceRequest> for RateLimit<Store>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    Store: RateLimitStore + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S, Store>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S, Store> {
+    service: Rc<S>,
+    inner: Rc<Inner<Store>>,
+}
+
+impl<S, B, Store> Service<ServiceRequest> for RateLimitMiddleware<S, Store>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    Store: RateLimitStore + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let inner = Rc::clone(&self.inner);
+
+        Box::pin(async move {
+            let key = match (inner.key)(&req) {
+                Some(key) => key,
+                None => return service.call(req).await,
+            };
+
+            let status = inner.store.check(&key, &inner.quota).await?;
+
+            if !status.allowed {
+                return Err(RateLimitError::new(status).into());
+            }
+
+            let mut res = service.call(req).await?;
+            status.insert_headers(res.headers_mut());
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[test]
+    fn test_token_bucket() {
+        let store = MemoryRateLimitStore::new();
+        let quota = Quota::new(2, Duration::from_secs(10));
+        let now = Instant::now();
+
+        let status = store.check_at("a", &quota, now);
+        assert!(status.allowed);
+        assert_eq!(status.remaining, 1);
+        assert_eq!(status.reset, Duration::from_secs(5));
+
+        let status = store.check_at("a", &quota, now);
+        assert!(status.allowed);
+        assert_eq!(status.remaining, 0);
+
+        let status = store.check_at("a", &quota, now);
+        assert!(!status.allowed);
+        assert_eq!(status.retry_after, Some(Duration::from_secs(5)));
+        assert_eq!(status.reset, Duration::from_secs(10));
+
+        // other keys are counted separately
+        assert!(store.check_at("b", &quota, now).allowed);
+
+        // one token is refilled every 5 seconds
+        let status = store.check_at("a", &quota, now + Duration::from_secs(5));
+        assert!(status.allowed);
+        assert!(
+            !store
+                .check_at("a", &quota, now + Duration::from_secs(5))
+                .allowed
+        );
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let store = MemoryRateLimitStore::new();
+        let quota = Quota::new(4, Duration::from_secs(10))
+            .with_algorithm(RateLimitAlgorithm::SlidingWindow);
+        let now = Instant::now();
+
+        for remaining in (0..4).rev() {
+            let status = store.check_at("a", &quota, now);
+            assert!(status.allowed);
+            assert_eq!(status.remaining, remaining);
+        }
+
+        let status = store.check_at("a", &quota, now + Duration::from_secs(5));
+        assert!(!status.allowed);
+        assert_eq!(status.retry_after, Some(Duration::from_secs(5)));
+
+        // at the start of the next window, the previous count still weighs fully
+        let later = now + Duration::from_secs(10);
+        assert!(!store.check_at("a", &quota, later).allowed);
+
+        // after a quarter of the window, one request fits
+        let later = now + Duration::from_secs(13);
+        let status = store.check_at("a", &quota, later);
+        assert!(status.allowed);
+        assert!(!store.check_at("a", &quota, later).allowed);
+
+        // counts are forgotten after two windows
+        let later = now + Duration::from_secs(30);
+        let status = store.check_at("a", &quota, later);
+        assert!(status.allowed);
+        assert_eq!(status.remaining, 3);
+    }
+
+    #[test]
+    fn test_headers() {
+        let status = RateLimitStatus {
+            allowed: false,
+            limit: 10,
+            remaining: 0,
+            reset: Duration::from_millis(2500),
+            retry_after: Some(Duration::from_millis(200)),
+        };
+
+        let mut headers = HeaderMap::new();
+        status.insert_headers(&mut headers);
+        assert_eq!(headers.get("ratelimit-limit").unwrap(), "10");
+        assert_eq!(headers.get("ratelimit-remaining").unwrap(), "0");
+        assert_eq!(headers.get("ratelimit-reset").unwrap(), "3");
+        assert_eq!(headers.get(RETRY_AFTER).unwrap(), "1");
+    }
+
+    #[actix_rt::test]
+    async fn test_rate_limit() {
+        let store = MemoryRateLimitStore::new();
+        let srv = test::init_service(
+            App::new()
+                .wrap(RateLimit::new(store, Quota::per_minute(1)))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let addr = "127.0.0.1:8080".parse().unwrap();
+        let req = TestRequest::default().peer_addr(addr).to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("ratelimit-limit").unwrap(), "1");
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "0");
+
+        // same IP on another port is the same client
+        let addr = "127.0.0.1:8081".parse().unwrap();
+        let req = TestRequest::default().peer_addr(addr).to_request();
+        let res = srv.call(req).await.unwrap_err().error_response();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get(RETRY_AFTER).unwrap(), "60");
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "0");
+
+        let addr = "127.0.0.2:8080".parse().unwrap();
+        let req = TestRequest::default().peer_addr(addr).to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_rate_limit_key() {
+        let store = MemoryRateLimitStore::new();
+        let srv = test::init_service(
+            App::new()
+                .wrap(RateLimit::new(store, Quota::per_minute(1)).key_header("x-api-key"))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header(("x-api-key", "a"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::default()
+            .insert_header(("x-api-key", "a"))
+            .to_request();
+        let err = srv.call(req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        // requests without key are not limited
+        for _ in 0..2 {
+            let req = TestRequest::default().to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert!(res.headers().get("ratelimit-limit").is_none());
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! For middleware documentation, see [`Timeout`].
+
+use std::{error::Error as StdError, fmt, rc::Rc, time::Duration};
+
+use actix_http::body::{AnyBody, MessageBody};
+use actix_rt::time::timeout;
+use actix_utils::future::{ready, Ready};
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    dev::{Service, Transform},
+    error::{Error, ErrorGatewayTimeout},
+    service::{ServiceRequest, ServiceResponse},
+    HttpRequest, HttpResponse,
+};
+
+/// Marker inserted into request extensions when a request is cancelled by [`Timeout`].
+///
+/// Contains the duration after which the request was cancelled. It can be read by outer middleware
+/// such as [`Logger`](super::Logger) using
+/// [`custom_response_replace`](super::Logger::custom_response_replace).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut(pub Duration);
+
+/// Middleware for limiting the time taken to handle a request.
+///
+/// If the wrapped service does not produce a response within the timeout, its future is dropped,
+/// cancelling any work it was awaiting, and an error response is returned instead. By default,
+/// this is a `504 Gateway Timeout` response; use [`error_handler`](Self::error_handler) to return a
+/// different error, such as `503 Service Unavailable`.
+///
+/// Cancelled requests are marked with [`TimedOut`] in their extensions. Since the original
+/// request is dropped along with the inner service's future, error responses refer to a copy of
+/// the request head; extensions set before the timeout are lost.
+///
+/// Only the time until the response head is produced is limited; streaming the response body is
+/// not. Can be used on `App`, `Scope` and `Resource`.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use actix_web::{error, middleware::Timeout, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(Timeout::new(Duration::from_secs(30)))
+///     .service(
+///         web::resource("/report")
+///             .wrap(
+///                 Timeout::new(Duration::from_secs(5))
+///                     .error_handler(|_req| error::ErrorServiceUnavailable("Try again later")),
+///             )
+///             .to(HttpResponse::Ok),
+///     );
+/// ```
+pub struct Timeout {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    timeout: Duration,
+    err_handler: Box<dyn Fn(&HttpRequest) -> Error>,
+}
+
+impl Timeout {
+    /// Constructs timeout middleware that cancels requests after `timeout`.
+    pub fn new(timeout: Duration) -> Self {
+        Timeout {
+            inner: Rc::new(Inner {
+                timeout,
+                err_handler: Box::new(|_| ErrorGatewayTimeout("Request timed out")),
+            }),
+        }
+    }
+
+    /// Sets function that produces the error returned for cancelled requests.
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HttpRequest) -> Error + 'static,
+    {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .err_handler = Box::new(f);
+        self
+    }
+}
+
+impl fmt::Debug for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timeout")
+            .field("timeout", &self.inner.timeout)
+            .finish()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Timeout
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = TimeoutMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TimeoutMiddleware {
+            service,
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct TimeoutMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for TimeoutMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+    B::Error: Into<Box<dyn StdError + 'static>>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let inner = Rc::clone(&self.inner);
+        // the request is moved into the inner service, which may need exclusive access to it
+        let http_req = req.parts_mut().0.detached_copy();
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            match timeout(inner.timeout, fut).await {
+                Ok(res) => Ok(res?.map_body(|_, body| AnyBody::from_message(body))),
+
+                Err(_) => {
+                    log::debug!(
+                        "Request to {} timed out after {:?}",
+                        http_req.path(),
+                        inner.timeout
+                    );
+
+                    http_req.extensions_mut().insert(TimedOut(inner.timeout));
+
+                    let res = HttpResponse::from_error((inner.err_handler)(&http_req));
+                    Ok(ServiceResponse::new(http_req, res))
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_rt::time::sleep;
+
+    use super::*;
+    use crate::{
+        error::ErrorServiceUnavailable,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    async fn slow() -> HttpResponse {
+        sleep(Duration::from_millis(200)).await;
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_rt::test]
+    async fn test_timeout() {
+        let srv = test::init_service(
+            App::new()
+                .wrap(Timeout::new(Duration::from_millis(50)))
+                .route("/fast", web::get().to(HttpResponse::Ok))
+                .route("/slow", web::get().to(slow)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/fast").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.request().extensions().get::<TimedOut>().is_none());
+
+        let req = TestRequest::with_uri("/slow").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(
+            res.request().extensions().get::<TimedOut>(),
+            Some(&TimedOut(Duration::from_millis(50)))
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_timeout_resource() {
+        let srv = test::init_service(
+            App::new()
+                .service(
+                    web::scope("/scope")
+                        .wrap(Timeout::new(Duration::from_millis(50)))
+                        .route("/slow", web::get().to(slow)),
+                )
+                .service(
+                    web::resource("/slow")
+                        .wrap(
+                            Timeout::new(Duration::from_millis(50))
+                                .error_handler(|_| ErrorServiceUnavailable("busy")),
+                        )
+                        .to(slow),
+                )
+                .route("/unlimited", web::get().to(slow)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/scope/slow").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        let req = TestRequest::with_uri("/slow").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = test::read_body(res).await;
+        assert_eq!(body, "busy");
+
+        let req = TestRequest::with_uri("/unlimited").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_timeout_logged() {
+        use std::{cell::RefCell, rc::Rc};
+
+        use crate::middleware::Logger;
+
+        let logged = Rc::new(RefCell::new(Vec::new()));
+        let logged2 = Rc::clone(&logged);
+
+        let logger =
+            Logger::new("%{TIMEOUT}xo").custom_response_replace("TIMEOUT", move |req, _| {
+                let timed_out = req.extensions().get::<TimedOut>().is_some();
+                logged2
+                    .borrow_mut()
+                    .push((req.path().to_owned(), timed_out));
+                "-".to_owned()
+            });
+
+        let srv = test::init_service(
+            App::new()
+                // logger must wrap the timeout middleware to see its responses
+                .wrap(Timeout::new(Duration::from_millis(50)))
+                .wrap(logger)
+                .route("/fast", web::get().to(HttpResponse::Ok))
+                .route("/slow", web::get().to(slow)),
+        )
+        .await;
+
+        for path in &["/fast", "/slow"] {
+            let req = TestRequest::with_uri(path).to_request();
+            test::call_service(&srv, req).await;
+        }
+
+        assert_eq!(
+            *logged.borrow(),
+            vec![("/fast".to_owned(), false), ("/slow".to_owned(), true)]
+        );
+    }
+}
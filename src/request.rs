@@ -76,6 +76,28 @@ impl HttpRequest {
         &mut Rc::get_mut(&mut self.inner).unwrap().head
     }
 
+    /// Constructs a request with a copy of this request's head, match info and app data.
+    ///
+    /// Unlike a clone, the copy does not share state with this request, so holding it does not
+    /// prevent this request from being mutated. Request extensions are not copied.
+    pub(crate) fn detached_copy(&self) -> HttpRequest {
+        let mut head = Message::<RequestHead>::new();
+        head.uri = self.inner.head.uri.clone();
+        head.method = self.inner.head.method.clone();
+        head.version = self.inner.head.version;
+        head.headers = self.inner.head.headers.clone();
+        head.peer_addr = self.inner.head.peer_addr;
+
+        HttpRequest {
+            inner: Rc::new(HttpRequestInner {
+                head,
+                path: self.inner.path.clone(),
+                app_data: self.inner.app_data.clone(),
+                app_state: Rc::clone(&self.inner.app_state),
+            }),
+        }
+    }
+
     /// Request's uri.
     #[inline]
     pub fn uri(&self) -> &Uri {
@@ -2,14 +2,22 @@ use std::{
     any::Any,
     cmp,
     error::Error as StdError,
-    fmt, io,
+    fmt,
+    future::Future,
+    io,
     marker::PhantomData,
     net,
+    pin::Pin,
     sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
 };
 
-use actix_http::{body::MessageBody, Extensions, HttpService, KeepAlive, Request, Response};
-use actix_server::{Server, ServerBuilder};
+use actix_http::{
+    body::MessageBody, Extensions, H2Config, HttpService, KeepAlive, Request, RequestLimits,
+    Response, ShutdownSignal,
+};
+use actix_server::ServerBuilder;
 use actix_service::{
     map_config, IntoServiceFactory, Service, ServiceFactory, ServiceFactoryExt as _,
 };
@@ -19,7 +27,7 @@ use actix_tls::accept::openssl::{AlpnError, SslAcceptor, SslAcceptorBuilder};
 #[cfg(feature = "rustls")]
 use actix_tls::accept::rustls::ServerConfig as RustlsServerConfig;
 
-use crate::{config::AppConfig, Error};
+use crate::{config::AppConfig, info::TrustedProxies, Error};
 
 struct Socket {
     scheme: &'static str,
@@ -31,6 +39,10 @@ struct Config {
     keep_alive: KeepAlive,
     client_timeout: u64,
     client_shutdown: u64,
+    limits: RequestLimits,
+    h2: H2Config,
+    shutdown: ShutdownSignal,
+    trusted_proxies: TrustedProxies,
 }
 
 /// An HTTP Server.
@@ -65,6 +77,7 @@ where
     backlog: u32,
     sockets: Vec<Socket>,
     builder: ServerBuilder,
+    signals: bool,
     on_connect_fn: Option<Arc<dyn Fn(&dyn Any, &mut Extensions) + Send + Sync>>,
     _phantom: PhantomData<(S, B)>,
 }
@@ -94,10 +107,15 @@ where
                 keep_alive: KeepAlive::Timeout(5),
                 client_timeout: 5000,
                 client_shutdown: 5000,
+                limits: RequestLimits::default(),
+                h2: H2Config::default(),
+                shutdown: ShutdownSignal::new(),
+                trusted_proxies: TrustedProxies::default(),
             })),
             backlog: 1024,
             sockets: Vec::new(),
             builder: ServerBuilder::default(),
+            signals: true,
             on_connect_fn: None,
             _phantom: PhantomData,
         }
@@ -123,6 +141,7 @@ where
             backlog: self.backlog,
             sockets: self.sockets,
             builder: self.builder,
+            signals: self.signals,
             on_connect_fn: Some(Arc::new(f)),
             _phantom: PhantomData,
         }
@@ -221,6 +240,188 @@ where
         self
     }
 
+    /// Set maximum number of headers allowed in a request head.
+    ///
+    /// Requests with more headers are responded to with `431 Request Header Fields Too Large`.
+    ///
+    /// By default 96 headers are allowed.
+    ///
+    /// # Panics
+    /// Panics if `val` is zero.
+    pub fn max_request_headers(self, val: usize) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.limits = cfg.limits.max_headers(val);
+        }
+        self
+    }
+
+    /// Set maximum size of a request head (request line and headers) in bytes.
+    ///
+    /// Requests with a larger head are responded to with
+    /// `431 Request Header Fields Too Large`.
+    ///
+    /// By default request head size is limited to 128KiB.
+    ///
+    /// # Panics
+    /// Panics if `val` is zero.
+    pub fn max_request_head_size(self, val: usize) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.limits = cfg.limits.max_head_size(val);
+        }
+        self
+    }
+
+    /// Set maximum length of a request URI in bytes.
+    ///
+    /// Requests with a longer URI are responded to with `414 URI Too Long`.
+    ///
+    /// By default URI length is only limited by the maximum request head size.
+    ///
+    /// # Panics
+    /// Panics if `val` is zero.
+    pub fn max_uri_len(self, val: usize) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.limits = cfg.limits.max_uri_len(val);
+        }
+        self
+    }
+
+    /// Set maximum size of the trailer section of a chunked request body in bytes.
+    ///
+    /// Request bodies with a larger trailer section fail with a payload error.
+    ///
+    /// By default trailer section size is limited to the maximum request head size.
+    ///
+    /// # Panics
+    /// Panics if `val` is zero.
+    pub fn max_request_trailer_size(self, val: usize) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.limits = cfg.limits.max_trailer_size(val);
+        }
+        self
+    }
+
+    /// Set HTTP/2 connection settings.
+    ///
+    /// See [`H2Config`] for defaults. Individual settings can also be set with the `h2_*`
+    /// methods of this builder.
+    pub fn h2_config(self, val: H2Config) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.h2 = val;
+        }
+        self
+    }
+
+    /// Set initial HTTP/2 flow control window size of each stream, in bytes.
+    ///
+    /// By default the window is 65,535 bytes.
+    ///
+    /// # Panics
+    /// Panics if `val` is larger than 2^31 - 1.
+    pub fn h2_initial_window_size(self, val: u32) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.h2 = cfg.h2.initial_window_size(val);
+        }
+        self
+    }
+
+    /// Set initial HTTP/2 flow control window size of a connection, in bytes.
+    ///
+    /// By default the window is 65,535 bytes.
+    ///
+    /// # Panics
+    /// Panics if `val` is larger than 2^31 - 1.
+    pub fn h2_initial_connection_window_size(self, val: u32) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.h2 = cfg.h2.initial_connection_window_size(val);
+        }
+        self
+    }
+
+    /// Set maximum number of concurrent streams a client may open on an HTTP/2 connection.
+    ///
+    /// By default the number of concurrent streams is not limited.
+    pub fn h2_max_concurrent_streams(self, val: u32) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.h2 = cfg.h2.max_concurrent_streams(val);
+        }
+        self
+    }
+
+    /// Set maximum size of an HTTP/2 frame payload the server is willing to receive, in bytes.
+    ///
+    /// By default frames are limited to 16,384 bytes.
+    ///
+    /// # Panics
+    /// Panics if `val` is not within 16,384 and 16,777,215 (inclusive).
+    pub fn h2_max_frame_size(self, val: u32) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.h2 = cfg.h2.max_frame_size(val);
+        }
+        self
+    }
+
+    /// Set maximum size of an HTTP/2 request's header list, in bytes.
+    ///
+    /// By default the header list size is limited to 16MiB.
+    pub fn h2_max_header_list_size(self, val: u32) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.h2 = cfg.h2.max_header_list_size(val);
+        }
+        self
+    }
+
+    /// Set interval at which keep-alive PING frames are sent on HTTP/2 connections.
+    ///
+    /// Connections that do not acknowledge a PING within the
+    /// [keep-alive timeout](Self::h2_keep_alive_timeout) are closed. This allows detecting dead
+    /// connections held open by load balancers and other intermediaries.
+    ///
+    /// By default keep-alive PINGs are disabled.
+    pub fn h2_keep_alive_interval(self, val: Duration) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.h2 = cfg.h2.keep_alive_interval(val);
+        }
+        self
+    }
+
+    /// Set time to wait for an HTTP/2 keep-alive PING acknowledgement.
+    ///
+    /// By default the timeout is 20 seconds.
+    pub fn h2_keep_alive_timeout(self, val: Duration) -> Self {
+        {
+            let mut cfg = self.config.lock().unwrap();
+            cfg.h2 = cfg.h2.keep_alive_timeout(val);
+        }
+        self
+    }
+
+    /// Set signal for gracefully shutting down HTTP/2 connections.
+    ///
+    /// Once the signal is triggered, HTTP/2 connections send a GOAWAY frame to their clients,
+    /// let in-flight streams complete within the [shutdown timeout](Self::shutdown_timeout) and
+    /// refuse new streams.
+    ///
+    /// The signal is triggered when the server is stopped gracefully with [`Server::stop`] and,
+    /// unless signal handling is disabled, when the server receives a signal that stops it. It
+    /// can also be triggered manually to shut down HTTP/2 connections without stopping the
+    /// server.
+    pub fn shutdown_signal(self, signal: ShutdownSignal) -> Self {
+        self.config.lock().unwrap().shutdown = signal;
+        self
+    }
+
     /// Set server host name.
     ///
     /// Host name is used by application router as a hostname for url generation.
@@ -233,6 +434,18 @@ where
         self
     }
 
+    /// Set proxies trusted to report client information through forwarding headers.
+    ///
+    /// Applies to all apps served by this server, unless overridden using
+    /// [`App::trusted_proxies`](crate::App::trusted_proxies). Check
+    /// [TrustedProxies](super::dev::TrustedProxies) documentation for more information.
+    ///
+    /// By default all peers are trusted.
+    pub fn trusted_proxies(self, trusted_proxies: TrustedProxies) -> Self {
+        self.config.lock().unwrap().trusted_proxies = trusted_proxies;
+        self
+    }
+
     /// Stop actix system.
     pub fn system_exit(mut self) -> Self {
         self.builder = self.builder.system_exit();
@@ -242,6 +455,7 @@ where
     /// Disable signal handling
     pub fn disable_signals(mut self) -> Self {
         self.builder = self.builder.disable_signals();
+        self.signals = false;
         self
     }
 
@@ -291,10 +505,14 @@ where
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
                     let c = cfg.lock().unwrap();
                     let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+                    let trusted_proxies = c.trusted_proxies.clone();
 
                     let mut svc = HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_timeout(c.client_timeout)
+                        .request_limits(c.limits)
+                        .h2_config(c.h2)
+                        .shutdown_signal(c.shutdown.clone())
                         .local_addr(addr);
 
                     if let Some(handler) = on_connect_fn.clone() {
@@ -308,7 +526,7 @@ where
                         .map_err(|err| err.into().error_response());
 
                     svc.finish(map_config(fac, move |_| {
-                        AppConfig::new(false, host.clone(), addr)
+                        AppConfig::new(false, host.clone(), addr, trusted_proxies.clone())
                     }))
                     .tcp()
                 })?;
@@ -348,10 +566,14 @@ where
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
                     let c = cfg.lock().unwrap();
                     let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+                    let trusted_proxies = c.trusted_proxies.clone();
 
                     let svc = HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_timeout(c.client_timeout)
+                        .request_limits(c.limits)
+                        .h2_config(c.h2)
+                        .shutdown_signal(c.shutdown.clone())
                         .client_disconnect(c.client_shutdown);
 
                     let svc = if let Some(handler) = on_connect_fn.clone() {
@@ -367,7 +589,7 @@ where
                         .map_err(|err| err.into().error_response());
 
                     svc.finish(map_config(fac, move |_| {
-                        AppConfig::new(true, host.clone(), addr)
+                        AppConfig::new(true, host.clone(), addr, trusted_proxies.clone())
                     }))
                     .openssl(acceptor.clone())
                 })?;
@@ -408,10 +630,14 @@ where
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
                     let c = cfg.lock().unwrap();
                     let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+                    let trusted_proxies = c.trusted_proxies.clone();
 
                     let svc = HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_timeout(c.client_timeout)
+                        .request_limits(c.limits)
+                        .h2_config(c.h2)
+                        .shutdown_signal(c.shutdown.clone())
                         .client_disconnect(c.client_shutdown);
 
                     let svc = if let Some(handler) = on_connect_fn.clone() {
@@ -425,7 +651,7 @@ where
                         .map_err(|err| err.into().error_response());
 
                     svc.finish(map_config(fac, move |_| {
-                        AppConfig::new(true, host.clone(), addr)
+                        AppConfig::new(true, host.clone(), addr, trusted_proxies.clone())
                     }))
                     .rustls(config.clone())
                 })?;
@@ -532,12 +758,16 @@ where
                 false,
                 c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
                 socket_addr,
+                c.trusted_proxies.clone(),
             );
 
             fn_service(|io: UnixStream| async { Ok((io, Protocol::Http1, None)) }).and_then({
                 let mut svc = HttpService::build()
                     .keep_alive(c.keep_alive)
-                    .client_timeout(c.client_timeout);
+                    .client_timeout(c.client_timeout)
+                    .request_limits(c.limits)
+                    .h2_config(c.h2)
+                    .shutdown_signal(c.shutdown.clone());
 
                 if let Some(handler) = on_connect_fn.clone() {
                     svc = svc
@@ -582,6 +812,7 @@ where
                     false,
                     c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
                     socket_addr,
+                    c.trusted_proxies.clone(),
                 );
 
                 let fac = factory()
@@ -592,6 +823,9 @@ where
                     HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_timeout(c.client_timeout)
+                        .request_limits(c.limits)
+                        .h2_config(c.h2)
+                        .shutdown_signal(c.shutdown.clone())
                         .finish(map_config(fac, move |_| config.clone())),
                 )
             },
@@ -634,7 +868,103 @@ where
     /// }
     /// ```
     pub fn run(self) -> Server {
-        self.builder.run()
+        let shutdown = self.config.lock().unwrap().shutdown.clone();
+
+        if self.signals {
+            shutdown_on_signals(&shutdown);
+        }
+
+        Server {
+            server: self.builder.run(),
+            shutdown,
+        }
+    }
+}
+
+/// Triggers `shutdown` when the process receives a signal that stops the server.
+fn shutdown_on_signals(shutdown: &ShutdownSignal) {
+    #[cfg(unix)]
+    {
+        use actix_rt::signal::unix::{signal, SignalKind};
+
+        let kinds = [
+            SignalKind::interrupt(),
+            SignalKind::terminate(),
+            SignalKind::quit(),
+        ];
+
+        for kind in kinds.iter().copied() {
+            let shutdown = shutdown.clone();
+
+            actix_rt::spawn(async move {
+                if let Ok(mut sig) = signal(kind) {
+                    if sig.recv().await.is_some() {
+                        shutdown.shutdown();
+                    }
+                }
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let shutdown = shutdown.clone();
+
+        actix_rt::spawn(async move {
+            if actix_rt::signal::ctrl_c().await.is_ok() {
+                shutdown.shutdown();
+            }
+        });
+    }
+}
+
+/// Handle of a server started with [`HttpServer::run`].
+///
+/// Resolves once the server has stopped. Stopping the server gracefully also triggers its
+/// [shutdown signal](HttpServer::shutdown_signal), so that HTTP/2 connections are sent a GOAWAY
+/// frame and drained along with HTTP/1 connections.
+///
+/// This wraps [`actix_server::Server`] because its stop command is handled internally and can not
+/// be observed by the services it runs.
+#[derive(Debug, Clone)]
+pub struct Server {
+    server: actix_server::Server,
+    shutdown: ShutdownSignal,
+}
+
+impl Server {
+    /// Pause accepting incoming connections.
+    ///
+    /// If socket contains some pending connection, they might be dropped.
+    /// All opened connection remains active.
+    pub fn pause(&self) -> impl Future<Output = ()> {
+        self.server.pause()
+    }
+
+    /// Resume accepting incoming connections.
+    pub fn resume(&self) -> impl Future<Output = ()> {
+        self.server.resume()
+    }
+
+    /// Stop incoming connection processing, stop all workers and exit.
+    ///
+    /// When `graceful` is true, open connections are given the
+    /// [shutdown timeout](HttpServer::shutdown_timeout) to complete and HTTP/2 connections are
+    /// sent a GOAWAY frame so that clients stop opening new streams on them.
+    pub fn stop(&self, graceful: bool) -> impl Future<Output = ()> {
+        if graceful {
+            self.shutdown.shutdown();
+        }
+
+        self.server.stop(graceful)
+    }
+}
+
+impl Future for Server {
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.server).poll(cx)
     }
 }
 
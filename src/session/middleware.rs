@@ -0,0 +1,496 @@
+use std::{fmt, rc::Rc, time::Duration};
+
+use actix_utils::future::{ready, Ready};
+use cookie::{Cookie, CookieJar, Key, SameSite};
+use futures_core::future::LocalBoxFuture;
+
+use super::{Session, SessionState, SessionStatus, SessionStore};
+use crate::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpResponse,
+};
+
+/// How the session cookie is protected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieContentSecurity {
+    /// Cookie value is encrypted; clients can neither read nor modify it.
+    Private,
+
+    /// Cookie value is signed; clients can read but not modify it.
+    Signed,
+}
+
+/// Middleware that loads and persists [`Session`] state.
+///
+/// Before calling the wrapped service, the session key is read from the session cookie and its
+/// state is loaded from the [`SessionStore`]. After the response is produced, the session is
+/// persisted according to its [status](super::SessionStatus): changed sessions are saved and the
+/// session cookie is set, renewed sessions are saved under a new key and purged sessions are
+/// deleted along with their cookie.
+///
+/// The session cookie is encrypted with `key` by default. Sessions and their cookies expire after
+/// the [session TTL](Self::session_ttl), which is reset whenever the session changes.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use actix_web::{
+///     cookie::{Key, SameSite},
+///     session::{CookieSessionStore, SessionMiddleware},
+///     App,
+/// };
+///
+/// let session = SessionMiddleware::new(CookieSessionStore::new(), Key::generate())
+///     .cookie_name("session")
+///     .cookie_same_site(SameSite::Strict)
+///     .session_ttl(Duration::from_secs(60 * 60));
+///
+/// let app = App::new().wrap(session);
+/// ```
+pub struct SessionMiddleware<Store: SessionStore> {
+    inner: Rc<Inner<Store>>,
+}
+
+struct Inner<Store> {
+    store: Store,
+    key: Key,
+    cookie_name: String,
+    cookie_path: String,
+    cookie_domain: Option<String>,
+    cookie_secure: bool,
+    cookie_http_only: bool,
+    cookie_same_site: SameSite,
+    content_security: CookieContentSecurity,
+    ttl: Duration,
+}
+
+impl<Store: SessionStore> SessionMiddleware<Store> {
+    /// Constructs session middleware using `store` and a cookie protected with `key`.
+    ///
+    /// By default, the session cookie is named `id`, is scoped to path `/`, is `Secure`,
+    /// `HttpOnly` and `SameSite=Lax`, and sessions expire after one day.
+    pub fn new(store: Store, key: Key) -> Self {
+        SessionMiddleware {
+            inner: Rc::new(Inner {
+                store,
+                key,
+                cookie_name: "id".to_owned(),
+                cookie_path: "/".to_owned(),
+                cookie_domain: None,
+                cookie_secure: true,
+                cookie_http_only: true,
+                cookie_same_site: SameSite::Lax,
+                content_security: CookieContentSecurity::Private,
+                ttl: Duration::from_secs(24 * 60 * 60),
+            }),
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner<Store> {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Sets name of the session cookie.
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.inner_mut().cookie_name = name.into();
+        self
+    }
+
+    /// Sets path of the session cookie.
+    pub fn cookie_path(mut self, path: impl Into<String>) -> Self {
+        self.inner_mut().cookie_path = path.into();
+        self
+    }
+
+    /// Sets domain of the session cookie.
+    ///
+    /// By default no domain is set, limiting the cookie to the host that set it.
+    pub fn cookie_domain(mut self, domain: impl Into<String>) -> Self {
+        self.inner_mut().cookie_domain = Some(domain.into());
+        self
+    }
+
+    /// Sets whether the session cookie is only sent over HTTPS.
+    pub fn cookie_secure(mut self, secure: bool) -> Self {
+        self.inner_mut().cookie_secure = secure;
+        self
+    }
+
+    /// Sets whether the session cookie is hidden from client-side scripts.
+    pub fn cookie_http_only(mut self, http_only: bool) -> Self {
+        self.inner_mut().cookie_http_only = http_only;
+        self
+    }
+
+    /// Sets `SameSite` attribute of the session cookie.
+    pub fn cookie_same_site(mut self, same_site: SameSite) -> Self {
+        self.inner_mut().cookie_same_site = same_site;
+        self
+    }
+
+    /// Sets whether the session cookie is encrypted or signed.
+    pub fn cookie_content_security(mut self, content_security: CookieContentSecurity) -> Self {
+        self.inner_mut().content_security = content_security;
+        self
+    }
+
+    /// Sets time after which unchanged sessions expire.
+    pub fn session_ttl(mut self, ttl: Duration) -> Self {
+        self.inner_mut().ttl = ttl;
+        self
+    }
+}
+
+impl<Store: SessionStore> fmt::Debug for SessionMiddleware<Store> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionMiddleware")
+            .field("cookie_name", &self.inner.cookie_name)
+            .field("content_security", &self.inner.content_security)
+            .field("ttl", &self.inner.ttl)
+            .finish()
+    }
+}
+
+impl<Store> Inner<Store> {
+    /// Reads and verifies session key from the session cookie.
+    fn session_key(&self, req: &ServiceRequest) -> Option<String> {
+        let cookie = req.cookie(&self.cookie_name)?;
+
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie);
+
+        let cookie = match self.content_security {
+            CookieContentSecurity::Private => jar.private(&self.key).get(&self.cookie_name),
+            CookieContentSecurity::Signed => jar.signed(&self.key).get(&self.cookie_name),
+        };
+
+        cookie.map(|cookie| cookie.value().to_owned())
+    }
+
+    fn cookie(&self, value: String) -> Cookie<'static> {
+        let mut cookie = Cookie::build(self.cookie_name.clone(), value)
+            .path(self.cookie_path.clone())
+            .secure(self.cookie_secure)
+            .http_only(self.cookie_http_only)
+            .same_site(self.cookie_same_site)
+            .max_age(time::Duration::seconds(self.ttl.as_secs() as i64))
+            .finish();
+
+        if let Some(ref domain) = self.cookie_domain {
+            cookie.set_domain(domain.clone());
+        }
+
+        cookie
+    }
+
+    fn set_cookie<B>(
+        &self,
+        res: &mut HttpResponse<B>,
+        session_key: String,
+    ) -> Result<(), Error> {
+        let mut jar = CookieJar::new();
+
+        match self.content_security {
+            CookieContentSecurity::Private => {
+                jar.private_mut(&self.key).add(self.cookie(session_key))
+            }
+            CookieContentSecurity::Signed => {
+                jar.signed_mut(&self.key).add(self.cookie(session_key))
+            }
+        }
+
+        for cookie in jar.delta() {
+            res.add_cookie(cookie)?;
+        }
+
+        Ok(())
+    }
+
+    fn remove_cookie<B>(&self, res: &mut HttpResponse<B>) -> Result<(), Error> {
+        let mut cookie = self.cookie(String::new());
+        cookie.make_removal();
+        res.add_cookie(&cookie)?;
+        Ok(())
+    }
+}
+
+impl<S, B, Store> Transform<S, ServiceRequest> for SessionMiddleware<Store>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+    Store: SessionStore + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = InnerSessionMiddleware<S, Store>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(InnerSessionMiddleware {
+            service: Rc::new(service),
+            inner: self.inner.clone(),
+        }))
+    }
+}
+
+pub struct InnerSessionMiddleware<S, Store> {
+    service: Rc<S>,
+    inner: Rc<Inner<Store>>,
+}
+
+impl<S, B, Store> Service<ServiceRequest> for InnerSessionMiddleware<S, Store>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+    Store: SessionStore + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let inner = Rc::clone(&self.inner);
+
+        Box::pin(async move {
+            let (session_key, state) = match inner.session_key(&req) {
+                Some(session_key) => match inner.store.load(&session_key).await? {
+                    Some(state) => (Some(session_key), state),
+                    None => (None, SessionState::new()),
+                },
+                None => (None, SessionState::new()),
+            };
+
+            Session::set_session(&mut req, state);
+
+            let mut res = service.call(req).await?;
+            let (status, state) = Session::get_changes(&res);
+
+            match status {
+                SessionStatus::Changed => {
+                    let session_key = match session_key {
+                        Some(session_key) => {
+                            inner.store.update(session_key, state, inner.ttl).await?
+                        }
+                        None => inner.store.save(state, inner.ttl).await?,
+                    };
+
+                    inner.set_cookie(res.response_mut(), session_key)?;
+                }
+
+                SessionStatus::Renewed => {
+                    if let Some(session_key) = session_key {
+                        inner.store.delete(&session_key).await?;
+                    }
+
+                    let session_key = inner.store.save(state, inner.ttl).await?;
+                    inner.set_cookie(res.response_mut(), session_key)?;
+                }
+
+                SessionStatus::Purged => {
+                    if let Some(session_key) = session_key {
+                        inner.store.delete(&session_key).await?;
+                        inner.remove_cookie(res.response_mut())?;
+                    }
+                }
+
+                SessionStatus::Unchanged => {}
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        session::{CookieSessionStore, MemorySessionStore},
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    async fn counter(session: Session) -> Result<HttpResponse, Error> {
+        let count = session.get::<u32>("count")?.unwrap_or(0) + 1;
+        session.insert("count", count)?;
+        Ok(HttpResponse::Ok().body(count.to_string()))
+    }
+
+    async fn login(session: Session) -> HttpResponse {
+        session.renew();
+        HttpResponse::Ok().finish()
+    }
+
+    async fn logout(session: Session) -> HttpResponse {
+        session.purge();
+        HttpResponse::Ok().finish()
+    }
+
+    async fn noop() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    fn session_cookie(res: &ServiceResponse) -> Option<Cookie<'static>> {
+        res.response()
+            .cookies()
+            .find(|c| c.name() == "id")
+            .map(|c| c.into_owned())
+    }
+
+    async fn check_store<Store: SessionStore + Clone + 'static>(
+        store: Store,
+        content_security: CookieContentSecurity,
+    ) {
+        let key = Key::generate();
+        let srv = test::init_service(
+            App::new()
+                .wrap(
+                    SessionMiddleware::new(store, key)
+                        .cookie_content_security(content_security),
+                )
+                .route("/count", web::get().to(counter))
+                .route("/login", web::get().to(login))
+                .route("/logout", web::get().to(logout))
+                .route("/noop", web::get().to(noop)),
+        )
+        .await;
+
+        // new session
+        let req = TestRequest::with_uri("/count").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let cookie = session_cookie(&res).unwrap();
+        assert!(cookie.http_only().unwrap());
+        assert!(cookie.secure().unwrap());
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert_eq!(cookie.max_age(), Some(time::Duration::days(1)));
+        let body = test::read_body(res).await;
+        assert_eq!(body, "1");
+
+        // existing session
+        let req = TestRequest::with_uri("/count").cookie(cookie).to_request();
+        let res = test::call_service(&srv, req).await;
+        let cookie = session_cookie(&res).unwrap();
+        let body = test::read_body(res).await;
+        assert_eq!(body, "2");
+
+        // unchanged session does not set cookie
+        let req = TestRequest::with_uri("/noop")
+            .cookie(cookie.clone())
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert!(session_cookie(&res).is_none());
+
+        // renewed session keeps state
+        let req = TestRequest::with_uri("/login").cookie(cookie).to_request();
+        let res = test::call_service(&srv, req).await;
+        let renewed = session_cookie(&res).unwrap();
+        assert_eq!(renewed.max_age(), Some(time::Duration::days(1)));
+
+        let req = TestRequest::with_uri("/count")
+            .cookie(renewed.clone())
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        let cookie = session_cookie(&res).unwrap();
+        let body = test::read_body(res).await;
+        assert_eq!(body, "3");
+
+        // tampered cookie starts a new session
+        let mut tampered = cookie.clone();
+        tampered.set_value(format!("{}x", cookie.value()));
+        let req = TestRequest::with_uri("/count")
+            .cookie(tampered)
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        let body = test::read_body(res).await;
+        assert_eq!(body, "1");
+
+        // purged session removes cookie
+        let req = TestRequest::with_uri("/logout")
+            .cookie(cookie.clone())
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        let removal = session_cookie(&res).unwrap();
+        assert_eq!(removal.value(), "");
+        assert_eq!(removal.max_age(), Some(time::Duration::zero()));
+    }
+
+    #[actix_rt::test]
+    async fn test_memory_session() {
+        let store = MemorySessionStore::new();
+        check_store(store.clone(), CookieContentSecurity::Private).await;
+        check_store(store, CookieContentSecurity::Signed).await;
+    }
+
+    #[actix_rt::test]
+    async fn test_cookie_session() {
+        check_store(CookieSessionStore::new(), CookieContentSecurity::Private).await;
+        check_store(CookieSessionStore::new(), CookieContentSecurity::Signed).await;
+    }
+
+    #[actix_rt::test]
+    async fn test_renewed_memory_session() {
+        let store = MemorySessionStore::new();
+        let srv = test::init_service(
+            App::new()
+                .wrap(
+                    SessionMiddleware::new(store, Key::generate())
+                        .cookie_content_security(CookieContentSecurity::Signed),
+                )
+                .route("/count", web::get().to(counter))
+                .route("/login", web::get().to(login)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/count").to_request();
+        let res = test::call_service(&srv, req).await;
+        let cookie = session_cookie(&res).unwrap();
+
+        let req = TestRequest::with_uri("/login")
+            .cookie(cookie.clone())
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        let renewed = session_cookie(&res).unwrap();
+        assert_ne!(renewed.value(), cookie.value());
+
+        // old session key is no longer valid
+        let req = TestRequest::with_uri("/count").cookie(cookie).to_request();
+        let res = test::call_service(&srv, req).await;
+        let body = test::read_body(res).await;
+        assert_eq!(body, "1");
+    }
+
+    #[actix_rt::test]
+    async fn test_purged_memory_session() {
+        let store = MemorySessionStore::new();
+        let srv = test::init_service(
+            App::new()
+                .wrap(SessionMiddleware::new(store, Key::generate()))
+                .route("/count", web::get().to(counter))
+                .route("/logout", web::get().to(logout)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/count").to_request();
+        let res = test::call_service(&srv, req).await;
+        let cookie = session_cookie(&res).unwrap();
+
+        let req = TestRequest::with_uri("/logout")
+            .cookie(cookie.clone())
+            .to_request();
+        test::call_service(&srv, req).await;
+
+        // server side state is gone even if client replays the old cookie
+        let req = TestRequest::with_uri("/count").cookie(cookie).to_request();
+        let res = test::call_service(&srv, req).await;
+        let body = test::read_body(res).await;
+        assert_eq!(body, "1");
+    }
+}
@@ -0,0 +1,308 @@
+//! Session management.
+//!
+//! Sessions associate state with a client across requests. [`SessionMiddleware`] loads session
+//! state from a [`SessionStore`] before a request is handled and persists changes after the
+//! response is produced; handlers access the state through the [`Session`] extractor.
+//!
+//! The client only holds a session key in a signed or encrypted cookie. What the key refers to
+//! is up to the store: [`CookieSessionStore`] keeps the whole state in the cookie itself, while
+//! [`MemorySessionStore`] keeps it in server memory.
+//!
+//! Session values are serialized to JSON when inserted and deserialized lazily when read, so any
+//! type implementing `Serialize`/`Deserialize` can be stored.
+//!
+//! # Examples
+//! ```
+//! use actix_web::{
+//!     cookie::Key,
+//!     session::{MemorySessionStore, Session, SessionMiddleware},
+//!     web, App, Error, HttpResponse,
+//! };
+//!
+//! async fn index(session: Session) -> Result<HttpResponse, Error> {
+//!     let counter = session.get::<i32>("counter")?.unwrap_or(0) + 1;
+//!     session.insert("counter", counter)?;
+//!
+//!     Ok(HttpResponse::Ok().body(format!("visits: {}", counter)))
+//! }
+//!
+//! // key should be loaded from configuration so sessions survive restarts
+//! let key = Key::generate();
+//! let store = MemorySessionStore::default();
+//!
+//! let app = App::new()
+//!     .wrap(SessionMiddleware::new(store, key))
+//!     .route("/", web::get().to(index));
+//! ```
+
+use std::{
+    cell::{Ref, RefCell},
+    mem,
+    rc::Rc,
+};
+
+use actix_utils::future::{ready, Ready};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    dev::{Extensions, Payload, ServiceRequest, ServiceResponse},
+    Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+
+mod middleware;
+mod storage;
+
+pub use self::middleware::{CookieContentSecurity, SessionMiddleware};
+pub use self::storage::{CookieSessionStore, MemorySessionStore, SessionState, SessionStore};
+
+/// Session extractor.
+///
+/// Gives handlers access to the session state of the current request. Requires
+/// [`SessionMiddleware`] to be registered; without it, changes are discarded.
+///
+/// `Session` is cheap to clone; all clones refer to the same state.
+///
+/// # Examples
+/// ```
+/// use actix_web::{session::Session, Error, HttpResponse};
+///
+/// async fn login(session: Session) -> Result<HttpResponse, Error> {
+///     session.renew();
+///     session.insert("user_id", 42)?;
+///     Ok(HttpResponse::Ok().finish())
+/// }
+///
+/// async fn logout(session: Session) -> HttpResponse {
+///     session.purge();
+///     HttpResponse::Ok().finish()
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Session(Rc<RefCell<SessionInner>>);
+
+#[derive(Default)]
+struct SessionInner {
+    state: SessionState,
+    status: SessionStatus,
+}
+
+/// Status of a session after a request has been handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStatus {
+    /// Session state was modified and will be persisted.
+    Changed,
+
+    /// Session was purged; its state will be deleted and the session cookie removed.
+    Purged,
+
+    /// Session state will be persisted under a new session key.
+    Renewed,
+
+    /// Session state was not modified.
+    Unchanged,
+}
+
+impl Default for SessionStatus {
+    fn default() -> Self {
+        SessionStatus::Unchanged
+    }
+}
+
+impl Session {
+    /// Returns value stored under `key`, deserialized as `T`.
+    ///
+    /// Returns an error if the value can not be deserialized as `T`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Error> {
+        match self.0.borrow().state.get(key) {
+            Some(value) => Ok(Some(serde_json::from_str(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns all serialized session entries.
+    pub fn entries(&self) -> Ref<'_, SessionState> {
+        Ref::map(self.0.borrow(), |inner| &inner.state)
+    }
+
+    /// Returns status of the session.
+    pub fn status(&self) -> SessionStatus {
+        self.0.borrow().status
+    }
+
+    /// Stores `value` under `key`, replacing any previous value.
+    ///
+    /// Has no effect if the session was purged. Returns an error if `value` can not be
+    /// serialized.
+    pub fn insert(&self, key: impl Into<String>, value: impl Serialize) -> Result<(), Error> {
+        let mut inner = self.0.borrow_mut();
+
+        if inner.status != SessionStatus::Purged {
+            let value = serde_json::to_string(&value)?;
+            inner.state.insert(key.into(), value);
+            inner.mark_changed();
+        }
+
+        Ok(())
+    }
+
+    /// Removes value stored under `key`, returning its serialized form.
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut inner = self.0.borrow_mut();
+
+        if inner.status == SessionStatus::Purged {
+            return None;
+        }
+
+        let value = inner.state.remove(key);
+        if value.is_some() {
+            inner.mark_changed();
+        }
+
+        value
+    }
+
+    /// Removes all values from the session.
+    pub fn clear(&self) {
+        let mut inner = self.0.borrow_mut();
+
+        if inner.status != SessionStatus::Purged {
+            inner.state.clear();
+            inner.mark_changed();
+        }
+    }
+
+    /// Removes the session, both from the client and from the session store.
+    pub fn purge(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.status = SessionStatus::Purged;
+        inner.state.clear();
+    }
+
+    /// Persists session state under a new session key.
+    ///
+    /// Renewing the session key after a privilege change, such as logging in, prevents session
+    /// fixation attacks.
+    pub fn renew(&self) {
+        let mut inner = self.0.borrow_mut();
+
+        if inner.status != SessionStatus::Purged {
+            inner.status = SessionStatus::Renewed;
+        }
+    }
+
+    /// Sets state loaded by the session middleware for a request.
+    pub(crate) fn set_session(req: &mut ServiceRequest, state: SessionState) {
+        let session = Session::get_session(&mut req.extensions_mut());
+        session.0.borrow_mut().state = state;
+    }
+
+    /// Takes status and state of the session of a handled request.
+    pub(crate) fn get_changes<B>(res: &ServiceResponse<B>) -> (SessionStatus, SessionState) {
+        match res
+            .request()
+            .extensions()
+            .get::<Rc<RefCell<SessionInner>>>()
+        {
+            Some(inner) => {
+                let mut inner = inner.borrow_mut();
+                let state = mem::take(&mut inner.state);
+                (inner.status, state)
+            }
+            None => (SessionStatus::Unchanged, SessionState::new()),
+        }
+    }
+
+    fn get_session(extensions: &mut Extensions) -> Session {
+        if let Some(inner) = extensions.get::<Rc<RefCell<SessionInner>>>() {
+            return Session(Rc::clone(inner));
+        }
+
+        let inner = Rc::new(RefCell::new(SessionInner::default()));
+        extensions.insert(inner.clone());
+        Session(inner)
+    }
+}
+
+impl SessionInner {
+    fn mark_changed(&mut self) {
+        if self.status != SessionStatus::Renewed {
+            self.status = SessionStatus::Changed;
+        }
+    }
+}
+
+/// Extractor implementation for [`Session`].
+impl FromRequest for Session {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Session, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Session::get_session(&mut req.extensions_mut())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[test]
+    fn test_session() {
+        let mut req = TestRequest::default().to_srv_request();
+
+        let mut state = SessionState::new();
+        state.insert("key".to_owned(), "\"value\"".to_owned());
+        Session::set_session(&mut req, state);
+
+        let session = Session::get_session(&mut req.extensions_mut());
+        assert_eq!(session.status(), SessionStatus::Unchanged);
+        assert_eq!(session.get::<String>("key").unwrap().unwrap(), "value");
+        assert!(session.get::<i32>("key").is_err());
+        assert!(session.get::<String>("missing").unwrap().is_none());
+
+        session.insert("key2", 1).unwrap();
+        assert_eq!(session.status(), SessionStatus::Changed);
+        assert_eq!(session.entries().len(), 2);
+
+        assert_eq!(session.remove("key").unwrap(), "\"value\"");
+        assert!(session.remove("key").is_none());
+
+        let res = req.into_response(crate::HttpResponse::Ok().finish());
+        let (status, state) = Session::get_changes(&res);
+        assert_eq!(status, SessionStatus::Changed);
+        assert_eq!(state.get("key2").unwrap(), "1");
+    }
+
+    #[test]
+    fn test_session_renew_purge() {
+        let req = TestRequest::default().to_srv_request();
+        let session = Session::get_session(&mut req.extensions_mut());
+
+        session.renew();
+        session.insert("key", "value").unwrap();
+        assert_eq!(session.status(), SessionStatus::Renewed);
+
+        session.purge();
+        assert_eq!(session.status(), SessionStatus::Purged);
+        assert!(session.entries().is_empty());
+
+        // purged sessions can not be modified
+        session.insert("key", "value").unwrap();
+        session.renew();
+        assert_eq!(session.status(), SessionStatus::Purged);
+        assert!(session.entries().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_extractor() {
+        let req = TestRequest::default().to_http_request();
+
+        let session = Session::extract(&req).await.unwrap();
+        session.insert("key", 1).unwrap();
+
+        let session = Session::extract(&req).await.unwrap();
+        assert_eq!(session.get::<i32>("key").unwrap(), Some(1));
+    }
+}
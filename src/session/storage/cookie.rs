@@ -0,0 +1,133 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_utils::future::ready;
+use futures_core::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+
+use super::{SessionState, SessionStore};
+use crate::{error::ErrorInternalServerError, Error};
+
+/// Session store that keeps session state in the session cookie itself.
+///
+/// No state is kept on the server. The session cookie is signed or encrypted by
+/// [`SessionMiddleware`](super::super::SessionMiddleware), so clients can not tamper with it.
+/// The cookie carries the expiry of the session, which is reset whenever the session is saved
+/// or renewed; expired cookies are ignored even if the client keeps sending them.
+///
+/// Browsers limit cookies to 4KiB; saving larger state fails with an error. Purging a session
+/// removes the cookie from the client but can not invalidate copies of it.
+#[derive(Debug, Clone, Default)]
+pub struct CookieSessionStore {
+    _priv: (),
+}
+
+impl CookieSessionStore {
+    /// Maximum size of serialized session state, leaving room for cookie attributes and
+    /// encryption overhead.
+    const MAX_STATE_SIZE: usize = 3072;
+
+    /// Constructs a new cookie session store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn serialize(state: SessionState, ttl: Duration) -> Result<String, Error> {
+        let value = serde_json::to_string(&CookieState {
+            state,
+            expires: unix_millis().saturating_add(ttl.as_millis() as u64),
+        })?;
+
+        if value.len() > Self::MAX_STATE_SIZE {
+            return Err(ErrorInternalServerError(
+                "Session state is too large to be stored in a cookie",
+            ));
+        }
+
+        Ok(value)
+    }
+}
+
+/// Session state as stored in the session cookie.
+#[derive(Serialize, Deserialize)]
+struct CookieState {
+    state: SessionState,
+    /// Expiry of the session in milliseconds since the Unix epoch.
+    expires: u64,
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64)
+}
+
+impl SessionStore for CookieSessionStore {
+    fn load(
+        &self,
+        session_key: &str,
+    ) -> LocalBoxFuture<'static, Result<Option<SessionState>, Error>> {
+        let state = serde_json::from_str::<CookieState>(session_key)
+            .ok()
+            .filter(|cookie| cookie.expires > unix_millis())
+            .map(|cookie| cookie.state);
+
+        Box::pin(ready(Ok(state)))
+    }
+
+    fn save(
+        &self,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>> {
+        Box::pin(ready(Self::serialize(state, ttl)))
+    }
+
+    fn update(
+        &self,
+        _session_key: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>> {
+        Box::pin(ready(Self::serialize(state, ttl)))
+    }
+
+    fn delete(&self, _session_key: &str) -> LocalBoxFuture<'static, Result<(), Error>> {
+        Box::pin(ready(Ok(())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_cookie_store() {
+        let store = CookieSessionStore::new();
+
+        let mut state = SessionState::new();
+        state.insert("key".to_owned(), "\"value\"".to_owned());
+
+        let key = store
+            .save(state.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(store.load(&key).await.unwrap().unwrap(), state);
+
+        assert!(store.load("invalid").await.unwrap().is_none());
+
+        // expired sessions are not loaded, renewing them resets the expiry
+        let expired = store
+            .save(state.clone(), Duration::from_secs(0))
+            .await
+            .unwrap();
+        assert!(store.load(&expired).await.unwrap().is_none());
+        let renewed = store
+            .update(expired, state.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(store.load(&renewed).await.unwrap().unwrap(), state);
+
+        state.insert("big".to_owned(), "a".repeat(4096));
+        assert!(store.save(state, Duration::from_secs(60)).await.is_err());
+    }
+}
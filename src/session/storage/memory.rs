@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use actix_utils::future::ready;
+use futures_core::future::LocalBoxFuture;
+
+use super::{generate_session_key, SessionState, SessionStore};
+use crate::Error;
+
+/// Session store that keeps session state in server memory.
+///
+/// Clones of the store share the same sessions, so a store created outside of the
+/// [`HttpServer`](crate::HttpServer) factory closure can be used by all workers. Sessions are
+/// lost when the server restarts and are not shared between server instances; a persistent
+/// store should be used in production deployments with multiple instances.
+///
+/// Expired sessions are removed when they are loaded, and all expired sessions are swept every
+/// 1024 saves.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySessionStore {
+    sessions: Arc<Mutex<Sessions>>,
+}
+
+/// Number of saved sessions after which expired sessions are swept.
+const SWEEP_INTERVAL: usize = 1024;
+
+#[derive(Debug, Default)]
+struct Sessions {
+    entries: HashMap<String, Entry>,
+    /// Sessions saved since the last sweep.
+    inserts: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    state: SessionState,
+    expires: Instant,
+}
+
+impl MemorySessionStore {
+    /// Constructs a new, empty memory session store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&self, session_key: String, state: SessionState, ttl: Duration) -> String {
+        let mut sessions = self.sessions.lock().unwrap();
+        let now = Instant::now();
+
+        sessions.inserts += 1;
+        if sessions.inserts >= SWEEP_INTERVAL {
+            sessions.entries.retain(|_, entry| entry.expires > now);
+            sessions.inserts = 0;
+        }
+
+        sessions.entries.insert(
+            session_key.clone(),
+            Entry {
+                state,
+                expires: now + ttl,
+            },
+        );
+
+        session_key
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn load(
+        &self,
+        session_key: &str,
+    ) -> LocalBoxFuture<'static, Result<Option<SessionState>, Error>> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        let state = match sessions.entries.get(session_key) {
+            Some(entry) if entry.expires > Instant::now() => Some(entry.state.clone()),
+            Some(_) => {
+                sessions.entries.remove(session_key);
+                None
+            }
+            None => None,
+        };
+
+        Box::pin(ready(Ok(state)))
+    }
+
+    fn save(
+        &self,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>> {
+        let session_key = self.insert(generate_session_key(), state, ttl);
+        Box::pin(ready(Ok(session_key)))
+    }
+
+    fn update(
+        &self,
+        session_key: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>> {
+        let exists = self
+            .sessions
+            .lock()
+            .unwrap()
+            .entries
+            .get(&session_key)
+            .map_or(false, |entry| entry.expires > Instant::now());
+
+        // do not resurrect sessions that were deleted or expired in the meantime
+        let session_key = if exists {
+            self.insert(session_key, state, ttl)
+        } else {
+            self.insert(generate_session_key(), state, ttl)
+        };
+
+        Box::pin(ready(Ok(session_key)))
+    }
+
+    fn delete(&self, session_key: &str) -> LocalBoxFuture<'static, Result<(), Error>> {
+        self.sessions.lock().unwrap().entries.remove(session_key);
+        Box::pin(ready(Ok(())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_memory_store() {
+        let store = MemorySessionStore::new();
+
+        let mut state = SessionState::new();
+        state.insert("key".to_owned(), "\"value\"".to_owned());
+
+        let key = store
+            .save(state.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(key.len(), 64);
+        assert_eq!(store.load(&key).await.unwrap().unwrap(), state);
+
+        // clones share sessions
+        let store2 = store.clone();
+        state.insert("key2".to_owned(), "1".to_owned());
+        let key2 = store2
+            .update(key.clone(), state.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(key2, key);
+        assert_eq!(store.load(&key).await.unwrap().unwrap(), state);
+
+        store.delete(&key).await.unwrap();
+        assert!(store.load(&key).await.unwrap().is_none());
+
+        // deleted sessions are saved under a new key
+        let key3 = store
+            .update(key.clone(), state, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_ne!(key3, key);
+    }
+
+    #[actix_rt::test]
+    async fn test_memory_store_expiry() {
+        let store = MemorySessionStore::new();
+
+        let key = store
+            .save(SessionState::new(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert!(store.load(&key).await.unwrap().is_some());
+
+        actix_rt::time::sleep(Duration::from_millis(20)).await;
+        assert!(store.load(&key).await.unwrap().is_none());
+        assert!(store.sessions.lock().unwrap().entries.is_empty());
+
+        // expired sessions are not renewed
+        let key = store
+            .save(SessionState::new(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        actix_rt::time::sleep(Duration::from_millis(20)).await;
+
+        let key2 = store
+            .update(key.clone(), SessionState::new(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_ne!(key2, key);
+
+        // expired sessions that are not loaded are swept
+        let store = MemorySessionStore::new();
+
+        store
+            .save(SessionState::new(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        actix_rt::time::sleep(Duration::from_millis(20)).await;
+
+        for _ in 1..SWEEP_INTERVAL - 1 {
+            store
+                .save(SessionState::new(), Duration::from_secs(60))
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            store.sessions.lock().unwrap().entries.len(),
+            SWEEP_INTERVAL - 1
+        );
+
+        store
+            .save(SessionState::new(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.sessions.lock().unwrap().entries.len(),
+            SWEEP_INTERVAL - 1
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use std::{collections::HashMap, time::Duration};
+
+use futures_core::future::LocalBoxFuture;
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng as _};
+
+use crate::Error;
+
+mod cookie;
+mod memory;
+
+pub use self::cookie::CookieSessionStore;
+pub use self::memory::MemorySessionStore;
+
+/// Serialized session state; values are stored as JSON strings.
+pub type SessionState = HashMap<String, String>;
+
+/// Backend that persists session state.
+///
+/// Session state is identified by a session key that is sent to the client in the session
+/// cookie. Stores that keep state on the server must generate keys that can not be guessed.
+pub trait SessionStore {
+    /// Loads state of the session identified by `session_key`.
+    ///
+    /// Returns `Ok(None)` if the session does not exist or has expired.
+    fn load(
+        &self,
+        session_key: &str,
+    ) -> LocalBoxFuture<'static, Result<Option<SessionState>, Error>>;
+
+    /// Persists state of a new session, returning its session key.
+    ///
+    /// The session should expire after `ttl`.
+    fn save(
+        &self,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>>;
+
+    /// Updates state of the session identified by `session_key`, returning its session key.
+    ///
+    /// The returned key may differ from `session_key`. Expiry of the session is reset to `ttl`.
+    fn update(
+        &self,
+        session_key: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>>;
+
+    /// Deletes the session identified by `session_key`.
+    fn delete(&self, session_key: &str) -> LocalBoxFuture<'static, Result<(), Error>>;
+}
+
+/// Generates a random, 64 character session key.
+pub(crate) fn generate_session_key() -> String {
+    OsRng
+        .sample_iter(&Alphanumeric)
+        .take(64)
+        .map(char::from)
+        .collect()
+}
@@ -0,0 +1,273 @@
+//! For authentication extractor helper documentation, see [`BasicAuth`] and [`BearerAuth`].
+
+use std::borrow::Cow;
+
+use actix_utils::future::{ready, Ready};
+
+use crate::{
+    dev::Payload,
+    error::AuthenticationError,
+    extract::FromRequest,
+    http::header::{
+        Authorization, Basic, BasicChallenge, Bearer, BearerChallenge, BearerError, Header as _,
+    },
+    web, HttpRequest,
+};
+
+/// Extract credentials of the `Basic` authentication scheme.
+///
+/// If the `Authorization` header is missing or malformed, extraction fails with an
+/// [`AuthenticationError`] that responds with `401 Unauthorized` and a `WWW-Authenticate`
+/// challenge. The challenge's realm can be set with [`BasicAuthConfig`].
+///
+/// Extracting credentials does not validate them; see
+/// [`HttpAuthentication`](crate::middleware::HttpAuthentication) for validating credentials of
+/// all requests.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web::BasicAuth};
+///
+/// #[get("/")]
+/// async fn index(auth: BasicAuth) -> String {
+///     format!("Hello, {}!", auth.user_id())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BasicAuth(Basic);
+
+impl BasicAuth {
+    /// Returns the user ID.
+    pub fn user_id(&self) -> &str {
+        self.0.user_id()
+    }
+
+    /// Returns the password, if one was provided.
+    pub fn password(&self) -> Option<&str> {
+        self.0.password()
+    }
+
+    /// Unwrap into the inner credentials.
+    pub fn into_inner(self) -> Basic {
+        self.0
+    }
+}
+
+impl FromRequest for BasicAuth {
+    type Error = AuthenticationError<BasicChallenge>;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = BasicAuthConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            Authorization::<Basic>::parse(req)
+                .map(|auth| BasicAuth(auth.into_scheme()))
+                .map_err(|_| AuthenticationError::new(BasicAuthConfig::from_req(req))),
+        )
+    }
+}
+
+/// [`BasicAuth`] extractor configuration.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+///
+/// let app = App::new().app_data(web::BasicAuthConfig::default().realm("Restricted area"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BasicAuthConfig(BasicChallenge);
+
+impl BasicAuthConfig {
+    /// Sets realm of the challenge sent when extraction fails.
+    pub fn realm(mut self, realm: impl Into<Cow<'static, str>>) -> Self {
+        self.0 = self.0.realm(realm);
+        self
+    }
+
+    /// Extract config from app data, checking both `T` and `Data<T>`, and return its challenge.
+    pub(crate) fn from_req(req: &HttpRequest) -> BasicChallenge {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .map(|config| config.0.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Extract credentials of the `Bearer` authentication scheme.
+///
+/// If the `Authorization` header is missing or malformed, extraction fails with an
+/// [`AuthenticationError`] that responds with `401 Unauthorized` and a `WWW-Authenticate`
+/// challenge. The challenge's realm and scope can be set with [`BearerAuthConfig`].
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web::BearerAuth};
+///
+/// #[get("/")]
+/// async fn index(auth: BearerAuth) -> String {
+///     format!("Your token is {}", auth.token())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BearerAuth(Bearer);
+
+impl BearerAuth {
+    /// Returns the bearer token.
+    pub fn token(&self) -> &str {
+        self.0.token()
+    }
+
+    /// Unwrap into the inner credentials.
+    pub fn into_inner(self) -> Bearer {
+        self.0
+    }
+}
+
+impl FromRequest for BearerAuth {
+    type Error = AuthenticationError<BearerChallenge>;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = BearerAuthConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(match Authorization::<Bearer>::parse(req) {
+            Ok(auth) => Ok(BearerAuth(auth.into_scheme())),
+
+            // malformed bearer credentials are an invalid request; missing ones need no error code
+            Err(_) if has_bearer_scheme(req) => {
+                let challenge =
+                    BearerAuthConfig::from_req(req).error(BearerError::InvalidRequest);
+                Err(AuthenticationError::new(challenge))
+            }
+            Err(_) => Err(AuthenticationError::new(BearerAuthConfig::from_req(req))),
+        })
+    }
+}
+
+/// Returns true if the `Authorization` header uses the `Bearer` scheme.
+fn has_bearer_scheme(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(Authorization::<Bearer>::name())
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(' ').next())
+        .map_or(false, |scheme| scheme.eq_ignore_ascii_case("Bearer"))
+}
+
+/// [`BearerAuth`] extractor configuration.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+///
+/// let app = App::new().app_data(
+///     web::BearerAuthConfig::default()
+///         .realm("example")
+///         .scope("read write"),
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BearerAuthConfig(BearerChallenge);
+
+impl BearerAuthConfig {
+    /// Sets realm of the challenge sent when extraction fails.
+    pub fn realm(mut self, realm: impl Into<Cow<'static, str>>) -> Self {
+        self.0 = self.0.realm(realm);
+        self
+    }
+
+    /// Sets scope of the challenge sent when extraction fails.
+    pub fn scope(mut self, scope: impl Into<Cow<'static, str>>) -> Self {
+        self.0 = self.0.scope(scope);
+        self
+    }
+
+    /// Extract config from app data, checking both `T` and `Data<T>`, and return its challenge.
+    pub(crate) fn from_req(req: &HttpRequest) -> BearerChallenge {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .map(|config| config.0.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{header, StatusCode},
+        test::TestRequest,
+        ResponseError as _,
+    };
+
+    #[actix_rt::test]
+    async fn test_basic_auth() {
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Basic dXNlcjpwYXNz"))
+            .to_http_request();
+        let auth = BasicAuth::extract(&req).await.unwrap();
+        assert_eq!(auth.user_id(), "user");
+        assert_eq!(auth.password(), Some("pass"));
+
+        let req = TestRequest::default()
+            .app_data(BasicAuthConfig::default().realm("Restricted area"))
+            .to_http_request();
+        let err = BasicAuth::extract(&req).await.unwrap_err();
+        let res = err.error_response();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Basic realm=\"Restricted area\""
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_bearer_auth() {
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer token"))
+            .to_http_request();
+        let auth = BearerAuth::extract(&req).await.unwrap();
+        assert_eq!(auth.token(), "token");
+
+        let config = BearerAuthConfig::default().realm("example").scope("read");
+
+        let req = TestRequest::default()
+            .app_data(config.clone())
+            .to_http_request();
+        let res = BearerAuth::extract(&req)
+            .await
+            .unwrap_err()
+            .error_response();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer realm=\"example\", scope=\"read\""
+        );
+
+        // credentials of other schemes are treated as missing
+        let req = TestRequest::default()
+            .app_data(config.clone())
+            .insert_header((header::AUTHORIZATION, "Basic dXNlcjpwYXNz"))
+            .to_http_request();
+        let res = BearerAuth::extract(&req)
+            .await
+            .unwrap_err()
+            .error_response();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let req = TestRequest::default()
+            .app_data(config)
+            .insert_header((header::AUTHORIZATION, "Bearer"))
+            .to_http_request();
+        let res = BearerAuth::extract(&req)
+            .await
+            .unwrap_err()
+            .error_response();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer realm=\"example\", scope=\"read\", error=\"invalid_request\""
+        );
+    }
+}
@@ -0,0 +1,750 @@
+//! For streaming JSON documentation, see [`JsonStream`] and [`JsonStreamResponse`].
+
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use actix_utils::future::{ready, Ready};
+use bytes::{Buf as _, Bytes, BytesMut};
+use futures_core::{ready, stream::Stream};
+use futures_util::{
+    future,
+    stream::{self, StreamExt as _},
+};
+use serde::{de::DeserializeOwned, de::Error as _, Serialize};
+
+#[cfg(feature = "__compress")]
+use crate::dev::Decompress;
+use crate::{
+    dev,
+    error::{Error, JsonPayloadError},
+    extract::FromRequest,
+    web, HttpMessage, HttpRequest, HttpResponse, Responder,
+};
+
+/// Format of a stream of JSON values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonStreamFormat {
+    /// Newline delimited JSON, with one value per line. Uses the `application/x-ndjson` content
+    /// type.
+    NdJson,
+
+    /// A top-level JSON array. Uses the `application/json` content type.
+    Array,
+}
+
+impl JsonStreamFormat {
+    /// Returns the format of a payload with the given content type, if it is a JSON stream.
+    fn from_mime(mime: &mime::Mime) -> Option<Self> {
+        match mime.subtype().as_str() {
+            "x-ndjson" | "ndjson" | "jsonl" | "x-jsonlines" => Some(JsonStreamFormat::NdJson),
+            _ if mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON) => {
+                Some(JsonStreamFormat::Array)
+            }
+            _ => None,
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            JsonStreamFormat::NdJson => "application/x-ndjson",
+            JsonStreamFormat::Array => "application/json",
+        }
+    }
+}
+
+/// Extractor that deserializes a stream of JSON values from the request payload.
+///
+/// Unlike [`Json`](super::Json), the payload is not buffered: each item is deserialized as soon as
+/// it is received. The format of the payload is chosen from its content type:
+/// - `application/x-ndjson` (also `ndjson`, `jsonl` and `x-jsonlines`) payloads contain one value
+///   per line. Blank lines are skipped.
+/// - `application/json` payloads contain a top-level array, whose elements are yielded.
+///
+/// Items larger than the limit set with [`JsonStreamConfig::item_limit`] are rejected with
+/// [`JsonPayloadError::ItemOverflow`]. An item that can not be deserialized yields a
+/// [`JsonPayloadError::Deserialize`] error and the following items are still read; any other
+/// error ends the stream.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, web};
+/// use futures_util::stream::StreamExt as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Record {
+///     id: u64,
+/// }
+///
+/// #[post("/import")]
+/// async fn import(mut records: web::JsonStream<Record>) -> Result<String, actix_web::Error> {
+///     let mut count = 0;
+///
+///     while let Some(record) = records.next().await {
+///         let _id = record?.id;
+///         count += 1;
+///     }
+///
+///     Ok(format!("imported {} records", count))
+/// }
+/// ```
+pub struct JsonStream<T> {
+    #[cfg(feature = "__compress")]
+    payload: Decompress<dev::Payload>,
+    #[cfg(not(feature = "__compress"))]
+    payload: dev::Payload,
+    buf: BytesMut,
+    framer: Framer,
+    item_limit: usize,
+    eof: bool,
+    done: bool,
+    _item: PhantomData<T>,
+}
+
+impl<T> JsonStream<T> {
+    /// Constructs a stream of the JSON values in `payload`.
+    #[allow(clippy::borrow_interior_mutable_const)]
+    pub fn new(
+        req: &HttpRequest,
+        payload: &mut dev::Payload,
+        item_limit: usize,
+    ) -> Result<Self, JsonPayloadError> {
+        let format = match req.mime_type() {
+            Ok(Some(mime)) => JsonStreamFormat::from_mime(&mime),
+            _ => None,
+        }
+        .ok_or(JsonPayloadError::ContentType)?;
+
+        let payload = {
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "__compress")] {
+                    Decompress::from_headers(payload.take(), req.headers())
+                } else {
+                    payload.take()
+                }
+            }
+        };
+
+        Ok(JsonStream {
+            payload,
+            buf: BytesMut::with_capacity(8192),
+            framer: Framer::new(format),
+            item_limit,
+            eof: false,
+            done: false,
+            _item: PhantomData,
+        })
+    }
+
+    /// Returns the format of the payload.
+    pub fn format(&self) -> JsonStreamFormat {
+        match self.framer {
+            Framer::NdJson { .. } => JsonStreamFormat::NdJson,
+            Framer::Array { .. } => JsonStreamFormat::Array,
+        }
+    }
+}
+
+impl<T> Unpin for JsonStream<T> {}
+
+impl<T> Stream for JsonStream<T>
+where
+    T: DeserializeOwned,
+{
+    type Item = Result<T, JsonPayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            let item = if this.eof {
+                this.done = true;
+                this.framer.finish(&mut this.buf)
+            } else {
+                this.framer.next_item(&mut this.buf, this.item_limit)
+            };
+
+            match item {
+                Ok(Some(item)) => {
+                    let item =
+                        serde_json::from_slice(&item).map_err(JsonPayloadError::Deserialize);
+                    return Poll::Ready(Some(item));
+                }
+                Ok(None) if this.eof => return Poll::Ready(None),
+                Ok(None) => {}
+                Err(err) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+
+            match ready!(Pin::new(&mut this.payload).poll_next(cx)) {
+                Some(Ok(chunk)) => this.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                None => this.eof = true,
+            }
+        }
+    }
+}
+
+/// See [here](#examples) for example of usage as an extractor.
+impl<T> FromRequest for JsonStream<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = JsonStreamConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let config = JsonStreamConfig::from_req(req);
+
+        let res = JsonStream::new(req, payload, config.item_limit).map_err(|err| {
+            log::debug!(
+                "Failed to read JSON stream from payload. Request path: {}",
+                req.path()
+            );
+
+            match config.err_handler {
+                Some(ref err_handler) => (*err_handler)(err, req),
+                None => err.into(),
+            }
+        });
+
+        ready(res)
+    }
+}
+
+type JsonStreamErrorHandler =
+    Option<Arc<dyn Fn(JsonPayloadError, &HttpRequest) -> Error + Send + Sync>>;
+
+/// [`JsonStream`] extractor configuration.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+///
+/// // limit items to 4kB
+/// App::new().app_data(web::JsonStreamConfig::default().item_limit(4096));
+/// ```
+#[derive(Clone)]
+pub struct JsonStreamConfig {
+    item_limit: usize,
+    err_handler: JsonStreamErrorHandler,
+}
+
+impl JsonStreamConfig {
+    /// Set maximum accepted size of each item. By default this limit is 256kB.
+    pub fn item_limit(mut self, limit: usize) -> Self {
+        self.item_limit = limit;
+        self
+    }
+
+    /// Set custom error handler, used when the payload is not a JSON stream.
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(JsonPayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.err_handler = Some(Arc::new(f));
+        self
+    }
+
+    /// Extract JSON stream config from app data. Check both `T` and `Data<T>`, in that order, and
+    /// fall back to the default JSON stream config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs used as default.
+const DEFAULT_CONFIG: JsonStreamConfig = JsonStreamConfig {
+    item_limit: 262_144, // 256kB
+    err_handler: None,
+};
+
+impl Default for JsonStreamConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
+/// Splits a buffered payload into the bytes of individual items.
+enum Framer {
+    NdJson {
+        /// Length of the start of the buffer known not to contain a newline.
+        scanned: usize,
+    },
+
+    Array {
+        state: ArrayState,
+
+        /// Length of the start of the current item that has been scanned.
+        scanned: usize,
+
+        /// Nesting level of arrays and objects within the current item.
+        depth: usize,
+
+        in_string: bool,
+        escaped: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArrayState {
+    Start,
+    BeforeItem { first: bool },
+    Item,
+    AfterItem,
+    End,
+}
+
+impl Framer {
+    fn new(format: JsonStreamFormat) -> Self {
+        match format {
+            JsonStreamFormat::NdJson => Framer::NdJson { scanned: 0 },
+            JsonStreamFormat::Array => Framer::Array {
+                state: ArrayState::Start,
+                scanned: 0,
+                depth: 0,
+                in_string: false,
+                escaped: false,
+            },
+        }
+    }
+
+    /// Takes the next complete item from the start of `buf`, if there is one.
+    fn next_item(
+        &mut self,
+        buf: &mut BytesMut,
+        limit: usize,
+    ) -> Result<Option<Bytes>, JsonPayloadError> {
+        match self {
+            Framer::NdJson { scanned } => loop {
+                match buf[*scanned..].iter().position(|&b| b == b'\n') {
+                    Some(idx) => {
+                        let line = buf.split_to(*scanned + idx + 1).freeze();
+                        *scanned = 0;
+
+                        let line = trim(line);
+                        if line.len() > limit {
+                            return Err(JsonPayloadError::ItemOverflow { limit });
+                        } else if !line.is_empty() {
+                            return Ok(Some(line));
+                        }
+                    }
+
+                    None => {
+                        *scanned = buf.len();
+
+                        if trim_start(buf) > limit {
+                            return Err(JsonPayloadError::ItemOverflow { limit });
+                        }
+
+                        return Ok(None);
+                    }
+                }
+            },
+
+            Framer::Array {
+                state,
+                scanned,
+                depth,
+                in_string,
+                escaped,
+            } => loop {
+                if *state == ArrayState::Item {
+                    while *scanned < buf.len() {
+                        let b = buf[*scanned];
+
+                        if *in_string {
+                            if *escaped {
+                                *escaped = false;
+                            } else if b == b'\\' {
+                                *escaped = true;
+                            } else if b == b'"' {
+                                *in_string = false;
+                            }
+                        } else {
+                            match b {
+                                b'"' => *in_string = true,
+                                b'[' | b'{' => *depth += 1,
+                                b']' | b'}' if *depth > 0 => *depth -= 1,
+                                b',' | b']' if *depth == 0 => {
+                                    let item = buf.split_to(*scanned).freeze();
+                                    *state = ArrayState::AfterItem;
+                                    *scanned = 0;
+                                    return Ok(Some(item));
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        *scanned += 1;
+
+                        if *scanned > limit {
+                            return Err(JsonPayloadError::ItemOverflow { limit });
+                        }
+                    }
+
+                    return Ok(None);
+                }
+
+                // skip whitespace between tokens
+                let b = match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+                    Some(idx) => {
+                        buf.advance(idx);
+                        buf[0]
+                    }
+                    None => {
+                        buf.clear();
+                        return Ok(None);
+                    }
+                };
+
+                *state = match (*state, b) {
+                    (ArrayState::Start, b'[') => ArrayState::BeforeItem { first: true },
+                    (ArrayState::BeforeItem { first: true }, b']') => ArrayState::End,
+                    (ArrayState::AfterItem, b',') => ArrayState::BeforeItem { first: false },
+                    (ArrayState::AfterItem, b']') => ArrayState::End,
+
+                    (ArrayState::BeforeItem { .. }, _) => {
+                        *depth = 0;
+                        *in_string = false;
+                        *escaped = false;
+                        ArrayState::Item
+                    }
+
+                    (ArrayState::Start, _) => return Err(syntax_error("expected `[`")),
+                    (ArrayState::AfterItem, _) => {
+                        return Err(syntax_error("expected `,` or `]`"))
+                    }
+                    (ArrayState::End, _) => {
+                        return Err(syntax_error("trailing characters after array"))
+                    }
+                    (ArrayState::Item, _) => unreachable!(),
+                };
+
+                if *state != ArrayState::Item {
+                    buf.advance(1);
+                }
+            },
+        }
+    }
+
+    /// Takes the last item from `buf` once the payload has ended.
+    fn finish(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, JsonPayloadError> {
+        match self {
+            Framer::NdJson { .. } => {
+                let line = trim(buf.split().freeze());
+                Ok(if line.is_empty() { None } else { Some(line) })
+            }
+
+            Framer::Array { state, .. } => match state {
+                ArrayState::End => Ok(None),
+                _ => Err(syntax_error("unexpected end of array")),
+            },
+        }
+    }
+}
+
+/// Returns the number of bytes in `buf` after leading whitespace.
+fn trim_start(buf: &[u8]) -> usize {
+    buf.iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .map_or(0, |idx| buf.len() - idx)
+}
+
+fn trim(mut bytes: Bytes) -> Bytes {
+    let start = bytes.len() - trim_start(&bytes);
+    bytes.advance(start);
+
+    let end = bytes
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |idx| idx + 1);
+    bytes.truncate(end);
+
+    bytes
+}
+
+fn syntax_error(msg: &str) -> JsonPayloadError {
+    JsonPayloadError::Deserialize(serde_json::Error::custom(msg))
+}
+
+/// Responder that serializes a stream of values as a stream of JSON values.
+///
+/// Items are serialized as they are produced, so the response is sent without buffering the whole
+/// stream. If serializing an item fails, the response is aborted.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web};
+/// use futures_util::stream;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Record {
+///     id: u64,
+/// }
+///
+/// #[get("/export")]
+/// async fn export() -> web::JsonStreamResponse<impl futures_core::Stream<Item = Record>> {
+///     web::JsonStreamResponse::ndjson(stream::iter((0..3).map(|id| Record { id })))
+/// }
+/// ```
+pub struct JsonStreamResponse<S> {
+    stream: S,
+    format: JsonStreamFormat,
+}
+
+impl<S> JsonStreamResponse<S> {
+    /// Constructs a response in the given format.
+    pub fn new(stream: S, format: JsonStreamFormat) -> Self {
+        JsonStreamResponse { stream, format }
+    }
+
+    /// Constructs a newline delimited JSON response.
+    pub fn ndjson(stream: S) -> Self {
+        JsonStreamResponse::new(stream, JsonStreamFormat::NdJson)
+    }
+
+    /// Constructs a response containing a top-level JSON array.
+    pub fn array(stream: S) -> Self {
+        JsonStreamResponse::new(stream, JsonStreamFormat::Array)
+    }
+}
+
+impl<S, T> Responder for JsonStreamResponse<S>
+where
+    S: Stream<Item = T> + 'static,
+    T: Serialize,
+{
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        let format = self.format;
+        let mut first = true;
+
+        let items = Box::pin(self.stream).map(move |item| {
+            let mut buf = Vec::with_capacity(128);
+
+            if format == JsonStreamFormat::Array && !first {
+                buf.push(b',');
+            }
+            first = false;
+
+            serde_json::to_writer(&mut buf, &item).map_err(JsonPayloadError::Serialize)?;
+
+            if format == JsonStreamFormat::NdJson {
+                buf.push(b'\n');
+            }
+
+            Ok::<_, JsonPayloadError>(Bytes::from(buf))
+        });
+
+        let mut res = HttpResponse::Ok();
+        res.content_type(format.content_type());
+
+        match format {
+            JsonStreamFormat::NdJson => res.streaming(items),
+            JsonStreamFormat::Array => res.streaming(
+                stream::once(future::ready(Ok(Bytes::from_static(b"["))))
+                    .chain(items)
+                    .chain(stream::once(future::ready(Ok(Bytes::from_static(b"]"))))),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::body::to_bytes;
+    use futures_util::stream::TryStreamExt as _;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{
+        http::{header, StatusCode},
+        test::TestRequest,
+    };
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        id: u64,
+        name: String,
+    }
+
+    fn item(id: u64, name: &str) -> Item {
+        Item {
+            id,
+            name: name.to_owned(),
+        }
+    }
+
+    /// Builds a stream from a payload that is received in chunks of `chunk_size` bytes.
+    fn json_stream(
+        content_type: &str,
+        body: &'static str,
+        chunk_size: usize,
+        limit: usize,
+    ) -> Result<JsonStream<Item>, JsonPayloadError> {
+        let (req, _) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .to_http_parts();
+
+        let chunks = body
+            .as_bytes()
+            .chunks(chunk_size)
+            .map(|chunk| Ok(Bytes::from_static(chunk)))
+            .collect::<Vec<_>>();
+        let stream: dev::PayloadStream = Box::pin(stream::iter(chunks));
+        let mut payload = dev::Payload::from(stream);
+
+        JsonStream::new(&req, &mut payload, limit)
+    }
+
+    #[actix_rt::test]
+    async fn test_ndjson() {
+        let body = "{\"id\":1,\"name\":\"a\"}\n\r\n  {\"id\":2,\"name\":\"b\\n\"}\r\n{\"id\":3,\"name\":\"c\"}";
+
+        for &chunk_size in &[1, 3, body.len()] {
+            let stream = json_stream("application/x-ndjson", body, chunk_size, 1024).unwrap();
+            assert_eq!(stream.format(), JsonStreamFormat::NdJson);
+
+            let items = stream.try_collect::<Vec<_>>().await.unwrap();
+            assert_eq!(items, vec![item(1, "a"), item(2, "b\n"), item(3, "c")]);
+        }
+
+        let stream = json_stream(
+            "application/x-ndjson",
+            "{\"id\":1}\n{\"id\":2,\"name\":\"b\"}",
+            4,
+            1024,
+        )
+        .unwrap();
+        let items = stream.collect::<Vec<_>>().await;
+        assert!(matches!(items[0], Err(JsonPayloadError::Deserialize(_))));
+        assert_eq!(*items[1].as_ref().unwrap(), item(2, "b"));
+
+        let stream = json_stream(
+            "application/x-ndjson",
+            "{\"id\":1,\"name\":\"long\"}\n",
+            4,
+            8,
+        )
+        .unwrap();
+        let items = stream.collect::<Vec<_>>().await;
+        assert_eq!(items.len(), 1);
+        assert!(matches!(
+            items[0],
+            Err(JsonPayloadError::ItemOverflow { limit: 8 })
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_array() {
+        let body =
+            " [ {\"id\":1,\"name\":\"a,]\"} ,\n{\"id\":2,\"name\":\"\\\"[\",\"x\":[1,{}]}]\n";
+
+        for &chunk_size in &[1, 5, body.len()] {
+            let stream = json_stream("application/json", body, chunk_size, 1024).unwrap();
+            assert_eq!(stream.format(), JsonStreamFormat::Array);
+
+            let items = stream.try_collect::<Vec<_>>().await.unwrap();
+            assert_eq!(items, vec![item(1, "a,]"), item(2, "\"[")]);
+        }
+
+        let stream = json_stream("application/json", "[]", 1, 1024).unwrap();
+        assert!(stream.try_collect::<Vec<_>>().await.unwrap().is_empty());
+
+        for &body in &[
+            "",
+            "{}",
+            "[{\"id\":1,\"name\":\"a\"}",
+            "[{\"id\":1,\"name\":\"a\"}] x",
+        ] {
+            let stream = json_stream("application/json", body, 2, 1024).unwrap();
+            assert!(stream.try_collect::<Vec<_>>().await.is_err());
+        }
+
+        let stream =
+            json_stream("application/json", "[{\"id\":1,\"name\":\"long\"}]", 4, 8).unwrap();
+        let items = stream.collect::<Vec<_>>().await;
+        assert_eq!(items.len(), 1);
+        assert!(matches!(
+            items[0],
+            Err(JsonPayloadError::ItemOverflow { limit: 8 })
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_extractor() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "text/plain"))
+            .to_http_parts();
+        let err = JsonStream::<Item>::from_request(&req, &mut pl)
+            .await
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/x-ndjson"))
+            .set_payload(Bytes::from_static(b"{\"id\":1,\"name\":\"long\"}\n"))
+            .app_data(JsonStreamConfig::default().item_limit(4))
+            .to_http_parts();
+        let stream = JsonStream::<Item>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        let items = stream.collect::<Vec<_>>().await;
+        assert!(matches!(
+            items[0],
+            Err(JsonPayloadError::ItemOverflow { limit: 4 })
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_responder() {
+        let req = TestRequest::default().to_http_request();
+
+        let res = JsonStreamResponse::ndjson(stream::iter(vec![item(1, "a"), item(2, "b")]))
+            .respond_to(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/x-ndjson"
+        );
+        assert_eq!(
+            to_bytes(res.into_body()).await.unwrap(),
+            "{\"id\":1,\"name\":\"a\"}\n{\"id\":2,\"name\":\"b\"}\n"
+        );
+
+        let res = JsonStreamResponse::array(stream::iter(vec![item(1, "a"), item(2, "b")]))
+            .respond_to(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        assert_eq!(
+            to_bytes(res.into_body()).await.unwrap(),
+            "[{\"id\":1,\"name\":\"a\"},{\"id\":2,\"name\":\"b\"}]"
+        );
+
+        let res = JsonStreamResponse::array(stream::iter(Vec::<Item>::new())).respond_to(&req);
+        assert_eq!(to_bytes(res.into_body()).await.unwrap(), "[]");
+    }
+}
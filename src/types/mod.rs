@@ -1,20 +1,30 @@
 //! Common extractors and responders.
 
 // TODO: review visibility
+mod auth;
 mod either;
 pub(crate) mod form;
 mod header;
 pub(crate) mod json;
+mod json_stream;
+mod negotiate;
 mod path;
 pub(crate) mod payload;
 mod query;
 pub(crate) mod readlines;
+mod sse;
 
+pub use self::auth::{BasicAuth, BasicAuthConfig, BearerAuth, BearerAuthConfig};
 pub use self::either::{Either, EitherExtractError};
 pub use self::form::{Form, FormConfig};
 pub use self::header::Header;
 pub use self::json::{Json, JsonConfig};
+pub use self::json_stream::{
+    JsonStream, JsonStreamConfig, JsonStreamFormat, JsonStreamResponse,
+};
+pub use self::negotiate::Negotiate;
 pub use self::path::{Path, PathConfig};
 pub use self::payload::{Payload, PayloadConfig};
 pub use self::query::{Query, QueryConfig};
 pub use self::readlines::Readlines;
+pub use self::sse::{Sse, SseEvent};
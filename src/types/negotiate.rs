@@ -0,0 +1,237 @@
+//! For content negotiation documentation, see [`Negotiate`].
+
+use std::fmt;
+
+use mime::Mime;
+use serde::Serialize;
+
+use crate::{
+    dev::AnyBody,
+    error::{Error, JsonPayloadError, UrlencodedError},
+    http::{
+        header::{self, Accept},
+        StatusCode,
+    },
+    HttpMessage as _, HttpRequest, HttpResponse, Responder,
+};
+
+type Renderer<T> = Box<dyn Fn(&T) -> Result<AnyBody, Error>>;
+
+/// Content negotiation responder.
+///
+/// Renders a value in the representation that best matches the request's
+/// [`Accept`](crate::http::header::Accept) header, out of those added with [`json`](Self::json),
+/// [`form`](Self::form), [`text`](Self::text) and [`custom`](Self::custom). Representations
+/// are compared by q-factor and then by the order they were added in. When the request has no
+/// `Accept` header, the first representation is used.
+///
+/// All responses include a `Vary: Accept` header. When no representation is acceptable, a
+/// `406 Not Acceptable` response is sent.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// #[get("/user")]
+/// async fn user() -> web::Negotiate<User> {
+///     let user = User { name: "ferris".to_owned() };
+///
+///     web::Negotiate::new(user)
+///         .json()
+///         .form()
+///         .custom(mime::TEXT_HTML_UTF_8, |user| {
+///             Ok::<_, actix_web::Error>(format!("<p>{}</p>", user.name))
+///         })
+/// }
+/// ```
+pub struct Negotiate<T> {
+    value: T,
+    renderers: Vec<(Mime, Renderer<T>)>,
+}
+
+impl<T> Negotiate<T> {
+    /// Constructs a responder for `value`, with no representations.
+    pub fn new(value: T) -> Self {
+        Negotiate {
+            value,
+            renderers: Vec::new(),
+        }
+    }
+
+    /// Adds a representation of type `mime`, rendered by `render`.
+    pub fn custom<F, B, E>(mut self, mime: Mime, render: F) -> Self
+    where
+        F: Fn(&T) -> Result<B, E> + 'static,
+        B: Into<AnyBody>,
+        E: Into<Error>,
+    {
+        self.renderers.push((
+            mime,
+            Box::new(move |value| render(value).map(Into::into).map_err(Into::into)),
+        ));
+        self
+    }
+
+    /// Adds a `text/plain` representation, rendered with the value's `Display` implementation.
+    pub fn text(self) -> Self
+    where
+        T: fmt::Display,
+    {
+        self.custom(mime::TEXT_PLAIN_UTF_8, |value| {
+            Ok::<_, Error>(value.to_string())
+        })
+    }
+}
+
+impl<T: Serialize> Negotiate<T> {
+    /// Adds an `application/json` representation.
+    pub fn json(self) -> Self {
+        self.custom(mime::APPLICATION_JSON, |value| {
+            serde_json::to_string(value).map_err(JsonPayloadError::Serialize)
+        })
+    }
+
+    /// Adds an `application/x-www-form-urlencoded` representation.
+    pub fn form(self) -> Self {
+        self.custom(mime::APPLICATION_WWW_FORM_URLENCODED, |value| {
+            serde_urlencoded::to_string(value).map_err(UrlencodedError::Serialize)
+        })
+    }
+}
+
+impl<T> Responder for Negotiate<T> {
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        let mimes = self
+            .renderers
+            .iter()
+            .map(|(mime, _)| mime.clone())
+            .collect::<Vec<_>>();
+
+        let selected = match req.get_header::<Accept>() {
+            Some(accept) => accept
+                .negotiate(&mimes)
+                .and_then(|selected| mimes.iter().position(|mime| mime == selected)),
+            None if mimes.is_empty() => None,
+            None => Some(0),
+        };
+
+        let (mime, render) = match selected {
+            Some(idx) => &self.renderers[idx],
+            None => {
+                return HttpResponse::build(StatusCode::NOT_ACCEPTABLE)
+                    .insert_header((header::VARY, "accept"))
+                    .finish();
+            }
+        };
+
+        match render(&self.value) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(mime.clone())
+                .insert_header((header::VARY, "accept"))
+                .body(body),
+            Err(err) => HttpResponse::from_error(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::body::to_bytes;
+    use serde::Serialize;
+
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[derive(Serialize)]
+    struct Counter {
+        count: u32,
+    }
+
+    impl fmt::Display for Counter {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "count is {}", self.count)
+        }
+    }
+
+    fn negotiate() -> Negotiate<Counter> {
+        Negotiate::new(Counter { count: 3 }).json().form().text()
+    }
+
+    async fn respond(accept: Option<&str>) -> (StatusCode, Option<String>, String) {
+        let mut req = TestRequest::default();
+        if let Some(accept) = accept {
+            req = req.insert_header((header::ACCEPT, accept));
+        }
+
+        let res = negotiate().respond_to(&req.to_http_request());
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "accept");
+
+        let status = res.status();
+        let content_type = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|ct| ct.to_str().unwrap().to_owned());
+        let body = to_bytes(res.into_body()).await.unwrap();
+
+        (
+            status,
+            content_type,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_negotiate() {
+        let (status, ct, body) = respond(None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ct.unwrap(), "application/json");
+        assert_eq!(body, r#"{"count":3}"#);
+
+        let (_, ct, body) = respond(Some("text/*, application/json; q=0.9")).await;
+        assert_eq!(ct.unwrap(), "text/plain; charset=utf-8");
+        assert_eq!(body, "count is 3");
+
+        let (_, ct, body) =
+            respond(Some("application/x-www-form-urlencoded, */*; q=0.1")).await;
+        assert_eq!(ct.unwrap(), "application/x-www-form-urlencoded");
+        assert_eq!(body, "count=3");
+
+        let (_, ct, _) = respond(Some("*/*, application/json; q=0")).await;
+        assert_eq!(ct.unwrap(), "application/x-www-form-urlencoded");
+
+        let (status, ct, body) = respond(Some("image/png, text/html")).await;
+        assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+        assert!(ct.is_none());
+        assert!(body.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_custom_renderer() {
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "text/html"))
+            .to_http_request();
+
+        let res = Negotiate::new(5)
+            .json()
+            .custom(mime::TEXT_HTML, |n| Ok::<_, Error>(format!("<b>{}</b>", n)))
+            .respond_to(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html"
+        );
+        assert_eq!(to_bytes(res.into_body()).await.unwrap(), "<b>5</b>");
+
+        let res = Negotiate::new(5)
+            .custom(mime::TEXT_HTML, |_| {
+                Err::<String, _>(crate::error::ErrorInternalServerError("failed"))
+            })
+            .respond_to(&req);
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
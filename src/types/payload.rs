@@ -50,6 +50,13 @@ impl Payload {
     pub fn into_inner(self) -> crate::dev::Payload {
         self.0
     }
+
+    /// Take trailer fields sent by the client after the request body.
+    ///
+    /// Trailers are only available once the payload stream has been read to completion.
+    pub fn take_trailers(&mut self) -> Option<header::HeaderMap> {
+        self.0.take_trailers()
+    }
 }
 
 impl Stream for Payload {
@@ -0,0 +1,351 @@
+//! For server-sent events documentation, see [`Sse`].
+
+use std::{
+    convert::Infallible,
+    fmt::Write as _,
+    future::Future as _,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::time::{sleep, Instant, Sleep};
+use bytes::{BufMut as _, Bytes, BytesMut};
+use futures_core::stream::Stream;
+use serde::Serialize;
+
+use crate::{
+    dev::BodyEncoding as _,
+    http::{
+        header::{CacheControl, CacheDirective},
+        ContentEncoding, StatusCode,
+    },
+    HttpRequest, HttpResponse, Responder,
+};
+
+/// An event sent in a [`Sse`] stream.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use actix_web::web::SseEvent;
+///
+/// let event = SseEvent::data("line one\nline two")
+///     .event("update")
+///     .id("42")
+///     .retry(Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    comment: Option<String>,
+    event: Option<String>,
+    id: Option<String>,
+    retry: Option<Duration>,
+    data: Option<String>,
+}
+
+impl SseEvent {
+    /// Constructs an event carrying `data`.
+    ///
+    /// Data spanning several lines is sent as several `data` fields, which clients join back
+    /// together with line feeds.
+    pub fn data(data: impl Into<String>) -> Self {
+        SseEvent {
+            data: Some(data.into()),
+            ..SseEvent::default()
+        }
+    }
+
+    /// Constructs an event carrying `value` serialized as JSON.
+    pub fn json<T: Serialize>(value: &T) -> Result<Self, serde_json::Error> {
+        serde_json::to_string(value).map(SseEvent::data)
+    }
+
+    /// Constructs a comment, which clients ignore.
+    pub fn comment(comment: impl Into<String>) -> Self {
+        SseEvent {
+            comment: Some(comment.into()),
+            ..SseEvent::default()
+        }
+    }
+
+    /// Sets the event name, which clients use to dispatch the event to listeners. Line breaks are
+    /// removed.
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(strip(event.into(), false));
+        self
+    }
+
+    /// Sets the event ID, which clients send back in the `Last-Event-ID` header when they
+    /// reconnect. Line breaks and null characters are removed.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(strip(id.into(), true));
+        self
+    }
+
+    /// Sets the time clients wait before reconnecting when the connection is lost.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Encodes the event in the `text/event-stream` format.
+    fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(64);
+
+        if let Some(ref comment) = self.comment {
+            for line in lines(comment) {
+                write_field(&mut buf, "", line);
+            }
+        }
+
+        if let Some(ref event) = self.event {
+            write_field(&mut buf, "event", event);
+        }
+
+        if let Some(ref id) = self.id {
+            write_field(&mut buf, "id", id);
+        }
+
+        if let Some(retry) = self.retry {
+            let _ = writeln!(buf, "retry: {}", retry.as_millis());
+        }
+
+        if let Some(ref data) = self.data {
+            for line in lines(data) {
+                write_field(&mut buf, "data", line);
+            }
+        }
+
+        buf.put_u8(b'\n');
+        buf.freeze()
+    }
+}
+
+/// Removes the characters that would end a field from its value.
+fn strip(mut value: String, null: bool) -> String {
+    value.retain(|c| c != '\r' && c != '\n' && !(null && c == '\0'));
+    value
+}
+
+/// Splits `text` at each `\r\n`, `\r` or `\n` line break.
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split('\n')
+        .flat_map(|line| line.strip_suffix('\r').unwrap_or(line).split('\r'))
+}
+
+fn write_field(buf: &mut BytesMut, name: &str, value: &str) {
+    buf.extend_from_slice(name.as_bytes());
+    buf.extend_from_slice(b": ");
+    buf.extend_from_slice(value.as_bytes());
+    buf.put_u8(b'\n');
+}
+
+/// Server-sent events responder.
+///
+/// Sends a stream of [`SseEvent`]s as a `text/event-stream` response, as defined in the
+/// [HTML Living Standard](https://html.spec.whatwg.org/multipage/server-sent-events.html). Each
+/// event is sent as soon as it is produced; the response is not compressed.
+///
+/// Reconnecting clients send the ID of the last event they received in the
+/// [`LastEventId`](crate::http::header::LastEventId) header.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use actix_web::{get, http::header::LastEventId, web};
+/// use futures_util::stream;
+///
+/// #[get("/events")]
+/// async fn events(
+///     last_event_id: Option<web::Header<LastEventId>>,
+/// ) -> web::Sse<impl futures_core::Stream<Item = web::SseEvent>> {
+///     let start = last_event_id
+///         .and_then(|id| id.0.parse::<u64>().ok())
+///         .map_or(0, |id| id + 1);
+///
+///     let events = stream::iter((start..start + 3).map(|id| {
+///         web::SseEvent::data(format!("update {}", id)).id(id.to_string())
+///     }));
+///
+///     web::Sse::new(events).keep_alive(Duration::from_secs(15))
+/// }
+/// ```
+pub struct Sse<S> {
+    stream: S,
+    keep_alive: Option<Duration>,
+    retry: Option<Duration>,
+}
+
+impl<S> Sse<S>
+where
+    S: Stream<Item = SseEvent> + 'static,
+{
+    /// Constructs a responder sending the events of `stream`.
+    pub fn new(stream: S) -> Self {
+        Sse {
+            stream,
+            keep_alive: None,
+            retry: None,
+        }
+    }
+
+    /// Sends a comment whenever no event has been sent for `interval`, so that the connection is
+    /// not closed by proxies for being idle.
+    ///
+    /// Disabled by default.
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+
+    /// Sets the time clients wait before reconnecting when the connection is lost, sent at the
+    /// start of the stream.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
+
+impl<S> Responder for Sse<S>
+where
+    S: Stream<Item = SseEvent> + 'static,
+{
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        let body = SseBody {
+            stream: Box::pin(self.stream),
+            keep_alive: self
+                .keep_alive
+                .map(|interval| (interval, Box::pin(sleep(interval)))),
+            retry: self.retry,
+        };
+
+        HttpResponse::build(StatusCode::OK)
+            .content_type("text/event-stream")
+            .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+            .encoding(ContentEncoding::Identity)
+            .streaming(body)
+    }
+}
+
+struct SseBody<S> {
+    stream: Pin<Box<S>>,
+    keep_alive: Option<(Duration, Pin<Box<Sleep>>)>,
+    retry: Option<Duration>,
+}
+
+impl<S> Stream for SseBody<S>
+where
+    S: Stream<Item = SseEvent>,
+{
+    type Item = Result<Bytes, Infallible>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(retry) = this.retry.take() {
+            let event = SseEvent::default().retry(retry);
+            return Poll::Ready(Some(Ok(event.encode())));
+        }
+
+        if let Poll::Ready(event) = this.stream.as_mut().poll_next(cx) {
+            if let Some((interval, ref mut timer)) = this.keep_alive {
+                timer.as_mut().reset(Instant::now() + interval);
+            }
+
+            return Poll::Ready(event.map(|event| Ok(event.encode())));
+        }
+
+        if let Some((interval, ref mut timer)) = this.keep_alive {
+            if timer.as_mut().poll(cx).is_ready() {
+                timer.as_mut().reset(Instant::now() + interval);
+                return Poll::Ready(Some(Ok(Bytes::from_static(b":\n\n"))));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::body::to_bytes;
+    use futures_util::{
+        future,
+        stream::{self, StreamExt as _},
+    };
+
+    use super::*;
+    use crate::{http::header, test::TestRequest};
+
+    #[test]
+    fn test_event_encoding() {
+        let event = SseEvent::data("one\ntwo\r\nthree\rfour")
+            .event("up\ndate")
+            .id("4\u{0}2")
+            .retry(Duration::from_secs(3));
+        assert_eq!(
+            event.encode(),
+            "event: update\nid: 42\nretry: 3000\n\
+             data: one\ndata: two\ndata: three\ndata: four\n\n"
+        );
+
+        assert_eq!(SseEvent::data("").encode(), "data: \n\n");
+        assert_eq!(SseEvent::comment("hi").encode(), ": hi\n\n");
+        assert_eq!(
+            SseEvent::json(&vec![1, 2]).unwrap().encode(),
+            "data: [1,2]\n\n"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_responder() {
+        let req = TestRequest::default().to_http_request();
+        let events = stream::iter(vec![SseEvent::data("a"), SseEvent::data("b").id("2")]);
+
+        let res = Sse::new(events)
+            .retry(Duration::from_millis(500))
+            .respond_to(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-cache"
+        );
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "retry: 500\n\ndata: a\n\nid: 2\ndata: b\n\n");
+    }
+
+    #[actix_rt::test]
+    async fn test_keep_alive() {
+        // the event is only sent after a few keep-alive comments
+        let event = future::ready(SseEvent::data("late"));
+        let delayed = sleep(Duration::from_millis(60));
+        let events = stream::once(async move {
+            delayed.await;
+            event.await
+        });
+
+        let mut body = SseBody {
+            stream: Box::pin(events),
+            keep_alive: Some((
+                Duration::from_millis(20),
+                Box::pin(sleep(Duration::from_millis(20))),
+            )),
+            retry: None,
+        };
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = body.next().await {
+            chunks.push(chunk.unwrap());
+        }
+
+        assert!(chunks.len() >= 2);
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| c == ":\n\n"));
+        assert_eq!(chunks[chunks.len() - 1], "data: late\n\n");
+    }
+}
@@ -147,3 +147,91 @@ async fn test_start_ssl() {
     thread::sleep(Duration::from_millis(100));
     let _ = sys.stop();
 }
+
+#[actix_rt::test]
+#[cfg(feature = "openssl")]
+async fn test_h2_graceful_stop() {
+    use std::pin::Pin;
+
+    use actix_web::http::StatusCode;
+    use bytes::BytesMut;
+    use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+    use tokio_openssl::SslStream;
+
+    let addr = actix_test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new();
+        let builder = ssl_acceptor();
+
+        let srv = HttpServer::new(|| {
+            App::new().service(web::resource("/").route(web::to(|| async {
+                actix_rt::time::sleep(Duration::from_millis(300)).await;
+                "test"
+            })))
+        })
+        .workers(1)
+        .shutdown_timeout(10)
+        .system_exit()
+        .disable_signals()
+        .bind_openssl(format!("{}", addr), builder)
+        .unwrap();
+
+        sys.block_on(async {
+            let srv = srv.run();
+            let _ = tx.send((srv, actix_rt::System::current()));
+        });
+
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+    builder.set_verify(SslVerifyMode::NONE);
+    builder.set_alpn_protos(b"\x02h2").unwrap();
+    let ssl = builder
+        .build()
+        .configure()
+        .unwrap()
+        .into_ssl("localhost")
+        .unwrap();
+
+    let io = actix_rt::net::TcpStream::connect(addr).await.unwrap();
+    let mut io = SslStream::new(ssl, io).unwrap();
+    Pin::new(&mut io).connect().await.unwrap();
+    assert_eq!(io.ssl().selected_alpn_protocol(), Some(&b"h2"[..]));
+
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    let conn = actix_rt::spawn(conn);
+
+    let req = ::http::Request::get("/").body(()).unwrap();
+    let (res, _) = client.send_request(req, true).unwrap();
+
+    // stop server while request is in flight
+    actix_rt::time::sleep(Duration::from_millis(100)).await;
+    let stop = srv.stop(true);
+
+    // in-flight stream completes
+    let res = res.await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let mut body = res.into_body();
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        bytes.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(bytes, "test".as_bytes());
+
+    // connection is closed after GOAWAY without error, well before workers are stopped
+    let conn = actix_rt::time::timeout(Duration::from_secs(2), conn)
+        .await
+        .expect("connection was not closed");
+    assert!(conn.unwrap().is_ok());
+
+    // new streams are refused
+    let req = ::http::Request::get("/").body(()).unwrap();
+    assert!(client.send_request(req, true).is_err());
+
+    stop.await;
+    sys.stop();
+}